@@ -0,0 +1,44 @@
+//! # Assembly Runtime API
+//!
+//! Queries over the genealogy indexes maintained by the Assembly pallet. Every query walks all
+//! assembly levels, so a raw material lot can be traced to the finished goods built from the
+//! sub-assemblies it went into. Also reports the components a work order is short of, which
+//! assembly errors can only identify by index.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use codec::Codec;
+use pallet_assembly::types::{GenealogyLink, ShortComponent, WorkOrderNumber};
+use pallet_inventory::types::{LotNumber, SerialNumber, Sku};
+
+sp_api::decl_runtime_apis! {
+    pub trait AssemblyApi<AccountId>
+    where
+        AccountId: Codec,
+    {
+        /// Every assembled serial a component lot ended up in
+        fn where_used_lot(who: AccountId, sku: Sku, lot_number: LotNumber) -> Vec<GenealogyLink>;
+
+        /// Every assembled serial a component serial, or a serial split off it, ended up in
+        fn where_used_serial(
+            who: AccountId,
+            sku: Sku,
+            serial_number: SerialNumber,
+        ) -> Vec<GenealogyLink>;
+
+        /// Every component inside an assembled serial
+        fn as_built_contents(
+            who: AccountId,
+            sku: Sku,
+            serial_number: SerialNumber,
+        ) -> Vec<GenealogyLink>;
+
+        /// Every component a work order is short of for assembly, with its SKU and quantity
+        fn component_shortages(
+            who: AccountId,
+            work_order_number: WorkOrderNumber,
+        ) -> Vec<ShortComponent>;
+    }
+}
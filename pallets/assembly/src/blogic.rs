@@ -1,227 +1,721 @@
-use crate::pallet::{AssembledProducts, Config, Error, WorkOrders};
+use crate::pallet::{
+    AssembledProducts, AssemblyYields, BackflushHistory, BomRevisions, ComponentWhereUsed, Config,
+    Error, LotWhereUsed, ManualIssues, NextReworkOrderNumber, ProductContents, ReworkOrders,
+    StagingArea, Teardowns, WorkOrderEquipment, WorkOrderOperations, WorkOrders,
+};
 use crate::{pallet::Pallet, types::*, Event};
 use frame_support::ensure;
-use frame_support::sp_runtime::DispatchResult;
+use frame_support::sp_runtime::{DispatchError, DispatchResult, Percent, SaturatedConversion};
+use frame_support::storage::with_storage_layer;
 use frame_support::BoundedVec;
-use pallet_inventory::types::{AdjustDetails, Item, Location, SerialNumber};
-use pallet_inventory::{GlobalInventory, Inventory, InventoryLocale};
+use pallet_inventory::types::{
+    AdjustDetails, AssetId, Employee, HoldSource, InventoryType, IssueMode, Item, Location,
+    LotNumber, Material, OperationNumber, Qty, Reason, Recipe, RecipeOutput, ScrapDetails,
+    SerialNumber, Sku, StockAllocations,
+};
+use pallet_inventory::{Inventory, Materials};
+use scale_info::prelude::collections::BTreeSet;
 use scale_info::prelude::vec::Vec;
 
 impl<T: Config> Pallet<T> {
     /// Assemble a product from a given Work Order
     ///
-    /// Must provide the serial number of the assembled product as well as the staging location.
+    /// Must provide the first serial number of the assembled products and the quantity actually
+    /// produced, which is recorded against the work order's expected yield. At least one unit must
+    /// be produced and no more than the work order started. Each unit produced becomes its own finished good, serialized
+    /// consecutively from `serial_number`, sharing the work order's production lot and holding
+    /// its share of the as-built BOM. The recipe's co-products and by-products, scaled to the
+    /// quantity produced, each take the next free serial number of their own SKU and share the
+    /// same genealogy. Pre-kitted components are consumed only from the work order's own
+    /// staging area, and whatever is left of it is returned to the warehouse. Backflushed
+    /// components are drawn straight from stock and manually issued ones must have been issued
+    /// in full.
+    ///
+    /// Every kitted component is allocated from staging before anything is consumed, and all
+    /// consumption and the output insertion happen in a single storage layer, so an assembly
+    /// either completes fully or leaves inventory untouched.
     pub fn do_assemble_product(
         who: &T::AccountId,
         work_order: WorkOrder,
         serial_number: SerialNumber,
-        staging_location: Location,
-    ) -> DispatchResult {
-        // Query the work order and get the details
-        let work_order = WorkOrders::<T>::get(work_order.work_order_number)
-            .ok_or(Error::<T>::WorkOrderNotFound)?;
+        produced_qty: Qty,
+    ) -> Result<YieldRecord, DispatchError> {
+        with_storage_layer(|| {
+            // Query the work order and get the details
+            let work_order = WorkOrders::<T>::get(work_order.work_order_number)
+                .ok_or(Error::<T>::WorkOrderNotFound)?;
 
-        // Create a new assembled product (Basic, for now)
-        let assembled_product = Item {
-            sku: work_order.recipe.sku.clone(),
-            serial_number: serial_number.clone(),
-            qty: work_order.recipe.output_quantity,
-            // Include other fields as needed for `Item`
-            ..Default::default()
-        };
+            // Components may only be consumed once the work order has been staged
+            ensure!(
+                work_order.status.can_assemble(),
+                Error::<T>::WorkOrderNotStaged
+            );
 
-        // Query the inventory locale area to get the BOM (this is assumed to be true because the staging area is prepped)
-        // TODO: Never assume you will get what is needed. Always check if the staging area is prepped - OR add a check on the prep
-        // at the end of the function to ensure the staging area is prepped...
-        let mut staging_inventory =
-            InventoryLocale::<T>::get(staging_location).ok_or(Error::<T>::StagingAreaNotFound)?;
-
-        // Initialize a BoundedVec for the BOM components
-        let mut bom = Bom::new();
-
-        // For each recipe component, consume the required quantity from the staging area
-        for recipe_item in work_order.recipe.required_components.iter() {
-            let recipe_quantity = recipe_item.qty;
-            let mut required_quantity = recipe_item.qty;
-            log::info!("Recipe Item: {:?}", recipe_item);
-
-            // Iterate through the staging area to find the required component
-            for (_, item) in staging_inventory.iter_mut() {
-                if recipe_item.sku == item.sku {
-                    if item.qty == 0 || required_quantity == 0 {
-                        continue;
-                    }
+            // Load the recipe the work order references
+            let recipe = Self::work_order_recipe(&work_order)?;
 
-                    let original_qty = item.qty;
+            // A routed recipe is assembled once every operation has closed
+            ensure!(
+                recipe.routing.iter().all(|operation| {
+                    WorkOrderOperations::<T>::get(
+                        work_order.work_order_number,
+                        operation.operation_number,
+                    )
+                    .is_some_and(|record| record.status == OperationStatus::Completed)
+                }),
+                Error::<T>::OperationsIncomplete
+            );
 
-                    ensure!(
-                        item.qty < recipe_quantity,
-                        Error::<T>::InsufficientInventory
-                    );
+            // At least one good unit must come out, and no more than the work order started
+            ensure!(produced_qty > 0, Error::<T>::NoUnitsProduced);
+            ensure!(
+                produced_qty <= work_order.planned_input_qty(&recipe),
+                Error::<T>::TooManyUnits
+            );
 
-                    let mut bom_item = item.clone();
+            // Every output serial number must be free
+            let serial_numbers = Self::output_serial_numbers(serial_number, produced_qty)?;
+            for serial_number in serial_numbers.iter() {
+                ensure!(
+                    !Inventory::<T>::contains_key((who, recipe.sku.clone(), *serial_number))
+                        && !AssembledProducts::<T>::contains_key((
+                            who,
+                            recipe.sku.clone(),
+                            *serial_number
+                        )),
+                    Error::<T>::SerialNumberInUse
+                );
+            }
+
+            // Secondary outputs made in any quantity, scaled to the units produced
+            let secondary_outputs: Vec<(RecipeOutput, Qty)> = recipe
+                .secondary_outputs
+                .iter()
+                .map(|output| (output.clone(), recipe.secondary_qty(output, produced_qty)))
+                .filter(|(_, qty)| *qty > 0)
+                .collect();
+
+            // The work order must hold the recipe's equipment for the duration of the assembly
+            Self::lock_work_order_equipment(&work_order, &recipe)?;
+
+            // Only material kitted for this work order, by this account, can be consumed
+            let (owner, kit) = StagingArea::<T>::get(work_order.work_order_number)
+                .ok_or(Error::<T>::StagingAreaNotFound)?;
+            ensure!(owner == *who, Error::<T>::NotStagingOwner);
+
+            // Allocate staged stock to every component up front, failing on the first short one
+            let consumption = Self::allocate_components(who, &work_order, &recipe, &kit)?;
+
+            // Manually issued components must have been issued in full, and are held in staging
+            let issued =
+                Self::issued_stock(who, &ManualIssues::<T>::take(work_order.work_order_number));
+            for (index, component) in recipe.required_components.iter().enumerate() {
+                if component.issue_mode != IssueMode::Manual {
+                    continue;
+                }
+                let issued_qty = issued
+                    .iter()
+                    .filter(|(item, _)| item.sku == component.sku)
+                    .fold(0 as Qty, |total, (_, qty)| total.saturating_add(*qty));
+                ensure!(
+                    issued_qty >= work_order.required_qty(&recipe, component),
+                    Error::<T>::ComponentShortage {
+                        component_index: index as u8
+                    }
+                );
+            }
+
+            // Consume the stock issued by hand, recording it in the BOM
+            let mut bom = Bom::new();
+            for (item, qty) in issued {
+                Self::consume_item(who, &item, qty, "Manual Issue")?;
+
+                let mut bom_item = item;
+                bom_item.qty = qty;
+                bom.materials
+                    .try_push(bom_item)
+                    .map_err(|_| Error::<T>::BomConstructIssue)?;
+            }
 
-                    // Adjust the quantity of the item according to consumption
-                    if item.qty >= recipe_quantity {
-                        // Remove the consumed quantity from the staging area
-                        item.qty -= recipe_quantity;
-                        required_quantity -= recipe_quantity;
-                        // Add the consumed quantity to the BOM
-                        bom_item.qty = recipe_quantity;
+            // Stock the routing operations consumed is already drawn down, recorded as it was
+            // consumed
+            for operation in recipe.routing.iter() {
+                if let Some(record) = WorkOrderOperations::<T>::get(
+                    work_order.work_order_number,
+                    operation.operation_number,
+                ) {
+                    for consumed in record.consumed {
                         bom.materials
-                            .try_push(bom_item)
+                            .try_push(consumed)
                             .map_err(|_| Error::<T>::BomConstructIssue)?;
-                    // There is not enough quantity in the staging area to fulfill the recipe requirement
-                    } else if item.qty < recipe_quantity {
-                        // There is enough quantity in the staging area to fulfill the leftover requirement
-                        if item.qty > required_quantity {
-                            // Remove the consumed quantity from the staging area
-                            item.qty -= required_quantity;
-                            // Add the consumed quantity to the BOM
-                            bom_item.qty = required_quantity;
-                            bom.materials
-                                .try_push(bom_item)
-                                .map_err(|_| Error::<T>::BomConstructIssue)?;
-                            // Set the required quantity to 0, it has been consumed
-                            required_quantity = 0;
-                        // There is not enough quantity in the staging area to fulfill the leftover requirement
-                        } else {
-                            // Add the consumed quantity to the BOM
-                            bom.materials
-                                .try_push(bom_item)
-                                .map_err(|_| Error::<T>::BomConstructIssue)?;
-                            // Update the required quantity
-                            required_quantity -= item.qty;
-                            // Set the item quantity to 0, it has been consumed
-                            item.qty = 0;
-                        }
                     }
+                }
+            }
 
-                    log::info!("Staging Item Consumed: {:?}", item.clone());
+            // Backflush the unrouted components that are not kitted
+            for (index, component) in recipe.required_components.iter().enumerate() {
+                if recipe.is_routed(index) || component.issue_mode != IssueMode::Backflush {
+                    continue;
+                }
 
-                    // Update the item's quantity in the staging area
-                    pallet_inventory::Pallet::<T>::do_inventory_adjust(
-                        who,
-                        item.moved_by.clone(),
-                        item.clone(),
-                        AdjustDetails::Quantity {
-                            original_qty,
-                            new_qty: item.qty,
-                            reason: BoundedVec::try_from("Assemble Product".as_bytes().to_vec())
-                                .map_err(|_| Error::<T>::DescriptionTooLong)?,
-                        },
-                    )?;
+                let consumption = Self::backflush(
+                    who,
+                    work_order.work_order_number,
+                    None,
+                    index as u8,
+                    &component.sku,
+                    work_order.required_qty(&recipe, component),
+                )?;
+                for (item, qty) in consumption {
+                    let mut bom_item = item;
+                    bom_item.qty = qty;
+                    bom.materials
+                        .try_push(bom_item)
+                        .map_err(|_| Error::<T>::BomConstructIssue)?;
+                }
+            }
 
-                    // Move the item back to the warehouse
-                    pallet_inventory::Pallet::<T>::do_inventory_move(
-                        who,
-                        item.clone(),
-                        item.moved_by.clone(),
-                        AdjustDetails::Location {
-                            original_location: item.location.clone(),
-                            new_location: Location::Warehouse,
-                            reason: BoundedVec::try_from(
-                                "Assembled Product complete, move to warehouse"
-                                    .as_bytes()
-                                    .to_vec(),
-                            )
-                            .map_err(|_| Error::<T>::DescriptionTooLong)?,
-                        },
-                    )?;
+            // Consume the allocated stock, recording each consumption in the BOM
+            for (item, qty) in consumption {
+                Self::consume_item(who, &item, qty, "Assemble Product")?;
+
+                let mut bom_item = item;
+                bom_item.qty = qty;
+                bom.materials
+                    .try_push(bom_item)
+                    .map_err(|_| Error::<T>::BomConstructIssue)?;
+            }
+
+            // Create and store one finished good per unit produced, with its share of the BOM
+            let unit_boms = Self::unit_boms(&bom, produced_qty)?;
+            for (serial_number, unit_bom) in serial_numbers.into_iter().zip(unit_boms) {
+                let assembled_product = Self::build_output_item(
+                    &work_order,
+                    &recipe.sku,
+                    serial_number,
+                    1,
+                    InventoryType::FinishedGood,
+                );
+
+                // Insert the assembled product into storage
+                AssembledProducts::<T>::insert(
+                    (who, recipe.sku.clone(), serial_number),
+                    (assembled_product.clone(), unit_bom.clone()),
+                );
+
+                // Link every consumed component to the new serial
+                Self::record_genealogy(who, &assembled_product, &unit_bom);
+
+                // Insert the newly created item into inventory
+                pallet_inventory::Pallet::<T>::do_inventory_insertion(who, assembled_product)?;
+            }
+
+            // Stock the co-products and by-products under their own SKU's next free serial,
+            // traced to the same components
+            for (output, qty) in secondary_outputs {
+                let serial_number = pallet_inventory::Pallet::<T>::free_serial_number(
+                    who,
+                    &output.sku,
+                    &work_order.output_location,
+                );
+                let secondary_output = Self::build_output_item(
+                    &work_order,
+                    &output.sku,
+                    serial_number,
+                    qty,
+                    output.kind.inventory_type(),
+                );
+
+                Self::record_genealogy(who, &secondary_output, &bom);
+
+                pallet_inventory::Pallet::<T>::do_inventory_insertion(who, secondary_output)?;
+            }
+
+            // Record the actual yield against what the recipe expected
+            let yield_record = YieldRecord::new(&work_order, &recipe, produced_qty);
+            AssemblyYields::<T>::insert(work_order.work_order_number, yield_record.clone());
+
+            // Return what is left of the kit and close the work order's staging area
+            Self::return_staged_items(who, &kit)?;
+            StagingArea::<T>::remove(work_order.work_order_number);
+
+            // The work order is done with its equipment
+            Self::release_work_order_equipment(work_order.work_order_number)?;
+
+            Self::set_work_order_status(&work_order, WorkOrderStatus::Completed);
+
+            Ok(yield_record)
+        })
+    }
+
+    /// Serial numbers for `qty` finished goods, numbered consecutively from `first`
+    fn output_serial_numbers(
+        first: SerialNumber,
+        qty: Qty,
+    ) -> Result<Vec<SerialNumber>, DispatchError> {
+        if qty == 0 {
+            return Ok(Vec::new());
+        }
+
+        let last = first
+            .checked_add(qty - 1)
+            .ok_or(Error::<T>::SerialNumberInUse)?;
+
+        Ok((first..=last).collect())
+    }
+
+    /// Split the BOM consumed by a work order into the share each of `units` finished goods holds
+    ///
+    /// Every material's quantity is spread as evenly as it goes, the first units taking one more
+    /// where it does not divide exactly, so the shares add up to what was consumed. Each unit
+    /// still lists every material, even where its share rounds down to nothing, so every lot the
+    /// work order consumed stays traceable from each unit.
+    fn unit_boms(bom: &Bom, units: Qty) -> Result<Vec<Bom>, DispatchError> {
+        if units == 0 {
+            return Ok(Vec::new());
+        }
+
+        // The same serial may have been drawn down more than once, count it once per unit
+        let mut materials: Vec<Item> = Vec::new();
+        for material in bom.materials.iter() {
+            match materials.iter_mut().find(|merged| {
+                merged.sku == material.sku && merged.serial_number == material.serial_number
+            }) {
+                Some(merged) => merged.qty = merged.qty.saturating_add(material.qty),
+                None => materials.push(material.clone()),
+            }
+        }
+
+        let mut unit_boms: Vec<Bom> = (0..units).map(|_| Bom::new()).collect();
+        for material in materials {
+            let (share, extra) = (material.qty / units, material.qty % units);
+            for (unit, unit_bom) in unit_boms.iter_mut().enumerate() {
+                let mut unit_material = material.clone();
+                unit_material.qty = share + Qty::from((unit as Qty) < extra);
+                unit_bom
+                    .materials
+                    .try_push(unit_material)
+                    .map_err(|_| Error::<T>::BomConstructIssue)?;
+            }
+        }
+
+        Ok(unit_boms)
+    }
+
+    /// Build an output item produced by a work order.
+    ///
+    /// Master data comes from the output SKU's material, the production lot is the work order
+    /// number and the item is put away at the work order's output location.
+    fn build_output_item(
+        work_order: &WorkOrder,
+        sku: &Sku,
+        serial_number: SerialNumber,
+        qty: Qty,
+        inventory_type: InventoryType,
+    ) -> Item {
+        let now = frame_system::Pallet::<T>::block_number().saturated_into::<u32>();
+
+        // Fall back to bare master data if the output SKU has no material record
+        let material = Materials::<T>::get(sku).unwrap_or(Material {
+            sku: sku.clone(),
+            ..Default::default()
+        });
+
+        Item {
+            moved_by: work_order.assigned_to.clone(),
+            sku: sku.clone(),
+            lot_number: work_order.work_order_number,
+            serial_number,
+            abc_code: material.abc_code.clone(),
+            inventory_type,
+            product_type: material.product_type.clone(),
+            qty,
+            weight: material.weight,
+            shelf_life: material.shelf_life,
+            cycle_count: 0,
+            created_at: now,
+            production_date: now,
+            location: work_order.output_location.clone(),
+            material,
+        }
+    }
+
+    /// Allocate a work order's kitted stock to every component of a recipe, in the order each
+    /// material is picked in.
+    ///
+    /// Returns each staged item with the quantity to consume from it, without touching storage.
+    /// Fails with `ComponentShortage` naming the first component that staging cannot cover.
+    fn allocate_components(
+        who: &T::AccountId,
+        work_order: &WorkOrder,
+        recipe: &Recipe,
+        kit: &StagingKit,
+    ) -> Result<Vec<(Item, Qty)>, DispatchError> {
+        let (consumption, shortages) = Self::claim_kit(who, work_order, recipe, kit);
+
+        if let Some(shortage) = shortages.first() {
+            log::warn!(
+                "Staging is short {} of component {:?} for recipe {:?}",
+                shortage.short_qty,
+                shortage.sku,
+                recipe.sku
+            );
+            return Err(Error::<T>::ComponentShortage {
+                component_index: shortage.component_index,
+            }
+            .into());
+        }
+
+        Ok(consumption)
+    }
+
+    /// Claim a work order's kitted stock for every pre-kitted component of a recipe, in the order
+    /// each material is picked in
+    ///
+    /// Returns each staged item with the quantity to consume from it, and every component the
+    /// kit falls short of.
+    fn claim_kit(
+        who: &T::AccountId,
+        work_order: &WorkOrder,
+        recipe: &Recipe,
+        kit: &StagingKit,
+    ) -> (Vec<(Item, Qty)>, Vec<ShortComponent>) {
+        // Each kitted item can give at most what was kitted and what it still holds
+        let staged: Vec<(Item, Qty)> = kit
+            .iter()
+            .filter_map(|staged| {
+                Inventory::<T>::get((who, staged.sku.clone(), staged.serial_number)).map(|item| {
+                    let qty = staged.qty.min(item.qty);
+                    (item, qty)
+                })
+            })
+            .filter(|(item, qty)| item.location == Location::Staging && *qty > 0)
+            // Held stock cannot be consumed until the hold is released
+            .filter(|(item, _)| {
+                !pallet_inventory::Pallet::<T>::is_on_hold(who, &item.sku, item.serial_number)
+            })
+            .collect();
+
+        // Quantity still available on each staged item as components claim it
+        let (staged, mut available): (Vec<Item>, Vec<Qty>) = staged.into_iter().unzip();
+        let mut consumption: Vec<(Item, Qty)> = Vec::new();
+        let mut shortages: Vec<ShortComponent> = Vec::new();
+
+        for (index, component) in recipe.required_components.iter().enumerate() {
+            // Routed components are consumed by their operation, and only kitted ones by assembly
+            if recipe.is_routed(index) || component.issue_mode != IssueMode::PreKitted {
+                continue;
+            }
+
+            let mut remaining = work_order.required_qty(recipe, component);
+
+            let candidates = staged
+                .iter()
+                .zip(available.iter())
+                .filter(|(item, left)| item.sku == component.sku && **left > 0)
+                .map(|(item, left)| (item.clone(), *left))
+                .collect();
+            for (item, take) in pallet_inventory::Pallet::<T>::pick_stock(
+                candidates,
+                remaining,
+                &pallet_inventory::Pallet::<T>::pick_strategy(&component.sku),
+                &Location::Staging,
+            ) {
+                if let Some(left) = staged
+                    .iter()
+                    .position(|staged| {
+                        staged.serial_number == item.serial_number && staged.sku == item.sku
+                    })
+                    .and_then(|position| available.get_mut(position))
+                {
+                    *left -= take;
+                }
+                remaining -= take;
+
+                // Recipes may list the same SKU more than once, merge claims on the same item
+                match consumption.iter_mut().find(|(claimed, _)| {
+                    claimed.sku == item.sku && claimed.serial_number == item.serial_number
+                }) {
+                    Some((_, qty)) => *qty += take,
+                    None => consumption.push((item.clone(), take)),
                 }
             }
+
+            if remaining > 0 {
+                shortages.push(ShortComponent {
+                    component_index: index as u8,
+                    sku: component.sku.clone(),
+                    short_qty: remaining,
+                });
+            }
         }
 
-        // Insert the assembled product into storage
-        AssembledProducts::<T>::insert(
-            (who, work_order.recipe.sku.clone(), serial_number.clone()),
-            (assembled_product.clone(), bom),
-        );
+        (consumption, shortages)
+    }
+
+    /// Every component a work order is short of for assembly, naming its SKU and the quantity
+    /// missing.
+    ///
+    /// Pre-kitted components are checked against the work order's kit, backflushed ones against
+    /// the stock that can be backflushed and manually issued ones against what has been issued.
+    /// Components consumed by routing operations are not checked. Assembly fails with
+    /// `ComponentShortage` on the first of these.
+    pub fn component_shortages(
+        who: &T::AccountId,
+        work_order_number: WorkOrderNumber,
+    ) -> Vec<ShortComponent> {
+        let Some(work_order) = WorkOrders::<T>::get(work_order_number) else {
+            return Vec::new();
+        };
+        let Ok(recipe) = Self::work_order_recipe(&work_order) else {
+            return Vec::new();
+        };
+
+        // Only the account that staged the work order can consume its kit
+        let kit = StagingArea::<T>::get(work_order_number)
+            .filter(|(owner, _)| owner == who)
+            .map(|(_, kit)| kit)
+            .unwrap_or_default();
+        let (_, mut shortages) = Self::claim_kit(who, &work_order, &recipe, &kit);
 
-        // Insert the newly created item into inventory
-        let _ =
-            pallet_inventory::Pallet::<T>::do_inventory_insertion(who, assembled_product.clone());
+        let issued = Self::issued_stock(who, &ManualIssues::<T>::get(work_order_number));
+        for (index, component) in recipe.required_components.iter().enumerate() {
+            let covered = match component.issue_mode {
+                IssueMode::PreKitted => continue,
+                IssueMode::Backflush if recipe.is_routed(index) => continue,
+                IssueMode::Backflush => Self::backflush_candidates(who, &component.sku)
+                    .iter()
+                    .fold(0 as Qty, |total, (_, qty)| total.saturating_add(*qty)),
+                IssueMode::Manual => issued
+                    .iter()
+                    .filter(|(item, _)| item.sku == component.sku)
+                    .fold(0 as Qty, |total, (_, qty)| total.saturating_add(*qty)),
+            };
+
+            let required_qty = work_order.required_qty(&recipe, component);
+            if covered < required_qty {
+                shortages.push(ShortComponent {
+                    component_index: index as u8,
+                    sku: component.sku.clone(),
+                    short_qty: required_qty - covered,
+                });
+            }
+        }
+
+        shortages.sort_by_key(|shortage| shortage.component_index);
+        shortages
+    }
+
+    /// Draw a consumed quantity down from an item
+    fn consume_item(who: &T::AccountId, item: &Item, qty: Qty, reason: &str) -> DispatchResult {
+        pallet_inventory::Pallet::<T>::ensure_not_consigned(who, &item.sku, item.serial_number)?;
+
+        // Update the item's quantity where it is stocked
+        pallet_inventory::Pallet::<T>::do_inventory_adjust(
+            who,
+            item.moved_by.clone(),
+            item.clone(),
+            AdjustDetails::Quantity {
+                original_qty: item.qty,
+                new_qty: item.qty.saturating_sub(qty),
+                reason: BoundedVec::try_from(reason.as_bytes().to_vec())
+                    .map_err(|_| Error::<T>::DescriptionTooLong)?,
+            },
+        )?;
+
+        Ok(())
+    }
+
+    /// Move every kitted item still in staging back to the warehouse
+    fn return_staged_items(who: &T::AccountId, kit: &StagingKit) -> DispatchResult {
+        for staged in kit.iter() {
+            // Skip items that have since left staging
+            let Some(item) = Inventory::<T>::get((who, staged.sku.clone(), staged.serial_number))
+            else {
+                continue;
+            };
+            if item.location != Location::Staging {
+                continue;
+            }
+
+            pallet_inventory::Pallet::<T>::do_inventory_move(
+                who,
+                item.clone(),
+                item.moved_by.clone(),
+                AdjustDetails::Location {
+                    original_location: item.location.clone(),
+                    new_location: Location::Warehouse,
+                    reason: BoundedVec::try_from(
+                        "Return staged material to warehouse".as_bytes().to_vec(),
+                    )
+                    .map_err(|_| Error::<T>::DescriptionTooLong)?,
+                },
+            )?;
+        }
 
         Ok(())
     }
 
     /// Prepare the staging area for a given Work Order.
+    ///
+    /// An item only partly kitted is split first, so that only the kitted quantity moves to
+    /// staging.
     pub fn do_prepare_staging_area(who: &T::AccountId, work_order: WorkOrder) -> DispatchResult {
         // Check if the work order exists
         let work_order = WorkOrders::<T>::get(work_order.work_order_number)
             .ok_or(Error::<T>::WorkOrderNotFound)?;
 
-        // Create a Bill of Materials (BOM) from the assembly details
-        let mut bom = Bom::new();
+        // Only released work orders may be kitted
+        ensure!(
+            work_order.status == WorkOrderStatus::Released,
+            Error::<T>::WorkOrderNotReleased
+        );
 
-        // For each component in the BOM, check if the inventory contains enough of it
-        for component in work_order.recipe.required_components.iter() {
-            let key = (who, component.sku.clone());
-            let mut items: Vec<Item> = <Inventory<T>>::iter_prefix(key).map(|(_, v)| v).collect();
+        // Load the recipe the work order references
+        let recipe = Self::work_order_recipe(&work_order)?;
 
-            // Ensure we have enough items in GlobalInventory for this component
-            let available_qty = <GlobalInventory<T>>::get(&component.sku).unwrap_or_default();
+        // Reserve the recipe's equipment before any material is kitted
+        Self::lock_work_order_equipment(&work_order, &recipe)?;
+
+        // The kit records exactly what is staged for this work order
+        let mut kit = StagingKit::default();
+
+        // For each pre-kitted component in the BOM, check if the inventory contains enough of it
+        for component in recipe
+            .required_components
+            .iter()
+            .filter(|component| component.issue_mode == IssueMode::PreKitted)
+        {
+            // Stage enough for every batch, covering the component's scrap factor and the yield
+            let required_qty = work_order.required_qty(&recipe, component);
+
+            // Choose unstaged stock in the order the material is picked in
+            let picked = pallet_inventory::Pallet::<T>::pick_stock(
+                Self::kitting_candidates(who, &component.sku),
+                required_qty,
+                &pallet_inventory::Pallet::<T>::pick_strategy(&component.sku),
+                &Location::Staging,
+            );
+
+            // Ensure the account holds enough unstaged stock for this component
+            let picked_qty = picked
+                .iter()
+                .fold(0 as Qty, |total, (_, qty)| total.saturating_add(*qty));
 
             ensure!(
-                available_qty >= component.qty,
+                picked_qty >= required_qty,
                 Error::<T>::InsufficientInventory
             );
 
-            // Sort the items by creation date (FIFO)
-            items.sort_by_key(|item| item.created_at);
+            // Take the chosen items from inventory, place them into the staging area
+            for (item, kitted_qty) in picked {
+                let item = Self::stage_item(who, item, kitted_qty, "Prepare Staging Area")?;
 
-            // Take the required items from inventory, place them into the staging area
-            let mut qty = component.qty;
-            for item in items.iter_mut() {
-                // Skip items with 0 quantity
-                if item.qty == 0 {
-                    continue;
-                }
+                // Record the kitted quantity against the work order
+                kit.try_push(StagedItem {
+                    sku: item.sku.clone(),
+                    serial_number: item.serial_number,
+                    qty: kitted_qty,
+                })
+                .map_err(|_| Error::<T>::TooManyStagedItems)?;
+            }
+        }
 
-                // Required quantity has been met
-                if qty <= 0 {
-                    break;
-                }
+        Self::do_stage_kit(who, &work_order, kit);
 
-                // Keep track of the required quantity
-                if item.qty > qty {
-                    qty = 0;
-                } else {
-                    qty -= item.qty;
-                }
+        Ok(())
+    }
 
-                // Move to staging
-                let _ = pallet_inventory::Pallet::<T>::do_inventory_move(
-                    who,
-                    item.clone(),
-                    item.moved_by.clone(),
-                    AdjustDetails::Location {
-                        original_location: item.location.clone(),
-                        new_location: Location::Staging,
-                        reason: BoundedVec::try_from("Prepare Staging Area".as_bytes().to_vec())
-                            .map_err(|_| Error::<T>::DescriptionTooLong)?,
-                    },
-                );
+    /// Move a quantity of an item to staging, returning the staged item
+    ///
+    /// An item only partly staged is split first, so that only the staged quantity moves and the
+    /// rest stays where it is.
+    fn stage_item(
+        who: &T::AccountId,
+        item: Item,
+        qty: Qty,
+        reason: &str,
+    ) -> Result<Item, DispatchError> {
+        let item = if qty < item.qty {
+            pallet_inventory::Pallet::<T>::do_split_item(who, &item, qty)?
+        } else {
+            item
+        };
 
-                // Add the item to the BOM
-                let _ = bom
-                    .materials
-                    .try_push(item.clone())
-                    .map_err(|_| Error::<T>::BomConstructIssue)?;
-            }
-        }
+        pallet_inventory::Pallet::<T>::do_inventory_move(
+            who,
+            item.clone(),
+            item.moved_by.clone(),
+            AdjustDetails::Location {
+                original_location: item.location.clone(),
+                new_location: Location::Staging,
+                reason: BoundedVec::try_from(reason.as_bytes().to_vec())
+                    .map_err(|_| Error::<T>::DescriptionTooLong)?,
+            },
+        )?;
+
+        let mut staged = item;
+        staged.location = Location::Staging;
+        Ok(staged)
+    }
+
+    /// Record the kit staged for a work order and mark the work order staged
+    pub fn do_stage_kit(who: &T::AccountId, work_order: &WorkOrder, kit: StagingKit) {
+        StagingArea::<T>::insert(work_order.work_order_number, (who.clone(), kit));
+
+        Self::set_work_order_status(work_order, WorkOrderStatus::Staged);
 
         // Emit staging has been prepped
         Self::deposit_event(Event::PrepStaging {
             assembler: who.clone(),
             work_order: work_order.clone(),
         });
+    }
 
-        Ok(())
+    /// Stock of a SKU that can be kitted, with the quantity available from each item
+    ///
+    /// Stock already kitted for another work order, held for review or scrapped, in transit to
+    /// another account, on quality hold or held on consignment, cannot be staged, nor can stock
+    /// allocated to orders.
+    pub fn kitting_candidates(who: &T::AccountId, sku: &Sku) -> Vec<(Item, Qty)> {
+        <Inventory<T>>::iter_prefix((who, sku.clone()))
+            .map(|(_, item)| item)
+            .filter(|item| {
+                item.location != Location::Staging
+                    && item.location.is_usable()
+                    && item.inventory_type != InventoryType::Transit
+                    && item.qty > 0
+            })
+            // Consigned stock is not the account's to consume until it is settled
+            .filter(|item| {
+                !pallet_inventory::Pallet::<T>::is_on_hold(who, &item.sku, item.serial_number)
+                    && pallet_inventory::Pallet::<T>::consignment_owner(
+                        who,
+                        &item.sku,
+                        item.serial_number,
+                    )
+                    .is_none()
+            })
+            .filter_map(|item| {
+                let qty = Self::unallocated_qty(who, &item);
+                (qty > 0).then_some((item, qty))
+            })
+            .collect()
     }
 
     /// Create a new work order, if it doesn't already exist.
-    pub fn do_create_work_order(work_order: WorkOrder) -> DispatchResult {
+    ///
+    /// The work order must reference a stored recipe and plan at least one unit, which it builds
+    /// in as many recipe batches as it takes. New work orders always start out as `Planned`.
+    pub fn do_create_work_order(mut work_order: WorkOrder) -> Result<WorkOrder, DispatchError> {
+        // Reject work orders for recipes that were never stored
+        Self::work_order_recipe(&work_order)?;
+
+        ensure!(
+            work_order.planned_quantity > 0,
+            Error::<T>::InvalidPlannedQuantity
+        );
+
         // Check if the work order already exists
         let work_order_check = WorkOrders::<T>::get(work_order.work_order_number);
 
@@ -230,9 +724,1250 @@ impl<T: Config> Pallet<T> {
             return Err(Error::<T>::WorkOrderAlreadyExists.into());
         }
 
+        work_order.status = WorkOrderStatus::Planned;
+
         // Insert the work order into storage
-        <WorkOrders<T>>::insert(work_order.work_order_number, work_order);
+        <WorkOrders<T>>::insert(work_order.work_order_number, work_order.clone());
+
+        Ok(work_order)
+    }
+
+    /// Move a work order to a new status, if the transition is allowed.
+    ///
+    /// Cancelling a work order releases any equipment it holds.
+    pub fn do_update_work_order_status(
+        work_order_number: WorkOrderNumber,
+        status: WorkOrderStatus,
+    ) -> DispatchResult {
+        let work_order =
+            WorkOrders::<T>::get(work_order_number).ok_or(Error::<T>::WorkOrderNotFound)?;
+
+        ensure!(
+            work_order.status.can_transition_to(&status),
+            Error::<T>::InvalidStatusTransition
+        );
+
+        if status == WorkOrderStatus::Cancelled {
+            Self::release_work_order_equipment(work_order_number)?;
+            Self::release_operation_equipment(work_order_number)?;
+        }
+
+        Self::set_work_order_status(&work_order, status);
+
+        Ok(())
+    }
+
+    /// Store a work order under its new status and emit the change
+    fn set_work_order_status(work_order: &WorkOrder, status: WorkOrderStatus) {
+        let mut updated = work_order.clone();
+        updated.status = status.clone();
+        <WorkOrders<T>>::insert(updated.work_order_number, updated);
+
+        Self::deposit_event(Event::WorkOrderStatusChanged {
+            work_order_number: work_order.work_order_number,
+            original_status: work_order.status.clone(),
+            new_status: status,
+        });
+    }
+
+    /// Lock an asset of the recipe's required equipment for a work order.
+    ///
+    /// Returns the asset already held by the work order, if any, otherwise locks the first
+    /// available asset and fails if none is available.
+    pub fn lock_work_order_equipment(
+        work_order: &WorkOrder,
+        recipe: &Recipe,
+    ) -> Result<AssetId, DispatchError> {
+        if let Some(asset_id) = WorkOrderEquipment::<T>::get(work_order.work_order_number) {
+            return Ok(asset_id);
+        }
+
+        let asset_id =
+            pallet_inventory::Pallet::<T>::do_lock_equipment(&recipe.required_equipment)?;
+        WorkOrderEquipment::<T>::insert(work_order.work_order_number, asset_id);
+
+        Self::deposit_event(Event::EquipmentLocked {
+            work_order_number: work_order.work_order_number,
+            asset_id,
+        });
+
+        Ok(asset_id)
+    }
+
+    /// Return the unused material kitted for a work order to the warehouse.
+    ///
+    /// A staged or in-progress work order goes back to `Released` and releases its equipment so
+    /// it can be kitted again. A cancelled work order keeps its status.
+    pub fn do_unstage(
+        who: &T::AccountId,
+        work_order_number: WorkOrderNumber,
+    ) -> Result<StagingKit, DispatchError> {
+        let work_order =
+            WorkOrders::<T>::get(work_order_number).ok_or(Error::<T>::WorkOrderNotFound)?;
+
+        ensure!(
+            matches!(
+                work_order.status,
+                WorkOrderStatus::Staged | WorkOrderStatus::InProgress | WorkOrderStatus::Cancelled
+            ),
+            Error::<T>::CannotUnstage
+        );
+
+        let (owner, kit) =
+            StagingArea::<T>::get(work_order_number).ok_or(Error::<T>::StagingAreaNotFound)?;
+        ensure!(owner == *who, Error::<T>::NotStagingOwner);
+
+        // Stock issued by hand goes back along with the kit
+        Self::return_staged_items(who, &kit)?;
+        Self::return_staged_items(who, &ManualIssues::<T>::take(work_order_number))?;
+        StagingArea::<T>::remove(work_order_number);
+
+        if work_order.status != WorkOrderStatus::Cancelled {
+            Self::release_work_order_equipment(work_order_number)?;
+            Self::release_operation_equipment(work_order_number)?;
+            Self::set_work_order_status(&work_order, WorkOrderStatus::Released);
+        }
+
+        Ok(kit)
+    }
+
+    /// Start a routing operation of a staged work order
+    ///
+    /// Operations start in routing order, each once the previous one has passed on good units.
+    /// Starting the first operation puts a staged work order in progress.
+    pub fn do_start_operation(
+        who: &T::AccountId,
+        work_order_number: WorkOrderNumber,
+        operation_number: OperationNumber,
+        operator: Employee,
+    ) -> Result<Option<AssetId>, DispatchError> {
+        let work_order =
+            WorkOrders::<T>::get(work_order_number).ok_or(Error::<T>::WorkOrderNotFound)?;
+        ensure!(
+            work_order.status.can_assemble(),
+            Error::<T>::WorkOrderNotStaged
+        );
+
+        // Only the account that kitted the work order works on it
+        let (owner, _) =
+            StagingArea::<T>::get(work_order_number).ok_or(Error::<T>::StagingAreaNotFound)?;
+        ensure!(owner == *who, Error::<T>::NotStagingOwner);
+
+        let recipe = Self::work_order_recipe(&work_order)?;
+        ensure!(!recipe.routing.is_empty(), Error::<T>::NoRouting);
+        let position = recipe
+            .routing
+            .iter()
+            .position(|operation| operation.operation_number == operation_number)
+            .ok_or(Error::<T>::OperationNotFound)?;
+
+        ensure!(
+            !WorkOrderOperations::<T>::contains_key(work_order_number, operation_number),
+            Error::<T>::OperationAlreadyStarted
+        );
+        if let Some(previous) = position.checked_sub(1) {
+            let previous = WorkOrderOperations::<T>::get(
+                work_order_number,
+                recipe.routing[previous].operation_number,
+            );
+            ensure!(
+                previous.is_some_and(|record| record.good_qty > 0),
+                Error::<T>::OperationOutOfSequence
+            );
+        }
+
+        let asset_id = match &recipe.routing[position].equipment {
+            Some(equipment) => Some(pallet_inventory::Pallet::<T>::do_lock_equipment(equipment)?),
+            None => None,
+        };
+
+        WorkOrderOperations::<T>::insert(
+            work_order_number,
+            operation_number,
+            OperationRecord {
+                status: OperationStatus::InProgress,
+                operator,
+                asset_id,
+                started_at: frame_system::Pallet::<T>::block_number().saturated_into::<u32>(),
+                completed_at: None,
+                good_qty: 0,
+                scrapped_qty: 0,
+                labor_minutes: 0,
+                standard_minutes: 0,
+                consumed: ConsumedItems::default(),
+            },
+        );
+
+        if work_order.status == WorkOrderStatus::Staged {
+            Self::set_work_order_status(&work_order, WorkOrderStatus::InProgress);
+        }
+
+        Ok(asset_id)
+    }
+
+    /// Report good units, scrap and labor against a routing operation
+    ///
+    /// Each of the operation's components is consumed in proportion to the units reported so
+    /// far against the units the recipe starts, so partial reports add up to the full
+    /// requirement. Pre-kitted components come from staging, backflushed ones straight from
+    /// stock, and manually issued ones are left in staging for assembly. An operation
+    /// cannot report more units than the previous operation passed on, nor close before the
+    /// previous operation has closed, and only a staged or in-progress work order can report at
+    /// all. The operation's standard labor for the units reported is kept alongside the labor
+    /// actually reported.
+    pub fn do_complete_operation(
+        who: &T::AccountId,
+        work_order_number: WorkOrderNumber,
+        operation_number: OperationNumber,
+        good_qty: Qty,
+        scrapped_qty: Qty,
+        labor_minutes: u32,
+        close: bool,
+    ) -> DispatchResult {
+        with_storage_layer(|| {
+            let work_order =
+                WorkOrders::<T>::get(work_order_number).ok_or(Error::<T>::WorkOrderNotFound)?;
+            ensure!(
+                work_order.status.can_assemble(),
+                Error::<T>::WorkOrderNotStaged
+            );
+            let (owner, kit) =
+                StagingArea::<T>::get(work_order_number).ok_or(Error::<T>::StagingAreaNotFound)?;
+            ensure!(owner == *who, Error::<T>::NotStagingOwner);
+
+            let recipe = Self::work_order_recipe(&work_order)?;
+            let position = recipe
+                .routing
+                .iter()
+                .position(|operation| operation.operation_number == operation_number)
+                .ok_or(Error::<T>::OperationNotFound)?;
+            let operation = &recipe.routing[position];
+
+            let mut record = WorkOrderOperations::<T>::get(work_order_number, operation_number)
+                .ok_or(Error::<T>::OperationNotStarted)?;
+            ensure!(
+                record.status == OperationStatus::InProgress,
+                Error::<T>::OperationAlreadyCompleted
+            );
+
+            let previously_reported = record.good_qty.saturating_add(record.scrapped_qty);
+            let reported = previously_reported
+                .saturating_add(good_qty)
+                .saturating_add(scrapped_qty);
+
+            // Units only flow on from what the previous operation passed on
+            if let Some(previous) = position.checked_sub(1) {
+                let passed_on = WorkOrderOperations::<T>::get(
+                    work_order_number,
+                    recipe.routing[previous].operation_number,
+                )
+                .map(|record| record.good_qty)
+                .unwrap_or_default();
+                ensure!(reported <= passed_on, Error::<T>::ExceedsPreviousOperation);
+            }
+
+            // Consume the operation's components for the units newly reported
+            let started = work_order.planned_input_qty(&recipe).max(1) as u64;
+            for index in operation.components.iter() {
+                let Some(component) = recipe.required_components.get(*index as usize) else {
+                    continue;
+                };
+                let required = work_order.required_qty(&recipe, component) as u64;
+                let consumed_by = |units: Qty| -> Qty {
+                    (required * units as u64).div_ceil(started).min(required) as Qty
+                };
+
+                let qty = consumed_by(reported) - consumed_by(previously_reported);
+                let consumption = match component.issue_mode {
+                    IssueMode::PreKitted => {
+                        Self::consume_from_kit(who, &kit, *index, &component.sku, qty)?
+                    }
+                    IssueMode::Backflush => Self::backflush(
+                        who,
+                        work_order_number,
+                        Some(operation_number),
+                        *index,
+                        &component.sku,
+                        qty,
+                    )?,
+                    // Manually issued stock is held in staging and consumed at assembly
+                    IssueMode::Manual => Vec::new(),
+                };
+                for (item, qty) in consumption.iter() {
+                    Self::merge_consumed_item(&mut record.consumed, item, *qty)?;
+                }
+            }
+
+            record.good_qty = record.good_qty.saturating_add(good_qty);
+            record.scrapped_qty = record.scrapped_qty.saturating_add(scrapped_qty);
+            record.labor_minutes = record.labor_minutes.saturating_add(labor_minutes);
+            record.standard_minutes = operation.standard_time.saturating_mul(reported);
+
+            if close {
+                // Operations close in routing order
+                if let Some(previous) = position.checked_sub(1) {
+                    ensure!(
+                        WorkOrderOperations::<T>::get(
+                            work_order_number,
+                            recipe.routing[previous].operation_number,
+                        )
+                        .is_some_and(|record| record.status == OperationStatus::Completed),
+                        Error::<T>::OperationOutOfSequence
+                    );
+                }
+
+                if let Some(asset_id) = record.asset_id {
+                    pallet_inventory::Pallet::<T>::do_release_equipment(asset_id)?;
+                }
+                record.status = OperationStatus::Completed;
+                record.completed_at =
+                    Some(frame_system::Pallet::<T>::block_number().saturated_into::<u32>());
+            }
+
+            WorkOrderOperations::<T>::insert(work_order_number, operation_number, record);
+
+            Ok(())
+        })
+    }
+
+    /// Consume a quantity of a component from a work order's kit, in the order the material is
+    /// picked in
+    fn consume_from_kit(
+        who: &T::AccountId,
+        kit: &StagingKit,
+        component_index: u8,
+        sku: &Sku,
+        qty: Qty,
+    ) -> Result<Vec<(Item, Qty)>, DispatchError> {
+        let staged: Vec<(Item, Qty)> = kit
+            .iter()
+            .filter(|staged| staged.sku == *sku)
+            .filter_map(|staged| {
+                Inventory::<T>::get((who, staged.sku.clone(), staged.serial_number))
+            })
+            .filter(|item| item.location == Location::Staging && item.qty > 0)
+            .filter(|item| {
+                !pallet_inventory::Pallet::<T>::is_on_hold(who, &item.sku, item.serial_number)
+            })
+            .map(|item| {
+                let qty = item.qty;
+                (item, qty)
+            })
+            .collect();
+
+        let mut remaining = qty;
+        let mut consumption = Vec::new();
+        for (item, take) in pallet_inventory::Pallet::<T>::pick_stock(
+            staged,
+            qty,
+            &pallet_inventory::Pallet::<T>::pick_strategy(sku),
+            &Location::Staging,
+        ) {
+            Self::consume_item(who, &item, take, "Operation Completion")?;
+            remaining -= take;
+            consumption.push((item, take));
+        }
+
+        ensure!(
+            remaining == 0,
+            Error::<T>::ComponentShortage { component_index }
+        );
+
+        Ok(consumption)
+    }
+
+    /// Draw a component straight from the account's unstaged stock, in the order the material is
+    /// picked in
+    ///
+    /// Every item drawn from records the consumption in its backflush history.
+    fn backflush(
+        who: &T::AccountId,
+        work_order_number: WorkOrderNumber,
+        operation_number: Option<OperationNumber>,
+        component_index: u8,
+        sku: &Sku,
+        qty: Qty,
+    ) -> Result<Vec<(Item, Qty)>, DispatchError> {
+        let now = frame_system::Pallet::<T>::block_number().saturated_into::<u32>();
+        let mut remaining = qty;
+        let mut consumption = Vec::new();
+        for (item, take) in pallet_inventory::Pallet::<T>::pick_stock(
+            Self::backflush_candidates(who, sku),
+            qty,
+            &pallet_inventory::Pallet::<T>::pick_strategy(sku),
+            &Location::Staging,
+        ) {
+            Self::consume_item(who, &item, take, "Backflush")?;
+            remaining -= take;
+
+            BackflushHistory::<T>::mutate(
+                (who, item.sku.clone(), item.serial_number, work_order_number),
+                |record| {
+                    let record = record.get_or_insert(BackflushRecord {
+                        qty: 0,
+                        operation_number,
+                        consumed_at: now,
+                    });
+                    record.qty = record.qty.saturating_add(take);
+                    record.operation_number = operation_number;
+                    record.consumed_at = now;
+                },
+            );
+
+            consumption.push((item, take));
+        }
+
+        ensure!(
+            remaining == 0,
+            Error::<T>::ComponentShortage { component_index }
+        );
+
+        Ok(consumption)
+    }
+
+    /// Stock of a SKU that can be backflushed
+    ///
+    /// Stock kitted for a work order, held for review or scrapped, in transit to another account,
+    /// on quality hold or held on consignment, cannot be drawn. Only the quantity not allocated to orders is offered.
+    fn backflush_candidates(who: &T::AccountId, sku: &Sku) -> Vec<(Item, Qty)> {
+        Inventory::<T>::iter_prefix((who, sku.clone()))
+            .map(|(_, item)| item)
+            .filter(|item| {
+                item.location != Location::Staging
+                    && item.location.is_usable()
+                    && item.inventory_type != InventoryType::Transit
+                    && item.qty > 0
+            })
+            // Consigned stock is not the account's to consume until it is settled
+            .filter(|item| {
+                !pallet_inventory::Pallet::<T>::is_on_hold(who, &item.sku, item.serial_number)
+                    && pallet_inventory::Pallet::<T>::consignment_owner(
+                        who,
+                        &item.sku,
+                        item.serial_number,
+                    )
+                    .is_none()
+            })
+            .filter_map(|item| {
+                let qty = Self::unallocated_qty(who, &item);
+                (qty > 0).then_some((item, qty))
+            })
+            .collect()
+    }
+
+    /// The quantity of an item not allocated to orders
+    fn unallocated_qty(who: &T::AccountId, item: &Item) -> Qty {
+        item.qty.saturating_sub(T::StockAllocations::allocated_qty(
+            who,
+            &item.sku,
+            item.serial_number,
+        ))
+    }
+
+    /// Add consumed stock to a list of staged items, merging repeat serials
+    fn merge_consumption(list: &mut StagingKit, item: &Item, qty: Qty) -> DispatchResult {
+        match list
+            .iter_mut()
+            .find(|listed| listed.sku == item.sku && listed.serial_number == item.serial_number)
+        {
+            Some(listed) => listed.qty = listed.qty.saturating_add(qty),
+            None => list
+                .try_push(StagedItem {
+                    sku: item.sku.clone(),
+                    serial_number: item.serial_number,
+                    qty,
+                })
+                .map_err(|_| Error::<T>::TooManyStagedItems)?,
+        }
+
+        Ok(())
+    }
+
+    /// Add a snapshot of consumed stock to a list of consumed items, merging repeat serials
+    fn merge_consumed_item(list: &mut ConsumedItems, item: &Item, qty: Qty) -> DispatchResult {
+        match list
+            .iter_mut()
+            .find(|listed| listed.sku == item.sku && listed.serial_number == item.serial_number)
+        {
+            Some(listed) => listed.qty = listed.qty.saturating_add(qty),
+            None => {
+                let mut consumed = item.clone();
+                consumed.qty = qty;
+                list.try_push(consumed)
+                    .map_err(|_| Error::<T>::BomConstructIssue)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Issue stock to a work order by hand, for its manually issued components
+    ///
+    /// The issued quantity moves to staging, split off the item if only part of it is issued,
+    /// and is held there until the work order is assembled or unstaged. Stock that is staged,
+    /// held for review or scrapped, in transit or on quality hold cannot be issued, nor can the
+    /// quantity of an item allocated to orders, nor more than the work order still needs.
+    pub fn do_issue_component(
+        who: &T::AccountId,
+        work_order_number: WorkOrderNumber,
+        sku: Sku,
+        serial_number: SerialNumber,
+        qty: Qty,
+    ) -> Result<StagedItem, DispatchError> {
+        with_storage_layer(|| {
+            let work_order =
+                WorkOrders::<T>::get(work_order_number).ok_or(Error::<T>::WorkOrderNotFound)?;
+            ensure!(
+                work_order.status.can_assemble(),
+                Error::<T>::WorkOrderNotStaged
+            );
+
+            let (owner, _) =
+                StagingArea::<T>::get(work_order_number).ok_or(Error::<T>::StagingAreaNotFound)?;
+            ensure!(owner == *who, Error::<T>::NotStagingOwner);
+
+            let recipe = Self::work_order_recipe(&work_order)?;
+            let required_qty = recipe
+                .required_components
+                .iter()
+                .filter(|component| {
+                    component.sku == sku && component.issue_mode == IssueMode::Manual
+                })
+                .fold(None, |total: Option<Qty>, component| {
+                    Some(
+                        total
+                            .unwrap_or_default()
+                            .saturating_add(work_order.required_qty(&recipe, component)),
+                    )
+                })
+                .ok_or(Error::<T>::ComponentNotManual)?;
+
+            // Only stock that backflush or kitting could choose can be issued, and stock kitted
+            // for a work order cannot be issued to another
+            let item = Inventory::<T>::get((who, sku.clone(), serial_number))
+                .ok_or(pallet_inventory::Error::<T>::InventoryNotFound)?;
+            ensure!(item.location != Location::Staging, Error::<T>::ItemStaged);
+            ensure!(item.location.is_usable(), Error::<T>::StockNotUsable);
+            ensure!(
+                item.inventory_type != InventoryType::Transit,
+                pallet_inventory::Error::<T>::ItemInTransit
+            );
+            ensure!(
+                !pallet_inventory::Pallet::<T>::is_on_hold(who, &sku, serial_number),
+                pallet_inventory::Error::<T>::ItemOnHold
+            );
+            ensure!(
+                qty > 0 && item.qty >= qty,
+                Error::<T>::InsufficientInventory
+            );
+            pallet_inventory::Pallet::<T>::ensure_allocation_kept(who, &item, item.qty - qty)?;
+            pallet_inventory::Pallet::<T>::ensure_not_consigned(who, &sku, serial_number)?;
+
+            // No more can be issued than the work order still needs
+            let mut issued = ManualIssues::<T>::get(work_order_number);
+            let issued_qty = issued
+                .iter()
+                .filter(|staged| staged.sku == sku)
+                .fold(0 as Qty, |total, staged| total.saturating_add(staged.qty));
+            ensure!(
+                issued_qty.saturating_add(qty) <= required_qty,
+                Error::<T>::ExceedsRequiredQty
+            );
+
+            let staged = Self::stage_item(who, item, qty, "Manual Issue")?;
+            Self::merge_consumption(&mut issued, &staged, qty)?;
+            ManualIssues::<T>::insert(work_order_number, issued);
+
+            Ok(StagedItem {
+                sku,
+                serial_number: staged.serial_number,
+                qty,
+            })
+        })
+    }
+
+    /// The stock issued by hand to a work order that is still held in staging, with the
+    /// quantity issued from each item
+    fn issued_stock(who: &T::AccountId, issued: &StagingKit) -> Vec<(Item, Qty)> {
+        issued
+            .iter()
+            .filter_map(|staged| {
+                Inventory::<T>::get((who, staged.sku.clone(), staged.serial_number))
+                    .filter(|item| item.location == Location::Staging)
+                    .map(|item| {
+                        let qty = staged.qty.min(item.qty);
+                        (item, qty)
+                    })
+            })
+            .collect()
+    }
+
+    /// Release the assets held by a work order's operations that have not closed
+    fn release_operation_equipment(work_order_number: WorkOrderNumber) -> DispatchResult {
+        let holding: Vec<(OperationNumber, OperationRecord)> =
+            WorkOrderOperations::<T>::iter_prefix(work_order_number)
+                .filter(|(_, record)| {
+                    record.status == OperationStatus::InProgress && record.asset_id.is_some()
+                })
+                .collect();
+
+        for (operation_number, mut record) in holding {
+            if let Some(asset_id) = record.asset_id.take() {
+                pallet_inventory::Pallet::<T>::do_release_equipment(asset_id)?;
+            }
+            WorkOrderOperations::<T>::insert(work_order_number, operation_number, record);
+        }
+
+        Ok(())
+    }
+
+    /// Disassemble an assembled serial
+    ///
+    /// Each component's share of the assembly is returned to inventory at `recovery` percent,
+    /// rounding down, under its original serial and lot so it stays traceable. The rest of each
+    /// share is scrapped under a new serial of the same lot, and the product itself leaves
+    /// inventory through scrap.
+    pub fn do_disassemble_product(
+        who: &T::AccountId,
+        sku: Sku,
+        serial_number: SerialNumber,
+        recovery: Percent,
+        scrap_details: ScrapDetails,
+    ) -> Result<TeardownRecord, DispatchError> {
+        with_storage_layer(|| {
+            let (_, bom) = AssembledProducts::<T>::get((who, sku.clone(), serial_number))
+                .ok_or(Error::<T>::NotAssembled)?;
+            ensure!(
+                !Teardowns::<T>::contains_key((who, sku.clone(), serial_number)),
+                Error::<T>::AlreadyDisassembled
+            );
+
+            // The product leaves inventory first, failing if it is gone or on hold
+            let issuer = scrap_details.issuer.clone();
+            pallet_inventory::Pallet::<T>::do_inventory_scrap(
+                who,
+                sku.clone(),
+                serial_number,
+                scrap_details.clone(),
+            )?;
+
+            // The serial's BOM holds its own share of what the work order consumed
+            let mut components = BoundedVec::default();
+            for component in bom.materials.iter() {
+                let unit_qty = component.qty;
+                let recovered_qty = recovery.mul_floor(unit_qty);
+
+                if recovered_qty > 0 {
+                    Self::return_component(
+                        who,
+                        component,
+                        recovered_qty,
+                        &issuer,
+                        Location::Warehouse,
+                        "Disassemble Product",
+                    )?;
+                }
+
+                // Whatever is not recovered is scrapped, so the share is accounted for in full
+                let scrapped_qty = unit_qty - recovered_qty;
+                let scrap_serial_number = if scrapped_qty > 0 {
+                    Some(Self::scrap_component(
+                        who,
+                        component,
+                        scrapped_qty,
+                        &scrap_details,
+                    )?)
+                } else {
+                    None
+                };
+
+                components
+                    .try_push(RecoveredComponent {
+                        component: ItemRef {
+                            sku: component.sku.clone(),
+                            serial_number: component.serial_number,
+                        },
+                        lot_number: component.lot_number,
+                        recovered_qty,
+                        scrapped_qty,
+                        scrap_serial_number,
+                    })
+                    .map_err(|_| Error::<T>::BomConstructIssue)?;
+            }
+
+            let teardown = TeardownRecord {
+                disassembled_at: frame_system::Pallet::<T>::block_number().saturated_into::<u32>(),
+                recovery,
+                components,
+            };
+            Teardowns::<T>::insert((who, sku, serial_number), teardown.clone());
+
+            Ok(teardown)
+        })
+    }
+
+    /// Scrap part of a component taken out of a product
+    ///
+    /// The quantity is stocked under the SKU's next serial free at `Location::Scrap`, keeping the
+    /// component's lot, and scrapped from there so that it is on record like any other scrap.
+    /// Returns the serial it was scrapped under.
+    fn scrap_component(
+        who: &T::AccountId,
+        component: &Item,
+        qty: Qty,
+        scrap_details: &ScrapDetails,
+    ) -> Result<SerialNumber, DispatchError> {
+        let mut item = component.clone();
+        item.serial_number = pallet_inventory::Pallet::<T>::free_serial_number(
+            who,
+            &component.sku,
+            &Location::Scrap,
+        );
+        item.qty = qty;
+        item.moved_by = scrap_details.issuer.clone();
+        item.location = Location::Scrap;
+
+        pallet_inventory::Pallet::<T>::do_inventory_insertion(who, item.clone())?;
+        pallet_inventory::Pallet::<T>::do_inventory_scrap(
+            who,
+            item.sku,
+            item.serial_number,
+            scrap_details.clone(),
+        )?;
+
+        Ok(item.serial_number)
+    }
+
+    /// Put a component taken out of a product back into inventory under its original serial
+    ///
+    /// What is left of the serial after assembly takes the quantity back if it sits at the
+    /// target location or is empty, and is free to be adjusted. Otherwise the serial is still in
+    /// use elsewhere and the quantity comes back under the SKU's next serial free at the target
+    /// location, keeping the component's lot so it stays traceable.
+    fn return_component(
+        who: &T::AccountId,
+        component: &Item,
+        qty: Qty,
+        issuer: &Employee,
+        location: Location,
+        reason: &str,
+    ) -> DispatchResult {
+        let reason: Reason = BoundedVec::try_from(reason.as_bytes().to_vec())
+            .map_err(|_| Error::<T>::DescriptionTooLong)?;
+
+        let existing = Inventory::<T>::get((who, component.sku.clone(), component.serial_number));
+        let Some(item) = existing.clone().filter(|item| {
+            (item.location == location || item.qty == 0)
+                && item.inventory_type != InventoryType::Transit
+                && !pallet_inventory::Pallet::<T>::is_on_hold(who, &item.sku, item.serial_number)
+        }) else {
+            let mut item = component.clone();
+            if existing.is_some() {
+                item.serial_number = pallet_inventory::Pallet::<T>::free_serial_number(
+                    who,
+                    &component.sku,
+                    &location,
+                );
+            }
+            item.qty = qty;
+            item.moved_by = issuer.clone();
+            item.location = location;
+
+            return pallet_inventory::Pallet::<T>::do_inventory_insertion(who, item);
+        };
+
+        pallet_inventory::Pallet::<T>::do_inventory_adjust(
+            who,
+            issuer.clone(),
+            item.clone(),
+            AdjustDetails::Quantity {
+                original_qty: item.qty,
+                new_qty: item.qty.saturating_add(qty),
+                reason: reason.clone(),
+            },
+        )?;
+
+        if item.location != location {
+            let mut item = item;
+            item.qty = item.qty.saturating_add(qty);
+
+            pallet_inventory::Pallet::<T>::do_inventory_move(
+                who,
+                item.clone(),
+                issuer.clone(),
+                AdjustDetails::Location {
+                    original_location: item.location.clone(),
+                    new_location: location,
+                    reason,
+                },
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Reopen an assembled serial for rework, archiving its current as-built BOM
+    ///
+    /// The serial is placed on quality hold until the rework order is completed.
+    pub fn do_create_rework_order(
+        who: &T::AccountId,
+        sku: Sku,
+        serial_number: SerialNumber,
+        reason: Reason,
+        assigned_to: Employee,
+    ) -> Result<ReworkOrder, DispatchError> {
+        let (_, bom) = AssembledProducts::<T>::get((who, sku.clone(), serial_number))
+            .ok_or(Error::<T>::NotAssembled)?;
+
+        // Only serials still in inventory can be reworked
+        ensure!(
+            Inventory::<T>::contains_key((who, sku.clone(), serial_number)),
+            pallet_inventory::Error::<T>::InventoryNotFound
+        );
+
+        // Revisions are numbered consecutively from 0
+        let bom_revision =
+            BomRevisions::<T>::iter_prefix((who, sku.clone(), serial_number)).count() as u32;
+        BomRevisions::<T>::insert((who, sku.clone(), serial_number, bom_revision), bom);
+
+        let rework_order_number = NextReworkOrderNumber::<T>::get();
+        NextReworkOrderNumber::<T>::put(rework_order_number.saturating_add(1));
+
+        // The serial is held out of use until the rework is done
+        pallet_inventory::Pallet::<T>::do_place_hold(
+            who,
+            sku.clone(),
+            serial_number,
+            HoldSource::Rework(rework_order_number),
+            reason.clone(),
+        )?;
+
+        let rework_order = ReworkOrder {
+            rework_order_number,
+            product: ItemRef { sku, serial_number },
+            reason,
+            assigned_to,
+            status: ReworkStatus::Open,
+            bom_revision,
+            opened_at: frame_system::Pallet::<T>::block_number().saturated_into::<u32>(),
+            completed_at: None,
+            replacements: BoundedVec::default(),
+        };
+
+        ReworkOrders::<T>::insert(rework_order_number, (who, rework_order.clone()));
+
+        Ok(rework_order)
+    }
+
+    /// Replace a component of a serial under rework with stock from inventory
+    ///
+    /// The replacement is consumed from inventory, the removed part is scrapped or returned to
+    /// inventory for the Material Review Board as its disposition calls for, and the serial's
+    /// as-built BOM and genealogy are updated.
+    pub fn do_replace_component(
+        who: &T::AccountId,
+        rework_order_number: ReworkOrderNumber,
+        removed: ItemRef,
+        replacement: ItemRef,
+        qty: Qty,
+        disposition: PartDisposition,
+    ) -> Result<ComponentReplacement, DispatchError> {
+        with_storage_layer(|| {
+            let mut rework_order = Self::open_rework_order(who, rework_order_number)?;
+            let product_key = (
+                who,
+                rework_order.product.sku.clone(),
+                rework_order.product.serial_number,
+            );
+
+            let (product, mut bom) =
+                AssembledProducts::<T>::get(product_key.clone()).ok_or(Error::<T>::NotAssembled)?;
+
+            // The removed component must be in the serial's BOM in at least the replaced quantity
+            let index = bom
+                .materials
+                .iter()
+                .position(|item| {
+                    item.sku == removed.sku && item.serial_number == removed.serial_number
+                })
+                .ok_or(Error::<T>::ComponentNotInBom)?;
+            ensure!(
+                bom.materials[index].qty >= qty,
+                Error::<T>::ComponentNotInBom
+            );
+
+            let new_part =
+                Inventory::<T>::get((who, replacement.sku.clone(), replacement.serial_number))
+                    .ok_or(pallet_inventory::Error::<T>::InventoryNotFound)?;
+            ensure!(
+                new_part.sku == removed.sku,
+                Error::<T>::ReplacementSkuMismatch
+            );
+            // Stock kitted for a work order cannot replace a part, nor can allocated quantity
+            ensure!(
+                new_part.location != Location::Staging,
+                Error::<T>::ItemStaged
+            );
+            ensure!(new_part.location.is_usable(), Error::<T>::StockNotUsable);
+            ensure!(new_part.qty >= qty, Error::<T>::InsufficientInventory);
+            pallet_inventory::Pallet::<T>::ensure_allocation_kept(
+                who,
+                &new_part,
+                new_part.qty - qty,
+            )?;
+
+            // Consume the replacement
+            pallet_inventory::Pallet::<T>::do_inventory_adjust(
+                who,
+                rework_order.assigned_to.clone(),
+                new_part.clone(),
+                AdjustDetails::Quantity {
+                    original_qty: new_part.qty,
+                    new_qty: new_part.qty - qty,
+                    reason: BoundedVec::try_from("Rework Order".as_bytes().to_vec())
+                        .map_err(|_| Error::<T>::DescriptionTooLong)?,
+                },
+            )?;
+
+            // Scrap the removed part or hold it for review, as its disposition calls for
+            let removed_part = bom.materials[index].clone();
+            match &disposition {
+                PartDisposition::Scrap(scrap_details) => {
+                    Self::scrap_component(who, &removed_part, qty, scrap_details)?;
+                }
+                PartDisposition::Mrb => Self::return_component(
+                    who,
+                    &removed_part,
+                    qty,
+                    &rework_order.assigned_to,
+                    Location::Mrb,
+                    "Rework Order",
+                )?,
+            }
+
+            // Update the as-built BOM and re-index the serial's genealogy from it
+            Self::clear_genealogy(who, &product, &bom);
+
+            bom.materials[index].qty -= qty;
+            if bom.materials[index].qty == 0 {
+                bom.materials.remove(index);
+            }
+            match bom.materials.iter_mut().find(|item| {
+                item.sku == new_part.sku && item.serial_number == new_part.serial_number
+            }) {
+                Some(item) => item.qty = item.qty.saturating_add(qty),
+                None => {
+                    let mut bom_item = new_part.clone();
+                    bom_item.qty = qty;
+                    bom.materials
+                        .try_push(bom_item)
+                        .map_err(|_| Error::<T>::BomConstructIssue)?;
+                }
+            }
+
+            Self::record_genealogy(who, &product, &bom);
+            AssembledProducts::<T>::insert(product_key, (product, bom));
+
+            let component_replacement = ComponentReplacement {
+                removed,
+                replacement,
+                replacement_lot: new_part.lot_number,
+                qty,
+                disposition,
+            };
+            rework_order
+                .replacements
+                .try_push(component_replacement.clone())
+                .map_err(|_| Error::<T>::TooManyReplacements)?;
+            ReworkOrders::<T>::insert(rework_order_number, (who, rework_order));
+
+            Ok(component_replacement)
+        })
+    }
+
+    /// Complete a rework order, releasing the hold it placed on its serial
+    pub fn do_complete_rework_order(
+        who: &T::AccountId,
+        rework_order_number: ReworkOrderNumber,
+    ) -> DispatchResult {
+        let mut rework_order = Self::open_rework_order(who, rework_order_number)?;
+
+        pallet_inventory::Pallet::<T>::do_release_hold(
+            who,
+            rework_order.product.sku.clone(),
+            rework_order.product.serial_number,
+            HoldSource::Rework(rework_order_number),
+        );
+
+        rework_order.status = ReworkStatus::Completed;
+        rework_order.completed_at =
+            Some(frame_system::Pallet::<T>::block_number().saturated_into::<u32>());
+        ReworkOrders::<T>::insert(rework_order_number, (who, rework_order));
 
         Ok(())
     }
+
+    /// Fetch a rework order that is still open and owned by the account
+    fn open_rework_order(
+        who: &T::AccountId,
+        rework_order_number: ReworkOrderNumber,
+    ) -> Result<ReworkOrder, DispatchError> {
+        let (owner, rework_order) =
+            ReworkOrders::<T>::get(rework_order_number).ok_or(Error::<T>::ReworkOrderNotFound)?;
+
+        ensure!(&owner == who, Error::<T>::NotReworkOwner);
+        ensure!(
+            rework_order.status == ReworkStatus::Open,
+            Error::<T>::ReworkOrderClosed
+        );
+
+        Ok(rework_order)
+    }
+
+    /// Load the stored recipe a work order references
+    pub fn work_order_recipe(work_order: &WorkOrder) -> Result<Recipe, DispatchError> {
+        pallet_inventory::Pallet::<T>::get_recipe(&work_order.recipe_sku, work_order.recipe_id)
+    }
+
+    /// Release the equipment asset held by a work order, if any
+    pub fn release_work_order_equipment(work_order_number: WorkOrderNumber) -> DispatchResult {
+        if let Some(asset_id) = WorkOrderEquipment::<T>::take(work_order_number) {
+            pallet_inventory::Pallet::<T>::do_release_equipment(asset_id)?;
+
+            Self::deposit_event(Event::EquipmentReleased {
+                work_order_number,
+                asset_id,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Index the components of an assembled serial in both genealogy directions
+    pub fn record_genealogy(who: &T::AccountId, product: &Item, bom: &Bom) {
+        for component in bom.materials.iter() {
+            ProductContents::<T>::insert(
+                (
+                    who,
+                    product.sku.clone(),
+                    product.serial_number,
+                    component.sku.clone(),
+                    component.serial_number,
+                ),
+                (component.lot_number, component.qty),
+            );
+            ComponentWhereUsed::<T>::insert(
+                (
+                    who,
+                    component.sku.clone(),
+                    component.serial_number,
+                    product.sku.clone(),
+                    product.serial_number,
+                ),
+                component.qty,
+            );
+            LotWhereUsed::<T>::insert(
+                (
+                    who,
+                    component.sku.clone(),
+                    component.lot_number,
+                    product.sku.clone(),
+                    product.serial_number,
+                    component.serial_number,
+                ),
+                (),
+            );
+        }
+    }
+
+    /// Remove the genealogy of an assembled serial recorded from its BOM
+    fn clear_genealogy(who: &T::AccountId, product: &Item, bom: &Bom) {
+        for component in bom.materials.iter() {
+            ProductContents::<T>::remove((
+                who,
+                product.sku.clone(),
+                product.serial_number,
+                component.sku.clone(),
+                component.serial_number,
+            ));
+            ComponentWhereUsed::<T>::remove((
+                who,
+                component.sku.clone(),
+                component.serial_number,
+                product.sku.clone(),
+                product.serial_number,
+            ));
+            LotWhereUsed::<T>::remove((
+                who,
+                component.sku.clone(),
+                component.lot_number,
+                product.sku.clone(),
+                product.serial_number,
+                component.serial_number,
+            ));
+        }
+    }
+
+    /// Every assembled serial a component lot ended up in, across all assembly levels
+    pub fn where_used_lot(
+        who: &T::AccountId,
+        sku: Sku,
+        lot_number: LotNumber,
+    ) -> Vec<GenealogyLink> {
+        let first_level = LotWhereUsed::<T>::iter_prefix((who, sku.clone(), lot_number))
+            .map(|((parent_sku, parent_serial, component_serial), ())| {
+                let qty = ComponentWhereUsed::<T>::get((
+                    who,
+                    sku.clone(),
+                    component_serial,
+                    parent_sku.clone(),
+                    parent_serial,
+                ))
+                .unwrap_or_default();
+
+                GenealogyLink {
+                    parent: ItemRef {
+                        sku: parent_sku,
+                        serial_number: parent_serial,
+                    },
+                    component: ItemRef {
+                        sku: sku.clone(),
+                        serial_number: component_serial,
+                    },
+                    lot_number,
+                    qty,
+                    level: 1,
+                }
+            })
+            .collect();
+
+        Self::walk_where_used(who, first_level)
+    }
+
+    /// Every assembled serial a component serial ended up in, across all assembly levels
+    ///
+    /// Serials split off the component, such as part of it kitted for a work order, are followed
+    /// too.
+    pub fn where_used_serial(
+        who: &T::AccountId,
+        sku: Sku,
+        serial_number: SerialNumber,
+    ) -> Vec<GenealogyLink> {
+        let splits = pallet_inventory::Pallet::<T>::split_descendants(who, &sku, serial_number);
+        let first_level = core::iter::once(serial_number)
+            .chain(splits)
+            .flat_map(|serial_number| {
+                let component = ItemRef {
+                    sku: sku.clone(),
+                    serial_number,
+                };
+                Self::direct_where_used(who, &component, 1)
+            })
+            .collect();
+
+        Self::walk_where_used(who, first_level)
+    }
+
+    /// Every component inside an assembled serial, across all assembly levels
+    pub fn as_built_contents(
+        who: &T::AccountId,
+        sku: Sku,
+        serial_number: SerialNumber,
+    ) -> Vec<GenealogyLink> {
+        let mut links = Vec::new();
+        let mut frontier = Self::direct_contents(who, &ItemRef { sku, serial_number }, 1);
+        let mut expanded = BTreeSet::new();
+
+        // Expand level by level, components may themselves be assemblies. A component reached
+        // through several parents is only expanded once.
+        while !frontier.is_empty() && links.len() < MAX_GENEALOGY_LINKS {
+            let mut next = Vec::new();
+            for link in frontier {
+                let component = (link.component.sku.clone(), link.component.serial_number);
+                if link.level < MAX_GENEALOGY_LEVELS && expanded.insert(component) {
+                    next.extend(Self::direct_contents(who, &link.component, link.level + 1));
+                }
+                links.push(link);
+            }
+            frontier = next;
+        }
+
+        links.truncate(MAX_GENEALOGY_LINKS);
+        links
+    }
+
+    /// Expand where-used links level by level until no parent is used any further
+    ///
+    /// A parent reached through several of its components, such as two serials of the same lot,
+    /// is only expanded once.
+    fn walk_where_used(who: &T::AccountId, first_level: Vec<GenealogyLink>) -> Vec<GenealogyLink> {
+        let mut links = Vec::new();
+        let mut frontier = first_level;
+        let mut expanded = BTreeSet::new();
+
+        while !frontier.is_empty() && links.len() < MAX_GENEALOGY_LINKS {
+            let mut next = Vec::new();
+            for link in frontier {
+                let parent = (link.parent.sku.clone(), link.parent.serial_number);
+                if link.level < MAX_GENEALOGY_LEVELS && expanded.insert(parent) {
+                    next.extend(Self::direct_where_used(who, &link.parent, link.level + 1));
+                }
+                links.push(link);
+            }
+            frontier = next;
+        }
+
+        links.truncate(MAX_GENEALOGY_LINKS);
+        links
+    }
+
+    /// The assembled serials a component serial went into directly
+    fn direct_where_used(
+        who: &T::AccountId,
+        component: &ItemRef,
+        level: u32,
+    ) -> Vec<GenealogyLink> {
+        ComponentWhereUsed::<T>::iter_prefix((who, component.sku.clone(), component.serial_number))
+            .map(|((parent_sku, parent_serial), qty)| {
+                let lot_number = ProductContents::<T>::get((
+                    who,
+                    parent_sku.clone(),
+                    parent_serial,
+                    component.sku.clone(),
+                    component.serial_number,
+                ))
+                .map(|(lot_number, _)| lot_number)
+                .unwrap_or_default();
+
+                GenealogyLink {
+                    parent: ItemRef {
+                        sku: parent_sku,
+                        serial_number: parent_serial,
+                    },
+                    component: component.clone(),
+                    lot_number,
+                    qty,
+                    level,
+                }
+            })
+            .collect()
+    }
+
+    /// The component serials consumed directly into an assembled serial
+    fn direct_contents(who: &T::AccountId, parent: &ItemRef, level: u32) -> Vec<GenealogyLink> {
+        ProductContents::<T>::iter_prefix((who, parent.sku.clone(), parent.serial_number))
+            .map(
+                |((component_sku, component_serial), (lot_number, qty))| GenealogyLink {
+                    parent: parent.clone(),
+                    component: ItemRef {
+                        sku: component_sku,
+                        serial_number: component_serial,
+                    },
+                    lot_number,
+                    qty,
+                    level,
+                },
+            )
+            .collect()
+    }
 }
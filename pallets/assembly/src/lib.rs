@@ -12,6 +12,13 @@
 //! - Prepare staging areas for product assembly.
 //! - Create and manage work orders.
 //! - Assemble products using predefined recipes and components.
+//! - Disassemble products, recovering their components back into inventory.
+//! - Rework assembled products, replacing components while keeping every BOM revision.
+//! - Run routed recipes operation by operation, with labor and scrap reported per operation.
+//! - Issue components per their recipe line: pre-kitted, backflushed or manually issued.
+//!
+//! Stock held on consignment is never kitted, backflushed, issued or consumed, until the account
+//! that owns it settles it through the inventory pallet.
 //!
 //! ## Interface
 //!
@@ -19,19 +26,64 @@
 //!
 //! - `assemble_product`: Assemble a product using inventory components and a work order.
 //! - `prepare_staging_area`: Prepare a staging area for a specific work order.
+//! - `unstage`: Return the unused material kitted or issued for a work order to the warehouse.
 //! - `create_work_order`: Create a new work order for assembly.
+//! - `update_work_order_status`: Release, start, close or cancel a work order.
+//! - `disassemble_product`: Tear down an assembled serial, returning recoverable components.
+//! - `create_rework_order`: Reopen an assembled serial for rework or repair.
+//! - `replace_component`: Swap a component of a serial under rework for one from inventory.
+//! - `complete_rework_order`: Close a rework order.
+//! - `start_operation`: Start a routing operation of a staged work order.
+//! - `complete_operation`: Report good units, scrap and labor against an operation, optionally
+//!   closing it.
+//! - `issue_component`: Issue stock by hand to a work order for a manually issued component,
+//!   holding it in staging until assembly.
 //!
 //! ## Storage
 //!
 //! - `AssembledProducts`: Tracks completed products, including their Bill of Materials (BOM).
-//! - `StagingArea`: Stages components and equipment for specific work orders.
+//! - `AssemblyYields`: Records expected versus actual yield for each assembled work order.
+//! - `StagingArea`: The serials and quantities kitted for each work order.
 //! - `WorkOrders`: Stores work orders for tracking and reference.
+//! - `WorkOrderEquipment`: Equipment assets locked by a work order until it is assembled.
+//! - `ProductContents`: Backward genealogy, the component serials inside each assembled serial.
+//! - `ComponentWhereUsed`: Forward genealogy, the assembled serials each component went into.
+//! - `LotWhereUsed`: Forward genealogy, the assembled serials each component lot went into.
+//! - `Teardowns`: What was recovered and scrapped when an assembled serial was disassembled.
+//! - `ReworkOrders`: Stores rework orders, and the account that opened them, by number.
+//! - `NextReworkOrderNumber`: The number assigned to the next rework order.
+//! - `BomRevisions`: Prior as-built BOMs of reworked serials, kept for audit.
+//! - `WorkOrderOperations`: Progress, labor, scrap and consumption of each routing operation.
+//! - `ManualIssues`: Stock issued by hand to each work order, consumed into its BOM at assembly.
+//! - `BackflushHistory`: Stock backflushed from each item, per work order.
+//!
+//! ## Migrations
+//!
+//! - `migrations::v1::MigrateV0ToV1`: Clears the yield records keyed by assembled serial, now that
+//!   `AssemblyYields` is keyed by work order.
+//!
+//! ## Runtime API
+//!
+//! Genealogy is queried across every assembly level through `pallet-assembly-runtime-api`:
+//! "where did this lot go" (`where_used_lot`, `where_used_serial`) and "what is inside this
+//! serial" (`as_built_contents`).
 //!
 //! ## Events
 //!
 //! - `ProductAssembled`: Emitted when a product is successfully assembled.
 //! - `PrepStaging`: Emitted when a staging area is prepared for assembly.
+//! - `Unstaged`: Emitted when kitted material is returned from a staging area.
 //! - `AddWorkOrder`: Emitted when a new work order is added to the system.
+//! - `EquipmentLocked`: Emitted when an equipment asset is locked for a work order.
+//! - `EquipmentReleased`: Emitted when a work order releases its equipment asset.
+//! - `WorkOrderStatusChanged`: Emitted when a work order moves to a new status.
+//! - `ProductDisassembled`: Emitted when an assembled serial is torn down.
+//! - `ReworkOrderCreated`: Emitted when an assembled serial is reopened for rework.
+//! - `ComponentReplaced`: Emitted when a rework order replaces a component.
+//! - `ReworkOrderCompleted`: Emitted when a rework order is closed and its serial released.
+//! - `OperationStarted`: Emitted when a routing operation starts.
+//! - `OperationReported`: Emitted when units, scrap and labor are reported against an operation.
+//! - `ComponentIssued`: Emitted when stock is issued by hand to a work order.
 //!
 //! ## Errors
 //!
@@ -44,19 +96,73 @@
 //! - `DescriptionTooLong`: The description is too long.
 //! - `WorkOrderNotFound`: The specified work order could not be located.
 //! - `BomConstructIssue`: Issue with the construction of the Bill of Materials.
+//! - `InvalidPlannedQuantity`: A work order must plan at least one unit.
+//! - `InvalidStatusTransition`: The work order cannot move to the requested status.
+//! - `WorkOrderNotReleased`: The work order must be released before it is staged.
+//! - `WorkOrderNotStaged`: The work order must be staged or in progress to be assembled.
+//! - `ComponentShortage`: Staging does not cover a recipe component, identified by its index.
+//!   The `component_shortages` runtime API reports the SKU and quantity short.
+//! - `SerialNumberInUse`: A serial number for the assembled products is already taken.
+//! - `TooManyUnits`: More units were produced than the work order started.
+//! - `NoUnitsProduced`: An assembly must produce at least one good unit.
+//! - `NotStagingOwner`: Only the account that staged a work order can unstage it.
+//! - `TooManyStagedItems`: The work order's staging area cannot hold any more items.
+//! - `CannotUnstage`: The work order is not in a status that allows unstaging.
+//! - `NotAssembled`: The serial was not produced by an assembly.
+//! - `AlreadyDisassembled`: The serial has already been torn down.
+//! - `ReworkOrderNotFound`: The specified rework order could not be located.
+//! - `NotReworkOwner`: Only the account that opened the rework order can work on it.
+//! - `ReworkOrderClosed`: The rework order is already completed.
+//! - `ComponentNotInBom`: The serial's BOM does not hold that much of the removed component.
+//! - `ReplacementSkuMismatch`: The replacement is not the same SKU as the removed component.
+//! - `StockNotUsable`: Stock held for the Material Review Board or set aside as scrap cannot be
+//!   used.
+//! - `TooManyReplacements`: The rework order cannot record any more replacements.
+//! - `NoRouting`: The work order's recipe has no routing.
+//! - `OperationNotFound`: The operation is not part of the recipe's routing.
+//! - `OperationAlreadyStarted`: The operation has already been started.
+//! - `OperationOutOfSequence`: The previous operation has not passed on any good units yet, or has
+//!   not closed before this one closes.
+//! - `OperationNotStarted`: The operation has not been started.
+//! - `OperationAlreadyCompleted`: The operation has already been closed.
+//! - `ExceedsPreviousOperation`: More units were reported than the previous operation passed on.
+//! - `OperationsIncomplete`: A routed work order cannot be assembled until every operation closes.
+//! - `ComponentNotManual`: The SKU is not a manually issued component of the recipe.
+//! - `ItemStaged`: Stock kitted into a staging area cannot be issued by hand or replace a part.
+//! - `ExceedsRequiredQty`: More stock was issued than the work order requires of the component.
 //!
 //! This pallet uses `no_std` for compatibility with Wasm environments, a polkadot standard.
 #![cfg_attr(not(feature = "std"), no_std)]
 
 pub use pallet::*;
+
+// FRAME pallets require their own "mock runtimes" to be able to run unit tests. This module
+// contains a mock runtime specific for testing this pallet's functionality.
+#[cfg(test)]
+mod mock;
+
+// This module contains the unit tests for this pallet.
+// Learn about pallet unit testing here: https://docs.substrate.io/test/unit-testing/
+#[cfg(test)]
+mod tests;
+
 mod blogic;
-mod types;
+pub mod migrations;
+pub mod types;
 pub mod weights;
 pub use weights::*;
 
 // Import the types and other modules from the inventory pallet
-use crate::types::{Bom, WorkOrder, WorkOrderNumber};
-use pallet_inventory::types::{Item, Location, SerialNumber, Sku};
+use crate::types::{
+    BackflushRecord, Bom, BomRevision, ComponentReplacement, ItemRef, OperationRecord,
+    PartDisposition, ReworkOrder, ReworkOrderNumber, StagedItem, StagingKit, TeardownRecord,
+    WorkOrder, WorkOrderNumber, WorkOrderStatus, YieldRecord, MAX_BOM_MATERIALS,
+};
+use frame_support::sp_runtime::Percent;
+use pallet_inventory::types::{
+    AssetId, Employee, Item, LotNumber, OperationNumber, Qty, Reason, ScrapDetails, SerialNumber,
+    Sku,
+};
 
 // Define the pallet and its configuration
 #[frame_support::pallet]
@@ -66,7 +172,11 @@ pub mod pallet {
     use frame_system::pallet_prelude::*;
     use pallet_inventory::pallet::Config as InventoryConfig;
 
+    /// The in-code storage version, see [`crate::migrations`]
+    const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
     #[pallet::pallet]
+    #[pallet::storage_version(STORAGE_VERSION)]
     pub struct Pallet<T>(_);
 
     /// The pallet's configuration trait.
@@ -93,6 +203,111 @@ pub mod pallet {
         OptionQuery,
     >;
 
+    /// Assembly Yield Storage
+    ///
+    /// Expected versus actual yield of every assembled work order, for reporting
+    #[pallet::storage]
+    pub type AssemblyYields<T: Config> =
+        StorageMap<_, Twox64Concat, WorkOrderNumber, YieldRecord, OptionQuery>;
+
+    /// Product Contents Storage
+    ///
+    /// Backward genealogy: assembled serial -> component serial, with the component's lot and
+    /// consumed quantity
+    #[pallet::storage]
+    pub type ProductContents<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, T::AccountId>,
+            NMapKey<Blake2_128Concat, Sku>,
+            NMapKey<Blake2_128Concat, SerialNumber>,
+            NMapKey<Blake2_128Concat, Sku>,
+            NMapKey<Blake2_128Concat, SerialNumber>,
+        ),
+        (LotNumber, Qty),
+        OptionQuery,
+    >;
+
+    /// Component Where-Used Storage
+    ///
+    /// Forward genealogy: component serial -> assembled serial, with the consumed quantity
+    #[pallet::storage]
+    pub type ComponentWhereUsed<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, T::AccountId>,
+            NMapKey<Blake2_128Concat, Sku>,
+            NMapKey<Blake2_128Concat, SerialNumber>,
+            NMapKey<Blake2_128Concat, Sku>,
+            NMapKey<Blake2_128Concat, SerialNumber>,
+        ),
+        Qty,
+        OptionQuery,
+    >;
+
+    /// Lot Where-Used Storage
+    ///
+    /// Forward genealogy: component lot -> assembled serial -> each component serial of the lot
+    /// consumed into it
+    #[pallet::storage]
+    pub type LotWhereUsed<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, T::AccountId>,
+            NMapKey<Blake2_128Concat, Sku>,
+            NMapKey<Blake2_128Concat, LotNumber>,
+            NMapKey<Blake2_128Concat, Sku>,
+            NMapKey<Blake2_128Concat, SerialNumber>,
+            NMapKey<Blake2_128Concat, SerialNumber>,
+        ),
+        (),
+        OptionQuery,
+    >;
+
+    /// Teardown Storage
+    ///
+    /// Assembled serials that have been disassembled, with what was recovered from each
+    /// component. The original genealogy is kept as is.
+    #[pallet::storage]
+    pub type Teardowns<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, T::AccountId>,
+            NMapKey<Blake2_128Concat, Sku>,
+            NMapKey<Blake2_128Concat, SerialNumber>,
+        ),
+        TeardownRecord,
+        OptionQuery,
+    >;
+
+    /// Rework Orders Storage
+    ///
+    /// Rework orders by number, with the account whose serial they rework
+    #[pallet::storage]
+    pub type ReworkOrders<T: Config> =
+        StorageMap<_, Twox64Concat, ReworkOrderNumber, (T::AccountId, ReworkOrder), OptionQuery>;
+
+    /// The number assigned to the next rework order
+    #[pallet::storage]
+    pub type NextReworkOrderNumber<T: Config> = StorageValue<_, ReworkOrderNumber, ValueQuery>;
+
+    /// BOM Revisions Storage
+    ///
+    /// Every as-built BOM a serial had before it was reworked, numbered from 0. The current BOM
+    /// stays in `AssembledProducts`.
+    #[pallet::storage]
+    pub type BomRevisions<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, T::AccountId>,
+            NMapKey<Blake2_128Concat, Sku>,
+            NMapKey<Blake2_128Concat, SerialNumber>,
+            NMapKey<Twox64Concat, BomRevision>,
+        ),
+        Bom,
+        OptionQuery,
+    >;
+
     /// Work Order Storage
     ///
     /// Work orders that are in progress or have not yet been completed
@@ -100,6 +315,59 @@ pub mod pallet {
     pub type WorkOrders<T: Config> =
         StorageMap<_, Twox64Concat, WorkOrderNumber, WorkOrder, OptionQuery>;
 
+    /// Staging Area Storage
+    ///
+    /// The account that kitted each work order and exactly which serials and quantities it
+    /// kitted. Only the work order it was kitted for may consume it.
+    #[pallet::storage]
+    pub type StagingArea<T: Config> =
+        StorageMap<_, Twox64Concat, WorkOrderNumber, (T::AccountId, StagingKit), OptionQuery>;
+
+    /// Work Order Equipment Storage
+    ///
+    /// The equipment asset each work order holds while it is being staged and assembled
+    #[pallet::storage]
+    pub type WorkOrderEquipment<T: Config> =
+        StorageMap<_, Twox64Concat, WorkOrderNumber, AssetId, OptionQuery>;
+
+    /// Work Order Operations Storage
+    ///
+    /// Progress of each routing operation a work order has started
+    #[pallet::storage]
+    pub type WorkOrderOperations<T: Config> = StorageDoubleMap<
+        _,
+        Twox64Concat,
+        WorkOrderNumber,
+        Twox64Concat,
+        OperationNumber,
+        OperationRecord,
+        OptionQuery,
+    >;
+
+    /// Manual Issues Storage
+    ///
+    /// Stock issued by hand to each work order, held in staging and consumed into its as-built BOM
+    /// at assembly
+    #[pallet::storage]
+    pub type ManualIssues<T: Config> =
+        StorageMap<_, Twox64Concat, WorkOrderNumber, StagingKit, ValueQuery>;
+
+    /// Backflush History Storage
+    ///
+    /// Stock backflushed from each item, per work order it was consumed by
+    #[pallet::storage]
+    pub type BackflushHistory<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, T::AccountId>,
+            NMapKey<Blake2_128Concat, Sku>,
+            NMapKey<Blake2_128Concat, SerialNumber>,
+            NMapKey<Twox64Concat, WorkOrderNumber>,
+        ),
+        BackflushRecord,
+        OptionQuery,
+    >;
+
     /// Events that functions in this pallet can emit.
     #[pallet::event]
     #[pallet::generate_deposit(pub(super) fn deposit_event)]
@@ -108,15 +376,85 @@ pub mod pallet {
             assembler: T::AccountId,
             work_order: WorkOrder,
         },
+        /// Unused material kitted for a work order has been returned to the warehouse
+        Unstaged {
+            who: T::AccountId,
+            work_order_number: WorkOrderNumber,
+            items: StagingKit,
+        },
         /// An item has been assembled
         ProductAssembled {
             assembler: T::AccountId,
             work_order: WorkOrder,
+            yield_record: YieldRecord,
         },
         AddWorkOrder {
             who: T::AccountId,
             work_order: WorkOrder,
         },
+        /// An equipment asset has been locked for a work order
+        EquipmentLocked {
+            work_order_number: WorkOrderNumber,
+            asset_id: AssetId,
+        },
+        /// A work order has released its equipment asset
+        EquipmentReleased {
+            work_order_number: WorkOrderNumber,
+            asset_id: AssetId,
+        },
+        /// A work order has moved to a new status
+        WorkOrderStatusChanged {
+            work_order_number: WorkOrderNumber,
+            original_status: WorkOrderStatus,
+            new_status: WorkOrderStatus,
+        },
+        /// An assembled serial has been torn down
+        ProductDisassembled {
+            who: T::AccountId,
+            sku: Sku,
+            serial_number: SerialNumber,
+            teardown: TeardownRecord,
+        },
+        /// An assembled serial has been reopened for rework
+        ReworkOrderCreated {
+            who: T::AccountId,
+            rework_order: ReworkOrder,
+        },
+        /// A rework order has replaced a component of its serial
+        ComponentReplaced {
+            who: T::AccountId,
+            rework_order_number: ReworkOrderNumber,
+            replacement: ComponentReplacement,
+        },
+        /// A rework order has been completed and its serial released from hold
+        ReworkOrderCompleted {
+            who: T::AccountId,
+            rework_order_number: ReworkOrderNumber,
+        },
+        /// A routing operation has started
+        OperationStarted {
+            who: T::AccountId,
+            work_order_number: WorkOrderNumber,
+            operation_number: OperationNumber,
+            asset_id: Option<AssetId>,
+        },
+        /// Units, scrap and labor have been reported against a routing operation
+        OperationReported {
+            who: T::AccountId,
+            work_order_number: WorkOrderNumber,
+            operation_number: OperationNumber,
+            good_qty: Qty,
+            scrapped_qty: Qty,
+            labor_minutes: u32,
+            closed: bool,
+        },
+        /// Stock has been issued by hand to a work order, and moved to staging under the serial
+        /// of the item
+        ComponentIssued {
+            who: T::AccountId,
+            work_order_number: WorkOrderNumber,
+            item: StagedItem,
+        },
     }
 
     /// Errors that can be returned by this pallet.
@@ -141,28 +479,106 @@ pub mod pallet {
         BomConstructIssue,
         /// The work order already exists
         WorkOrderAlreadyExists,
+        /// A work order must plan at least one unit
+        InvalidPlannedQuantity,
+        /// The work order cannot move to the requested status
+        InvalidStatusTransition,
+        /// The work order must be released before it can be staged
+        WorkOrderNotReleased,
+        /// The work order must be staged or in progress before it can be assembled
+        WorkOrderNotStaged,
+        /// Staging does not hold enough of a recipe component
+        ///
+        /// `component_index` is the position of the short SKU in the recipe's
+        /// `required_components`. Errors are too small to carry the SKU, the `component_shortages`
+        /// runtime API names the SKU and quantity of every short component.
+        ComponentShortage { component_index: u8 },
+        /// An output serial number is already in use or out of range
+        SerialNumberInUse,
+        /// More units were produced than the work order started
+        TooManyUnits,
+        /// An assembly must produce at least one good unit
+        NoUnitsProduced,
+        /// Only the account that staged the work order may unstage it
+        NotStagingOwner,
+        /// The staging area cannot hold any more items
+        TooManyStagedItems,
+        /// The work order is not in a status that allows unstaging
+        CannotUnstage,
+        /// The serial was not produced by an assembly
+        NotAssembled,
+        /// The serial has already been disassembled
+        AlreadyDisassembled,
+        /// Unable to find the defined rework order
+        ReworkOrderNotFound,
+        /// The rework order was opened by another account
+        NotReworkOwner,
+        /// The rework order is already completed
+        ReworkOrderClosed,
+        /// The serial's BOM does not hold that much of the removed component
+        ComponentNotInBom,
+        /// A replacement must be the same SKU as the component it replaces
+        ReplacementSkuMismatch,
+        /// Stock held for the Material Review Board or set aside as scrap cannot be used
+        StockNotUsable,
+        /// The rework order cannot record any more replacements
+        TooManyReplacements,
+        /// The work order's recipe has no routing
+        NoRouting,
+        /// The operation is not part of the recipe's routing
+        OperationNotFound,
+        /// The operation has already been started
+        OperationAlreadyStarted,
+        /// The previous operation has not passed on any good units yet, or has not closed before this
+        /// one closes
+        OperationOutOfSequence,
+        /// The operation has not been started
+        OperationNotStarted,
+        /// The operation has already been closed
+        OperationAlreadyCompleted,
+        /// More units were reported than the previous operation passed on
+        ExceedsPreviousOperation,
+        /// Every operation of a routed work order must close before it is assembled
+        OperationsIncomplete,
+        /// The SKU is not a manually issued component of the work order's recipe
+        ComponentNotManual,
+        /// Stock kitted into a staging area cannot be issued by hand or replace a part
+        ItemStaged,
+        /// More stock was issued than the work order requires of the component
+        ExceedsRequiredQty,
     }
 
     /// The pallet's dispatchable functions
     #[pallet::call]
     impl<T: Config> Pallet<T> {
         /// Assemble a product from components in staging
+        ///
+        /// One finished good is created per unit produced, serialized consecutively from
+        /// `serial_number`, so the weight grows with `produced_qty`.
         #[pallet::call_index(0)]
-        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        #[pallet::weight(
+            Weight::from_parts(10_000, 0)
+                + T::DbWeight::get().writes(1)
+                + T::DbWeight::get()
+                    .reads_writes(3, 7)
+                    .saturating_mul(*produced_qty as u64)
+        )]
         pub fn assemble_product(
             origin: OriginFor<T>,
             work_order: WorkOrder,
             serial_number: SerialNumber,
-            staging_location: Location,
+            produced_qty: Qty,
         ) -> DispatchResult {
             let who = ensure_signed(origin)?;
 
-            Self::do_assemble_product(&who, work_order.clone(), serial_number, staging_location)?;
+            let yield_record =
+                Self::do_assemble_product(&who, work_order.clone(), serial_number, produced_qty)?;
 
             // Emit the assembled product
             Self::deposit_event(Event::ProductAssembled {
                 assembler: who,
                 work_order,
+                yield_record,
             });
 
             Ok(())
@@ -171,7 +587,8 @@ pub mod pallet {
         #[pallet::call_index(1)]
         #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
         /// Prepare the staging area for assembly
-        /// TODO: Later, add location to define different staging areas
+        ///
+        /// Kits the work order's components into its own staging area.
         pub fn prepare_staging_area(origin: OriginFor<T>, work_order: WorkOrder) -> DispatchResult {
             let who = ensure_signed(origin)?;
 
@@ -186,12 +603,254 @@ pub mod pallet {
         pub fn create_work_order(origin: OriginFor<T>, work_order: WorkOrder) -> DispatchResult {
             let who = ensure_signed(origin)?;
 
-            Self::do_create_work_order(work_order.clone())?;
+            let work_order = Self::do_create_work_order(work_order)?;
 
             // Emit staging has been prepped
             Self::deposit_event(Event::AddWorkOrder { who, work_order });
 
             Ok(())
         }
+
+        #[pallet::call_index(3)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        /// Move a work order to a new status
+        ///
+        /// Releases, starts, closes or cancels a work order. Staging and assembly move the work
+        /// order to `Staged` and `Completed` themselves.
+        pub fn update_work_order_status(
+            origin: OriginFor<T>,
+            work_order_number: WorkOrderNumber,
+            status: WorkOrderStatus,
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+
+            Self::do_update_work_order_status(work_order_number, status)?;
+
+            Ok(())
+        }
+
+        #[pallet::call_index(4)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        /// Return the unused material kitted for a work order to the warehouse
+        ///
+        /// A staged or in-progress work order goes back to `Released` and gives up its equipment.
+        pub fn unstage(origin: OriginFor<T>, work_order_number: WorkOrderNumber) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let items = Self::do_unstage(&who, work_order_number)?;
+
+            Self::deposit_event(Event::Unstaged {
+                who,
+                work_order_number,
+                items,
+            });
+
+            Ok(())
+        }
+
+        #[pallet::call_index(5)]
+        #[pallet::weight(
+            Weight::from_parts(10_000, 0)
+                + T::DbWeight::get().reads_writes(4, 6)
+                + T::DbWeight::get()
+                    .reads_writes(6, 8)
+                    .saturating_mul(MAX_BOM_MATERIALS as u64)
+        )]
+        /// Tear down an assembled serial
+        ///
+        /// `recovery` of each component's quantity is returned to inventory, the rest is scrapped
+        /// along with the product. Every material of the serial's BOM is returned and scrapped on
+        /// its own, so the weight covers the largest BOM.
+        pub fn disassemble_product(
+            origin: OriginFor<T>,
+            sku: Sku,
+            serial_number: SerialNumber,
+            recovery: Percent,
+            scrap_details: ScrapDetails,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let teardown = Self::do_disassemble_product(
+                &who,
+                sku.clone(),
+                serial_number,
+                recovery,
+                scrap_details,
+            )?;
+
+            Self::deposit_event(Event::ProductDisassembled {
+                who,
+                sku,
+                serial_number,
+                teardown,
+            });
+
+            Ok(())
+        }
+
+        #[pallet::call_index(6)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        /// Reopen an assembled serial for rework or repair
+        ///
+        /// The serial's current as-built BOM is archived as a new revision, and the serial is held
+        /// until the rework order is completed.
+        pub fn create_rework_order(
+            origin: OriginFor<T>,
+            sku: Sku,
+            serial_number: SerialNumber,
+            reason: Reason,
+            assigned_to: Employee,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let rework_order =
+                Self::do_create_rework_order(&who, sku, serial_number, reason, assigned_to)?;
+
+            Self::deposit_event(Event::ReworkOrderCreated { who, rework_order });
+
+            Ok(())
+        }
+
+        #[pallet::call_index(7)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        /// Replace a component of a serial under rework
+        ///
+        /// The replacement is consumed from inventory and the removed part is scrapped or held
+        /// for the Material Review Board, as its disposition calls for.
+        pub fn replace_component(
+            origin: OriginFor<T>,
+            rework_order_number: ReworkOrderNumber,
+            removed: ItemRef,
+            replacement: ItemRef,
+            qty: Qty,
+            disposition: PartDisposition,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let replacement = Self::do_replace_component(
+                &who,
+                rework_order_number,
+                removed,
+                replacement,
+                qty,
+                disposition,
+            )?;
+
+            Self::deposit_event(Event::ComponentReplaced {
+                who,
+                rework_order_number,
+                replacement,
+            });
+
+            Ok(())
+        }
+
+        #[pallet::call_index(8)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        /// Complete a rework order, releasing its serial from hold
+        pub fn complete_rework_order(
+            origin: OriginFor<T>,
+            rework_order_number: ReworkOrderNumber,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            Self::do_complete_rework_order(&who, rework_order_number)?;
+
+            Self::deposit_event(Event::ReworkOrderCompleted {
+                who,
+                rework_order_number,
+            });
+
+            Ok(())
+        }
+
+        #[pallet::call_index(9)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        /// Start a routing operation of a staged work order
+        ///
+        /// Locks the operation's own equipment, if it needs any, until the operation closes.
+        pub fn start_operation(
+            origin: OriginFor<T>,
+            work_order_number: WorkOrderNumber,
+            operation_number: OperationNumber,
+            operator: Employee,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let asset_id =
+                Self::do_start_operation(&who, work_order_number, operation_number, operator)?;
+
+            Self::deposit_event(Event::OperationStarted {
+                who,
+                work_order_number,
+                operation_number,
+                asset_id,
+            });
+
+            Ok(())
+        }
+
+        #[pallet::call_index(10)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        /// Report good units, scrap and labor against a routing operation
+        ///
+        /// The operation's components are consumed per their issue mode, in proportion to the
+        /// units reported. Reports may be partial; `close` completes the operation.
+        pub fn complete_operation(
+            origin: OriginFor<T>,
+            work_order_number: WorkOrderNumber,
+            operation_number: OperationNumber,
+            good_qty: Qty,
+            scrapped_qty: Qty,
+            labor_minutes: u32,
+            close: bool,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            Self::do_complete_operation(
+                &who,
+                work_order_number,
+                operation_number,
+                good_qty,
+                scrapped_qty,
+                labor_minutes,
+                close,
+            )?;
+
+            Self::deposit_event(Event::OperationReported {
+                who,
+                work_order_number,
+                operation_number,
+                good_qty,
+                scrapped_qty,
+                labor_minutes,
+                closed: close,
+            });
+
+            Ok(())
+        }
+
+        #[pallet::call_index(11)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().reads_writes(6, 6))]
+        /// Issue stock by hand to a staged work order for a manually issued component
+        pub fn issue_component(
+            origin: OriginFor<T>,
+            work_order_number: WorkOrderNumber,
+            sku: Sku,
+            serial_number: SerialNumber,
+            qty: Qty,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let item = Self::do_issue_component(&who, work_order_number, sku, serial_number, qty)?;
+
+            Self::deposit_event(Event::ComponentIssued {
+                who,
+                work_order_number,
+                item,
+            });
+
+            Ok(())
+        }
     }
 }
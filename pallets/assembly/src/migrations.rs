@@ -0,0 +1,65 @@
+//! Storage migrations of the assembly pallet
+//!
+//! Add the migrations to the runtime's `Migrations`. Each one only runs from the storage version
+//! it migrates and bumps the pallet to the next one.
+
+use crate::pallet::{Config, Pallet};
+use core::marker::PhantomData;
+use frame_support::migrations::VersionedMigration;
+use frame_support::traits::{Get, UncheckedOnRuntimeUpgrade};
+use frame_support::weights::Weight;
+
+/// Version 1 keys `AssemblyYields` by work order instead of by assembled serial
+pub mod v1 {
+    use super::*;
+
+    /// Storage as it was laid out before version 1
+    pub(crate) mod v0 {
+        use crate::pallet::{Config, Pallet};
+        use crate::types::YieldRecord;
+        use frame_support::pallet_prelude::{Blake2_128Concat, NMapKey, OptionQuery};
+        use pallet_inventory::types::{SerialNumber, Sku};
+
+        /// Yield records keyed by the assembled serial they were recorded against
+        #[frame_support::storage_alias]
+        pub type AssemblyYields<T: Config> = StorageNMap<
+            Pallet<T>,
+            (
+                NMapKey<Blake2_128Concat, <T as frame_system::Config>::AccountId>,
+                NMapKey<Blake2_128Concat, Sku>,
+                NMapKey<Blake2_128Concat, SerialNumber>,
+            ),
+            YieldRecord,
+            OptionQuery,
+        >;
+    }
+
+    /// Remove the yield records keyed by assembled serial
+    ///
+    /// The old records do not name the work order they were recorded for, so they cannot be
+    /// re-keyed. They are cleared rather than left under keys the new map cannot decode.
+    pub struct InnerMigrateV0ToV1<T>(PhantomData<T>);
+
+    impl<T: Config> UncheckedOnRuntimeUpgrade for InnerMigrateV0ToV1<T> {
+        fn on_runtime_upgrade() -> Weight {
+            let removed = v0::AssemblyYields::<T>::clear(u32::MAX, None).unique;
+
+            log::info!(
+                target: "runtime::assembly",
+                "removed {} yield records keyed by assembled serial",
+                removed
+            );
+
+            T::DbWeight::get().reads_writes(removed.into(), removed.into())
+        }
+    }
+
+    /// [`InnerMigrateV0ToV1`], run only while the pallet is at storage version 0
+    pub type MigrateV0ToV1<T> = VersionedMigration<
+        0,
+        1,
+        InnerMigrateV0ToV1<T>,
+        Pallet<T>,
+        <T as frame_system::Config>::DbWeight,
+    >;
+}
@@ -0,0 +1,2400 @@
+use crate::{mock::*, types::*, Error, Event};
+use frame_support::sp_runtime::{DispatchResult, Percent};
+use frame_support::{assert_noop, assert_ok, BoundedVec};
+use pallet_inventory::types::{
+    AbcCode, AdjustDetails, Employee, Equipment, EquipmentAsset, EquipmentStatus, HoldSource,
+    InventoryType, IssueMode, Item, Location, LotNumber, Material, Operation, OperationNumber,
+    OutputKind, PickStrategy, ProductType, Qty, Reason, Recipe, RecipeComponent, RecipeOutput,
+    ScrapDetails, SerialNumber, Sku,
+};
+
+const ALICE: u64 = 1;
+const BOB: u64 = 2;
+
+fn sku(sku: &str) -> Sku {
+    BoundedVec::truncate_from(sku.as_bytes().to_vec())
+}
+
+fn component(sku_name: &str, qty: Qty) -> RecipeComponent {
+    RecipeComponent {
+        sku: sku(sku_name),
+        qty,
+        scrap_percent: Percent::from_percent(0),
+        issue_mode: IssueMode::PreKitted,
+    }
+}
+
+fn widget_recipe(components: Vec<RecipeComponent>) -> Recipe {
+    Recipe {
+        sku: sku("WIDGET"),
+        required_components: BoundedVec::truncate_from(components),
+        output_quantity: 1,
+        expected_yield: Percent::from_percent(100),
+        ..Default::default()
+    }
+}
+
+/// Stock of a SKU, in its own lot and created in the order of its serial number
+fn stock(sku_name: &str, serial_number: SerialNumber, qty: Qty) -> Item {
+    Item {
+        sku: sku(sku_name),
+        lot_number: serial_number,
+        serial_number,
+        qty,
+        created_at: serial_number,
+        ..Default::default()
+    }
+}
+
+fn work_order(work_order_number: WorkOrderNumber, planned_quantity: Qty) -> WorkOrder {
+    WorkOrder {
+        work_order_number,
+        recipe_sku: sku("WIDGET"),
+        recipe_id: 0,
+        planned_quantity,
+        ..Default::default()
+    }
+}
+
+/// Store the recipe, register an asset of the equipment it needs and stock the account
+fn set_up(recipe: Recipe, items: Vec<Item>) {
+    assert_ok!(Inventory::insert_recipe(
+        RuntimeOrigin::signed(ALICE),
+        recipe.clone()
+    ));
+    assert_ok!(Inventory::insert_equipment(
+        RuntimeOrigin::signed(ALICE),
+        EquipmentAsset {
+            asset_id: 0,
+            equipment_type: recipe.required_equipment,
+            ..Default::default()
+        }
+    ));
+    for item in items {
+        assert_ok!(Inventory::inventory_insertion(
+            RuntimeOrigin::signed(ALICE),
+            item
+        ));
+    }
+}
+
+/// Create, release and kit a work order
+fn stage(work_order: WorkOrder) {
+    assert_ok!(Assembly::create_work_order(
+        RuntimeOrigin::signed(ALICE),
+        work_order.clone()
+    ));
+    assert_ok!(Assembly::update_work_order_status(
+        RuntimeOrigin::signed(ALICE),
+        work_order.work_order_number,
+        WorkOrderStatus::Released
+    ));
+    assert_ok!(Assembly::prepare_staging_area(
+        RuntimeOrigin::signed(ALICE),
+        work_order
+    ));
+}
+
+/// Total quantity kitted for a work order
+fn kitted_qty(work_order_number: WorkOrderNumber) -> Qty {
+    crate::StagingArea::<Test>::get(work_order_number)
+        .map(|(_, kit)| kit.iter().map(|staged| staged.qty).sum())
+        .unwrap_or_default()
+}
+
+#[test]
+fn staging_covers_scrap_factor_for_every_batch() {
+    new_test_ext().execute_with(|| {
+        let mut bolt = component("BOLT", 1);
+        bolt.scrap_percent = Percent::from_percent(20);
+        let mut recipe = widget_recipe(vec![bolt]);
+        recipe.output_quantity = 2;
+        set_up(recipe, vec![stock("BOLT", 1, 10)]);
+
+        // Three units take two batches, each staging 1.25 bolts rounded up
+        stage(work_order(0, 3));
+
+        assert_eq!(kitted_qty(0), 4);
+    });
+}
+
+#[test]
+fn assembly_records_actual_against_expected_yield() {
+    new_test_ext().execute_with(|| {
+        let mut recipe = widget_recipe(vec![component("BOLT", 2)]);
+        recipe.expected_yield = Percent::from_percent(50);
+        set_up(recipe, vec![stock("BOLT", 1, 10)]);
+
+        // Two good units at 50% yield start four, which need eight bolts
+        stage(work_order(0, 2));
+        assert_eq!(kitted_qty(0), 8);
+
+        assert_ok!(Assembly::assemble_product(
+            RuntimeOrigin::signed(ALICE),
+            work_order(0, 2),
+            100,
+            3
+        ));
+
+        let yield_record = YieldRecord {
+            planned_input_qty: 4,
+            expected_qty: 2,
+            actual_qty: 3,
+            expected_yield: Percent::from_percent(50),
+            actual_yield: Percent::from_percent(75),
+        };
+        assert_eq!(
+            crate::AssemblyYields::<Test>::get(0),
+            Some(yield_record.clone())
+        );
+        System::assert_last_event(
+            Event::ProductAssembled {
+                assembler: ALICE,
+                work_order: work_order(0, 2),
+                yield_record,
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn assembly_cannot_produce_more_than_started() {
+    new_test_ext().execute_with(|| {
+        let mut recipe = widget_recipe(vec![component("BOLT", 2)]);
+        recipe.expected_yield = Percent::from_percent(50);
+        set_up(recipe, vec![stock("BOLT", 1, 10)]);
+        stage(work_order(0, 2));
+
+        assert_noop!(
+            Assembly::assemble_product(RuntimeOrigin::signed(ALICE), work_order(0, 2), 100, 5),
+            Error::<Test>::TooManyUnits
+        );
+    });
+}
+
+#[test]
+fn assembly_must_produce_a_unit() {
+    new_test_ext().execute_with(|| {
+        set_up(
+            widget_recipe(vec![component("BOLT", 2)]),
+            vec![stock("BOLT", 1, 10)],
+        );
+        stage(work_order(0, 2));
+
+        assert_noop!(
+            Assembly::assemble_product(RuntimeOrigin::signed(ALICE), work_order(0, 2), 100, 0),
+            Error::<Test>::NoUnitsProduced
+        );
+    });
+}
+
+#[test]
+fn migration_clears_yields_keyed_by_serial() {
+    use crate::migrations::v1::{v0, MigrateV0ToV1};
+    use frame_support::traits::{GetStorageVersion, OnRuntimeUpgrade, StorageVersion};
+
+    new_test_ext().execute_with(|| {
+        StorageVersion::new(0).put::<Assembly>();
+        v0::AssemblyYields::<Test>::insert(
+            (ALICE, sku("WIDGET"), 100),
+            YieldRecord {
+                planned_input_qty: 1,
+                expected_qty: 1,
+                actual_qty: 1,
+                expected_yield: Percent::from_percent(100),
+                actual_yield: Percent::from_percent(100),
+            },
+        );
+
+        MigrateV0ToV1::<Test>::on_runtime_upgrade();
+
+        assert_eq!(v0::AssemblyYields::<Test>::iter().count(), 0);
+        assert_eq!(Assembly::on_chain_storage_version(), 1);
+    });
+}
+
+#[test]
+fn staging_fails_without_available_equipment() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Inventory::insert_recipe(
+            RuntimeOrigin::signed(ALICE),
+            widget_recipe(vec![component("BOLT", 1)])
+        ));
+        assert_ok!(Inventory::inventory_insertion(
+            RuntimeOrigin::signed(ALICE),
+            stock("BOLT", 1, 10)
+        ));
+        assert_ok!(Assembly::create_work_order(
+            RuntimeOrigin::signed(ALICE),
+            work_order(0, 1)
+        ));
+        assert_ok!(Assembly::update_work_order_status(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            WorkOrderStatus::Released
+        ));
+
+        assert_noop!(
+            Assembly::prepare_staging_area(RuntimeOrigin::signed(ALICE), work_order(0, 1)),
+            pallet_inventory::Error::<Test>::EquipmentUnavailable
+        );
+    });
+}
+
+#[test]
+fn work_order_holds_its_equipment_until_assembled() {
+    new_test_ext().execute_with(|| {
+        set_up(
+            widget_recipe(vec![component("BOLT", 1)]),
+            vec![stock("BOLT", 1, 10)],
+        );
+        stage(work_order(0, 1));
+
+        assert_eq!(crate::WorkOrderEquipment::<Test>::get(0), Some(0));
+        assert_eq!(
+            pallet_inventory::EquipmentRegistry::<Test>::get(0)
+                .unwrap()
+                .status,
+            EquipmentStatus::InUse
+        );
+
+        // The only asset is locked, so no other work order can be staged
+        assert_ok!(Assembly::create_work_order(
+            RuntimeOrigin::signed(ALICE),
+            work_order(1, 1)
+        ));
+        assert_ok!(Assembly::update_work_order_status(
+            RuntimeOrigin::signed(ALICE),
+            1,
+            WorkOrderStatus::Released
+        ));
+        assert_noop!(
+            Assembly::prepare_staging_area(RuntimeOrigin::signed(ALICE), work_order(1, 1)),
+            pallet_inventory::Error::<Test>::EquipmentUnavailable
+        );
+
+        assert_ok!(Assembly::assemble_product(
+            RuntimeOrigin::signed(ALICE),
+            work_order(0, 1),
+            100,
+            1
+        ));
+
+        assert_eq!(crate::WorkOrderEquipment::<Test>::get(0), None);
+        let asset = pallet_inventory::EquipmentRegistry::<Test>::get(0).unwrap();
+        assert_eq!(asset.status, EquipmentStatus::Available);
+        assert_eq!(asset.usage_count, 1);
+        System::assert_has_event(
+            Event::EquipmentReleased {
+                work_order_number: 0,
+                asset_id: 0,
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn work_orders_start_out_planned() {
+    new_test_ext().execute_with(|| {
+        set_up(widget_recipe(vec![component("BOLT", 1)]), vec![]);
+
+        let mut closed = work_order(0, 1);
+        closed.status = WorkOrderStatus::Closed;
+        assert_ok!(Assembly::create_work_order(
+            RuntimeOrigin::signed(ALICE),
+            closed
+        ));
+
+        assert_eq!(
+            crate::WorkOrders::<Test>::get(0).unwrap().status,
+            WorkOrderStatus::Planned
+        );
+        System::assert_last_event(
+            Event::AddWorkOrder {
+                who: ALICE,
+                work_order: work_order(0, 1),
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn work_order_moves_through_its_lifecycle() {
+    new_test_ext().execute_with(|| {
+        set_up(
+            widget_recipe(vec![component("BOLT", 1)]),
+            vec![stock("BOLT", 1, 10)],
+        );
+        assert_ok!(Assembly::create_work_order(
+            RuntimeOrigin::signed(ALICE),
+            work_order(0, 1)
+        ));
+
+        assert_noop!(
+            Assembly::update_work_order_status(
+                RuntimeOrigin::signed(ALICE),
+                0,
+                WorkOrderStatus::Completed
+            ),
+            Error::<Test>::InvalidStatusTransition
+        );
+        // Only staging a work order marks it staged
+        assert_noop!(
+            Assembly::update_work_order_status(
+                RuntimeOrigin::signed(ALICE),
+                0,
+                WorkOrderStatus::Staged
+            ),
+            Error::<Test>::InvalidStatusTransition
+        );
+
+        assert_ok!(Assembly::update_work_order_status(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            WorkOrderStatus::Released
+        ));
+        System::assert_last_event(
+            Event::WorkOrderStatusChanged {
+                work_order_number: 0,
+                original_status: WorkOrderStatus::Planned,
+                new_status: WorkOrderStatus::Released,
+            }
+            .into(),
+        );
+
+        assert_ok!(Assembly::prepare_staging_area(
+            RuntimeOrigin::signed(ALICE),
+            work_order(0, 1)
+        ));
+        assert_eq!(
+            crate::WorkOrders::<Test>::get(0).unwrap().status,
+            WorkOrderStatus::Staged
+        );
+
+        assert_ok!(Assembly::update_work_order_status(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            WorkOrderStatus::InProgress
+        ));
+        assert_ok!(Assembly::assemble_product(
+            RuntimeOrigin::signed(ALICE),
+            work_order(0, 1),
+            100,
+            1
+        ));
+        assert_eq!(
+            crate::WorkOrders::<Test>::get(0).unwrap().status,
+            WorkOrderStatus::Completed
+        );
+
+        // A completed work order can be closed, but no longer cancelled
+        assert_noop!(
+            Assembly::update_work_order_status(
+                RuntimeOrigin::signed(ALICE),
+                0,
+                WorkOrderStatus::Cancelled
+            ),
+            Error::<Test>::InvalidStatusTransition
+        );
+        assert_ok!(Assembly::update_work_order_status(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            WorkOrderStatus::Closed
+        ));
+        assert_eq!(
+            crate::WorkOrders::<Test>::get(0).unwrap().status,
+            WorkOrderStatus::Closed
+        );
+    });
+}
+
+#[test]
+fn status_update_fails_for_unknown_work_order() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Assembly::update_work_order_status(
+                RuntimeOrigin::signed(ALICE),
+                0,
+                WorkOrderStatus::Released
+            ),
+            Error::<Test>::WorkOrderNotFound
+        );
+    });
+}
+
+#[test]
+fn only_released_work_orders_are_staged() {
+    new_test_ext().execute_with(|| {
+        set_up(
+            widget_recipe(vec![component("BOLT", 1)]),
+            vec![stock("BOLT", 1, 10)],
+        );
+        assert_ok!(Assembly::create_work_order(
+            RuntimeOrigin::signed(ALICE),
+            work_order(0, 1)
+        ));
+
+        assert_noop!(
+            Assembly::prepare_staging_area(RuntimeOrigin::signed(ALICE), work_order(0, 1)),
+            Error::<Test>::WorkOrderNotReleased
+        );
+    });
+}
+
+#[test]
+fn only_staged_work_orders_are_assembled() {
+    new_test_ext().execute_with(|| {
+        set_up(
+            widget_recipe(vec![component("BOLT", 1)]),
+            vec![stock("BOLT", 1, 10)],
+        );
+        assert_ok!(Assembly::create_work_order(
+            RuntimeOrigin::signed(ALICE),
+            work_order(0, 1)
+        ));
+        assert_ok!(Assembly::update_work_order_status(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            WorkOrderStatus::Released
+        ));
+
+        assert_noop!(
+            Assembly::assemble_product(RuntimeOrigin::signed(ALICE), work_order(0, 1), 100, 1),
+            Error::<Test>::WorkOrderNotStaged
+        );
+    });
+}
+
+#[test]
+fn cancelling_a_work_order_releases_its_equipment() {
+    new_test_ext().execute_with(|| {
+        set_up(
+            widget_recipe(vec![component("BOLT", 1)]),
+            vec![stock("BOLT", 1, 10)],
+        );
+        stage(work_order(0, 1));
+
+        assert_ok!(Assembly::update_work_order_status(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            WorkOrderStatus::Cancelled
+        ));
+
+        assert_eq!(crate::WorkOrderEquipment::<Test>::get(0), None);
+        assert_eq!(
+            pallet_inventory::EquipmentRegistry::<Test>::get(0)
+                .unwrap()
+                .status,
+            EquipmentStatus::Available
+        );
+        System::assert_has_event(
+            Event::EquipmentReleased {
+                work_order_number: 0,
+                asset_id: 0,
+            }
+            .into(),
+        );
+
+        // A cancelled work order cannot be picked up again
+        assert_noop!(
+            Assembly::update_work_order_status(
+                RuntimeOrigin::signed(ALICE),
+                0,
+                WorkOrderStatus::Released
+            ),
+            Error::<Test>::InvalidStatusTransition
+        );
+    });
+}
+
+#[test]
+fn work_orders_must_reference_a_stored_recipe() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Assembly::create_work_order(RuntimeOrigin::signed(ALICE), work_order(0, 1)),
+            pallet_inventory::Error::<Test>::RecipeNotFound
+        );
+
+        set_up(widget_recipe(vec![component("BOLT", 1)]), vec![]);
+
+        let mut unknown_version = work_order(0, 1);
+        unknown_version.recipe_id = 1;
+        assert_noop!(
+            Assembly::create_work_order(RuntimeOrigin::signed(ALICE), unknown_version),
+            pallet_inventory::Error::<Test>::RecipeNotFound
+        );
+    });
+}
+
+#[test]
+fn work_orders_build_the_recipe_version_they_reference() {
+    new_test_ext().execute_with(|| {
+        set_up(
+            widget_recipe(vec![component("BOLT", 1)]),
+            vec![stock("BOLT", 1, 10)],
+        );
+        let mut revised = widget_recipe(vec![component("BOLT", 3)]);
+        revised.recipe_id = 1;
+        assert_ok!(Inventory::insert_recipe(
+            RuntimeOrigin::signed(ALICE),
+            revised
+        ));
+
+        let mut work_order = work_order(0, 1);
+        work_order.recipe_id = 1;
+        stage(work_order);
+
+        assert_eq!(kitted_qty(0), 3);
+    });
+}
+
+#[test]
+fn stored_recipe_versions_cannot_be_replaced() {
+    new_test_ext().execute_with(|| {
+        set_up(widget_recipe(vec![component("BOLT", 1)]), vec![]);
+
+        assert_noop!(
+            Inventory::insert_recipe(
+                RuntimeOrigin::signed(ALICE),
+                widget_recipe(vec![component("BOLT", 5)])
+            ),
+            pallet_inventory::Error::<Test>::RecipeAlreadyExists
+        );
+    });
+}
+
+#[test]
+fn create_work_order_rejects_duplicates_and_empty_plans() {
+    new_test_ext().execute_with(|| {
+        set_up(widget_recipe(vec![component("BOLT", 1)]), vec![]);
+
+        assert_noop!(
+            Assembly::create_work_order(RuntimeOrigin::signed(ALICE), work_order(0, 0)),
+            Error::<Test>::InvalidPlannedQuantity
+        );
+
+        assert_ok!(Assembly::create_work_order(
+            RuntimeOrigin::signed(ALICE),
+            work_order(0, 1)
+        ));
+        assert_noop!(
+            Assembly::create_work_order(RuntimeOrigin::signed(ALICE), work_order(0, 2)),
+            Error::<Test>::WorkOrderAlreadyExists
+        );
+    });
+}
+
+/// Stage a work order for a bolt and two nuts, then lose one of the kitted nuts
+fn stage_short_of_a_nut() {
+    set_up(
+        widget_recipe(vec![component("BOLT", 1), component("NUT", 2)]),
+        vec![stock("BOLT", 1, 1), stock("NUT", 2, 2)],
+    );
+    stage(work_order(0, 1));
+
+    assert_ok!(Inventory::inventory_adjust(
+        RuntimeOrigin::signed(ALICE),
+        Employee::Bob,
+        sku("NUT"),
+        2,
+        AdjustDetails::Quantity {
+            original_qty: 2,
+            new_qty: 1,
+            reason: BoundedVec::truncate_from(b"Dropped".to_vec()),
+        }
+    ));
+}
+
+#[test]
+fn assembly_fails_without_consuming_anything_when_a_component_is_short() {
+    new_test_ext().execute_with(|| {
+        stage_short_of_a_nut();
+
+        assert_noop!(
+            Assembly::assemble_product(RuntimeOrigin::signed(ALICE), work_order(0, 1), 100, 1),
+            Error::<Test>::ComponentShortage { component_index: 1 }
+        );
+    });
+}
+
+#[test]
+fn component_shortages_name_the_short_sku_and_quantity() {
+    new_test_ext().execute_with(|| {
+        stage_short_of_a_nut();
+
+        assert_eq!(
+            Assembly::component_shortages(&ALICE, 0),
+            vec![ShortComponent {
+                component_index: 1,
+                sku: sku("NUT"),
+                short_qty: 1,
+            }]
+        );
+        // Nothing is short of a work order that does not exist
+        assert_eq!(Assembly::component_shortages(&ALICE, 1), vec![]);
+    });
+}
+
+#[test]
+fn kitted_items_sharing_a_serial_are_consumed_separately() {
+    new_test_ext().execute_with(|| {
+        set_up(
+            widget_recipe(vec![component("BOLT", 1), component("NUT", 1)]),
+            vec![stock("BOLT", 1, 1), stock("NUT", 1, 1)],
+        );
+        stage(work_order(0, 1));
+
+        assert_ok!(Assembly::assemble_product(
+            RuntimeOrigin::signed(ALICE),
+            work_order(0, 1),
+            100,
+            1
+        ));
+
+        for sku_name in ["BOLT", "NUT"] {
+            let consumed = pallet_inventory::Inventory::<Test>::get((ALICE, sku(sku_name), 1));
+            assert_eq!(consumed.map_or(0, |item| item.qty), 0);
+        }
+    });
+}
+
+#[test]
+fn assembly_rejects_output_serial_numbers_in_use() {
+    new_test_ext().execute_with(|| {
+        set_up(
+            widget_recipe(vec![component("BOLT", 1)]),
+            vec![stock("BOLT", 1, 1), stock("WIDGET", 100, 1)],
+        );
+        stage(work_order(0, 1));
+
+        assert_noop!(
+            Assembly::assemble_product(RuntimeOrigin::signed(ALICE), work_order(0, 1), 100, 1),
+            Error::<Test>::SerialNumberInUse
+        );
+    });
+}
+
+#[test]
+fn staging_kits_only_the_quantity_needed() {
+    new_test_ext().execute_with(|| {
+        set_up(
+            widget_recipe(vec![component("BOLT", 3)]),
+            vec![stock("BOLT", 1, 10)],
+        );
+        stage(work_order(0, 1));
+
+        // The three bolts are split off under the next serial and staged on their own
+        let kit: Vec<StagedItem> = vec![StagedItem {
+            sku: sku("BOLT"),
+            serial_number: 2,
+            qty: 3,
+        }];
+        assert_eq!(
+            crate::StagingArea::<Test>::get(0),
+            Some((ALICE, BoundedVec::truncate_from(kit)))
+        );
+
+        let remainder = pallet_inventory::Inventory::<Test>::get((ALICE, sku("BOLT"), 1)).unwrap();
+        assert_eq!(remainder.qty, 7);
+        assert_eq!(remainder.location, Location::Warehouse);
+        let staged = pallet_inventory::Inventory::<Test>::get((ALICE, sku("BOLT"), 2)).unwrap();
+        assert_eq!(staged.qty, 3);
+        assert_eq!(staged.location, Location::Staging);
+    });
+}
+
+#[test]
+fn staging_picks_stock_with_the_material_strategy() {
+    new_test_ext().execute_with(|| {
+        let mut older = stock("BOLT", 1, 3);
+        older.shelf_life = 100;
+        let mut newer = stock("BOLT", 2, 3);
+        newer.shelf_life = 10;
+        set_up(
+            widget_recipe(vec![component("BOLT", 3)]),
+            vec![older, newer],
+        );
+        assert_ok!(Inventory::insert_material(
+            RuntimeOrigin::signed(ALICE),
+            Material {
+                sku: sku("BOLT"),
+                ..Default::default()
+            }
+        ));
+        assert_ok!(Inventory::set_pick_strategy(
+            RuntimeOrigin::signed(ALICE),
+            sku("BOLT"),
+            PickStrategy::Fefo
+        ));
+
+        stage(work_order(0, 1));
+
+        // The newer bolts expire first
+        let kit: Vec<StagedItem> = vec![StagedItem {
+            sku: sku("BOLT"),
+            serial_number: 2,
+            qty: 3,
+        }];
+        assert_eq!(
+            crate::StagingArea::<Test>::get(0),
+            Some((ALICE, BoundedVec::truncate_from(kit)))
+        );
+        let older = pallet_inventory::Inventory::<Test>::get((ALICE, sku("BOLT"), 1)).unwrap();
+        assert_eq!(older.location, Location::Warehouse);
+    });
+}
+
+#[test]
+fn work_orders_only_consume_their_own_kit() {
+    new_test_ext().execute_with(|| {
+        set_up(
+            widget_recipe(vec![component("BOLT", 1)]),
+            vec![stock("BOLT", 1, 10)],
+        );
+        assert_ok!(Inventory::insert_equipment(
+            RuntimeOrigin::signed(ALICE),
+            EquipmentAsset {
+                asset_id: 1,
+                ..Default::default()
+            }
+        ));
+        stage(work_order(0, 1));
+        stage(work_order(1, 1));
+
+        assert_ok!(Assembly::assemble_product(
+            RuntimeOrigin::signed(ALICE),
+            work_order(0, 1),
+            100,
+            1
+        ));
+
+        assert_eq!(crate::StagingArea::<Test>::get(0), None);
+        assert_eq!(kitted_qty(1), 1);
+        let other_kit = pallet_inventory::Inventory::<Test>::get((ALICE, sku("BOLT"), 3)).unwrap();
+        assert_eq!(other_kit.qty, 1);
+        assert_eq!(other_kit.location, Location::Staging);
+    });
+}
+
+#[test]
+fn only_the_staging_owner_consumes_a_kit() {
+    new_test_ext().execute_with(|| {
+        set_up(
+            widget_recipe(vec![component("BOLT", 1)]),
+            vec![stock("BOLT", 1, 1)],
+        );
+        stage(work_order(0, 1));
+
+        assert_noop!(
+            Assembly::assemble_product(RuntimeOrigin::signed(BOB), work_order(0, 1), 100, 1),
+            Error::<Test>::NotStagingOwner
+        );
+        assert_noop!(
+            Assembly::unstage(RuntimeOrigin::signed(BOB), 0),
+            Error::<Test>::NotStagingOwner
+        );
+    });
+}
+
+#[test]
+fn unstage_returns_the_kit_and_releases_the_work_order() {
+    new_test_ext().execute_with(|| {
+        set_up(
+            widget_recipe(vec![component("BOLT", 1)]),
+            vec![stock("BOLT", 1, 1)],
+        );
+        stage(work_order(0, 1));
+        let (_, kit) = crate::StagingArea::<Test>::get(0).unwrap();
+
+        assert_ok!(Assembly::unstage(RuntimeOrigin::signed(ALICE), 0));
+
+        assert_eq!(crate::StagingArea::<Test>::get(0), None);
+        assert_eq!(
+            pallet_inventory::Inventory::<Test>::get((ALICE, sku("BOLT"), 1))
+                .unwrap()
+                .location,
+            Location::Warehouse
+        );
+        assert_eq!(
+            crate::WorkOrders::<Test>::get(0).unwrap().status,
+            WorkOrderStatus::Released
+        );
+        assert_eq!(crate::WorkOrderEquipment::<Test>::get(0), None);
+        System::assert_last_event(
+            Event::Unstaged {
+                who: ALICE,
+                work_order_number: 0,
+                items: kit,
+            }
+            .into(),
+        );
+
+        // The work order can be kitted again
+        assert_ok!(Assembly::prepare_staging_area(
+            RuntimeOrigin::signed(ALICE),
+            work_order(0, 1)
+        ));
+    });
+}
+
+#[test]
+fn unstage_requires_a_staged_or_cancelled_work_order() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Assembly::unstage(RuntimeOrigin::signed(ALICE), 0),
+            Error::<Test>::WorkOrderNotFound
+        );
+
+        set_up(widget_recipe(vec![component("BOLT", 1)]), vec![]);
+        assert_ok!(Assembly::create_work_order(
+            RuntimeOrigin::signed(ALICE),
+            work_order(0, 1)
+        ));
+        assert_ok!(Assembly::update_work_order_status(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            WorkOrderStatus::Released
+        ));
+
+        assert_noop!(
+            Assembly::unstage(RuntimeOrigin::signed(ALICE), 0),
+            Error::<Test>::CannotUnstage
+        );
+    });
+}
+
+#[test]
+fn cancelled_work_orders_can_still_return_their_kit() {
+    new_test_ext().execute_with(|| {
+        set_up(
+            widget_recipe(vec![component("BOLT", 1)]),
+            vec![stock("BOLT", 1, 1)],
+        );
+        stage(work_order(0, 1));
+        assert_ok!(Assembly::update_work_order_status(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            WorkOrderStatus::Cancelled
+        ));
+
+        assert_ok!(Assembly::unstage(RuntimeOrigin::signed(ALICE), 0));
+
+        assert_eq!(
+            pallet_inventory::Inventory::<Test>::get((ALICE, sku("BOLT"), 1))
+                .unwrap()
+                .location,
+            Location::Warehouse
+        );
+        assert_eq!(
+            crate::WorkOrders::<Test>::get(0).unwrap().status,
+            WorkOrderStatus::Cancelled
+        );
+    });
+}
+
+#[test]
+fn assembled_units_take_their_master_data_from_the_material() {
+    new_test_ext().execute_with(|| {
+        let material = Material {
+            sku: sku("WIDGET"),
+            abc_code: AbcCode::C,
+            product_type: ProductType::ComponentParts,
+            weight: 4,
+            shelf_life: 50,
+            ..Default::default()
+        };
+        assert_ok!(Inventory::insert_material(
+            RuntimeOrigin::signed(ALICE),
+            material.clone()
+        ));
+        set_up(
+            widget_recipe(vec![component("BOLT", 1)]),
+            vec![stock("BOLT", 1, 2)],
+        );
+
+        let mut work_order = work_order(7, 2);
+        work_order.assigned_to = Employee::Charlie;
+        work_order.output_location = Location::Shipping;
+        stage(work_order.clone());
+
+        System::set_block_number(5);
+        assert_ok!(Assembly::assemble_product(
+            RuntimeOrigin::signed(ALICE),
+            work_order,
+            100,
+            2
+        ));
+
+        // One finished good per unit, serialized consecutively in the work order's lot
+        for serial_number in [100, 101] {
+            let expected = Item {
+                moved_by: Employee::Charlie,
+                sku: sku("WIDGET"),
+                lot_number: 7,
+                serial_number,
+                material: material.clone(),
+                abc_code: AbcCode::C,
+                inventory_type: InventoryType::FinishedGood,
+                product_type: ProductType::ComponentParts,
+                qty: 1,
+                weight: 4,
+                shelf_life: 50,
+                cycle_count: 0,
+                created_at: 5,
+                production_date: 5,
+                location: Location::Shipping,
+            };
+            assert_eq!(
+                pallet_inventory::Inventory::<Test>::get((ALICE, sku("WIDGET"), serial_number)),
+                Some(expected.clone())
+            );
+
+            // Each unit holds its own share of the consumed bolts
+            let (product, bom) =
+                crate::AssembledProducts::<Test>::get((ALICE, sku("WIDGET"), serial_number))
+                    .unwrap();
+            assert_eq!(product, expected);
+            let materials: Vec<(Sku, SerialNumber, Qty)> = bom
+                .materials
+                .iter()
+                .map(|item| (item.sku.clone(), item.serial_number, item.qty))
+                .collect();
+            assert_eq!(materials, vec![(sku("BOLT"), 1, 1)]);
+        }
+        assert_eq!(
+            pallet_inventory::Inventory::<Test>::get((ALICE, sku("WIDGET"), 102)),
+            None
+        );
+    });
+}
+
+#[test]
+fn consumed_quantity_is_spread_across_the_units_produced() {
+    new_test_ext().execute_with(|| {
+        let mut recipe = widget_recipe(vec![component("BOLT", 1)]);
+        recipe.expected_yield = Percent::from_percent(50);
+        set_up(recipe, vec![stock("BOLT", 1, 4)]);
+
+        // Two units start four, but only three come out good
+        stage(work_order(0, 2));
+        assert_ok!(Assembly::assemble_product(
+            RuntimeOrigin::signed(ALICE),
+            work_order(0, 2),
+            100,
+            3
+        ));
+
+        let shares: Vec<Qty> = [100, 101, 102]
+            .into_iter()
+            .map(|serial_number| {
+                let (_, bom) =
+                    crate::AssembledProducts::<Test>::get((ALICE, sku("WIDGET"), serial_number))
+                        .unwrap();
+                bom.materials[0].qty
+            })
+            .collect();
+        assert_eq!(shares, vec![2, 1, 1]);
+    });
+}
+
+fn link(
+    parent: (&str, SerialNumber),
+    component: (&str, SerialNumber),
+    lot_number: LotNumber,
+    level: u32,
+) -> GenealogyLink {
+    GenealogyLink {
+        parent: ItemRef {
+            sku: sku(parent.0),
+            serial_number: parent.1,
+        },
+        component: ItemRef {
+            sku: sku(component.0),
+            serial_number: component.1,
+        },
+        lot_number,
+        qty: 1,
+        level,
+    }
+}
+
+/// Build two widgets from bolts of lot 9, one each in lots 0 and 1, then a gadget from both
+fn build_gadget() {
+    let mut bolts = vec![stock("BOLT", 1, 1), stock("BOLT", 2, 1)];
+    for bolt in bolts.iter_mut() {
+        bolt.lot_number = 9;
+    }
+    set_up(widget_recipe(vec![component("BOLT", 1)]), bolts);
+    for (work_order_number, serial_number) in [(0, 100), (1, 101)] {
+        stage(work_order(work_order_number, 1));
+        assert_ok!(Assembly::assemble_product(
+            RuntimeOrigin::signed(ALICE),
+            work_order(work_order_number, 1),
+            serial_number,
+            1
+        ));
+    }
+
+    let mut gadget = widget_recipe(vec![component("WIDGET", 2)]);
+    gadget.sku = sku("GADGET");
+    assert_ok!(Inventory::insert_recipe(
+        RuntimeOrigin::signed(ALICE),
+        gadget
+    ));
+    let mut gadget_order = work_order(2, 1);
+    gadget_order.recipe_sku = sku("GADGET");
+    stage(gadget_order.clone());
+    assert_ok!(Assembly::assemble_product(
+        RuntimeOrigin::signed(ALICE),
+        gadget_order,
+        200,
+        1
+    ));
+}
+
+fn assert_same_links(mut links: Vec<GenealogyLink>, expected: Vec<GenealogyLink>) {
+    assert_eq!(links.len(), expected.len());
+    for link in expected {
+        let position = links.iter().position(|found| *found == link).unwrap();
+        links.remove(position);
+    }
+}
+
+#[test]
+fn lot_where_used_follows_every_serial_of_the_lot_up_every_level() {
+    new_test_ext().execute_with(|| {
+        build_gadget();
+
+        assert_same_links(
+            Assembly::where_used_lot(&ALICE, sku("BOLT"), 9),
+            vec![
+                link(("WIDGET", 100), ("BOLT", 1), 9, 1),
+                link(("WIDGET", 101), ("BOLT", 2), 9, 1),
+                link(("GADGET", 200), ("WIDGET", 100), 0, 2),
+                link(("GADGET", 200), ("WIDGET", 101), 1, 2),
+            ],
+        );
+        assert_eq!(Assembly::where_used_lot(&ALICE, sku("BOLT"), 8), vec![]);
+    });
+}
+
+#[test]
+fn lot_where_used_lists_each_parent_once() {
+    new_test_ext().execute_with(|| {
+        // One widget takes both bolts of lot 9, then goes into a gadget
+        let mut bolts = vec![stock("BOLT", 1, 1), stock("BOLT", 2, 1)];
+        for bolt in bolts.iter_mut() {
+            bolt.lot_number = 9;
+        }
+        set_up(widget_recipe(vec![component("BOLT", 2)]), bolts);
+        stage(work_order(0, 1));
+        assert_ok!(Assembly::assemble_product(
+            RuntimeOrigin::signed(ALICE),
+            work_order(0, 1),
+            100,
+            1
+        ));
+
+        let mut gadget = widget_recipe(vec![component("WIDGET", 1)]);
+        gadget.sku = sku("GADGET");
+        assert_ok!(Inventory::insert_recipe(
+            RuntimeOrigin::signed(ALICE),
+            gadget
+        ));
+        let mut gadget_order = work_order(1, 1);
+        gadget_order.recipe_sku = sku("GADGET");
+        stage(gadget_order.clone());
+        assert_ok!(Assembly::assemble_product(
+            RuntimeOrigin::signed(ALICE),
+            gadget_order,
+            200,
+            1
+        ));
+
+        assert_same_links(
+            Assembly::where_used_lot(&ALICE, sku("BOLT"), 9),
+            vec![
+                link(("WIDGET", 100), ("BOLT", 1), 9, 1),
+                link(("WIDGET", 100), ("BOLT", 2), 9, 1),
+                link(("GADGET", 200), ("WIDGET", 100), 0, 2),
+            ],
+        );
+    });
+}
+
+#[test]
+fn serial_where_used_follows_only_that_serial() {
+    new_test_ext().execute_with(|| {
+        build_gadget();
+
+        assert_same_links(
+            Assembly::where_used_serial(&ALICE, sku("BOLT"), 2),
+            vec![
+                link(("WIDGET", 101), ("BOLT", 2), 9, 1),
+                link(("GADGET", 200), ("WIDGET", 101), 1, 2),
+            ],
+        );
+    });
+}
+
+#[test]
+fn serial_where_used_follows_serials_split_off_it() {
+    new_test_ext().execute_with(|| {
+        set_up(
+            widget_recipe(vec![component("BOLT", 1)]),
+            vec![stock("BOLT", 1, 10), stock("NUT", 2, 1)],
+        );
+        stage(work_order(0, 1));
+
+        // The bolts' next serial is taken by the nut at the warehouse
+        let kitted = pallet_inventory::Inventory::<Test>::get((ALICE, sku("BOLT"), 3)).unwrap();
+        assert_eq!(kitted.qty, 1);
+        let warehouse =
+            pallet_inventory::InventoryLocale::<Test>::get(Location::Warehouse).unwrap();
+        assert_eq!(
+            warehouse.get(&2).map(|item| item.sku.clone()),
+            Some(sku("NUT"))
+        );
+
+        assert_ok!(Assembly::assemble_product(
+            RuntimeOrigin::signed(ALICE),
+            work_order(0, 1),
+            100,
+            1
+        ));
+
+        assert_eq!(
+            Assembly::where_used_serial(&ALICE, sku("BOLT"), 1),
+            vec![link(("WIDGET", 100), ("BOLT", 3), 1, 1)]
+        );
+    });
+}
+
+#[test]
+fn as_built_contents_lists_components_at_every_level() {
+    new_test_ext().execute_with(|| {
+        build_gadget();
+
+        assert_same_links(
+            Assembly::as_built_contents(&ALICE, sku("GADGET"), 200),
+            vec![
+                link(("GADGET", 200), ("WIDGET", 100), 0, 1),
+                link(("GADGET", 200), ("WIDGET", 101), 1, 1),
+                link(("WIDGET", 100), ("BOLT", 1), 9, 2),
+                link(("WIDGET", 101), ("BOLT", 2), 9, 2),
+            ],
+        );
+        // Genealogy is kept per account
+        assert_eq!(
+            Assembly::as_built_contents(&BOB, sku("GADGET"), 200),
+            vec![]
+        );
+    });
+}
+
+fn scrap_details() -> ScrapDetails {
+    ScrapDetails {
+        issuer: Employee::Dave,
+        reason: BoundedVec::truncate_from(b"Teardown".to_vec()),
+        equipment: Equipment::Forklift,
+    }
+}
+
+/// Assemble widget 100 from the four bolts of serial 1
+fn build_widget() {
+    set_up(
+        widget_recipe(vec![component("BOLT", 4)]),
+        vec![stock("BOLT", 1, 4)],
+    );
+    stage(work_order(0, 1));
+    assert_ok!(Assembly::assemble_product(
+        RuntimeOrigin::signed(ALICE),
+        work_order(0, 1),
+        100,
+        1
+    ));
+}
+
+#[test]
+fn disassembly_recovers_part_of_each_component_and_scraps_the_rest() {
+    new_test_ext().execute_with(|| {
+        build_widget();
+
+        assert_ok!(Assembly::disassemble_product(
+            RuntimeOrigin::signed(ALICE),
+            sku("WIDGET"),
+            100,
+            Percent::from_percent(50),
+            scrap_details()
+        ));
+
+        // The product itself is scrapped
+        assert_eq!(
+            pallet_inventory::Inventory::<Test>::get((ALICE, sku("WIDGET"), 100)),
+            None
+        );
+        assert!(pallet_inventory::ScrapInventory::<Test>::contains_key((
+            ALICE,
+            sku("WIDGET"),
+            100
+        )));
+
+        // Half the bolts go back under their own serial, the other half are scrapped under the next
+        let recovered = pallet_inventory::Inventory::<Test>::get((ALICE, sku("BOLT"), 1)).unwrap();
+        assert_eq!(recovered.qty, 2);
+        assert_eq!(recovered.location, Location::Warehouse);
+        let scrapped = pallet_inventory::ScrapInventory::<Test>::get((ALICE, sku("BOLT"), 2))
+            .unwrap()
+            .item;
+        assert_eq!(scrapped.qty, 2);
+        assert_eq!(scrapped.lot_number, 1);
+
+        let teardown = TeardownRecord {
+            disassembled_at: 1,
+            recovery: Percent::from_percent(50),
+            components: BoundedVec::truncate_from(vec![RecoveredComponent {
+                component: ItemRef {
+                    sku: sku("BOLT"),
+                    serial_number: 1,
+                },
+                lot_number: 1,
+                recovered_qty: 2,
+                scrapped_qty: 2,
+                scrap_serial_number: Some(2),
+            }]),
+        };
+        assert_eq!(
+            crate::Teardowns::<Test>::get((ALICE, sku("WIDGET"), 100)),
+            Some(teardown.clone())
+        );
+        System::assert_last_event(
+            Event::ProductDisassembled {
+                who: ALICE,
+                sku: sku("WIDGET"),
+                serial_number: 100,
+                teardown,
+            }
+            .into(),
+        );
+
+        // The original genealogy is kept alongside the teardown
+        assert!(crate::AssembledProducts::<Test>::contains_key((
+            ALICE,
+            sku("WIDGET"),
+            100
+        )));
+    });
+}
+
+#[test]
+fn disassembly_scraps_under_a_serial_free_at_the_scrap_location() {
+    new_test_ext().execute_with(|| {
+        build_widget();
+
+        // Serial 2 is already listed at the scrap location, under another SKU
+        let mut nut = stock("NUT", 2, 1);
+        nut.location = Location::Scrap;
+        assert_ok!(Inventory::inventory_insertion(
+            RuntimeOrigin::signed(ALICE),
+            nut.clone()
+        ));
+
+        assert_ok!(Assembly::disassemble_product(
+            RuntimeOrigin::signed(ALICE),
+            sku("WIDGET"),
+            100,
+            Percent::from_percent(50),
+            scrap_details()
+        ));
+
+        let teardown = crate::Teardowns::<Test>::get((ALICE, sku("WIDGET"), 100)).unwrap();
+        assert_eq!(teardown.components[0].scrap_serial_number, Some(3));
+        assert_eq!(
+            pallet_inventory::InventoryLocale::<Test>::get(Location::Scrap)
+                .unwrap()
+                .get(&2),
+            Some(&nut)
+        );
+    });
+}
+
+#[test]
+fn full_recovery_scraps_no_components() {
+    new_test_ext().execute_with(|| {
+        build_widget();
+
+        assert_ok!(Assembly::disassemble_product(
+            RuntimeOrigin::signed(ALICE),
+            sku("WIDGET"),
+            100,
+            Percent::from_percent(100),
+            scrap_details()
+        ));
+
+        let teardown = crate::Teardowns::<Test>::get((ALICE, sku("WIDGET"), 100)).unwrap();
+        assert_eq!(teardown.components[0].recovered_qty, 4);
+        assert_eq!(teardown.components[0].scrap_serial_number, None);
+        assert_eq!(
+            pallet_inventory::Inventory::<Test>::get((ALICE, sku("BOLT"), 1))
+                .unwrap()
+                .qty,
+            4
+        );
+    });
+}
+
+#[test]
+fn only_assembled_serials_are_disassembled_once() {
+    new_test_ext().execute_with(|| {
+        build_widget();
+
+        assert_noop!(
+            Assembly::disassemble_product(
+                RuntimeOrigin::signed(ALICE),
+                sku("BOLT"),
+                1,
+                Percent::from_percent(100),
+                scrap_details()
+            ),
+            Error::<Test>::NotAssembled
+        );
+
+        assert_ok!(Assembly::disassemble_product(
+            RuntimeOrigin::signed(ALICE),
+            sku("WIDGET"),
+            100,
+            Percent::from_percent(100),
+            scrap_details()
+        ));
+        assert_noop!(
+            Assembly::disassemble_product(
+                RuntimeOrigin::signed(ALICE),
+                sku("WIDGET"),
+                100,
+                Percent::from_percent(100),
+                scrap_details()
+            ),
+            Error::<Test>::AlreadyDisassembled
+        );
+    });
+}
+
+#[test]
+fn held_products_cannot_be_disassembled() {
+    new_test_ext().execute_with(|| {
+        build_widget();
+        assert_ok!(Inventory::do_place_hold(
+            &ALICE,
+            sku("WIDGET"),
+            100,
+            HoldSource::Recall(0),
+            BoundedVec::truncate_from(b"Recalled".to_vec())
+        ));
+
+        assert_noop!(
+            Assembly::disassemble_product(
+                RuntimeOrigin::signed(ALICE),
+                sku("WIDGET"),
+                100,
+                Percent::from_percent(100),
+                scrap_details()
+            ),
+            pallet_inventory::Error::<Test>::ItemOnHold
+        );
+    });
+}
+
+fn reason(reason: &str) -> Reason {
+    BoundedVec::truncate_from(reason.as_bytes().to_vec())
+}
+
+fn item_ref(sku_name: &str, serial_number: SerialNumber) -> ItemRef {
+    ItemRef {
+        sku: sku(sku_name),
+        serial_number,
+    }
+}
+
+/// Build widget 100, stock replacement bolts and open a rework order on the widget
+fn open_rework_order(extra_stock: Vec<Item>) {
+    build_widget();
+    for item in extra_stock {
+        assert_ok!(Inventory::inventory_insertion(
+            RuntimeOrigin::signed(ALICE),
+            item
+        ));
+    }
+    assert_ok!(Assembly::create_rework_order(
+        RuntimeOrigin::signed(ALICE),
+        sku("WIDGET"),
+        100,
+        reason("Loose bolt"),
+        Employee::Charlie
+    ));
+}
+
+fn bom_quantities(serial_number: SerialNumber) -> Vec<(SerialNumber, Qty)> {
+    let (_, bom) =
+        crate::AssembledProducts::<Test>::get((ALICE, sku("WIDGET"), serial_number)).unwrap();
+    bom.materials
+        .iter()
+        .map(|item| (item.serial_number, item.qty))
+        .collect()
+}
+
+#[test]
+fn rework_order_archives_the_as_built_bom() {
+    new_test_ext().execute_with(|| {
+        open_rework_order(vec![]);
+
+        let (owner, rework_order) = crate::ReworkOrders::<Test>::get(0).unwrap();
+        assert_eq!(owner, ALICE);
+        assert_eq!(rework_order.product, item_ref("WIDGET", 100));
+        assert_eq!(rework_order.status, ReworkStatus::Open);
+        assert_eq!(rework_order.bom_revision, 0);
+        System::assert_last_event(
+            Event::ReworkOrderCreated {
+                who: ALICE,
+                rework_order,
+            }
+            .into(),
+        );
+
+        let (_, bom) = crate::AssembledProducts::<Test>::get((ALICE, sku("WIDGET"), 100)).unwrap();
+        assert_eq!(
+            crate::BomRevisions::<Test>::get((ALICE, sku("WIDGET"), 100, 0)),
+            Some(bom)
+        );
+
+        // Every later rework order archives the next revision
+        assert_ok!(Assembly::create_rework_order(
+            RuntimeOrigin::signed(ALICE),
+            sku("WIDGET"),
+            100,
+            reason("Scratched"),
+            Employee::Charlie
+        ));
+        assert_eq!(
+            crate::ReworkOrders::<Test>::get(1).unwrap().1.bom_revision,
+            1
+        );
+    });
+}
+
+#[test]
+fn replaced_parts_go_to_the_review_board() {
+    new_test_ext().execute_with(|| {
+        open_rework_order(vec![stock("BOLT", 5, 3)]);
+
+        assert_ok!(Assembly::replace_component(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            item_ref("BOLT", 1),
+            item_ref("BOLT", 5),
+            1,
+            PartDisposition::Mrb
+        ));
+
+        // The replacement is consumed and the removed bolt held for review
+        assert_eq!(
+            pallet_inventory::Inventory::<Test>::get((ALICE, sku("BOLT"), 5))
+                .unwrap()
+                .qty,
+            2
+        );
+        let removed = pallet_inventory::Inventory::<Test>::get((ALICE, sku("BOLT"), 1)).unwrap();
+        assert_eq!(removed.qty, 1);
+        assert_eq!(removed.location, Location::Mrb);
+
+        assert_eq!(bom_quantities(100), vec![(1, 3), (5, 1)]);
+        assert_eq!(
+            Assembly::where_used_serial(&ALICE, sku("BOLT"), 5),
+            vec![GenealogyLink {
+                parent: item_ref("WIDGET", 100),
+                component: item_ref("BOLT", 5),
+                lot_number: 5,
+                qty: 1,
+                level: 1,
+            }]
+        );
+
+        let replacement = ComponentReplacement {
+            removed: item_ref("BOLT", 1),
+            replacement: item_ref("BOLT", 5),
+            replacement_lot: 5,
+            qty: 1,
+            disposition: PartDisposition::Mrb,
+        };
+        assert_eq!(
+            crate::ReworkOrders::<Test>::get(0)
+                .unwrap()
+                .1
+                .replacements
+                .into_inner(),
+            vec![replacement.clone()]
+        );
+        System::assert_last_event(
+            Event::ComponentReplaced {
+                who: ALICE,
+                rework_order_number: 0,
+                replacement,
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn replaced_parts_can_be_scrapped() {
+    new_test_ext().execute_with(|| {
+        open_rework_order(vec![stock("BOLT", 5, 4)]);
+
+        assert_ok!(Assembly::replace_component(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            item_ref("BOLT", 1),
+            item_ref("BOLT", 5),
+            4,
+            PartDisposition::Scrap(scrap_details())
+        ));
+
+        // The removed bolts are scrapped under the next serial and leave the BOM
+        let scrapped = pallet_inventory::ScrapInventory::<Test>::get((ALICE, sku("BOLT"), 6))
+            .unwrap()
+            .item;
+        assert_eq!(scrapped.qty, 4);
+        assert_eq!(scrapped.lot_number, 1);
+        assert_eq!(bom_quantities(100), vec![(5, 4)]);
+        assert_eq!(Assembly::where_used_serial(&ALICE, sku("BOLT"), 1), vec![]);
+    });
+}
+
+#[test]
+fn replacements_must_match_the_removed_component() {
+    new_test_ext().execute_with(|| {
+        let mut held_bolt = stock("BOLT", 6, 1);
+        held_bolt.location = Location::Mrb;
+        let mut kitted_bolt = stock("BOLT", 8, 1);
+        kitted_bolt.location = Location::Staging;
+        open_rework_order(vec![
+            stock("BOLT", 5, 10),
+            held_bolt,
+            stock("NUT", 7, 1),
+            kitted_bolt,
+        ]);
+
+        let replace = |removed: ItemRef, replacement: ItemRef, qty: Qty| {
+            Assembly::replace_component(
+                RuntimeOrigin::signed(ALICE),
+                0,
+                removed,
+                replacement,
+                qty,
+                PartDisposition::Mrb,
+            )
+        };
+
+        assert_noop!(
+            replace(item_ref("BOLT", 1), item_ref("BOLT", 5), 5),
+            Error::<Test>::ComponentNotInBom
+        );
+        assert_noop!(
+            replace(item_ref("NUT", 7), item_ref("NUT", 7), 1),
+            Error::<Test>::ComponentNotInBom
+        );
+        assert_noop!(
+            replace(item_ref("BOLT", 1), item_ref("NUT", 7), 1),
+            Error::<Test>::ReplacementSkuMismatch
+        );
+        assert_noop!(
+            replace(item_ref("BOLT", 1), item_ref("BOLT", 6), 1),
+            Error::<Test>::StockNotUsable
+        );
+        assert_noop!(
+            replace(item_ref("BOLT", 1), item_ref("BOLT", 8), 1),
+            Error::<Test>::ItemStaged
+        );
+    });
+}
+
+#[test]
+fn rework_orders_are_worked_by_their_owner_until_completed() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Assembly::create_rework_order(
+                RuntimeOrigin::signed(ALICE),
+                sku("WIDGET"),
+                100,
+                reason("Loose bolt"),
+                Employee::Charlie
+            ),
+            Error::<Test>::NotAssembled
+        );
+        assert_noop!(
+            Assembly::complete_rework_order(RuntimeOrigin::signed(ALICE), 0),
+            Error::<Test>::ReworkOrderNotFound
+        );
+
+        open_rework_order(vec![stock("BOLT", 5, 1)]);
+
+        // The serial is held while it is under rework
+        assert!(pallet_inventory::Pallet::<Test>::is_on_hold(
+            &ALICE,
+            &sku("WIDGET"),
+            100
+        ));
+
+        assert_noop!(
+            Assembly::complete_rework_order(RuntimeOrigin::signed(BOB), 0),
+            Error::<Test>::NotReworkOwner
+        );
+
+        System::set_block_number(4);
+        assert_ok!(Assembly::complete_rework_order(
+            RuntimeOrigin::signed(ALICE),
+            0
+        ));
+        let (_, rework_order) = crate::ReworkOrders::<Test>::get(0).unwrap();
+        assert_eq!(rework_order.status, ReworkStatus::Completed);
+        assert_eq!(rework_order.completed_at, Some(4));
+        assert!(!pallet_inventory::Pallet::<Test>::is_on_hold(
+            &ALICE,
+            &sku("WIDGET"),
+            100
+        ));
+        System::assert_last_event(
+            Event::ReworkOrderCompleted {
+                who: ALICE,
+                rework_order_number: 0,
+            }
+            .into(),
+        );
+
+        assert_noop!(
+            Assembly::replace_component(
+                RuntimeOrigin::signed(ALICE),
+                0,
+                item_ref("BOLT", 1),
+                item_ref("BOLT", 5),
+                1,
+                PartDisposition::Mrb
+            ),
+            Error::<Test>::ReworkOrderClosed
+        );
+    });
+}
+
+fn output(sku_name: &str, qty: Qty, kind: OutputKind) -> RecipeOutput {
+    RecipeOutput {
+        sku: sku(sku_name),
+        qty,
+        kind,
+    }
+}
+
+/// A recipe making two widgets per batch, with three offcuts and a trim piece alongside
+fn cutting_recipe() -> Recipe {
+    let mut recipe = widget_recipe(vec![component("BOLT", 1)]);
+    recipe.output_quantity = 2;
+    recipe.secondary_outputs = BoundedVec::truncate_from(vec![
+        output("OFFCUT", 3, OutputKind::ByProduct),
+        output("TRIM", 1, OutputKind::CoProduct),
+    ]);
+    recipe
+}
+
+#[test]
+fn secondary_outputs_are_stocked_under_their_own_serials() {
+    new_test_ext().execute_with(|| {
+        set_up(cutting_recipe(), vec![stock("BOLT", 1, 1)]);
+        stage(work_order(0, 2));
+
+        assert_ok!(Assembly::assemble_product(
+            RuntimeOrigin::signed(ALICE),
+            work_order(0, 2),
+            100,
+            2
+        ));
+
+        // Each secondary output takes its own SKU's next serial free at the output location
+        let offcut = pallet_inventory::Inventory::<Test>::get((ALICE, sku("OFFCUT"), 0)).unwrap();
+        assert_eq!(offcut.qty, 3);
+        assert_eq!(offcut.lot_number, 0);
+        assert_eq!(offcut.inventory_type, InventoryType::RawMaterial);
+        let trim = pallet_inventory::Inventory::<Test>::get((ALICE, sku("TRIM"), 1)).unwrap();
+        assert_eq!(trim.qty, 1);
+        assert_eq!(trim.inventory_type, InventoryType::FinishedGood);
+
+        // Every output is traced to the bolt they were all made from
+        let mut parents: Vec<ItemRef> = Assembly::where_used_serial(&ALICE, sku("BOLT"), 1)
+            .into_iter()
+            .map(|link| link.parent)
+            .collect();
+        parents.sort_by_key(|parent| parent.serial_number);
+        assert_eq!(
+            parents,
+            vec![
+                item_ref("OFFCUT", 0),
+                item_ref("TRIM", 1),
+                item_ref("WIDGET", 100),
+                item_ref("WIDGET", 101),
+            ]
+        );
+    });
+}
+
+#[test]
+fn secondary_outputs_scale_with_the_quantity_produced() {
+    new_test_ext().execute_with(|| {
+        let mut recipe = cutting_recipe();
+        recipe.expected_yield = Percent::from_percent(50);
+        set_up(recipe, vec![stock("BOLT", 1, 2)]);
+        stage(work_order(0, 2));
+
+        // Half a batch makes one of the three offcuts and none of the trim
+        assert_ok!(Assembly::assemble_product(
+            RuntimeOrigin::signed(ALICE),
+            work_order(0, 2),
+            100,
+            1
+        ));
+
+        assert_eq!(
+            pallet_inventory::Inventory::<Test>::get((ALICE, sku("OFFCUT"), 0))
+                .unwrap()
+                .qty,
+            1
+        );
+        assert!(
+            pallet_inventory::Inventory::<Test>::iter_prefix((ALICE, sku("TRIM")))
+                .next()
+                .is_none()
+        );
+    });
+}
+
+#[test]
+fn secondary_outputs_skip_serials_already_stocked() {
+    new_test_ext().execute_with(|| {
+        set_up(
+            cutting_recipe(),
+            vec![stock("BOLT", 1, 1), stock("OFFCUT", 102, 1)],
+        );
+        stage(work_order(0, 2));
+
+        assert_ok!(Assembly::assemble_product(
+            RuntimeOrigin::signed(ALICE),
+            work_order(0, 2),
+            100,
+            2
+        ));
+
+        let offcut = |serial_number| {
+            pallet_inventory::Inventory::<Test>::get((ALICE, sku("OFFCUT"), serial_number))
+                .unwrap()
+                .qty
+        };
+        assert_eq!(offcut(102), 1);
+        assert_eq!(offcut(103), 3);
+    });
+}
+
+#[test]
+fn recipes_reject_invalid_secondary_outputs() {
+    new_test_ext().execute_with(|| {
+        let mut empty_output = cutting_recipe();
+        empty_output.secondary_outputs =
+            BoundedVec::truncate_from(vec![output("OFFCUT", 0, OutputKind::ByProduct)]);
+        assert_noop!(
+            Inventory::insert_recipe(RuntimeOrigin::signed(ALICE), empty_output),
+            pallet_inventory::Error::<Test>::InvalidSecondaryOutput
+        );
+
+        let mut primary_output = cutting_recipe();
+        primary_output.secondary_outputs =
+            BoundedVec::truncate_from(vec![output("WIDGET", 1, OutputKind::CoProduct)]);
+        assert_noop!(
+            Inventory::insert_recipe(RuntimeOrigin::signed(ALICE), primary_output),
+            pallet_inventory::Error::<Test>::InvalidSecondaryOutput
+        );
+    });
+}
+
+fn operation(
+    operation_number: OperationNumber,
+    equipment: Option<Equipment>,
+    components: Vec<u8>,
+) -> Operation {
+    Operation {
+        operation_number,
+        work_center: BoundedVec::truncate_from(b"LINE-1".to_vec()),
+        equipment,
+        standard_time: 10,
+        components: BoundedVec::truncate_from(components),
+    }
+}
+
+/// Stage two widgets built in two operations, bolting and then craning on the nut
+fn stage_routed_work_order() {
+    let mut recipe = widget_recipe(vec![component("BOLT", 2), component("NUT", 1)]);
+    recipe.routing = BoundedVec::truncate_from(vec![
+        operation(10, None, vec![0]),
+        operation(20, Some(Equipment::Crane), vec![1]),
+    ]);
+    set_up(recipe, vec![stock("BOLT", 1, 4), stock("NUT", 2, 2)]);
+    assert_ok!(Inventory::insert_equipment(
+        RuntimeOrigin::signed(ALICE),
+        EquipmentAsset {
+            asset_id: 1,
+            equipment_type: Equipment::Crane,
+            ..Default::default()
+        }
+    ));
+    stage(work_order(0, 2));
+}
+
+fn start(operation_number: OperationNumber) -> DispatchResult {
+    Assembly::start_operation(
+        RuntimeOrigin::signed(ALICE),
+        0,
+        operation_number,
+        Employee::Dave,
+    )
+}
+
+fn report(operation_number: OperationNumber, good_qty: Qty, close: bool) -> DispatchResult {
+    Assembly::complete_operation(
+        RuntimeOrigin::signed(ALICE),
+        0,
+        operation_number,
+        good_qty,
+        0,
+        15,
+        close,
+    )
+}
+
+fn qty_of(sku_name: &str, serial_number: SerialNumber) -> Qty {
+    pallet_inventory::Inventory::<Test>::get((ALICE, sku(sku_name), serial_number))
+        .unwrap()
+        .qty
+}
+
+#[test]
+fn operations_start_in_routing_order() {
+    new_test_ext().execute_with(|| {
+        stage_routed_work_order();
+
+        assert_noop!(start(30), Error::<Test>::OperationNotFound);
+        assert_noop!(start(20), Error::<Test>::OperationOutOfSequence);
+
+        assert_ok!(start(10));
+        assert_eq!(
+            crate::WorkOrders::<Test>::get(0).unwrap().status,
+            WorkOrderStatus::InProgress
+        );
+        System::assert_last_event(
+            Event::OperationStarted {
+                who: ALICE,
+                work_order_number: 0,
+                operation_number: 10,
+                asset_id: None,
+            }
+            .into(),
+        );
+        assert_noop!(start(10), Error::<Test>::OperationAlreadyStarted);
+
+        // The next operation starts once the first has passed on good units
+        assert_noop!(start(20), Error::<Test>::OperationOutOfSequence);
+        assert_ok!(report(10, 1, false));
+        assert_ok!(start(20));
+        assert_eq!(
+            crate::WorkOrderOperations::<Test>::get(0, 20)
+                .unwrap()
+                .asset_id,
+            Some(1)
+        );
+        assert_eq!(
+            pallet_inventory::EquipmentRegistry::<Test>::get(1)
+                .unwrap()
+                .status,
+            EquipmentStatus::InUse
+        );
+    });
+}
+
+#[test]
+fn partial_reports_consume_components_in_proportion() {
+    new_test_ext().execute_with(|| {
+        stage_routed_work_order();
+        assert_ok!(start(10));
+
+        // One of the two units started takes half the bolts
+        assert_ok!(report(10, 1, false));
+        assert_eq!(qty_of("BOLT", 1), 2);
+        let record = crate::WorkOrderOperations::<Test>::get(0, 10).unwrap();
+        assert_eq!(record.good_qty, 1);
+        assert_eq!(record.labor_minutes, 15);
+        assert_eq!(record.standard_minutes, 10);
+        assert_eq!(record.status, OperationStatus::InProgress);
+        System::assert_last_event(
+            Event::OperationReported {
+                who: ALICE,
+                work_order_number: 0,
+                operation_number: 10,
+                good_qty: 1,
+                scrapped_qty: 0,
+                labor_minutes: 15,
+                closed: false,
+            }
+            .into(),
+        );
+
+        assert_ok!(report(10, 1, true));
+        assert_eq!(qty_of("BOLT", 1), 0);
+        let record = crate::WorkOrderOperations::<Test>::get(0, 10).unwrap();
+        assert_eq!(record.good_qty, 2);
+        assert_eq!(record.labor_minutes, 30);
+        assert_eq!(record.standard_minutes, 20);
+        assert_eq!(record.status, OperationStatus::Completed);
+
+        // The bolts are recorded as they were when consumed from staging
+        assert_eq!(
+            record
+                .consumed
+                .iter()
+                .map(|item| (
+                    item.sku.clone(),
+                    item.serial_number,
+                    item.qty,
+                    &item.location
+                ))
+                .collect::<Vec<_>>(),
+            vec![(sku("BOLT"), 1, 4, &Location::Staging)]
+        );
+
+        assert_noop!(
+            report(10, 1, false),
+            Error::<Test>::OperationAlreadyCompleted
+        );
+    });
+}
+
+#[test]
+fn operations_cannot_report_more_than_was_passed_on() {
+    new_test_ext().execute_with(|| {
+        stage_routed_work_order();
+        assert_noop!(report(10, 1, false), Error::<Test>::OperationNotStarted);
+
+        assert_ok!(start(10));
+        assert_ok!(report(10, 1, false));
+        assert_ok!(start(20));
+
+        assert_noop!(
+            report(20, 2, false),
+            Error::<Test>::ExceedsPreviousOperation
+        );
+    });
+}
+
+#[test]
+fn operations_close_in_routing_order() {
+    new_test_ext().execute_with(|| {
+        stage_routed_work_order();
+        assert_ok!(start(10));
+        assert_ok!(report(10, 2, false));
+        assert_ok!(start(20));
+
+        // The second operation may report units but not close while the first is open
+        assert_noop!(report(20, 2, true), Error::<Test>::OperationOutOfSequence);
+        assert_ok!(report(20, 1, false));
+
+        assert_ok!(report(10, 0, true));
+        assert_ok!(report(20, 1, true));
+    });
+}
+
+#[test]
+fn routed_work_orders_assemble_once_every_operation_closes() {
+    new_test_ext().execute_with(|| {
+        stage_routed_work_order();
+        assert_ok!(start(10));
+        assert_ok!(report(10, 2, true));
+        assert_ok!(start(20));
+
+        assert_noop!(
+            Assembly::assemble_product(RuntimeOrigin::signed(ALICE), work_order(0, 2), 100, 2),
+            Error::<Test>::OperationsIncomplete
+        );
+
+        // Closing the last operation consumes the nuts and releases its crane
+        assert_ok!(report(20, 2, true));
+        assert_eq!(qty_of("NUT", 2), 0);
+        assert_eq!(
+            pallet_inventory::EquipmentRegistry::<Test>::get(1)
+                .unwrap()
+                .status,
+            EquipmentStatus::Available
+        );
+
+        assert_ok!(Assembly::assemble_product(
+            RuntimeOrigin::signed(ALICE),
+            work_order(0, 2),
+            100,
+            2
+        ));
+        assert_eq!(bom_quantities(100), vec![(1, 2), (2, 1)]);
+    });
+}
+
+#[test]
+fn operations_need_a_staged_routed_work_order() {
+    new_test_ext().execute_with(|| {
+        set_up(
+            widget_recipe(vec![component("BOLT", 1)]),
+            vec![stock("BOLT", 1, 1)],
+        );
+        assert_ok!(Assembly::create_work_order(
+            RuntimeOrigin::signed(ALICE),
+            work_order(0, 1)
+        ));
+        assert_noop!(start(10), Error::<Test>::WorkOrderNotStaged);
+
+        assert_ok!(Assembly::update_work_order_status(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            WorkOrderStatus::Released
+        ));
+        assert_ok!(Assembly::prepare_staging_area(
+            RuntimeOrigin::signed(ALICE),
+            work_order(0, 1)
+        ));
+        assert_noop!(start(10), Error::<Test>::NoRouting);
+    });
+}
+
+#[test]
+fn recipes_reject_invalid_routings() {
+    new_test_ext().execute_with(|| {
+        let routed = |routing: Vec<Operation>| {
+            let mut recipe = widget_recipe(vec![component("BOLT", 1), component("NUT", 1)]);
+            recipe.routing = BoundedVec::truncate_from(routing);
+            Inventory::insert_recipe(RuntimeOrigin::signed(ALICE), recipe)
+        };
+
+        // Out of order
+        assert_noop!(
+            routed(vec![
+                operation(20, None, vec![0]),
+                operation(10, None, vec![1])
+            ]),
+            pallet_inventory::Error::<Test>::InvalidRouting
+        );
+        // Unknown component
+        assert_noop!(
+            routed(vec![operation(10, None, vec![2])]),
+            pallet_inventory::Error::<Test>::InvalidRouting
+        );
+        // Component consumed twice
+        assert_noop!(
+            routed(vec![
+                operation(10, None, vec![0]),
+                operation(20, None, vec![0])
+            ]),
+            pallet_inventory::Error::<Test>::InvalidRouting
+        );
+    });
+}
+
+/// Stage a widget needing a kitted bolt, two backflushed drops of glue and a manual label
+fn stage_mixed_issue_work_order(glue_qty: Qty) {
+    let mut glue = component("GLUE", 2);
+    glue.issue_mode = IssueMode::Backflush;
+    let mut label = component("LABEL", 1);
+    label.issue_mode = IssueMode::Manual;
+    let mut staged_label = stock("LABEL", 4, 1);
+    staged_label.location = Location::Staging;
+    set_up(
+        widget_recipe(vec![component("BOLT", 1), glue, label]),
+        vec![
+            stock("BOLT", 1, 1),
+            stock("GLUE", 2, glue_qty),
+            stock("LABEL", 3, 3),
+            staged_label,
+        ],
+    );
+    stage(work_order(0, 1));
+}
+
+fn issue(sku_name: &str, serial_number: SerialNumber) -> DispatchResult {
+    Assembly::issue_component(
+        RuntimeOrigin::signed(ALICE),
+        0,
+        sku(sku_name),
+        serial_number,
+        1,
+    )
+}
+
+#[test]
+fn only_pre_kitted_components_are_staged() {
+    new_test_ext().execute_with(|| {
+        stage_mixed_issue_work_order(5);
+
+        let (_, kit) = crate::StagingArea::<Test>::get(0).unwrap();
+        assert_eq!(
+            kit.into_inner(),
+            vec![StagedItem {
+                sku: sku("BOLT"),
+                serial_number: 1,
+                qty: 1,
+            }]
+        );
+        assert_eq!(
+            Assembly::component_shortages(&ALICE, 0),
+            vec![ShortComponent {
+                component_index: 2,
+                sku: sku("LABEL"),
+                short_qty: 1,
+            }]
+        );
+    });
+}
+
+#[test]
+fn manual_components_must_be_issued_before_assembly() {
+    new_test_ext().execute_with(|| {
+        stage_mixed_issue_work_order(5);
+
+        assert_noop!(
+            Assembly::assemble_product(RuntimeOrigin::signed(ALICE), work_order(0, 1), 100, 1),
+            Error::<Test>::ComponentShortage { component_index: 2 }
+        );
+
+        // The issued label is split off into staging, the rest stays in stock
+        assert_ok!(issue("LABEL", 3));
+        assert_eq!(qty_of("LABEL", 3), 2);
+        assert_eq!(qty_of("LABEL", 5), 1);
+        assert_eq!(
+            pallet_inventory::Inventory::<Test>::get((ALICE, sku("LABEL"), 5))
+                .unwrap()
+                .location,
+            Location::Staging
+        );
+        let issued = StagedItem {
+            sku: sku("LABEL"),
+            serial_number: 5,
+            qty: 1,
+        };
+        assert_eq!(
+            crate::ManualIssues::<Test>::get(0).into_inner(),
+            vec![issued.clone()]
+        );
+        System::assert_last_event(
+            Event::ComponentIssued {
+                who: ALICE,
+                work_order_number: 0,
+                item: issued,
+            }
+            .into(),
+        );
+        assert_eq!(Assembly::component_shortages(&ALICE, 0), vec![]);
+
+        assert_ok!(Assembly::assemble_product(
+            RuntimeOrigin::signed(ALICE),
+            work_order(0, 1),
+            100,
+            1
+        ));
+        assert_eq!(crate::ManualIssues::<Test>::get(0).len(), 0);
+        assert_eq!(qty_of("LABEL", 5), 0);
+        assert_eq!(qty_of("LABEL", 3), 2);
+    });
+}
+
+#[test]
+fn issued_components_are_returned_when_unstaged() {
+    new_test_ext().execute_with(|| {
+        stage_mixed_issue_work_order(5);
+        assert_ok!(issue("LABEL", 3));
+
+        assert_ok!(Assembly::unstage(RuntimeOrigin::signed(ALICE), 0));
+
+        assert_eq!(crate::ManualIssues::<Test>::get(0).len(), 0);
+        assert_eq!(
+            pallet_inventory::Inventory::<Test>::get((ALICE, sku("LABEL"), 5))
+                .unwrap()
+                .location,
+            Location::Warehouse
+        );
+        assert_eq!(qty_of("LABEL", 5) + qty_of("LABEL", 3), 3);
+    });
+}
+
+#[test]
+fn no_more_can_be_issued_than_required() {
+    new_test_ext().execute_with(|| {
+        stage_mixed_issue_work_order(5);
+
+        assert_noop!(
+            Assembly::issue_component(RuntimeOrigin::signed(ALICE), 0, sku("LABEL"), 3, 2),
+            Error::<Test>::ExceedsRequiredQty
+        );
+        assert_ok!(issue("LABEL", 3));
+        assert_noop!(issue("LABEL", 3), Error::<Test>::ExceedsRequiredQty);
+    });
+}
+
+#[test]
+fn backflushed_components_are_drawn_from_stock_at_assembly() {
+    new_test_ext().execute_with(|| {
+        stage_mixed_issue_work_order(5);
+        assert_ok!(issue("LABEL", 3));
+
+        assert_ok!(Assembly::assemble_product(
+            RuntimeOrigin::signed(ALICE),
+            work_order(0, 1),
+            100,
+            1
+        ));
+
+        assert_eq!(qty_of("GLUE", 2), 3);
+        assert_eq!(
+            crate::BackflushHistory::<Test>::get((ALICE, sku("GLUE"), 2, 0)),
+            Some(BackflushRecord {
+                qty: 2,
+                operation_number: None,
+                consumed_at: 1,
+            })
+        );
+        assert_eq!(bom_quantities(100), vec![(5, 1), (2, 2), (1, 1)]);
+    });
+}
+
+#[test]
+fn backflushed_components_are_drawn_in_their_pick_order() {
+    new_test_ext().execute_with(|| {
+        stage_mixed_issue_work_order(5);
+        assert_ok!(issue("LABEL", 3));
+        assert_ok!(Inventory::inventory_insertion(
+            RuntimeOrigin::signed(ALICE),
+            stock("GLUE", 6, 5)
+        ));
+        assert_ok!(Inventory::insert_material(
+            RuntimeOrigin::signed(ALICE),
+            Material {
+                sku: sku("GLUE"),
+                ..Default::default()
+            }
+        ));
+        assert_ok!(Inventory::set_pick_strategy(
+            RuntimeOrigin::signed(ALICE),
+            sku("GLUE"),
+            PickStrategy::Lifo
+        ));
+
+        assert_ok!(Assembly::assemble_product(
+            RuntimeOrigin::signed(ALICE),
+            work_order(0, 1),
+            100,
+            1
+        ));
+
+        // The newest glue is drawn first
+        assert_eq!(qty_of("GLUE", 2), 5);
+        assert_eq!(qty_of("GLUE", 6), 3);
+    });
+}
+
+#[test]
+fn assembly_fails_when_backflush_stock_runs_short() {
+    new_test_ext().execute_with(|| {
+        stage_mixed_issue_work_order(1);
+        assert_ok!(issue("LABEL", 3));
+
+        assert_eq!(
+            Assembly::component_shortages(&ALICE, 0),
+            vec![ShortComponent {
+                component_index: 1,
+                sku: sku("GLUE"),
+                short_qty: 1,
+            }]
+        );
+        assert_noop!(
+            Assembly::assemble_product(RuntimeOrigin::signed(ALICE), work_order(0, 1), 100, 1),
+            Error::<Test>::ComponentShortage { component_index: 1 }
+        );
+    });
+}
+
+#[test]
+fn consigned_components_are_kitted_only_once_settled() {
+    new_test_ext().execute_with(|| {
+        set_up(widget_recipe(vec![component("BOLT", 3)]), vec![]);
+        assert_ok!(Inventory::inventory_insertion(
+            RuntimeOrigin::signed(BOB),
+            stock("BOLT", 1, 5)
+        ));
+        assert_ok!(Inventory::ship_transfer(
+            RuntimeOrigin::signed(BOB),
+            ALICE,
+            sku("BOLT"),
+            1,
+            true,
+            Employee::Bob
+        ));
+        assert_ok!(Inventory::receive_transfer(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            Employee::Bob
+        ));
+        assert_ok!(Assembly::create_work_order(
+            RuntimeOrigin::signed(ALICE),
+            work_order(0, 1)
+        ));
+        assert_ok!(Assembly::update_work_order_status(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            WorkOrderStatus::Released
+        ));
+
+        // The bolts still belong to Bob
+        assert_eq!(Assembly::kitting_candidates(&ALICE, &sku("BOLT")), vec![]);
+        assert_noop!(
+            Assembly::prepare_staging_area(RuntimeOrigin::signed(ALICE), work_order(0, 1)),
+            Error::<Test>::InsufficientInventory
+        );
+
+        assert_ok!(Inventory::settle_consignment(
+            RuntimeOrigin::signed(BOB),
+            ALICE,
+            sku("BOLT"),
+            1
+        ));
+        assert_ok!(Assembly::prepare_staging_area(
+            RuntimeOrigin::signed(ALICE),
+            work_order(0, 1)
+        ));
+    });
+}
+
+#[test]
+fn only_unstaged_manual_components_can_be_issued() {
+    new_test_ext().execute_with(|| {
+        stage_mixed_issue_work_order(5);
+
+        assert_noop!(issue("BOLT", 1), Error::<Test>::ComponentNotManual);
+        assert_noop!(issue("LABEL", 4), Error::<Test>::ItemStaged);
+        assert_noop!(
+            Assembly::issue_component(RuntimeOrigin::signed(BOB), 0, sku("LABEL"), 3, 1),
+            Error::<Test>::NotStagingOwner
+        );
+        assert_noop!(
+            Assembly::issue_component(RuntimeOrigin::signed(ALICE), 0, sku("LABEL"), 3, 0),
+            Error::<Test>::InsufficientInventory
+        );
+    });
+}
@@ -1,21 +1,171 @@
 use codec::{Decode, Encode};
 use frame_support::pallet_prelude::ConstU32;
 use frame_support::pallet_prelude::MaxEncodedLen;
+use frame_support::sp_runtime::Percent;
 use frame_support::BoundedVec;
-use pallet_inventory::types::{Item, Recipe};
+use pallet_inventory::types::{
+    AssetId, Employee, Item, Location, LotNumber, OperationNumber, Qty, Reason, Recipe,
+    RecipeComponent, RecipeId, ScrapDetails, SerialNumber, Sku,
+};
 use scale_info::TypeInfo;
 
 pub type WorkOrderNumber = u32;
+pub type DueDate = u32;
+pub type ReworkOrderNumber = u32;
+pub type BomRevision = u32;
+
+/// Deepest assembly level a genealogy query will walk
+pub const MAX_GENEALOGY_LEVELS: u32 = 16;
+/// Most links a single genealogy query will return
+pub const MAX_GENEALOGY_LINKS: usize = 1_000;
+/// Most materials an as-built BOM can hold
+pub const MAX_BOM_MATERIALS: u32 = 100;
+
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
+pub enum WorkOrderStatus {
+    #[default]
+    Planned,
+    Released,
+    Staged,
+    InProgress,
+    Completed,
+    Closed,
+    Cancelled,
+}
+
+impl WorkOrderStatus {
+    /// Whether a work order may be moved from this status to `next` by a status update call.
+    ///
+    /// `Staged` and `Completed` are only reached by staging and assembling the work order.
+    pub fn can_transition_to(&self, next: &WorkOrderStatus) -> bool {
+        use WorkOrderStatus::*;
+
+        matches!(
+            (self, next),
+            (Planned, Released)
+                | (Staged, InProgress)
+                | (Completed, Closed)
+                | (Planned | Released | Staged | InProgress, Cancelled)
+        )
+    }
+
+    /// Whether components may be consumed against a work order in this status
+    pub fn can_assemble(&self) -> bool {
+        matches!(self, WorkOrderStatus::Staged | WorkOrderStatus::InProgress)
+    }
+}
+
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+    Urgent,
+}
 
 #[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
 pub struct WorkOrder {
     pub work_order_number: WorkOrderNumber,
-    pub recipe: Recipe,
+    /// SKU of the stored recipe this work order builds
+    pub recipe_sku: Sku,
+    /// Version of the stored recipe this work order builds
+    pub recipe_id: RecipeId,
+    pub status: WorkOrderStatus,
+    pub planned_quantity: Qty,
+    /// Block by which the work order should be completed
+    pub due_date: DueDate,
+    pub assigned_to: Employee,
+    pub priority: Priority,
+    /// Where the finished goods are put away once assembled
+    pub output_location: Location,
 }
 
+impl WorkOrder {
+    /// Number of recipe batches needed to make the planned quantity, rounding up
+    pub fn batches(&self, recipe: &Recipe) -> Qty {
+        self.planned_quantity
+            .div_ceil(recipe.output_quantity.max(1))
+    }
+
+    /// Quantity of a component the whole work order needs, scaled from one recipe batch
+    pub fn required_qty(&self, recipe: &Recipe, component: &RecipeComponent) -> Qty {
+        recipe
+            .required_qty(component)
+            .saturating_mul(self.batches(recipe))
+    }
+
+    /// Number of units the whole work order starts, scaled from one recipe batch
+    pub fn planned_input_qty(&self, recipe: &Recipe) -> Qty {
+        recipe
+            .planned_input_qty()
+            .saturating_mul(self.batches(recipe))
+    }
+}
+
+/// A recipe component a work order does not have enough of
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct ShortComponent {
+    /// Position of the component in the recipe's `required_components`
+    pub component_index: u8,
+    pub sku: Sku,
+    pub short_qty: Qty,
+}
+
+/// Quantity of a serialized item kitted for a work order
 #[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct StagedItem {
+    pub sku: Sku,
+    pub serial_number: SerialNumber,
+    pub qty: Qty,
+}
+
+/// Every item kitted for a single work order
+pub type StagingKit = BoundedVec<StagedItem, ConstU32<100>>;
+
+/// Snapshots of consumed stock, each with the quantity consumed
+pub type ConsumedItems = BoundedVec<Item, ConstU32<MAX_BOM_MATERIALS>>;
+
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
+pub enum OperationStatus {
+    #[default]
+    InProgress,
+    Completed,
+}
+
+/// Stock backflushed from a single item for a single work order
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct BackflushRecord {
+    pub qty: Qty,
+    /// The operation whose report drew the stock down, `None` when drawn at assembly
+    pub operation_number: Option<OperationNumber>,
+    /// Block of the latest backflush
+    pub consumed_at: u32,
+}
+
+/// Progress of one routing operation of a work order
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct OperationRecord {
+    pub status: OperationStatus,
+    pub operator: Employee,
+    /// The asset locked for the operation's own equipment, if it needs any
+    pub asset_id: Option<AssetId>,
+    pub started_at: u32,
+    pub completed_at: Option<u32>,
+    /// Good units passed on to the next operation so far
+    pub good_qty: Qty,
+    pub scrapped_qty: Qty,
+    /// Labor reported against the operation, in minutes
+    pub labor_minutes: u32,
+    /// Standard labor for the units reported so far, from the operation's standard time
+    pub standard_minutes: u32,
+    /// Stock the operation has consumed so far, as it was when consumed
+    pub consumed: ConsumedItems,
+}
+
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
 pub struct Bom {
-    pub materials: BoundedVec<Item, ConstU32<100>>,
+    pub materials: BoundedVec<Item, ConstU32<MAX_BOM_MATERIALS>>,
 }
 
 impl Bom {
@@ -26,3 +176,112 @@ impl Bom {
         }
     }
 }
+
+/// Expected versus actual yield of a single assembly, kept for reporting
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct YieldRecord {
+    /// Units started, i.e. the planned quantity grossed up for the expected yield
+    pub planned_input_qty: Qty,
+    /// Good units the work order plans to produce
+    pub expected_qty: Qty,
+    /// Good units actually produced
+    pub actual_qty: Qty,
+    pub expected_yield: Percent,
+    pub actual_yield: Percent,
+}
+
+impl YieldRecord {
+    /// Build the yield record for a work order given the quantity actually produced
+    pub fn new(work_order: &WorkOrder, recipe: &Recipe, actual_qty: Qty) -> Self {
+        let planned_input_qty = work_order.planned_input_qty(recipe);
+
+        YieldRecord {
+            planned_input_qty,
+            expected_qty: work_order.planned_quantity,
+            actual_qty,
+            expected_yield: recipe.expected_yield,
+            actual_yield: Percent::from_rational(actual_qty, planned_input_qty.max(1)),
+        }
+    }
+}
+
+/// A serialized item, identified by its SKU and serial number
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct ItemRef {
+    pub sku: Sku,
+    pub serial_number: SerialNumber,
+}
+
+/// One edge of the assembly genealogy: a component consumed into an assembled serial
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct GenealogyLink {
+    /// The assembled serial the component went into
+    pub parent: ItemRef,
+    pub component: ItemRef,
+    pub lot_number: LotNumber,
+    pub qty: Qty,
+    /// Number of assembly levels between this link and the queried item, starting at 1
+    pub level: u32,
+}
+
+/// A component recovered, in part or in full, from a disassembled product
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct RecoveredComponent {
+    pub component: ItemRef,
+    pub lot_number: LotNumber,
+    /// Quantity returned to inventory
+    pub recovered_qty: Qty,
+    /// Quantity scrapped with the product
+    pub scrapped_qty: Qty,
+    /// The serial the scrapped quantity was scrapped under, if any was
+    pub scrap_serial_number: Option<SerialNumber>,
+}
+
+/// The teardown of an assembled serial, kept alongside its original genealogy
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct TeardownRecord {
+    pub disassembled_at: u32,
+    pub recovery: Percent,
+    pub components: BoundedVec<RecoveredComponent, ConstU32<100>>,
+}
+
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
+pub enum ReworkStatus {
+    #[default]
+    Open,
+    Completed,
+}
+
+/// Where a part removed by a rework order is sent
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub enum PartDisposition {
+    /// Scrapped out of inventory
+    Scrap(ScrapDetails),
+    /// Returned to inventory at `Location::Mrb`, for the Material Review Board to decide on
+    Mrb,
+}
+
+/// A component swapped out of an assembled serial by a rework order
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct ComponentReplacement {
+    pub removed: ItemRef,
+    pub replacement: ItemRef,
+    pub replacement_lot: LotNumber,
+    pub qty: Qty,
+    pub disposition: PartDisposition,
+}
+
+/// A rework or repair order reopening an assembled serial
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct ReworkOrder {
+    pub rework_order_number: ReworkOrderNumber,
+    pub product: ItemRef,
+    pub reason: Reason,
+    pub assigned_to: Employee,
+    pub status: ReworkStatus,
+    /// The revision the serial's as-built BOM was archived under when the order was opened
+    pub bom_revision: BomRevision,
+    pub opened_at: u32,
+    pub completed_at: Option<u32>,
+    pub replacements: BoundedVec<ComponentReplacement, ConstU32<50>>,
+}
@@ -2,11 +2,19 @@ use crate::Config;
 use crate::Error;
 use crate::{pallet::Pallet, types::*};
 use crate::{AdjustInventory, GlobalInventory, Inventory, Materials, Recipes, ScrapInventory};
-use crate::{Event, InventoryLocale};
+use crate::{ApprovalPolicies, ApprovalRequests, Approvers, NextApprovalRequestId};
+use crate::{Consignments, NextTransferId, TransferredItems, Transfers};
+use crate::{EquipmentRegistry, Event, InventoryLocale, PickStrategies, QualityHolds, SplitItems};
+use crate::{NextRequisitionId, OpenRequisitions, PurchaseRequisitions, ReorderPolicies};
+use crate::{RequestApprovals, RequestExpiries};
 use codec::{Encode, MaxEncodedLen};
-use frame_support::pallet_prelude::DispatchError;
-use frame_support::sp_runtime::DispatchResult;
+use frame_support::ensure;
+use frame_support::pallet_prelude::{DispatchError, Weight};
+use frame_support::sp_runtime::{DispatchResult, Percent, SaturatedConversion};
+use frame_support::storage::with_storage_layer;
+use frame_support::traits::Get;
 use frame_support::BoundedBTreeMap;
+use scale_info::prelude::vec::Vec;
 
 impl<T: Config> Pallet<T> {
     /// Insert a new item into inventory
@@ -41,14 +49,14 @@ impl<T: Config> Pallet<T> {
                 match inventory {
                     Some(ref mut map) => {
                         // Insert the item using its serial number as the key
-                        map.try_insert(item.serial_number.clone(), item.clone())
+                        map.try_insert(item.serial_number, item.clone())
                             .map_err(|_| Error::<T>::InventoryFull)?;
                     }
                     None => {
                         // Create a new BoundedBTreeMap and insert the item
                         let mut new_map = BoundedBTreeMap::default();
                         new_map
-                            .try_insert(item.serial_number.clone(), item.clone())
+                            .try_insert(item.serial_number, item.clone())
                             .map_err(|_| Error::<T>::InventoryFull)?;
                         *inventory = Some(new_map);
                     }
@@ -75,18 +83,37 @@ impl<T: Config> Pallet<T> {
         serial_number: SerialNumber,
         scrap_details: ScrapDetails,
     ) -> DispatchResult {
+        // Items on quality hold must be released before they can be scrapped
+        ensure!(
+            !Self::is_on_hold(who, &sku, serial_number),
+            Error::<T>::ItemOnHold
+        );
+        // Allocated items must be deallocated before they can be scrapped
+        ensure!(
+            T::StockAllocations::allocated_qty(who, &sku, serial_number) == 0,
+            Error::<T>::ItemAllocated
+        );
+        // Consigned items are still owned by another account
+        Self::ensure_not_consigned(who, &sku, serial_number)?;
+
         // Fetch the item to be scrapped
         let item = <Inventory<T>>::take((who, sku.clone(), serial_number))
             .ok_or(Error::<T>::InventoryNotFound)?;
+        ensure!(
+            item.inventory_type != InventoryType::Transit,
+            Error::<T>::ItemInTransit
+        );
 
         let scrap_item = ScrapItem {
             item: item.clone(),
             details: scrap_details,
         };
 
+        let previous_qty = <GlobalInventory<T>>::get(&sku).unwrap_or_default();
+
         // Scrap the item by sending it to the ScrapInventory storage
         <ScrapInventory<T>>::insert((who, sku.clone(), serial_number), scrap_item);
-        <GlobalInventory<T>>::try_mutate_exists(sku, |qty| -> DispatchResult {
+        <GlobalInventory<T>>::try_mutate_exists(&sku, |qty| -> DispatchResult {
             if let Some(current_qty) = qty {
                 if *current_qty >= item.qty {
                     *current_qty -= item.qty;
@@ -114,6 +141,9 @@ impl<T: Config> Pallet<T> {
             },
         )?;
 
+        Self::check_reorder_point(&sku, previous_qty);
+        T::StockChanges::on_stock_changed(who, &item, None);
+
         Ok(())
     }
 
@@ -124,6 +154,17 @@ impl<T: Config> Pallet<T> {
         moved_by: Employee,
         adjust_details: AdjustDetails,
     ) -> DispatchResult {
+        // Items on quality hold cannot be moved
+        ensure!(
+            !Self::is_on_hold(who, &item.sku, item.serial_number),
+            Error::<T>::ItemOnHold
+        );
+        // Items shipped to another account are moved by receiving or cancelling the transfer
+        ensure!(
+            item.inventory_type != InventoryType::Transit,
+            Error::<T>::ItemInTransit
+        );
+
         // Get the enum variant of the AdjustDetails
         match adjust_details {
             AdjustDetails::Location {
@@ -132,6 +173,7 @@ impl<T: Config> Pallet<T> {
                 ..
             } => {
                 // Update the item's fields
+                let before = item.clone();
                 item.moved_by = moved_by.clone();
                 item.location = new_location.clone();
 
@@ -153,12 +195,12 @@ impl<T: Config> Pallet<T> {
                     new_location.clone(),
                     |location_items| -> Result<(), DispatchError> {
                         if let Some(ref mut map) = location_items {
-                            map.try_insert(item.serial_number.clone(), item.clone())
+                            map.try_insert(item.serial_number, item.clone())
                                 .map_err(|_| DispatchError::from(Error::<T>::InventoryFull))?;
                         } else {
                             let mut new_map = BoundedBTreeMap::default();
                             new_map
-                                .try_insert(item.serial_number.clone(), item.clone())
+                                .try_insert(item.serial_number, item.clone())
                                 .map_err(|_| DispatchError::from(Error::<T>::InventoryFull))?;
                             *location_items = Some(new_map);
                         }
@@ -184,6 +226,8 @@ impl<T: Config> Pallet<T> {
                     },
                 );
 
+                T::StockChanges::on_stock_changed(who, &before, Some(&item));
+
                 // Emit the move
                 Self::deposit_event(Event::MoveItem {
                     sender: who.clone(),
@@ -206,13 +250,28 @@ impl<T: Config> Pallet<T> {
         mut item: Item,
         adjust_details: AdjustDetails,
     ) -> DispatchResult {
+        // Items on quality hold cannot be consumed or adjusted
+        ensure!(
+            !Self::is_on_hold(who, &item.sku, item.serial_number),
+            Error::<T>::ItemOnHold
+        );
+        ensure!(
+            item.inventory_type != InventoryType::Transit,
+            Error::<T>::ItemInTransit
+        );
+
         match adjust_details {
             AdjustDetails::Quantity {
                 ref original_qty,
                 ref new_qty,
                 ..
             } => {
+                let before = Item {
+                    qty: *original_qty,
+                    ..item.clone()
+                };
                 item.qty = *new_qty;
+                let previous_qty = <GlobalInventory<T>>::get(&item.sku).unwrap_or_default();
                 // Update the item in storage
                 <Inventory<T>>::mutate(
                     (who, item.sku.clone(), item.serial_number),
@@ -232,7 +291,7 @@ impl<T: Config> Pallet<T> {
                             if diff > 0 {
                                 *current_qty = current_qty.saturating_add(diff as u32);
                             } else {
-                                let abs_diff = diff.abs() as u32;
+                                let abs_diff = diff.unsigned_abs() as u32;
                                 if *current_qty >= abs_diff {
                                     *current_qty -= abs_diff;
                                 } else {
@@ -252,7 +311,7 @@ impl<T: Config> Pallet<T> {
                     item.location.clone(),
                     |location_items| -> DispatchResult {
                         if let Some(ref mut map) = location_items {
-                            map.try_insert(item.serial_number.clone(), item.clone())
+                            map.try_insert(item.serial_number, item.clone())
                                 .map_err(|_| Error::<T>::InventoryFull)?;
                         } else {
                             return Err(Error::<T>::LocationNotFound.into());
@@ -271,6 +330,9 @@ impl<T: Config> Pallet<T> {
                     },
                 );
 
+                Self::check_reorder_point(&item.sku, previous_qty);
+                T::StockChanges::on_stock_changed(who, &before, Some(&item));
+
                 // Emit the adjustment
                 Self::deposit_event(Event::AdjustItem {
                     sender: who.clone(),
@@ -296,12 +358,62 @@ impl<T: Config> Pallet<T> {
             return Err(Error::<T>::InvalidSkuLength.into());
         }
 
-        // Insert the updated BoundedBTreeMap back into storage
-        <Recipes<T>>::insert(recipe.sku.clone(), recipe);
+        // A recipe that yields nothing can never satisfy its output quantity
+        ensure!(!recipe.expected_yield.is_zero(), Error::<T>::InvalidYield);
+
+        // A component that is entirely scrapped can never be consumed
+        ensure!(
+            recipe
+                .required_components
+                .iter()
+                .all(|component| component.scrap_percent < Percent::from_percent(100)),
+            Error::<T>::InvalidScrapFactor
+        );
+
+        // Secondary outputs are stocked under their own SKU
+        ensure!(
+            recipe
+                .secondary_outputs
+                .iter()
+                .all(|output| output.qty > 0 && output.sku != recipe.sku),
+            Error::<T>::InvalidSecondaryOutput
+        );
+
+        // Operations run in order, and each component is consumed by at most one of them
+        let mut routed_components: Vec<u8> = Vec::new();
+        let mut previous_operation: Option<OperationNumber> = None;
+        for operation in recipe.routing.iter() {
+            ensure!(
+                previous_operation.map_or(true, |previous| operation.operation_number > previous),
+                Error::<T>::InvalidRouting
+            );
+            for index in operation.components.iter() {
+                ensure!(
+                    (*index as usize) < recipe.required_components.len()
+                        && !routed_components.contains(index),
+                    Error::<T>::InvalidRouting
+                );
+                routed_components.push(*index);
+            }
+            previous_operation = Some(operation.operation_number);
+        }
+
+        // Recipe versions are immutable once work orders can reference them
+        ensure!(
+            !<Recipes<T>>::contains_key(&recipe.sku, recipe.recipe_id),
+            Error::<T>::RecipeAlreadyExists
+        );
+
+        <Recipes<T>>::insert(recipe.sku.clone(), recipe.recipe_id, recipe);
 
         Ok(())
     }
 
+    /// Fetch a recipe by SKU and version
+    pub fn get_recipe(sku: &Sku, recipe_id: RecipeId) -> Result<Recipe, DispatchError> {
+        <Recipes<T>>::get(sku, recipe_id).ok_or(Error::<T>::RecipeNotFound.into())
+    }
+
     /// Insert a new material into storage
     pub fn do_insert_material(material: Material) -> DispatchResult {
         // Check if the material already exists
@@ -322,8 +434,10 @@ impl<T: Config> Pallet<T> {
             return Err(Error::<T>::MaterialNotFound.into());
         }
 
-        // Remove the material
+        // Remove the material, and stop reordering it
         <Materials<T>>::remove(&sku);
+        <ReorderPolicies<T>>::remove(&sku);
+        <PickStrategies<T>>::remove(&sku);
 
         Ok(())
     }
@@ -347,4 +461,859 @@ impl<T: Config> Pallet<T> {
 
         Ok(())
     }
+
+    /// Register a new equipment asset
+    pub fn do_insert_equipment(asset: EquipmentAsset) -> DispatchResult {
+        // Check if the asset ID is already taken
+        if <EquipmentRegistry<T>>::contains_key(asset.asset_id) {
+            return Err(Error::<T>::EquipmentAlreadyExists.into());
+        }
+
+        // New assets enter service available, locks and maintenance set the other statuses
+        ensure!(
+            asset.status == EquipmentStatus::Available,
+            Error::<T>::InvalidEquipmentStatus
+        );
+
+        <EquipmentRegistry<T>>::insert(asset.asset_id, asset);
+
+        Ok(())
+    }
+
+    /// Set the status of an equipment asset, returning its previous status
+    pub fn do_update_equipment_status(
+        asset_id: AssetId,
+        status: EquipmentStatus,
+    ) -> Result<EquipmentStatus, DispatchError> {
+        <EquipmentRegistry<T>>::try_mutate(
+            asset_id,
+            |asset| -> Result<EquipmentStatus, DispatchError> {
+                let asset = asset.as_mut().ok_or(Error::<T>::EquipmentNotFound)?;
+                let original_status = asset.status.clone();
+                asset.status = status;
+                Ok(original_status)
+            },
+        )
+    }
+
+    /// Lock the first available asset of the given equipment type
+    ///
+    /// The asset is marked as in use until it is released with [`Self::do_release_equipment`].
+    pub fn do_lock_equipment(equipment: &Equipment) -> Result<AssetId, DispatchError> {
+        let mut asset = <EquipmentRegistry<T>>::iter_values()
+            .find(|asset| {
+                asset.equipment_type == *equipment && asset.status == EquipmentStatus::Available
+            })
+            .ok_or(Error::<T>::EquipmentUnavailable)?;
+
+        asset.status = EquipmentStatus::InUse;
+        asset.usage_count = asset.usage_count.saturating_add(1);
+        <EquipmentRegistry<T>>::insert(asset.asset_id, asset.clone());
+
+        Ok(asset.asset_id)
+    }
+
+    /// Release an asset locked by [`Self::do_lock_equipment`]
+    ///
+    /// Assets that were taken down or sent to maintenance while locked keep their status.
+    pub fn do_release_equipment(asset_id: AssetId) -> DispatchResult {
+        <EquipmentRegistry<T>>::try_mutate(asset_id, |asset| -> DispatchResult {
+            let asset = asset.as_mut().ok_or(Error::<T>::EquipmentNotFound)?;
+            if asset.status == EquipmentStatus::InUse {
+                asset.status = EquipmentStatus::Available;
+            }
+            Ok(())
+        })
+    }
+
+    /// Place an item on quality hold on behalf of a hold source
+    pub fn do_place_hold(
+        who: &T::AccountId,
+        sku: Sku,
+        serial_number: SerialNumber,
+        source: HoldSource,
+        reason: Reason,
+    ) -> DispatchResult {
+        ensure!(
+            <Inventory<T>>::contains_key((who, sku.clone(), serial_number)),
+            Error::<T>::InventoryNotFound
+        );
+
+        <QualityHolds<T>>::insert((who, sku, serial_number, source), reason);
+
+        Ok(())
+    }
+
+    /// Release the hold a source placed on an item
+    ///
+    /// The item stays on hold while any other source still holds it.
+    pub fn do_release_hold(
+        who: &T::AccountId,
+        sku: Sku,
+        serial_number: SerialNumber,
+        source: HoldSource,
+    ) {
+        <QualityHolds<T>>::remove((who, sku, serial_number, source));
+    }
+
+    /// Choose the stock to pick to cover a required quantity.
+    ///
+    /// Candidates are paired with the quantity that can be taken from each. Those the strategy
+    /// allows are taken in the strategy's order, for stock taken to `destination`, until the
+    /// requirement is met. Returns each chosen item with the quantity taken from it, which
+    /// falls short when the candidates do.
+    pub fn pick_stock(
+        mut candidates: Vec<(Item, Qty)>,
+        required_qty: Qty,
+        strategy: &PickStrategy,
+        destination: &Location,
+    ) -> Vec<(Item, Qty)> {
+        candidates.retain(|(item, _)| strategy.allows(item));
+        candidates.sort_by_key(|(item, _)| strategy.sort_key(item, destination));
+
+        let mut remaining = required_qty;
+        let mut picked = Vec::new();
+        for (item, available) in candidates {
+            if remaining == 0 {
+                break;
+            }
+
+            let qty = remaining.min(available);
+            if qty == 0 {
+                continue;
+            }
+            remaining -= qty;
+            picked.push((item, qty));
+        }
+
+        picked
+    }
+
+    /// The strategy a material's stock is picked with, FIFO unless one has been set
+    pub fn pick_strategy(sku: &Sku) -> PickStrategy {
+        <PickStrategies<T>>::get(sku).unwrap_or_default()
+    }
+
+    /// Set the strategy a material's stock is picked with
+    pub fn do_set_pick_strategy(sku: Sku, strategy: PickStrategy) -> DispatchResult {
+        ensure!(
+            <Materials<T>>::contains_key(&sku),
+            Error::<T>::MaterialNotFound
+        );
+
+        <PickStrategies<T>>::insert(&sku, strategy);
+
+        Ok(())
+    }
+
+    /// Whether an item is on quality hold from any source
+    pub fn is_on_hold(who: &T::AccountId, sku: &Sku, serial_number: SerialNumber) -> bool {
+        <QualityHolds<T>>::iter_prefix((who, sku, serial_number))
+            .next()
+            .is_some()
+    }
+
+    /// Split part of an item's quantity off into a new serial at the same location
+    ///
+    /// The new serial takes the SKU's next serial number not already stocked at the location,
+    /// and keeps every other detail of the item, including who it is consigned from. The split is
+    /// recorded against the original serial for genealogy. The SKU's global quantity is
+    /// unchanged. Returns the new item.
+    pub fn do_split_item(who: &T::AccountId, item: &Item, qty: Qty) -> Result<Item, DispatchError> {
+        ensure!(
+            !Self::is_on_hold(who, &item.sku, item.serial_number),
+            Error::<T>::ItemOnHold
+        );
+        ensure!(
+            item.inventory_type != InventoryType::Transit,
+            Error::<T>::ItemInTransit
+        );
+        ensure!(qty > 0 && qty < item.qty, Error::<T>::InsufficientInventory);
+
+        let mut remainder = item.clone();
+        remainder.qty -= qty;
+
+        let serial_number = Self::free_serial_number(who, &item.sku, &item.location);
+
+        let mut split = item.clone();
+        split.serial_number = serial_number;
+        split.qty = qty;
+
+        for stored in [&remainder, &split] {
+            <Inventory<T>>::insert((who, stored.sku.clone(), stored.serial_number), stored);
+            <InventoryLocale<T>>::try_mutate(
+                stored.location.clone(),
+                |location_items| -> DispatchResult {
+                    location_items
+                        .get_or_insert_with(BoundedBTreeMap::default)
+                        .try_insert(stored.serial_number, stored.clone())
+                        .map_err(|_| Error::<T>::InventoryFull)?;
+                    Ok(())
+                },
+            )?;
+        }
+
+        if let Some(owner) = <Consignments<T>>::get((who, item.sku.clone(), item.serial_number)) {
+            <Consignments<T>>::insert((who, split.sku.clone(), split.serial_number), owner);
+        }
+
+        <SplitItems<T>>::insert(
+            (
+                who,
+                item.sku.clone(),
+                item.serial_number,
+                split.serial_number,
+            ),
+            qty,
+        );
+        T::StockChanges::on_stock_changed(who, item, Some(&remainder));
+
+        Self::deposit_event(Event::ItemSplit {
+            sender: who.clone(),
+            item: remainder,
+            split: split.clone(),
+        });
+
+        Ok(split)
+    }
+
+    /// Every serial split off an item, directly or from an earlier split, up to
+    /// `MAX_SPLIT_DESCENDANTS`
+    pub fn split_descendants(
+        who: &T::AccountId,
+        sku: &Sku,
+        serial_number: SerialNumber,
+    ) -> Vec<SerialNumber> {
+        let mut descendants = Vec::new();
+        let mut frontier = Vec::from([serial_number]);
+
+        while let Some(parent) = frontier.pop() {
+            for child in <SplitItems<T>>::iter_key_prefix((who, sku, parent)) {
+                if descendants.len() >= MAX_SPLIT_DESCENDANTS {
+                    return descendants;
+                }
+                descendants.push(child);
+                frontier.push(child);
+            }
+        }
+
+        descendants
+    }
+
+    /// The serial number after the highest one an account has stocked or scrapped for a SKU
+    pub fn next_serial_number(who: &T::AccountId, sku: &Sku) -> SerialNumber {
+        <Inventory<T>>::iter_key_prefix((who, sku.clone()))
+            .chain(<ScrapInventory<T>>::iter_key_prefix((who, sku.clone())))
+            .max()
+            .map_or(0, |serial_number| serial_number.saturating_add(1))
+    }
+
+    /// Bound a reason for an inventory change
+    pub fn reason(reason: &str) -> Result<Reason, DispatchError> {
+        Reason::try_from(reason.as_bytes().to_vec()).map_err(|_| Error::<T>::ReasonTooLong.into())
+    }
+
+    /// The current block number
+    pub fn current_block() -> u32 {
+        frame_system::Pallet::<T>::block_number().saturated_into::<u32>()
+    }
+
+    /// The SKU's next serial number that is also free at a location
+    ///
+    /// Locations list their items by serial alone, whatever their SKU, so the next serial is
+    /// skipped past any serial already stocked there.
+    pub fn free_serial_number(who: &T::AccountId, sku: &Sku, location: &Location) -> SerialNumber {
+        let stocked_here = <InventoryLocale<T>>::get(location).unwrap_or_default();
+        let mut serial_number = Self::next_serial_number(who, sku);
+        while stocked_here.contains_key(&serial_number) {
+            serial_number = serial_number.saturating_add(1);
+        }
+        serial_number
+    }
+
+    /// Ensure a change leaves at least an item's allocated quantity in it
+    pub fn ensure_allocation_kept(
+        who: &T::AccountId,
+        item: &Item,
+        remaining_qty: Qty,
+    ) -> DispatchResult {
+        let allocated = T::StockAllocations::allocated_qty(who, &item.sku, item.serial_number);
+        ensure!(remaining_qty >= allocated, Error::<T>::ItemAllocated);
+
+        Ok(())
+    }
+
+    /// Ensure an item is not held on consignment, and so can be consumed, scrapped or shipped
+    pub fn ensure_not_consigned(
+        who: &T::AccountId,
+        sku: &Sku,
+        serial_number: SerialNumber,
+    ) -> DispatchResult {
+        ensure!(
+            !<Consignments<T>>::contains_key((who, sku, serial_number)),
+            Error::<T>::ItemConsigned
+        );
+
+        Ok(())
+    }
+
+    /// Ship an item to another account
+    ///
+    /// The item moves to shipping as `InventoryType::Transit`, in the shipping account's
+    /// inventory until it is received.
+    pub fn do_ship_transfer(
+        who: &T::AccountId,
+        to: &T::AccountId,
+        sku: Sku,
+        serial_number: SerialNumber,
+        consignment: bool,
+        shipped_by: Employee,
+    ) -> Result<Transfer, DispatchError> {
+        ensure!(who != to, Error::<T>::InvalidTransfer);
+
+        let mut item = <Inventory<T>>::get((who, sku.clone(), serial_number))
+            .ok_or(Error::<T>::InventoryNotFound)?;
+        ensure!(
+            !Self::is_on_hold(who, &sku, serial_number),
+            Error::<T>::ItemOnHold
+        );
+        ensure!(
+            item.inventory_type != InventoryType::Transit,
+            Error::<T>::ItemInTransit
+        );
+        ensure!(
+            !<Inventory<T>>::contains_key((to, sku.clone(), serial_number)),
+            Error::<T>::SerialNumberInUse
+        );
+        Self::ensure_allocation_kept(who, &item, 0)?;
+
+        // Stock held on consignment can only go back to its owner, who then holds it outright
+        if let Some(owner) = <Consignments<T>>::get((who, sku.clone(), serial_number)) {
+            ensure!(owner == *to && !consignment, Error::<T>::ConsignedStock);
+        }
+
+        let transfer_id = <NextTransferId<T>>::get();
+        let transfer = Transfer {
+            transfer_id,
+            sku,
+            serial_number,
+            qty: item.qty,
+            inventory_type: item.inventory_type.clone(),
+            from_location: item.location.clone(),
+            consignment,
+            status: TransferStatus::InTransit,
+            shipped_by: shipped_by.clone(),
+            shipped_at: Self::current_block(),
+            received_at: None,
+        };
+
+        let before = item.clone();
+        item.inventory_type = InventoryType::Transit;
+        item.location = Location::Shipping;
+        item.moved_by = shipped_by;
+        Self::rehome_item(who, who, &item, &transfer.from_location)?;
+        T::StockChanges::on_stock_changed(who, &before, Some(&item));
+
+        <Transfers<T>>::insert(transfer_id, (who.clone(), to.clone(), transfer.clone()));
+        <NextTransferId<T>>::put(transfer_id.saturating_add(1));
+
+        Ok(transfer)
+    }
+
+    /// Receive an item shipped to the account into receiving, under the account's ownership
+    /// unless it was shipped on consignment
+    ///
+    /// Returns the shipping account and the received transfer.
+    pub fn do_receive_transfer(
+        who: &T::AccountId,
+        transfer_id: TransferId,
+        received_by: Employee,
+    ) -> Result<(T::AccountId, Transfer), DispatchError> {
+        let (from, to, mut transfer) =
+            <Transfers<T>>::get(transfer_id).ok_or(Error::<T>::TransferNotFound)?;
+        ensure!(to == *who, Error::<T>::NotTransferRecipient);
+        ensure!(
+            transfer.status == TransferStatus::InTransit,
+            Error::<T>::TransferNotInTransit
+        );
+
+        let sku = transfer.sku.clone();
+        let serial_number = transfer.serial_number;
+        let mut item = <Inventory<T>>::get((&from, sku.clone(), serial_number))
+            .ok_or(Error::<T>::InventoryNotFound)?;
+        ensure!(
+            !Self::is_on_hold(&from, &sku, serial_number),
+            Error::<T>::ItemOnHold
+        );
+        ensure!(
+            !<Inventory<T>>::contains_key((who, sku.clone(), serial_number)),
+            Error::<T>::SerialNumberInUse
+        );
+
+        item.inventory_type = transfer.inventory_type.clone();
+        item.location = Location::Receiving;
+        item.moved_by = received_by;
+        Self::rehome_item(&from, who, &item, &Location::Shipping)?;
+
+        // Consigned stock shipped back to its owner is no longer consigned
+        <Consignments<T>>::remove((&from, sku.clone(), serial_number));
+        if transfer.consignment {
+            <Consignments<T>>::insert((who, sku.clone(), serial_number), from.clone());
+        }
+        <TransferredItems<T>>::insert((&from, sku, serial_number), who.clone());
+
+        transfer.status = TransferStatus::Received;
+        transfer.received_at = Some(Self::current_block());
+        <Transfers<T>>::insert(transfer_id, (from.clone(), to, transfer.clone()));
+
+        Ok((from, transfer))
+    }
+
+    /// Call off a transfer that has not been received, returning the item to where it was
+    /// shipped from
+    pub fn do_cancel_transfer(who: &T::AccountId, transfer_id: TransferId) -> DispatchResult {
+        let (from, to, mut transfer) =
+            <Transfers<T>>::get(transfer_id).ok_or(Error::<T>::TransferNotFound)?;
+        ensure!(from == *who, Error::<T>::NotTransferShipper);
+        ensure!(
+            transfer.status == TransferStatus::InTransit,
+            Error::<T>::TransferNotInTransit
+        );
+
+        let mut item = <Inventory<T>>::get((who, transfer.sku.clone(), transfer.serial_number))
+            .ok_or(Error::<T>::InventoryNotFound)?;
+        ensure!(
+            !Self::is_on_hold(who, &transfer.sku, transfer.serial_number),
+            Error::<T>::ItemOnHold
+        );
+
+        item.inventory_type = transfer.inventory_type.clone();
+        item.location = transfer.from_location.clone();
+        Self::rehome_item(who, who, &item, &Location::Shipping)?;
+
+        transfer.status = TransferStatus::Cancelled;
+        <Transfers<T>>::insert(transfer_id, (from, to, transfer));
+
+        Ok(())
+    }
+
+    /// Hand ownership of an item `holder` holds on consignment over to it
+    ///
+    /// Only the account that owns the consigned stock can settle it.
+    pub fn do_settle_consignment(
+        who: &T::AccountId,
+        holder: &T::AccountId,
+        sku: Sku,
+        serial_number: SerialNumber,
+    ) -> DispatchResult {
+        let owner = <Consignments<T>>::get((holder, sku.clone(), serial_number))
+            .ok_or(Error::<T>::NotConsigned)?;
+        ensure!(owner == *who, Error::<T>::NotConsignmentOwner);
+
+        <Consignments<T>>::remove((holder, sku, serial_number));
+
+        Ok(())
+    }
+
+    /// The account that owns an item held on consignment, if it is
+    pub fn consignment_owner(
+        who: &T::AccountId,
+        sku: &Sku,
+        serial_number: SerialNumber,
+    ) -> Option<T::AccountId> {
+        <Consignments<T>>::get((who, sku, serial_number))
+    }
+
+    /// Raise an approval request for a change to an item, if it is above the account's thresholds
+    ///
+    /// Returns `None` when the change needs no approval and can be applied straight away.
+    pub fn do_request_approval(
+        who: &T::AccountId,
+        item: &Item,
+        change: PendingChange,
+    ) -> Result<Option<ApprovalRequest>, DispatchError> {
+        let Some(policy) = <ApprovalPolicies<T>>::get(who) else {
+            return Ok(None);
+        };
+
+        let qty = match &change {
+            PendingChange::Adjust {
+                adjust_details: AdjustDetails::Quantity { new_qty, .. },
+                ..
+            } => item.qty.abs_diff(*new_qty),
+            // Anything but a quantity change is rejected when it is applied
+            PendingChange::Adjust { .. } => return Ok(None),
+            PendingChange::Scrap(_) => item.qty,
+        };
+        let unit_value = <Materials<T>>::get(&item.sku)
+            .map(|material| material.unit_value)
+            .unwrap_or_default();
+        let value = (unit_value as InventoryValue).saturating_mul(qty as InventoryValue);
+        if !policy.requires_approval(qty, value) {
+            return Ok(None);
+        }
+
+        // Changes that could not be applied now are not worth approving
+        ensure!(
+            !Self::is_on_hold(who, &item.sku, item.serial_number),
+            Error::<T>::ItemOnHold
+        );
+        ensure!(
+            item.inventory_type != InventoryType::Transit,
+            Error::<T>::ItemInTransit
+        );
+
+        let now = Self::current_block();
+        let request_id = <NextApprovalRequestId<T>>::get();
+        let request = ApprovalRequest {
+            request_id,
+            sku: item.sku.clone(),
+            serial_number: item.serial_number,
+            change,
+            item_qty: item.qty,
+            qty,
+            value,
+            required_approvals: policy.required_approvals,
+            approvals: 0,
+            status: ApprovalStatus::Pending,
+            created_at: now,
+            expires_at: now.saturating_add(policy.expires_after),
+        };
+
+        <RequestExpiries<T>>::try_mutate(request.expires_at, |request_ids| {
+            request_ids
+                .try_push(request_id)
+                .map_err(|_| Error::<T>::TooManyExpiringRequests)
+        })?;
+        <ApprovalRequests<T>>::insert(request_id, (who.clone(), request.clone()));
+        <NextApprovalRequestId<T>>::put(request_id.saturating_add(1));
+
+        Ok(Some(request))
+    }
+
+    /// Set an account's approval policy, which cannot need more approvals than it has approvers
+    pub fn do_set_approval_policy(owner: &T::AccountId, policy: ApprovalPolicy) -> DispatchResult {
+        ensure!(
+            (policy.qty_threshold.is_some() || policy.value_threshold.is_some())
+                && policy.required_approvals > 0
+                && policy.expires_after > 0,
+            Error::<T>::InvalidApprovalPolicy
+        );
+        ensure!(
+            policy.required_approvals as usize <= <Approvers<T>>::iter_prefix(owner).count(),
+            Error::<T>::NotEnoughApprovers
+        );
+
+        <ApprovalPolicies<T>>::insert(owner, policy);
+
+        Ok(())
+    }
+
+    /// Remove one of an account's approvers, keeping enough for its approval policy
+    pub fn do_remove_approver(owner: &T::AccountId, approver: &T::AccountId) -> DispatchResult {
+        <Approvers<T>>::take(owner, approver).ok_or(Error::<T>::ApproverNotFound)?;
+
+        if let Some(policy) = <ApprovalPolicies<T>>::get(owner) {
+            ensure!(
+                policy.required_approvals as usize <= <Approvers<T>>::iter_prefix(owner).count(),
+                Error::<T>::NotEnoughApprovers
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Approve a pending change, applying it to the owner's stock once it has enough approvals
+    ///
+    /// A change that can no longer be applied, e.g. because the item changed since it was
+    /// requested, leaves nothing of it behind and closes the request as failed. Returns the
+    /// owning account, the updated request, and the error the change failed with, if it did.
+    pub fn do_approve_request(
+        who: &T::AccountId,
+        request_id: ApprovalRequestId,
+    ) -> Result<(T::AccountId, ApprovalRequest, Option<DispatchError>), DispatchError> {
+        let (owner, mut request) = Self::pending_request(who, request_id)?;
+        ensure!(
+            !<RequestApprovals<T>>::contains_key(request_id, who),
+            Error::<T>::AlreadyApproved
+        );
+
+        <RequestApprovals<T>>::insert(request_id, who, ());
+        request.approvals = request.approvals.saturating_add(1);
+
+        let mut failure = None;
+        if request.approvals >= request.required_approvals {
+            match with_storage_layer(|| Self::apply_request(&owner, &request)) {
+                Ok(()) => request.status = ApprovalStatus::Approved,
+                Err(error) => {
+                    request.status = ApprovalStatus::Failed;
+                    failure = Some(error);
+                }
+            }
+        }
+
+        <ApprovalRequests<T>>::insert(request_id, (owner.clone(), request.clone()));
+
+        Ok((owner, request, failure))
+    }
+
+    /// Apply an approved change to the owner's stock
+    fn apply_request(owner: &T::AccountId, request: &ApprovalRequest) -> DispatchResult {
+        let item = <Inventory<T>>::get((owner, request.sku.clone(), request.serial_number))
+            .ok_or(Error::<T>::InventoryNotFound)?;
+        ensure!(item.qty == request.item_qty, Error::<T>::ItemChanged);
+
+        match request.change.clone() {
+            PendingChange::Adjust {
+                issuer,
+                adjust_details,
+            } => {
+                // Stock may have been allocated since the change was requested
+                if let AdjustDetails::Quantity { new_qty, .. } = &adjust_details {
+                    Self::ensure_allocation_kept(owner, &item, *new_qty)?;
+                }
+                Self::do_inventory_adjust(owner, issuer, item, adjust_details)
+            }
+            PendingChange::Scrap(scrap_details) => Self::do_inventory_scrap(
+                owner,
+                request.sku.clone(),
+                request.serial_number,
+                scrap_details,
+            ),
+        }
+    }
+
+    /// Reject a pending change, returning the owning account
+    pub fn do_reject_request(
+        who: &T::AccountId,
+        request_id: ApprovalRequestId,
+    ) -> Result<T::AccountId, DispatchError> {
+        let (owner, mut request) = Self::pending_request(who, request_id)?;
+
+        request.status = ApprovalStatus::Rejected;
+        <ApprovalRequests<T>>::insert(request_id, (owner.clone(), request));
+
+        Ok(owner)
+    }
+
+    /// Expire the approval requests still pending at their expiry block
+    pub fn do_expire_requests(now: u32) -> Weight {
+        let request_ids = <RequestExpiries<T>>::take(now);
+        let mut reads: u64 = 1;
+        let mut writes: u64 = 1;
+
+        for request_id in request_ids {
+            reads = reads.saturating_add(1);
+            let Some((owner, mut request)) = <ApprovalRequests<T>>::get(request_id) else {
+                continue;
+            };
+            if request.status != ApprovalStatus::Pending {
+                continue;
+            }
+
+            request.status = ApprovalStatus::Expired;
+            <ApprovalRequests<T>>::insert(request_id, (owner.clone(), request));
+            writes = writes.saturating_add(1);
+
+            Self::deposit_event(Event::RequestExpired { owner, request_id });
+        }
+
+        T::DbWeight::get().reads_writes(reads, writes)
+    }
+
+    /// Set the reorder policy of a material, reordering straight away if stock is already low
+    pub fn do_set_reorder_policy(sku: Sku, policy: ReorderPolicy) -> DispatchResult {
+        ensure!(
+            <Materials<T>>::contains_key(&sku),
+            Error::<T>::MaterialNotFound
+        );
+        ensure!(
+            policy.safety_stock <= policy.reorder_point && policy.reorder_qty > 0,
+            Error::<T>::InvalidReorderPolicy
+        );
+
+        <ReorderPolicies<T>>::insert(&sku, policy.clone());
+
+        let on_hand = <GlobalInventory<T>>::get(&sku).unwrap_or_default();
+        if on_hand <= policy.reorder_point {
+            Self::raise_reorder(&sku, &policy, on_hand);
+        }
+
+        Ok(())
+    }
+
+    /// Close a draft purchase requisition as converted or cancelled
+    pub fn do_close_requisition(
+        requisition_id: RequisitionId,
+        status: RequisitionStatus,
+    ) -> Result<PurchaseRequisition, DispatchError> {
+        <PurchaseRequisitions<T>>::try_mutate(
+            requisition_id,
+            |requisition| -> Result<PurchaseRequisition, DispatchError> {
+                let requisition = requisition
+                    .as_mut()
+                    .ok_or(Error::<T>::RequisitionNotFound)?;
+                ensure!(
+                    requisition.status == RequisitionStatus::Draft,
+                    Error::<T>::RequisitionNotDraft
+                );
+
+                requisition.status = status;
+                if <OpenRequisitions<T>>::get(&requisition.sku) == Some(requisition_id) {
+                    <OpenRequisitions<T>>::remove(&requisition.sku);
+                }
+
+                Ok(requisition.clone())
+            },
+        )
+    }
+
+    /// Return a converted purchase requisition to draft, once the order it was converted into
+    /// is cancelled
+    ///
+    /// It becomes the SKU's open requisition again, unless another has been raised since.
+    pub fn do_reopen_requisition(
+        requisition_id: RequisitionId,
+    ) -> Result<PurchaseRequisition, DispatchError> {
+        <PurchaseRequisitions<T>>::try_mutate(
+            requisition_id,
+            |requisition| -> Result<PurchaseRequisition, DispatchError> {
+                let requisition = requisition
+                    .as_mut()
+                    .ok_or(Error::<T>::RequisitionNotFound)?;
+                ensure!(
+                    requisition.status == RequisitionStatus::Converted,
+                    Error::<T>::RequisitionNotConverted
+                );
+
+                requisition.status = RequisitionStatus::Draft;
+                if !<OpenRequisitions<T>>::contains_key(&requisition.sku) {
+                    <OpenRequisitions<T>>::insert(&requisition.sku, requisition_id);
+                }
+
+                Ok(requisition.clone())
+            },
+        )
+    }
+
+    /// A pending request that `who` may approve or reject, with the account whose stock it changes
+    fn pending_request(
+        who: &T::AccountId,
+        request_id: ApprovalRequestId,
+    ) -> Result<(T::AccountId, ApprovalRequest), DispatchError> {
+        let (owner, request) =
+            <ApprovalRequests<T>>::get(request_id).ok_or(Error::<T>::ApprovalRequestNotFound)?;
+        ensure!(
+            <Approvers<T>>::contains_key(&owner, who),
+            Error::<T>::NotApprover
+        );
+        ensure!(
+            request.status == ApprovalStatus::Pending,
+            Error::<T>::RequestNotPending
+        );
+
+        Ok((owner, request))
+    }
+
+    /// Store an item under the `to` account in place of its record under `from`, and move it
+    /// from `original_location` to its current location
+    ///
+    /// Fails if another item is already stocked under the serial at its current location.
+    fn rehome_item(
+        from: &T::AccountId,
+        to: &T::AccountId,
+        item: &Item,
+        original_location: &Location,
+    ) -> DispatchResult {
+        <InventoryLocale<T>>::try_mutate_exists(
+            original_location.clone(),
+            |location_items| -> DispatchResult {
+                if let Some(ref mut map) = location_items {
+                    map.remove(&item.serial_number);
+                    Ok(())
+                } else {
+                    Err(Error::<T>::LocationNotFound.into())
+                }
+            },
+        )?;
+        <InventoryLocale<T>>::try_mutate(
+            item.location.clone(),
+            |location_items| -> DispatchResult {
+                let map = location_items.get_or_insert_with(BoundedBTreeMap::default);
+                // Inserting would silently overwrite an item already stocked under the serial
+                ensure!(
+                    !map.contains_key(&item.serial_number),
+                    Error::<T>::SerialNumberInUse
+                );
+                map.try_insert(item.serial_number, item.clone())
+                    .map_err(|_| Error::<T>::InventoryFull)?;
+                Ok(())
+            },
+        )?;
+
+        <Inventory<T>>::remove((from, item.sku.clone(), item.serial_number));
+        <Inventory<T>>::insert((to, item.sku.clone(), item.serial_number), item.clone());
+
+        Ok(())
+    }
+
+    /// Reorder the SKU if a drop in stock from `previous_qty` reached its reorder point
+    fn check_reorder_point(sku: &Sku, previous_qty: Qty) {
+        let Some(policy) = <ReorderPolicies<T>>::get(sku) else {
+            return;
+        };
+
+        // Only the drop that crosses the reorder point reorders, not every one below it
+        let on_hand = <GlobalInventory<T>>::get(sku).unwrap_or_default();
+        if previous_qty > policy.reorder_point && on_hand <= policy.reorder_point {
+            Self::raise_reorder(sku, &policy, on_hand);
+        }
+    }
+
+    /// Emit `ReorderRequired`, raising a draft requisition if the policy asks for one
+    fn raise_reorder(sku: &Sku, policy: &ReorderPolicy, on_hand: Qty) {
+        // Whatever has been drawn from the safety stock is bought back on top
+        let safety_shortfall = policy.safety_stock.saturating_sub(on_hand);
+        let suggested_qty = policy.reorder_qty.saturating_add(safety_shortfall);
+        let safety_stock_breached = safety_shortfall > 0;
+
+        let requisition_id = policy
+            .create_requisition
+            .then(|| Self::open_requisition(sku, suggested_qty, on_hand, safety_stock_breached));
+
+        Self::deposit_event(Event::ReorderRequired {
+            sku: sku.clone(),
+            on_hand,
+            reorder_point: policy.reorder_point,
+            suggested_qty,
+            safety_stock_breached,
+            requisition_id,
+        });
+    }
+
+    /// The SKU's open draft requisition, raising a new one if there is none
+    fn open_requisition(sku: &Sku, qty: Qty, on_hand: Qty, urgent: bool) -> RequisitionId {
+        if let Some(requisition_id) = <OpenRequisitions<T>>::get(sku) {
+            return requisition_id;
+        }
+
+        let requisition_id = <NextRequisitionId<T>>::get();
+        <NextRequisitionId<T>>::put(requisition_id.saturating_add(1));
+
+        <PurchaseRequisitions<T>>::insert(
+            requisition_id,
+            PurchaseRequisition {
+                requisition_id,
+                sku: sku.clone(),
+                qty,
+                on_hand,
+                urgent,
+                created_at: Self::current_block(),
+                status: RequisitionStatus::Draft,
+            },
+        );
+        <OpenRequisitions<T>>::insert(sku, requisition_id);
+
+        requisition_id
+    }
 }
@@ -13,6 +13,42 @@
 //! - Scrap defective or damaged items.
 //! - Adjust and move items within the inventory.
 //! - Manage recipes and materials.
+//! - Reorder materials whose stock falls to their reorder point.
+//! - Choose the order each material's stock is picked in: FIFO, FEFO, LIFO, a specific lot, or
+//!   nearest location first.
+//! - Transfer items between accounts, and hold stock on consignment for the account that owns it.
+//! - Require approval of large quantity adjustments and scraps from several approvers.
+//!
+//! Scraps and quantity adjustments, including consumption by the assembly pallet, check the
+//! SKU's reorder policy. When total stock drops to or below the reorder point, the pallet emits
+//! `ReorderRequired` and, if the policy asks for it, raises a draft purchase requisition. Only
+//! one draft requisition is kept open per SKU. Reorder policies are set and requisitions
+//! cancelled by the configured `ReorderAdminOrigin`. A requisition converted into a purchase
+//! order goes back to draft if the order is cancelled.
+//!
+//! Transfers between accounts are shipped and received in two steps. A shipped item stays in the
+//! shipping account's inventory as `InventoryType::Transit` at `Location::Shipping`, where it
+//! cannot be moved, adjusted or scrapped, until the receiving account receives it into
+//! `Location::Receiving`. An item shipped on consignment is held by the receiving account but
+//! still owned by the shipping account, until the owner settles it or the holder ships it back.
+//! Until it is settled it cannot be scrapped, nor consumed, packed or shipped to customers by the
+//! pallets that check `ensure_not_consigned`. An item is not received, shipped or returned under a
+//! serial already stocked at the location it is taken to.
+//!
+//! Stock allocated to orders by other pallets, as reported by the configured
+//! `StockAllocations`, stays where it is. It cannot be scrapped, moved or shipped through this
+//! pallet, and adjustments cannot take its quantity below what is allocated. Every move,
+//! adjustment, split, shipment and scrap is reported to the configured `StockChanges`.
+//!
+//! The configured `ApprovalAdminOrigin` sets an account's approval policy, with quantity and
+//! value thresholds, and registers its approvers. A policy cannot require more approvals than the
+//! account has approvers. A quantity
+//! adjustment or scrap through `inventory_adjust` or `inventory_scrap` that adds or removes more
+//! units, or more value at the material's unit value, than a threshold allows is not applied
+//! straight away. It becomes a pending request that is applied once enough of the account's
+//! approvers approve it, and closed if any of them rejects it or it expires first. A request
+//! that can no longer be applied once it has enough approvals, such as one for an item whose
+//! quantity has changed since, is closed as failed rather than left pending.
 //!
 //! ## Interface
 //!
@@ -26,6 +62,22 @@
 //! - `insert_material`: Add a new material to the system.
 //! - `delete_material`: Remove a material from the system.
 //! - `update_material`: Update the details of an existing material.
+//! - `insert_equipment`: Register a new equipment asset.
+//! - `update_equipment_status`: Mark an equipment asset as available, down or in maintenance.
+//! - `set_reorder_policy`: Set the reorder point, safety stock and reorder quantity of a material.
+//! - `clear_reorder_policy`: Stop reordering a material automatically.
+//! - `cancel_requisition`: Cancel a draft purchase requisition.
+//! - `set_pick_strategy`: Set the order a material's stock is picked in.
+//! - `ship_transfer`: Ship an item to another account, optionally on consignment.
+//! - `receive_transfer`: Receive an item shipped to the account.
+//! - `cancel_transfer`: Call off a transfer that has not been received.
+//! - `settle_consignment`: Hand ownership of consigned stock over to the account holding it.
+//! - `set_approval_policy`: Set when an account's adjustments and scraps need approval.
+//! - `clear_approval_policy`: Stop requiring approval of an account's adjustments and scraps.
+//! - `add_approver`: Allow an account to approve another account's pending changes.
+//! - `remove_approver`: Stop an account approving another account's pending changes.
+//! - `approve_request`: Approve a pending change, applying it once it has enough approvals.
+//! - `reject_request`: Reject a pending change.
 //!
 //! ## Storage
 //!
@@ -33,9 +85,32 @@
 //! - `Inventory`: Stores detailed inventory data by SKU and serial number.
 //! - `ScrapInventory`: Tracks scrapped items, such as damaged or unusable items.
 //! - `AdjustInventory`: Tracks adjustments made to inventory items.
-//! - `Recipes`: Stores recipes for assembling products.
+//! - `Recipes`: Stores recipes for assembling products, by SKU and version.
 //! - `Materials`: Stores materials used in recipes and assembly.
 //! - `InventoryLocale`: Maps locations to items and tracks inventory per location.
+//! - `SplitItems`: The serials split off each item, for serial genealogy.
+//! - `EquipmentRegistry`: Stores individual equipment assets and their availability.
+//! - `QualityHolds`: Items on quality hold, which cannot be moved, adjusted or scrapped.
+//! - `ReorderPolicies`: The reorder policy of each material.
+//! - `PurchaseRequisitions`: Purchase requisitions raised by reorders.
+//! - `NextRequisitionId`: The ID assigned to the next purchase requisition.
+//! - `OpenRequisitions`: The draft purchase requisition open for each SKU.
+//! - `PickStrategies`: The strategy each material's stock is picked with, when not FIFO.
+//! - `Transfers`: Transfers, with the shipping and receiving accounts, by ID.
+//! - `NextTransferId`: The ID assigned to the next transfer.
+//! - `Consignments`: The account that owns each item held on consignment.
+//! - `TransferredItems`: The account each item was last received by, by the account that shipped it.
+//! - `ApprovalPolicies`: When each account's adjustments and scraps need approval.
+//! - `Approvers`: The accounts that can approve each account's pending changes.
+//! - `ApprovalRequests`: Adjustments and scraps awaiting approval, by ID.
+//! - `NextApprovalRequestId`: The ID assigned to the next approval request.
+//! - `RequestApprovals`: The approvers that have approved each request.
+//! - `RequestExpiries`: The pending requests that expire at each block.
+//!
+//! ## Migrations
+//!
+//! - `migrations::v1::MigrateV0ToV1`: Carries stored recipes over to the layout with secondary
+//!   outputs, routings and component issue modes.
 //!
 //! ## Events
 //!
@@ -43,10 +118,32 @@
 //! - `ItemScrapped`: Emitted when an item is marked as scrapped.
 //! - `MoveItem`: Emitted when an item is moved to a new location.
 //! - `AdjustItem`: Emitted when an item's details are adjusted.
+//! - `ItemSplit`: Emitted when part of an item's quantity is split off into a new serial.
 //! - `AddRecipe`: Emitted when a new recipe is added.
 //! - `AddMaterial`: Emitted when a new material is added.
 //! - `DeleteMaterial`: Emitted when a material is deleted.
 //! - `UpdateMaterial`: Emitted when a material is updated.
+//! - `AddEquipment`: Emitted when a new equipment asset is registered.
+//! - `UpdateEquipmentStatus`: Emitted when an equipment asset changes status.
+//! - `ReorderPolicySet`: Emitted when a material's reorder policy is set.
+//! - `ReorderPolicyCleared`: Emitted when a material's reorder policy is cleared.
+//! - `ReorderRequired`: Emitted when a SKU's stock reaches its reorder point.
+//! - `RequisitionCancelled`: Emitted when a draft purchase requisition is cancelled.
+//! - `PickStrategySet`: Emitted when a material's pick strategy is set.
+//! - `TransferShipped`: Emitted when an item is shipped to another account.
+//! - `TransferReceived`: Emitted when a shipped item is received.
+//! - `TransferCancelled`: Emitted when a transfer is called off.
+//! - `ConsignmentSettled`: Emitted when the owner of consigned stock hands it over to its holder.
+//! - `ApprovalPolicySet`: Emitted when an account's approval policy is set.
+//! - `ApprovalPolicyCleared`: Emitted when an account's approval policy is cleared.
+//! - `ApproverAdded`: Emitted when an account is allowed to approve another's changes.
+//! - `ApproverRemoved`: Emitted when an account can no longer approve another's changes.
+//! - `ApprovalRequested`: Emitted when an adjustment or scrap needs approval.
+//! - `RequestApproved`: Emitted when an approver approves a pending change.
+//! - `RequestApplied`: Emitted when a pending change has enough approvals and is applied.
+//! - `RequestRejected`: Emitted when an approver rejects a pending change.
+//! - `RequestExpired`: Emitted when a pending change expires without enough approvals.
+//! - `RequestFailed`: Emitted when a pending change has enough approvals but cannot be applied.
 //!
 //! ## Errors
 //!
@@ -57,6 +154,48 @@
 //! - `LocationNotFound`: The specified location could not be found.
 //! - `MaterialAlreadyExists`: Attempted to insert a material that already exists.
 //! - `MaterialNotFound`: The specified material could not be located.
+//! - `InvalidYield`: A recipe was given an expected yield of zero.
+//! - `InvalidScrapFactor`: A recipe component was given a scrap factor of 100% or more.
+//! - `InvalidSecondaryOutput`: A recipe's secondary output has no quantity or is its own SKU.
+//! - `InvalidRouting`: A recipe's operations are out of order or consume unknown components.
+//! - `EquipmentAlreadyExists`: Attempted to register an asset ID that already exists.
+//! - `EquipmentNotFound`: The specified equipment asset could not be located.
+//! - `EquipmentUnavailable`: No equipment of the required type is available.
+//! - `InvalidEquipmentStatus`: The status of locked or maintained equipment cannot be set by hand,
+//!   and new equipment must be registered as available.
+//! - `RecipeAlreadyExists`: Attempted to insert a recipe version that already exists.
+//! - `RecipeNotFound`: The specified recipe could not be located.
+//! - `ItemOnHold`: The item is on quality hold and cannot be moved, adjusted or scrapped.
+//! - `InvalidReorderPolicy`: The safety stock exceeds the reorder point or nothing is reordered.
+//! - `ReorderPolicyNotFound`: The material has no reorder policy.
+//! - `RequisitionNotFound`: The purchase requisition could not be located.
+//! - `RequisitionNotDraft`: The purchase requisition has already been converted or cancelled.
+//! - `ItemInTransit`: The item has been shipped to another account and cannot be changed.
+//! - `InvalidTransfer`: An item cannot be transferred to the account that holds it.
+//! - `SerialNumberInUse`: The receiving account already holds an item with the serial number.
+//! - `TransferNotFound`: The specified transfer could not be located.
+//! - `NotTransferShipper`: Only the shipping account can cancel a transfer.
+//! - `NotTransferRecipient`: Only the receiving account can receive a transfer.
+//! - `TransferNotInTransit`: The transfer has already been received or cancelled.
+//! - `ConsignedStock`: Consigned stock can only be shipped back to the account that owns it.
+//! - `NotConsigned`: The item is not held on consignment.
+//! - `NotConsignmentOwner`: Only the account that owns consigned stock can settle it.
+//! - `InvalidApprovalPolicy`: The policy sets no threshold, needs no approvals or never expires.
+//! - `ApprovalPolicyNotFound`: The account has no approval policy.
+//! - `NotEnoughApprovers`: The policy would need more approvals than the account has approvers.
+//! - `InvalidApprover`: An account cannot approve its own changes.
+//! - `ApproverNotFound`: The account is not one of the approvers.
+//! - `NotApprover`: Only the account's approvers can approve or reject its pending changes.
+//! - `ApprovalRequestNotFound`: The specified approval request could not be located.
+//! - `RequestNotPending`: The request has already been applied, rejected, expired or failed.
+//! - `AlreadyApproved`: The approver has already approved the request.
+//! - `ItemChanged`: The item's quantity has changed since the change was requested.
+//! - `TooManyExpiringRequests`: Too many requests already expire at the same block.
+//! - `ItemAllocated`: The change would take stock allocated to an order.
+//! - `RequisitionNotConverted`: Only a requisition converted into a purchase order can reopen.
+//! - `ReasonTooLong`: The reason given for a change is too long.
+//! - `ItemConsigned`: Consigned stock must be settled before it can be consumed, scrapped or
+//!   shipped.
 //!
 //! This pallet uses `no_std` for compatibility with Wasm environments, a polkadot standard.
 
@@ -76,6 +215,7 @@ mod mock;
 mod tests;
 
 mod blogic;
+pub mod migrations;
 pub mod types;
 
 // Every callable function or "dispatchable" a pallet exposes must have weight values that correctly
@@ -94,12 +234,17 @@ pub mod pallet {
     use super::*;
     use crate::WeightInfo;
     use frame_support::pallet_prelude::*;
+    use frame_support::sp_runtime::SaturatedConversion;
     use frame_system::pallet_prelude::*;
     use types::{SerialNumber, *};
 
     // The `Pallet` struct serves as a placeholder to implement traits, methods and dispatchables
     // (`Call`s) in this pallet.
+    /// The in-code storage version, see [`crate::migrations`]
+    const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
     #[pallet::pallet]
+    #[pallet::storage_version(STORAGE_VERSION)]
     pub struct Pallet<T>(_);
 
     /// The pallet's configuration trait.
@@ -113,6 +258,15 @@ pub mod pallet {
         type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
         /// A type representing the weights required by the dispatchables of this pallet.
         type WeightInfo: WeightInfo;
+        /// Stock allocated to orders by other pallets, which moves, adjustments, transfers and
+        /// scraps must leave in place.
+        type StockAllocations: StockAllocations<Self::AccountId>;
+        /// Notified whenever an item in inventory is moved, adjusted, split, shipped or scrapped
+        type StockChanges: StockChanges<Self::AccountId>;
+        /// Origin allowed to set approval policies and register approvers
+        type ApprovalAdminOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+        /// Origin allowed to set reorder policies and cancel purchase requisitions
+        type ReorderAdminOrigin: EnsureOrigin<Self::RuntimeOrigin>;
     }
 
     /// Events that functions in this pallet can emit.
@@ -141,6 +295,12 @@ pub mod pallet {
             issuer: Employee,
             adjust_details: AdjustDetails,
         },
+        /// Part of `item`'s quantity was split off into the new serial `split`
+        ItemSplit {
+            sender: T::AccountId,
+            item: Item,
+            split: Item,
+        },
         AddRecipe {
             sender: T::AccountId,
             recipe: Recipe,
@@ -158,6 +318,108 @@ pub mod pallet {
             original_material: Material,
             new_material: Material,
         },
+        AddEquipment {
+            sender: T::AccountId,
+            asset: EquipmentAsset,
+        },
+        UpdateEquipmentStatus {
+            sender: T::AccountId,
+            asset_id: AssetId,
+            original_status: EquipmentStatus,
+            new_status: EquipmentStatus,
+        },
+        ReorderPolicySet {
+            sku: Sku,
+            policy: ReorderPolicy,
+        },
+        ReorderPolicyCleared {
+            sku: Sku,
+        },
+        /// Stock of the SKU has dropped to or below its reorder point
+        ReorderRequired {
+            sku: Sku,
+            on_hand: Qty,
+            reorder_point: Qty,
+            suggested_qty: Qty,
+            safety_stock_breached: bool,
+            /// The draft requisition covering the reorder, if the policy raises one
+            requisition_id: Option<RequisitionId>,
+        },
+        RequisitionCancelled {
+            requisition_id: RequisitionId,
+        },
+        PickStrategySet {
+            sender: T::AccountId,
+            sku: Sku,
+            strategy: PickStrategy,
+        },
+        TransferShipped {
+            sender: T::AccountId,
+            to: T::AccountId,
+            transfer: Transfer,
+        },
+        TransferReceived {
+            sender: T::AccountId,
+            from: T::AccountId,
+            transfer: Transfer,
+        },
+        TransferCancelled {
+            sender: T::AccountId,
+            transfer_id: TransferId,
+        },
+        /// The owner of consigned stock handed ownership of it over to `holder`
+        ConsignmentSettled {
+            sender: T::AccountId,
+            holder: T::AccountId,
+            sku: Sku,
+            serial_number: SerialNumber,
+        },
+        ApprovalPolicySet {
+            owner: T::AccountId,
+            policy: ApprovalPolicy,
+        },
+        ApprovalPolicyCleared {
+            owner: T::AccountId,
+        },
+        ApproverAdded {
+            owner: T::AccountId,
+            approver: T::AccountId,
+        },
+        ApproverRemoved {
+            owner: T::AccountId,
+            approver: T::AccountId,
+        },
+        /// An adjustment or scrap is above the account's thresholds and waits for approval
+        ApprovalRequested {
+            sender: T::AccountId,
+            request: ApprovalRequest,
+        },
+        RequestApproved {
+            sender: T::AccountId,
+            owner: T::AccountId,
+            request_id: ApprovalRequestId,
+            approvals: u8,
+        },
+        /// A pending change had enough approvals and was applied to the owner's stock
+        RequestApplied {
+            owner: T::AccountId,
+            request_id: ApprovalRequestId,
+        },
+        RequestRejected {
+            sender: T::AccountId,
+            owner: T::AccountId,
+            request_id: ApprovalRequestId,
+        },
+        RequestExpired {
+            owner: T::AccountId,
+            request_id: ApprovalRequestId,
+        },
+        /// A pending change had enough approvals but could not be applied, and was closed
+        RequestFailed {
+            owner: T::AccountId,
+            request_id: ApprovalRequestId,
+            error: DispatchError,
+        },
     }
 
     /// Global Inventory Storage
@@ -214,9 +476,10 @@ pub mod pallet {
 
     /// Recipes Storage
     ///
-    /// This storage is used to store the recipes for assembling products.
+    /// This storage is used to store the recipes for assembling products, by SKU and version.
     #[pallet::storage]
-    pub type Recipes<T: Config> = StorageMap<_, Twox64Concat, Sku, Recipe>;
+    pub type Recipes<T: Config> =
+        StorageDoubleMap<_, Twox64Concat, Sku, Twox64Concat, RecipeId, Recipe>;
 
     #[pallet::storage]
     pub type Materials<T: Config> = StorageMap<_, Twox64Concat, Sku, Material>;
@@ -225,6 +488,160 @@ pub mod pallet {
     pub type InventoryLocale<T: Config> =
         StorageMap<_, Twox64Concat, Location, BoundedBTreeMap<SerialNumber, Item, ConstU32<1000>>>;
 
+    /// Split Items Storage
+    ///
+    /// The serials split off each item, with the quantity split off. Splits keep their lot, so
+    /// serial genealogy follows them through this index.
+    #[pallet::storage]
+    pub type SplitItems<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, T::AccountId>,
+            NMapKey<Blake2_128Concat, Sku>,
+            NMapKey<Blake2_128Concat, SerialNumber>,
+            NMapKey<Blake2_128Concat, SerialNumber>,
+        ),
+        Qty,
+        OptionQuery,
+    >;
+
+    /// Quality Holds Storage
+    ///
+    /// Items on quality hold, with the reason for each hold. An item stays on hold until every
+    /// source that placed a hold on it has released it.
+    #[pallet::storage]
+    pub type QualityHolds<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, T::AccountId>,
+            NMapKey<Blake2_128Concat, Sku>,
+            NMapKey<Blake2_128Concat, SerialNumber>,
+            NMapKey<Blake2_128Concat, HoldSource>,
+        ),
+        Reason,
+        OptionQuery,
+    >;
+
+    /// Equipment Registry Storage
+    ///
+    /// This storage is used to store individual equipment assets and their availability.
+    #[pallet::storage]
+    pub type EquipmentRegistry<T: Config> = StorageMap<_, Twox64Concat, AssetId, EquipmentAsset>;
+
+    /// Reorder Policies Storage
+    ///
+    /// The reorder point, safety stock and reorder quantity of each material
+    #[pallet::storage]
+    pub type ReorderPolicies<T: Config> = StorageMap<_, Twox64Concat, Sku, ReorderPolicy>;
+
+    /// Purchase Requisitions Storage
+    ///
+    /// Purchase requisitions raised when stock reaches a reorder point
+    #[pallet::storage]
+    pub type PurchaseRequisitions<T: Config> =
+        StorageMap<_, Twox64Concat, RequisitionId, PurchaseRequisition>;
+
+    /// The ID assigned to the next purchase requisition
+    #[pallet::storage]
+    pub type NextRequisitionId<T: Config> = StorageValue<_, RequisitionId, ValueQuery>;
+
+    /// Open Requisitions Storage
+    ///
+    /// The draft purchase requisition open for each SKU, so a reorder is only requested once
+    #[pallet::storage]
+    pub type OpenRequisitions<T: Config> = StorageMap<_, Twox64Concat, Sku, RequisitionId>;
+
+    /// Pick Strategies Storage
+    ///
+    /// The order each material's stock is picked in, for materials not picked FIFO
+    #[pallet::storage]
+    pub type PickStrategies<T: Config> = StorageMap<_, Twox64Concat, Sku, PickStrategy>;
+
+    /// Transfers Storage
+    ///
+    /// Transfers by ID, with the shipping and receiving accounts
+    #[pallet::storage]
+    pub type Transfers<T: Config> =
+        StorageMap<_, Twox64Concat, TransferId, (T::AccountId, T::AccountId, Transfer)>;
+
+    /// The ID assigned to the next transfer
+    #[pallet::storage]
+    pub type NextTransferId<T: Config> = StorageValue<_, TransferId, ValueQuery>;
+
+    /// Consignments Storage
+    ///
+    /// Items held on consignment, keyed by the holding account, with the account that owns them
+    #[pallet::storage]
+    pub type Consignments<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, T::AccountId>,
+            NMapKey<Blake2_128Concat, Sku>,
+            NMapKey<Blake2_128Concat, SerialNumber>,
+        ),
+        T::AccountId,
+        OptionQuery,
+    >;
+
+    /// Transferred Items Storage
+    ///
+    /// The account each item was last received by, keyed by the account that shipped it, so
+    /// recalls can follow stock that has changed hands
+    #[pallet::storage]
+    pub type TransferredItems<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, T::AccountId>,
+            NMapKey<Blake2_128Concat, Sku>,
+            NMapKey<Blake2_128Concat, SerialNumber>,
+        ),
+        T::AccountId,
+        OptionQuery,
+    >;
+
+    /// Approval Policies Storage
+    ///
+    /// When each account's manual quantity adjustments and scraps need approval
+    #[pallet::storage]
+    pub type ApprovalPolicies<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, ApprovalPolicy>;
+
+    /// Approvers Storage
+    ///
+    /// The accounts that can approve each account's pending changes
+    #[pallet::storage]
+    pub type Approvers<T: Config> =
+        StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Blake2_128Concat, T::AccountId, ()>;
+
+    /// Approval Requests Storage
+    ///
+    /// Adjustments and scraps above an approval threshold by ID, with the account whose stock
+    /// they change
+    #[pallet::storage]
+    pub type ApprovalRequests<T: Config> =
+        StorageMap<_, Twox64Concat, ApprovalRequestId, (T::AccountId, ApprovalRequest)>;
+
+    /// The ID assigned to the next approval request
+    #[pallet::storage]
+    pub type NextApprovalRequestId<T: Config> = StorageValue<_, ApprovalRequestId, ValueQuery>;
+
+    /// The approvers that have approved each request
+    #[pallet::storage]
+    pub type RequestApprovals<T: Config> =
+        StorageDoubleMap<_, Twox64Concat, ApprovalRequestId, Blake2_128Concat, T::AccountId, ()>;
+
+    /// Request Expiries Storage
+    ///
+    /// The requests that expire at each block, if they are still pending
+    #[pallet::storage]
+    pub type RequestExpiries<T: Config> = StorageMap<
+        _,
+        Twox64Concat,
+        u32,
+        BoundedVec<ApprovalRequestId, ConstU32<MAX_EXPIRING_REQUESTS>>,
+        ValueQuery,
+    >;
+
     /// Errors that can be returned by this pallet.
     ///
     /// This type of runtime error can be up to 4 bytes in size should you want to return additional
@@ -251,6 +668,95 @@ pub mod pallet {
         MaterialAlreadyExists,
         /// The material was not found
         MaterialNotFound,
+        /// The recipe's expected yield must be greater than zero
+        InvalidYield,
+        /// A recipe component's scrap factor must be below 100%
+        InvalidScrapFactor,
+        /// A secondary output must have a quantity and differ from the recipe's SKU
+        InvalidSecondaryOutput,
+        /// Operations must be numbered in ascending order and each consume distinct components
+        InvalidRouting,
+        /// The equipment asset already exists
+        EquipmentAlreadyExists,
+        /// The equipment asset was not found
+        EquipmentNotFound,
+        /// No equipment of the required type is available
+        EquipmentUnavailable,
+        /// The equipment status cannot be set manually, the asset is locked or under maintenance, or
+        /// a new asset is not available
+        InvalidEquipmentStatus,
+        /// A recipe with the same SKU and version already exists
+        RecipeAlreadyExists,
+        /// The recipe was not found
+        RecipeNotFound,
+        /// The item is on quality hold
+        ItemOnHold,
+        /// The safety stock must not exceed the reorder point and the reorder quantity must be set
+        InvalidReorderPolicy,
+        /// The material has no reorder policy
+        ReorderPolicyNotFound,
+        /// The purchase requisition was not found
+        RequisitionNotFound,
+        /// The purchase requisition is no longer a draft
+        RequisitionNotDraft,
+        /// The item is in transit to another account
+        ItemInTransit,
+        /// The item cannot be transferred to the account that holds it
+        InvalidTransfer,
+        /// The receiving account already holds an item with the serial number
+        SerialNumberInUse,
+        /// The transfer was not found
+        TransferNotFound,
+        /// The transfer was shipped by another account
+        NotTransferShipper,
+        /// The transfer was shipped to another account
+        NotTransferRecipient,
+        /// The transfer has already been received or cancelled
+        TransferNotInTransit,
+        /// Consigned stock can only be shipped back to the account that owns it
+        ConsignedStock,
+        /// The item is not held on consignment
+        NotConsigned,
+        /// The consigned stock is owned by another account
+        NotConsignmentOwner,
+        /// The policy must set a threshold, need at least one approval and expire
+        InvalidApprovalPolicy,
+        /// The account has no approval policy
+        ApprovalPolicyNotFound,
+        /// The policy would need more approvals than the account has approvers
+        NotEnoughApprovers,
+        /// An account cannot approve its own changes
+        InvalidApprover,
+        /// The account is not one of the approvers
+        ApproverNotFound,
+        /// The account cannot approve or reject the request
+        NotApprover,
+        /// The approval request was not found
+        ApprovalRequestNotFound,
+        /// The request has already been applied, rejected, expired or failed
+        RequestNotPending,
+        /// The approver has already approved the request
+        AlreadyApproved,
+        /// The item's quantity has changed since the change was requested
+        ItemChanged,
+        /// Too many requests already expire at the same block
+        TooManyExpiringRequests,
+        /// The change would take stock allocated to an order
+        ItemAllocated,
+        /// Only a purchase requisition converted into a purchase order can be reopened
+        RequisitionNotConverted,
+        /// The reason given for a change is too long
+        ReasonTooLong,
+        /// Consigned stock must be settled before it can be consumed, scrapped or shipped
+        ItemConsigned,
+    }
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        /// Expire the approval requests still pending at their expiry block
+        fn on_initialize(n: BlockNumberFor<T>) -> Weight {
+            Self::do_expire_requests(n.saturated_into::<u32>())
+        }
     }
 
     /// The pallet's dispatchable functions
@@ -279,6 +785,21 @@ pub mod pallet {
         ) -> DispatchResult {
             let who = ensure_signed(origin)?;
 
+            // Check if the item exists in inventory
+            let item = Inventory::<T>::get((&who, sku.clone(), serial_number))
+                .ok_or(Error::<T>::InventoryNotFound)?;
+            Self::ensure_allocation_kept(&who, &item, 0)?;
+
+            // Large scraps wait for the account's approvers
+            let change = PendingChange::Scrap(scrap_details.clone());
+            if let Some(request) = Self::do_request_approval(&who, &item, change)? {
+                Self::deposit_event(Event::ApprovalRequested {
+                    sender: who,
+                    request,
+                });
+                return Ok(());
+            }
+
             // Scrap the item, if it exists
             Self::do_inventory_scrap(&who, sku.clone(), serial_number, scrap_details.clone())?;
 
@@ -308,6 +829,8 @@ pub mod pallet {
             // Check if the item exists in inventory
             let item = Inventory::<T>::get((&who, sku.clone(), serial_number))
                 .ok_or(Error::<T>::InventoryNotFound)?;
+            // Allocated stock is moved by the flow that allocated it
+            Self::ensure_allocation_kept(&who, &item, 0)?;
 
             // Move the item to the new location
             Self::do_inventory_move(&who, item.clone(), moved_by.clone(), adjust_details.clone())?;
@@ -338,6 +861,22 @@ pub mod pallet {
             // Check if the item exists in inventory
             let item = Inventory::<T>::get((&who, sku.clone(), serial_number))
                 .ok_or(Error::<T>::InventoryNotFound)?;
+            if let AdjustDetails::Quantity { new_qty, .. } = &adjust_details {
+                Self::ensure_allocation_kept(&who, &item, *new_qty)?;
+            }
+
+            // Large adjustments wait for the account's approvers
+            let change = PendingChange::Adjust {
+                issuer: issuer.clone(),
+                adjust_details: adjust_details.clone(),
+            };
+            if let Some(request) = Self::do_request_approval(&who, &item, change)? {
+                Self::deposit_event(Event::ApprovalRequested {
+                    sender: who,
+                    request,
+                });
+                return Ok(());
+            }
 
             // Adjust the item's quantity
             Self::do_inventory_adjust(&who, issuer.clone(), item.clone(), adjust_details.clone())?;
@@ -351,7 +890,7 @@ pub mod pallet {
         pub fn insert_recipe(origin: OriginFor<T>, recipe: Recipe) -> DispatchResult {
             let who = ensure_signed(origin)?;
 
-            // Adjust the item's quantity
+            // Insert the recipe, if this version does not already exist
             Self::do_insert_recipe(recipe.clone())?;
 
             // Emit the adjustment
@@ -415,5 +954,357 @@ pub mod pallet {
 
             Ok(())
         }
+
+        /// Register an equipment asset
+        ///
+        /// The asset must be registered as `Available`.
+        #[pallet::call_index(8)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn insert_equipment(origin: OriginFor<T>, asset: EquipmentAsset) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            // Register the asset, if the ID is not already taken
+            Self::do_insert_equipment(asset.clone())?;
+
+            Self::deposit_event(Event::AddEquipment { sender: who, asset });
+
+            Ok(())
+        }
+
+        /// Update the status of an equipment asset
+        ///
+        /// Assets locked by a work order or under maintenance keep their status until the work
+        /// order releases them or the maintenance order is completed.
+        #[pallet::call_index(9)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn update_equipment_status(
+            origin: OriginFor<T>,
+            asset_id: AssetId,
+            status: EquipmentStatus,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            // In-use and maintenance are reserved for work orders and maintenance orders
+            let asset =
+                EquipmentRegistry::<T>::get(asset_id).ok_or(Error::<T>::EquipmentNotFound)?;
+            ensure!(
+                !matches!(
+                    status,
+                    EquipmentStatus::InUse | EquipmentStatus::Maintenance
+                ) && !matches!(
+                    asset.status,
+                    EquipmentStatus::InUse | EquipmentStatus::Maintenance
+                ),
+                Error::<T>::InvalidEquipmentStatus
+            );
+
+            let original_status = Self::do_update_equipment_status(asset_id, status.clone())?;
+
+            Self::deposit_event(Event::UpdateEquipmentStatus {
+                sender: who,
+                asset_id,
+                original_status,
+                new_status: status,
+            });
+
+            Ok(())
+        }
+
+        /// Set the reorder policy of a material
+        ///
+        /// Raises a reorder straight away if stock is already at or below the reorder point.
+        #[pallet::call_index(10)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn set_reorder_policy(
+            origin: OriginFor<T>,
+            sku: Sku,
+            policy: ReorderPolicy,
+        ) -> DispatchResult {
+            T::ReorderAdminOrigin::ensure_origin(origin)?;
+
+            Self::do_set_reorder_policy(sku.clone(), policy.clone())?;
+
+            Self::deposit_event(Event::ReorderPolicySet { sku, policy });
+
+            Ok(())
+        }
+
+        /// Stop reordering a material automatically
+        ///
+        /// Draft requisitions already raised for the material stay open.
+        #[pallet::call_index(11)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn clear_reorder_policy(origin: OriginFor<T>, sku: Sku) -> DispatchResult {
+            T::ReorderAdminOrigin::ensure_origin(origin)?;
+
+            ReorderPolicies::<T>::take(&sku).ok_or(Error::<T>::ReorderPolicyNotFound)?;
+
+            Self::deposit_event(Event::ReorderPolicyCleared { sku });
+
+            Ok(())
+        }
+
+        /// Cancel a draft purchase requisition
+        #[pallet::call_index(12)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn cancel_requisition(
+            origin: OriginFor<T>,
+            requisition_id: RequisitionId,
+        ) -> DispatchResult {
+            T::ReorderAdminOrigin::ensure_origin(origin)?;
+
+            Self::do_close_requisition(requisition_id, RequisitionStatus::Cancelled)?;
+
+            Self::deposit_event(Event::RequisitionCancelled { requisition_id });
+
+            Ok(())
+        }
+
+        /// Set the order a material's stock is picked in for staging and shipping
+        #[pallet::call_index(13)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn set_pick_strategy(
+            origin: OriginFor<T>,
+            sku: Sku,
+            strategy: PickStrategy,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            Self::do_set_pick_strategy(sku.clone(), strategy.clone())?;
+
+            Self::deposit_event(Event::PickStrategySet {
+                sender: who,
+                sku,
+                strategy,
+            });
+
+            Ok(())
+        }
+
+        /// Ship an item to another account
+        ///
+        /// On consignment, the receiving account holds the item but the sender keeps owning it.
+        /// Consigned stock can only be shipped back to its owner.
+        #[pallet::call_index(14)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn ship_transfer(
+            origin: OriginFor<T>,
+            to: T::AccountId,
+            sku: Sku,
+            serial_number: SerialNumber,
+            consignment: bool,
+            shipped_by: Employee,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let transfer =
+                Self::do_ship_transfer(&who, &to, sku, serial_number, consignment, shipped_by)?;
+
+            Self::deposit_event(Event::TransferShipped {
+                sender: who,
+                to,
+                transfer,
+            });
+
+            Ok(())
+        }
+
+        /// Receive an item shipped to the account into receiving
+        #[pallet::call_index(15)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn receive_transfer(
+            origin: OriginFor<T>,
+            transfer_id: TransferId,
+            received_by: Employee,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let (from, transfer) = Self::do_receive_transfer(&who, transfer_id, received_by)?;
+
+            Self::deposit_event(Event::TransferReceived {
+                sender: who,
+                from,
+                transfer,
+            });
+
+            Ok(())
+        }
+
+        /// Call off a transfer that has not been received, returning the item to where it was
+        #[pallet::call_index(16)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn cancel_transfer(origin: OriginFor<T>, transfer_id: TransferId) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            Self::do_cancel_transfer(&who, transfer_id)?;
+
+            Self::deposit_event(Event::TransferCancelled {
+                sender: who,
+                transfer_id,
+            });
+
+            Ok(())
+        }
+
+        /// Hand ownership of an item held on consignment over to its holder, e.g. once it has
+        /// been paid for
+        ///
+        /// Only the account that owns the consigned stock can settle it.
+        #[pallet::call_index(17)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn settle_consignment(
+            origin: OriginFor<T>,
+            holder: T::AccountId,
+            sku: Sku,
+            serial_number: SerialNumber,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            Self::do_settle_consignment(&who, &holder, sku.clone(), serial_number)?;
+
+            Self::deposit_event(Event::ConsignmentSettled {
+                sender: who,
+                holder,
+                sku,
+                serial_number,
+            });
+
+            Ok(())
+        }
+
+        /// Set when an account's manual quantity adjustments and scraps need approval
+        ///
+        /// The account must already have as many approvers as the policy requires. Requests
+        /// already pending keep the approvals they were raised with.
+        #[pallet::call_index(18)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn set_approval_policy(
+            origin: OriginFor<T>,
+            owner: T::AccountId,
+            policy: ApprovalPolicy,
+        ) -> DispatchResult {
+            T::ApprovalAdminOrigin::ensure_origin(origin)?;
+
+            Self::do_set_approval_policy(&owner, policy.clone())?;
+
+            Self::deposit_event(Event::ApprovalPolicySet { owner, policy });
+
+            Ok(())
+        }
+
+        /// Stop requiring approval of an account's adjustments and scraps
+        ///
+        /// Requests already pending stay open.
+        #[pallet::call_index(19)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn clear_approval_policy(origin: OriginFor<T>, owner: T::AccountId) -> DispatchResult {
+            T::ApprovalAdminOrigin::ensure_origin(origin)?;
+
+            ApprovalPolicies::<T>::take(&owner).ok_or(Error::<T>::ApprovalPolicyNotFound)?;
+
+            Self::deposit_event(Event::ApprovalPolicyCleared { owner });
+
+            Ok(())
+        }
+
+        /// Allow an account to approve or reject another account's pending changes
+        #[pallet::call_index(20)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn add_approver(
+            origin: OriginFor<T>,
+            owner: T::AccountId,
+            approver: T::AccountId,
+        ) -> DispatchResult {
+            T::ApprovalAdminOrigin::ensure_origin(origin)?;
+
+            ensure!(approver != owner, Error::<T>::InvalidApprover);
+
+            Approvers::<T>::insert(&owner, &approver, ());
+
+            Self::deposit_event(Event::ApproverAdded { owner, approver });
+
+            Ok(())
+        }
+
+        /// Stop an account approving or rejecting another account's pending changes
+        ///
+        /// Fails if the owner's policy would need more approvals than the approvers left. Approvals
+        /// already given still count.
+        #[pallet::call_index(21)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn remove_approver(
+            origin: OriginFor<T>,
+            owner: T::AccountId,
+            approver: T::AccountId,
+        ) -> DispatchResult {
+            T::ApprovalAdminOrigin::ensure_origin(origin)?;
+
+            Self::do_remove_approver(&owner, &approver)?;
+
+            Self::deposit_event(Event::ApproverRemoved { owner, approver });
+
+            Ok(())
+        }
+
+        /// Approve a pending change, applying it once it has enough approvals
+        ///
+        /// A change that can no longer be applied closes the request as failed.
+        #[pallet::call_index(22)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn approve_request(
+            origin: OriginFor<T>,
+            request_id: ApprovalRequestId,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let (owner, request, failure) = Self::do_approve_request(&who, request_id)?;
+
+            Self::deposit_event(Event::RequestApproved {
+                sender: who,
+                owner: owner.clone(),
+                request_id,
+                approvals: request.approvals,
+            });
+
+            if request.status == ApprovalStatus::Approved {
+                if let PendingChange::Scrap(scrap_details) = request.change {
+                    Self::deposit_event(Event::ItemScrapped {
+                        sender: owner.clone(),
+                        sku: request.sku,
+                        serial_number: request.serial_number,
+                        scrap_details,
+                    });
+                }
+                Self::deposit_event(Event::RequestApplied { owner, request_id });
+            } else if let Some(error) = failure {
+                Self::deposit_event(Event::RequestFailed {
+                    owner,
+                    request_id,
+                    error,
+                });
+            }
+
+            Ok(())
+        }
+
+        /// Reject a pending change
+        #[pallet::call_index(23)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn reject_request(
+            origin: OriginFor<T>,
+            request_id: ApprovalRequestId,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let owner = Self::do_reject_request(&who, request_id)?;
+
+            Self::deposit_event(Event::RequestRejected {
+                sender: who,
+                owner,
+                request_id,
+            });
+
+            Ok(())
+        }
     }
 }
@@ -0,0 +1,102 @@
+//! Storage migrations of the inventory pallet
+//!
+//! Add the migrations to the runtime's `Migrations`. Each one only runs from the storage version
+//! it migrates and bumps the pallet to the next one.
+
+use crate::pallet::{Config, Pallet, Recipes};
+use core::marker::PhantomData;
+use frame_support::migrations::VersionedMigration;
+use frame_support::traits::{Get, UncheckedOnRuntimeUpgrade};
+use frame_support::weights::Weight;
+
+/// Version 1 adds secondary outputs and a routing to recipes, and an issue mode to their
+/// components
+pub mod v1 {
+    use super::*;
+    use crate::types::{IssueMode, Recipe, RecipeComponent};
+
+    /// Types as they were laid out before version 1
+    pub(crate) mod v0 {
+        use crate::types::{Employee, Equipment, RecipeId, Sku};
+        use codec::{Decode, Encode};
+        use frame_support::pallet_prelude::ConstU32;
+        use frame_support::sp_runtime::Percent;
+        use frame_support::BoundedVec;
+
+        #[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+        pub struct RecipeComponent {
+            pub sku: Sku,
+            pub qty: u32,
+            pub scrap_percent: Percent,
+        }
+
+        #[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+        pub struct Recipe {
+            pub inserted_by: Employee,
+            pub sku: Sku,
+            pub recipe_id: RecipeId,
+            pub required_components: BoundedVec<RecipeComponent, ConstU32<100>>,
+            pub required_equipment: Equipment,
+            pub output_quantity: u32,
+            pub expected_yield: Percent,
+        }
+    }
+
+    /// Carry every stored recipe over to the new layout
+    ///
+    /// Recipes keep their single-step assembly without secondary outputs, and their components
+    /// keep being kitted.
+    pub struct InnerMigrateV0ToV1<T>(PhantomData<T>);
+
+    impl<T: Config> UncheckedOnRuntimeUpgrade for InnerMigrateV0ToV1<T> {
+        fn on_runtime_upgrade() -> Weight {
+            let mut translated: u64 = 0;
+
+            Recipes::<T>::translate_values::<v0::Recipe, _>(|old| {
+                translated += 1;
+
+                let required_components = old
+                    .required_components
+                    .into_iter()
+                    .map(|component| RecipeComponent {
+                        sku: component.sku,
+                        qty: component.qty,
+                        scrap_percent: component.scrap_percent,
+                        issue_mode: IssueMode::PreKitted,
+                    })
+                    .collect::<scale_info::prelude::vec::Vec<_>>()
+                    .try_into()
+                    .ok()?;
+
+                Some(Recipe {
+                    inserted_by: old.inserted_by,
+                    sku: old.sku,
+                    recipe_id: old.recipe_id,
+                    required_components,
+                    required_equipment: old.required_equipment,
+                    output_quantity: old.output_quantity,
+                    expected_yield: old.expected_yield,
+                    secondary_outputs: Default::default(),
+                    routing: Default::default(),
+                })
+            });
+
+            log::info!(
+                target: "runtime::inventory",
+                "migrated {} recipes",
+                translated
+            );
+
+            T::DbWeight::get().reads_writes(translated, translated)
+        }
+    }
+
+    /// [`InnerMigrateV0ToV1`], run only while the pallet is at storage version 0
+    pub type MigrateV0ToV1<T> = VersionedMigration<
+        0,
+        1,
+        InnerMigrateV0ToV1<T>,
+        Pallet<T>,
+        <T as frame_system::Config>::DbWeight,
+    >;
+}
@@ -1,5 +1,6 @@
 use crate as pallet_inventory;
 use frame_support::derive_impl;
+use frame_system::EnsureRoot;
 use sp_runtime::BuildStorage;
 
 type Block = frame_system::mocking::MockBlock<Test>;
@@ -21,6 +22,10 @@ impl frame_system::Config for Test {
 impl pallet_inventory::Config for Test {
     type RuntimeEvent = RuntimeEvent;
     type WeightInfo = ();
+    type StockAllocations = ();
+    type StockChanges = ();
+    type ApprovalAdminOrigin = EnsureRoot<u64>;
+    type ReorderAdminOrigin = EnsureRoot<u64>;
 }
 
 // Build genesis storage according to the mock runtime.
@@ -29,5 +34,8 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
         .build_storage()
         .unwrap();
 
-    t.into()
+    // Events are only recorded from block 1 on
+    let mut ext: sp_io::TestExternalities = t.into();
+    ext.execute_with(|| System::set_block_number(1));
+    ext
 }
@@ -1,2 +1,1508 @@
-use crate::{mock::*, Error, Event};
-use frame_support::{assert_noop, assert_ok};
+use crate::{mock::*, types::*, Error, Event};
+use frame_support::sp_runtime::{DispatchError, Percent};
+use frame_support::traits::Hooks;
+use frame_support::{assert_noop, assert_ok, BoundedVec};
+
+const ALICE: u64 = 1;
+const BOB: u64 = 2;
+const CHARLIE: u64 = 3;
+
+fn sku(sku: &str) -> Sku {
+    BoundedVec::truncate_from(sku.as_bytes().to_vec())
+}
+
+fn component(sku_name: &str, qty: Qty, scrap_percent: u8) -> RecipeComponent {
+    RecipeComponent {
+        sku: sku(sku_name),
+        qty,
+        scrap_percent: Percent::from_percent(scrap_percent),
+        issue_mode: IssueMode::PreKitted,
+    }
+}
+
+fn recipe(components: Vec<RecipeComponent>, expected_yield: u8) -> Recipe {
+    Recipe {
+        sku: sku("WIDGET"),
+        required_components: BoundedVec::truncate_from(components),
+        output_quantity: 1,
+        expected_yield: Percent::from_percent(expected_yield),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn required_qty_grosses_up_for_scrap_and_yield() {
+    let recipe = recipe(vec![component("BOLT", 10, 10)], 90);
+
+    // 10 bolts, 10% of which are scrapped, for a process that yields 90%: 12.35 rounded up
+    assert_eq!(recipe.required_qty(&recipe.required_components[0]), 13);
+    // One good unit at 90% yield means starting two
+    assert_eq!(recipe.planned_input_qty(), 2);
+}
+
+#[test]
+fn insert_recipe_stores_scrap_factors_and_yield() {
+    new_test_ext().execute_with(|| {
+        let recipe = recipe(vec![component("BOLT", 10, 10)], 90);
+
+        assert_ok!(Inventory::insert_recipe(
+            RuntimeOrigin::signed(ALICE),
+            recipe.clone()
+        ));
+
+        assert_eq!(
+            crate::Recipes::<Test>::get(sku("WIDGET"), 0),
+            Some(recipe.clone())
+        );
+        System::assert_last_event(
+            Event::AddRecipe {
+                sender: ALICE,
+                recipe,
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn insert_recipe_rejects_zero_yield() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Inventory::insert_recipe(
+                RuntimeOrigin::signed(ALICE),
+                recipe(vec![component("BOLT", 10, 0)], 0)
+            ),
+            Error::<Test>::InvalidYield
+        );
+    });
+}
+
+#[test]
+fn insert_recipe_rejects_full_scrap_factor() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Inventory::insert_recipe(
+                RuntimeOrigin::signed(ALICE),
+                recipe(vec![component("BOLT", 10, 100)], 90)
+            ),
+            Error::<Test>::InvalidScrapFactor
+        );
+    });
+}
+
+#[test]
+fn migration_carries_recipes_over_to_the_new_layout() {
+    use crate::migrations::v1::{v0, MigrateV0ToV1};
+    use frame_support::traits::{GetStorageVersion, OnRuntimeUpgrade, StorageVersion};
+
+    new_test_ext().execute_with(|| {
+        StorageVersion::new(0).put::<Inventory>();
+        let old = v0::Recipe {
+            inserted_by: Employee::Dave,
+            sku: sku("WIDGET"),
+            recipe_id: 0,
+            required_components: BoundedVec::truncate_from(vec![v0::RecipeComponent {
+                sku: sku("BOLT"),
+                qty: 2,
+                scrap_percent: Percent::from_percent(10),
+            }]),
+            required_equipment: Equipment::Forklift,
+            output_quantity: 1,
+            expected_yield: Percent::from_percent(90),
+        };
+        frame_support::storage::unhashed::put(
+            &crate::Recipes::<Test>::hashed_key_for(sku("WIDGET"), 0),
+            &old,
+        );
+
+        MigrateV0ToV1::<Test>::on_runtime_upgrade();
+
+        assert_eq!(
+            crate::Recipes::<Test>::get(sku("WIDGET"), 0),
+            Some(Recipe {
+                inserted_by: Employee::Dave,
+                required_equipment: Equipment::Forklift,
+                ..recipe(vec![component("BOLT", 2, 10)], 90)
+            })
+        );
+        assert_eq!(Inventory::on_chain_storage_version(), 1);
+    });
+}
+
+fn forklift(asset_id: AssetId) -> EquipmentAsset {
+    EquipmentAsset {
+        asset_id,
+        equipment_type: Equipment::Forklift,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn insert_equipment_rejects_duplicate_asset_ids() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Inventory::insert_equipment(
+            RuntimeOrigin::signed(ALICE),
+            forklift(0)
+        ));
+
+        assert_noop!(
+            Inventory::insert_equipment(RuntimeOrigin::signed(ALICE), forklift(0)),
+            Error::<Test>::EquipmentAlreadyExists
+        );
+    });
+}
+
+#[test]
+fn insert_equipment_requires_available_status() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Inventory::insert_equipment(
+                RuntimeOrigin::signed(ALICE),
+                EquipmentAsset {
+                    status: EquipmentStatus::InUse,
+                    ..forklift(0)
+                }
+            ),
+            Error::<Test>::InvalidEquipmentStatus
+        );
+    });
+}
+
+#[test]
+fn equipment_status_updates_exclude_in_use() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Inventory::insert_equipment(
+            RuntimeOrigin::signed(ALICE),
+            forklift(0)
+        ));
+
+        assert_noop!(
+            Inventory::update_equipment_status(
+                RuntimeOrigin::signed(ALICE),
+                0,
+                EquipmentStatus::InUse
+            ),
+            Error::<Test>::InvalidEquipmentStatus
+        );
+        assert_noop!(
+            Inventory::update_equipment_status(
+                RuntimeOrigin::signed(ALICE),
+                1,
+                EquipmentStatus::Down
+            ),
+            Error::<Test>::EquipmentNotFound
+        );
+
+        assert_ok!(Inventory::update_equipment_status(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            EquipmentStatus::Down
+        ));
+        System::assert_last_event(
+            Event::UpdateEquipmentStatus {
+                sender: ALICE,
+                asset_id: 0,
+                original_status: EquipmentStatus::Available,
+                new_status: EquipmentStatus::Down,
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn locked_and_maintained_equipment_keeps_its_status() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Inventory::insert_equipment(
+            RuntimeOrigin::signed(ALICE),
+            forklift(0)
+        ));
+        assert_eq!(Inventory::do_lock_equipment(&Equipment::Forklift), Ok(0));
+
+        // Only releasing the lock frees the asset for another work order
+        for status in [EquipmentStatus::Available, EquipmentStatus::Down] {
+            assert_noop!(
+                Inventory::update_equipment_status(RuntimeOrigin::signed(ALICE), 0, status),
+                Error::<Test>::InvalidEquipmentStatus
+            );
+        }
+        assert_noop!(
+            Inventory::do_lock_equipment(&Equipment::Forklift),
+            Error::<Test>::EquipmentUnavailable
+        );
+        assert_ok!(Inventory::do_release_equipment(0));
+
+        // Maintenance is set and cleared by maintenance orders alone
+        assert_noop!(
+            Inventory::update_equipment_status(
+                RuntimeOrigin::signed(ALICE),
+                0,
+                EquipmentStatus::Maintenance
+            ),
+            Error::<Test>::InvalidEquipmentStatus
+        );
+        assert_ok!(Inventory::do_update_equipment_status(
+            0,
+            EquipmentStatus::Maintenance
+        ));
+        assert_noop!(
+            Inventory::update_equipment_status(
+                RuntimeOrigin::signed(ALICE),
+                0,
+                EquipmentStatus::Available
+            ),
+            Error::<Test>::InvalidEquipmentStatus
+        );
+    });
+}
+
+#[test]
+fn locking_equipment_takes_the_first_available_asset() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Inventory::insert_equipment(
+            RuntimeOrigin::signed(ALICE),
+            forklift(0)
+        ));
+
+        assert_eq!(Inventory::do_lock_equipment(&Equipment::Forklift), Ok(0));
+        let asset = crate::EquipmentRegistry::<Test>::get(0).unwrap();
+        assert_eq!(asset.status, EquipmentStatus::InUse);
+        assert_eq!(asset.usage_count, 1);
+
+        // The only forklift is taken, and there never was a crane
+        assert_noop!(
+            Inventory::do_lock_equipment(&Equipment::Forklift),
+            Error::<Test>::EquipmentUnavailable
+        );
+        assert_noop!(
+            Inventory::do_lock_equipment(&Equipment::Crane),
+            Error::<Test>::EquipmentUnavailable
+        );
+
+        assert_ok!(Inventory::do_release_equipment(0));
+        assert_eq!(
+            crate::EquipmentRegistry::<Test>::get(0).unwrap().status,
+            EquipmentStatus::Available
+        );
+    });
+}
+
+fn reason(reason: &str) -> Reason {
+    BoundedVec::truncate_from(reason.as_bytes().to_vec())
+}
+
+fn bolts(serial_number: SerialNumber, qty: Qty) -> Item {
+    Item {
+        sku: sku("BOLT"),
+        serial_number,
+        qty,
+        ..Default::default()
+    }
+}
+
+/// Register bolts as a material, stock them and set their reorder policy
+fn set_up_reorders(items: Vec<Item>, policy: ReorderPolicy) {
+    assert_ok!(Inventory::insert_material(
+        RuntimeOrigin::signed(ALICE),
+        Material {
+            sku: sku("BOLT"),
+            ..Default::default()
+        }
+    ));
+    for item in items {
+        assert_ok!(Inventory::inventory_insertion(
+            RuntimeOrigin::signed(ALICE),
+            item
+        ));
+    }
+    assert_ok!(Inventory::set_reorder_policy(
+        RuntimeOrigin::root(),
+        sku("BOLT"),
+        policy
+    ));
+}
+
+fn adjust_bolts(serial_number: SerialNumber, original_qty: Qty, new_qty: Qty) {
+    assert_ok!(Inventory::inventory_adjust(
+        RuntimeOrigin::signed(ALICE),
+        Employee::Bob,
+        sku("BOLT"),
+        serial_number,
+        AdjustDetails::Quantity {
+            original_qty,
+            new_qty,
+            reason: reason("Cycle count"),
+        }
+    ));
+}
+
+fn reorders_raised() -> usize {
+    System::events()
+        .into_iter()
+        .filter(|record| {
+            matches!(
+                record.event,
+                RuntimeEvent::Inventory(Event::ReorderRequired { .. })
+            )
+        })
+        .count()
+}
+
+#[test]
+fn reorder_policies_must_be_for_materials_and_reorder_something() {
+    new_test_ext().execute_with(|| {
+        let policy = ReorderPolicy {
+            reorder_point: 10,
+            safety_stock: 5,
+            reorder_qty: 20,
+            create_requisition: false,
+        };
+        assert_noop!(
+            Inventory::set_reorder_policy(
+                RuntimeOrigin::signed(ALICE),
+                sku("BOLT"),
+                policy.clone()
+            ),
+            DispatchError::BadOrigin
+        );
+        assert_noop!(
+            Inventory::set_reorder_policy(RuntimeOrigin::root(), sku("BOLT"), policy.clone()),
+            Error::<Test>::MaterialNotFound
+        );
+
+        set_up_reorders(vec![bolts(1, 50)], policy.clone());
+        assert_eq!(
+            crate::ReorderPolicies::<Test>::get(sku("BOLT")),
+            Some(policy.clone())
+        );
+        System::assert_last_event(
+            Event::ReorderPolicySet {
+                sku: sku("BOLT"),
+                policy: policy.clone(),
+            }
+            .into(),
+        );
+
+        for invalid in [
+            ReorderPolicy {
+                safety_stock: 11,
+                ..policy.clone()
+            },
+            ReorderPolicy {
+                reorder_qty: 0,
+                ..policy
+            },
+        ] {
+            assert_noop!(
+                Inventory::set_reorder_policy(RuntimeOrigin::root(), sku("BOLT"), invalid),
+                Error::<Test>::InvalidReorderPolicy
+            );
+        }
+    });
+}
+
+#[test]
+fn stock_dropping_to_the_reorder_point_reorders_once() {
+    new_test_ext().execute_with(|| {
+        set_up_reorders(
+            vec![bolts(1, 20)],
+            ReorderPolicy {
+                reorder_point: 10,
+                safety_stock: 0,
+                reorder_qty: 50,
+                create_requisition: false,
+            },
+        );
+
+        adjust_bolts(1, 20, 11);
+        assert_eq!(reorders_raised(), 0);
+
+        adjust_bolts(1, 11, 8);
+        System::assert_has_event(
+            Event::ReorderRequired {
+                sku: sku("BOLT"),
+                on_hand: 8,
+                reorder_point: 10,
+                suggested_qty: 50,
+                safety_stock_breached: false,
+                requisition_id: None,
+            }
+            .into(),
+        );
+
+        // Further drops below the reorder point do not reorder again
+        adjust_bolts(1, 8, 5);
+        assert_eq!(reorders_raised(), 1);
+        assert_eq!(crate::NextRequisitionId::<Test>::get(), 0);
+    });
+}
+
+#[test]
+fn scrapping_into_the_safety_stock_raises_an_urgent_requisition() {
+    new_test_ext().execute_with(|| {
+        set_up_reorders(
+            vec![bolts(1, 10), bolts(2, 3)],
+            ReorderPolicy {
+                reorder_point: 10,
+                safety_stock: 5,
+                reorder_qty: 20,
+                create_requisition: true,
+            },
+        );
+
+        assert_ok!(Inventory::inventory_scrap(
+            RuntimeOrigin::signed(ALICE),
+            sku("BOLT"),
+            1,
+            ScrapDetails {
+                issuer: Employee::Bob,
+                reason: reason("Rusted"),
+                equipment: Equipment::Forklift,
+            }
+        ));
+
+        // The two bolts drawn from the safety stock are bought back on top
+        assert_eq!(
+            crate::PurchaseRequisitions::<Test>::get(0),
+            Some(PurchaseRequisition {
+                requisition_id: 0,
+                sku: sku("BOLT"),
+                qty: 22,
+                on_hand: 3,
+                urgent: true,
+                created_at: 1,
+                status: RequisitionStatus::Draft,
+            })
+        );
+        assert_eq!(crate::OpenRequisitions::<Test>::get(sku("BOLT")), Some(0));
+        System::assert_has_event(
+            Event::ReorderRequired {
+                sku: sku("BOLT"),
+                on_hand: 3,
+                reorder_point: 10,
+                suggested_qty: 22,
+                safety_stock_breached: true,
+                requisition_id: Some(0),
+            }
+            .into(),
+        );
+
+        // The next reorder is covered by the requisition still open
+        adjust_bolts(2, 3, 30);
+        adjust_bolts(2, 30, 4);
+        assert_eq!(reorders_raised(), 2);
+        assert_eq!(crate::NextRequisitionId::<Test>::get(), 1);
+    });
+}
+
+#[test]
+fn policies_set_on_low_stock_reorder_straight_away() {
+    new_test_ext().execute_with(|| {
+        set_up_reorders(
+            vec![bolts(1, 4)],
+            ReorderPolicy {
+                reorder_point: 10,
+                safety_stock: 0,
+                reorder_qty: 20,
+                create_requisition: true,
+            },
+        );
+
+        assert_eq!(crate::OpenRequisitions::<Test>::get(sku("BOLT")), Some(0));
+        assert!(!crate::PurchaseRequisitions::<Test>::get(0).unwrap().urgent);
+    });
+}
+
+#[test]
+fn draft_requisitions_can_be_cancelled() {
+    new_test_ext().execute_with(|| {
+        set_up_reorders(
+            vec![bolts(1, 4)],
+            ReorderPolicy {
+                reorder_point: 10,
+                safety_stock: 0,
+                reorder_qty: 20,
+                create_requisition: true,
+            },
+        );
+
+        assert_noop!(
+            Inventory::cancel_requisition(RuntimeOrigin::signed(ALICE), 0),
+            DispatchError::BadOrigin
+        );
+        assert_ok!(Inventory::cancel_requisition(RuntimeOrigin::root(), 0));
+        assert_eq!(
+            crate::PurchaseRequisitions::<Test>::get(0).unwrap().status,
+            RequisitionStatus::Cancelled
+        );
+        assert_eq!(crate::OpenRequisitions::<Test>::get(sku("BOLT")), None);
+        System::assert_last_event(Event::RequisitionCancelled { requisition_id: 0 }.into());
+
+        assert_noop!(
+            Inventory::cancel_requisition(RuntimeOrigin::root(), 0),
+            Error::<Test>::RequisitionNotDraft
+        );
+        assert_noop!(
+            Inventory::cancel_requisition(RuntimeOrigin::root(), 1),
+            Error::<Test>::RequisitionNotFound
+        );
+    });
+}
+
+#[test]
+fn cleared_policies_stop_reordering() {
+    new_test_ext().execute_with(|| {
+        set_up_reorders(
+            vec![bolts(1, 20)],
+            ReorderPolicy {
+                reorder_point: 10,
+                safety_stock: 0,
+                reorder_qty: 20,
+                create_requisition: false,
+            },
+        );
+
+        assert_noop!(
+            Inventory::clear_reorder_policy(RuntimeOrigin::signed(ALICE), sku("BOLT")),
+            DispatchError::BadOrigin
+        );
+        assert_ok!(Inventory::clear_reorder_policy(
+            RuntimeOrigin::root(),
+            sku("BOLT")
+        ));
+        System::assert_last_event(Event::ReorderPolicyCleared { sku: sku("BOLT") }.into());
+
+        adjust_bolts(1, 20, 5);
+        assert_eq!(reorders_raised(), 0);
+
+        assert_noop!(
+            Inventory::clear_reorder_policy(RuntimeOrigin::root(), sku("BOLT")),
+            Error::<Test>::ReorderPolicyNotFound
+        );
+    });
+}
+
+#[test]
+fn pick_strategies_are_set_for_materials() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(Inventory::pick_strategy(&sku("BOLT")), PickStrategy::Fifo);
+        assert_noop!(
+            Inventory::set_pick_strategy(
+                RuntimeOrigin::signed(ALICE),
+                sku("BOLT"),
+                PickStrategy::Fefo
+            ),
+            Error::<Test>::MaterialNotFound
+        );
+
+        assert_ok!(Inventory::insert_material(
+            RuntimeOrigin::signed(ALICE),
+            Material {
+                sku: sku("BOLT"),
+                ..Default::default()
+            }
+        ));
+        assert_ok!(Inventory::set_pick_strategy(
+            RuntimeOrigin::signed(ALICE),
+            sku("BOLT"),
+            PickStrategy::Fefo
+        ));
+
+        assert_eq!(Inventory::pick_strategy(&sku("BOLT")), PickStrategy::Fefo);
+        System::assert_last_event(
+            Event::PickStrategySet {
+                sender: ALICE,
+                sku: sku("BOLT"),
+                strategy: PickStrategy::Fefo,
+            }
+            .into(),
+        );
+    });
+}
+
+/// Four bolts of each of three serials, created in the order of their serial number:
+/// serial 1 of lot 10 never expires and is in the warehouse, serial 2 of lot 20 expires at
+/// block 7 and is at receiving, and serial 3 of lot 10 expires at block 23 and is in production
+fn candidates() -> Vec<(Item, Qty)> {
+    [
+        (1, 10, 0, Location::Warehouse),
+        (2, 20, 5, Location::Receiving),
+        (3, 10, 20, Location::Production),
+    ]
+    .into_iter()
+    .map(|(serial_number, lot_number, shelf_life, location)| {
+        let item = Item {
+            lot_number,
+            shelf_life,
+            production_date: serial_number,
+            created_at: serial_number,
+            location,
+            ..bolts(serial_number, 4)
+        };
+        (item, 4)
+    })
+    .collect()
+}
+
+/// The serials picked to cover the required quantity for staging, with the quantity of each
+fn picked(strategy: PickStrategy, required_qty: Qty) -> Vec<(SerialNumber, Qty)> {
+    Inventory::pick_stock(candidates(), required_qty, &strategy, &Location::Staging)
+        .into_iter()
+        .map(|(item, qty)| (item.serial_number, qty))
+        .collect()
+}
+
+#[test]
+fn stock_is_picked_in_the_strategy_order() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(picked(PickStrategy::Fifo, 6), vec![(1, 4), (2, 2)]);
+        assert_eq!(picked(PickStrategy::Lifo, 6), vec![(3, 4), (2, 2)]);
+
+        // Stock that never expires goes last
+        assert_eq!(picked(PickStrategy::Fefo, 6), vec![(2, 4), (3, 2)]);
+        assert_eq!(picked(PickStrategy::Fefo, 10), vec![(2, 4), (3, 4), (1, 2)]);
+
+        // The warehouse and production are both next to staging, so the older is picked first
+        assert_eq!(
+            picked(PickStrategy::NearestLocation, 6),
+            vec![(1, 4), (3, 2)]
+        );
+    });
+}
+
+#[test]
+fn specific_lot_picks_fall_short_rather_than_take_another_lot() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(
+            picked(PickStrategy::SpecificLot(10), 10),
+            vec![(1, 4), (3, 4)]
+        );
+        assert_eq!(picked(PickStrategy::SpecificLot(30), 1), vec![]);
+    });
+}
+
+/// Stock ten finished bolts in Alice's warehouse and ship them to Bob
+fn ship_bolts(consignment: bool) {
+    assert_ok!(Inventory::inventory_insertion(
+        RuntimeOrigin::signed(ALICE),
+        Item {
+            inventory_type: InventoryType::FinishedGood,
+            ..bolts(1, 10)
+        }
+    ));
+    assert_ok!(Inventory::ship_transfer(
+        RuntimeOrigin::signed(ALICE),
+        BOB,
+        sku("BOLT"),
+        1,
+        consignment,
+        Employee::Bob
+    ));
+}
+
+fn held_item(who: u64) -> Option<Item> {
+    crate::Inventory::<Test>::get((who, sku("BOLT"), 1))
+}
+
+fn transfer(transfer_id: TransferId) -> Transfer {
+    crate::Transfers::<Test>::get(transfer_id).unwrap().2
+}
+
+#[test]
+fn shipped_stock_stays_with_the_shipper_in_transit() {
+    new_test_ext().execute_with(|| {
+        ship_bolts(false);
+
+        let in_transit = held_item(ALICE).unwrap();
+        assert_eq!(in_transit.inventory_type, InventoryType::Transit);
+        assert_eq!(in_transit.location, Location::Shipping);
+        assert_eq!(held_item(BOB), None);
+
+        let shipped = Transfer {
+            transfer_id: 0,
+            sku: sku("BOLT"),
+            serial_number: 1,
+            qty: 10,
+            inventory_type: InventoryType::FinishedGood,
+            from_location: Location::Warehouse,
+            consignment: false,
+            status: TransferStatus::InTransit,
+            shipped_by: Employee::Bob,
+            shipped_at: 1,
+            received_at: None,
+        };
+        assert_eq!(
+            crate::Transfers::<Test>::get(0),
+            Some((ALICE, BOB, shipped.clone()))
+        );
+        System::assert_last_event(
+            Event::TransferShipped {
+                sender: ALICE,
+                to: BOB,
+                transfer: shipped,
+            }
+            .into(),
+        );
+
+        // Stock in transit is only moved by receiving or cancelling the transfer
+        assert_noop!(
+            Inventory::ship_transfer(
+                RuntimeOrigin::signed(ALICE),
+                CHARLIE,
+                sku("BOLT"),
+                1,
+                false,
+                Employee::Bob
+            ),
+            Error::<Test>::ItemInTransit
+        );
+        assert_noop!(
+            Inventory::inventory_move(
+                RuntimeOrigin::signed(ALICE),
+                sku("BOLT"),
+                1,
+                Employee::Bob,
+                AdjustDetails::Location {
+                    original_location: Location::Shipping,
+                    new_location: Location::Warehouse,
+                    reason: reason("Return"),
+                }
+            ),
+            Error::<Test>::ItemInTransit
+        );
+    });
+}
+
+#[test]
+fn transfers_ship_unheld_stock_to_another_account() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Inventory::inventory_insertion(
+            RuntimeOrigin::signed(ALICE),
+            bolts(1, 10)
+        ));
+
+        assert_noop!(
+            Inventory::ship_transfer(
+                RuntimeOrigin::signed(ALICE),
+                ALICE,
+                sku("BOLT"),
+                1,
+                false,
+                Employee::Bob
+            ),
+            Error::<Test>::InvalidTransfer
+        );
+        assert_noop!(
+            Inventory::ship_transfer(
+                RuntimeOrigin::signed(ALICE),
+                BOB,
+                sku("BOLT"),
+                2,
+                false,
+                Employee::Bob
+            ),
+            Error::<Test>::InventoryNotFound
+        );
+
+        // Bob already stocks bolts under the serial
+        assert_ok!(Inventory::inventory_insertion(
+            RuntimeOrigin::signed(BOB),
+            Item {
+                location: Location::Production,
+                ..bolts(1, 2)
+            }
+        ));
+        assert_noop!(
+            Inventory::ship_transfer(
+                RuntimeOrigin::signed(ALICE),
+                BOB,
+                sku("BOLT"),
+                1,
+                false,
+                Employee::Bob
+            ),
+            Error::<Test>::SerialNumberInUse
+        );
+
+        assert_ok!(Inventory::do_place_hold(
+            &ALICE,
+            sku("BOLT"),
+            1,
+            HoldSource::Recall(0),
+            reason("Recall")
+        ));
+        assert_noop!(
+            Inventory::ship_transfer(
+                RuntimeOrigin::signed(ALICE),
+                CHARLIE,
+                sku("BOLT"),
+                1,
+                false,
+                Employee::Bob
+            ),
+            Error::<Test>::ItemOnHold
+        );
+    });
+}
+
+#[test]
+fn received_transfers_change_ownership() {
+    new_test_ext().execute_with(|| {
+        ship_bolts(false);
+
+        assert_noop!(
+            Inventory::receive_transfer(RuntimeOrigin::signed(BOB), 1, Employee::Dave),
+            Error::<Test>::TransferNotFound
+        );
+        assert_noop!(
+            Inventory::receive_transfer(RuntimeOrigin::signed(CHARLIE), 0, Employee::Dave),
+            Error::<Test>::NotTransferRecipient
+        );
+
+        System::set_block_number(4);
+        assert_ok!(Inventory::receive_transfer(
+            RuntimeOrigin::signed(BOB),
+            0,
+            Employee::Dave
+        ));
+
+        assert_eq!(held_item(ALICE), None);
+        let received = held_item(BOB).unwrap();
+        assert_eq!(received.inventory_type, InventoryType::FinishedGood);
+        assert_eq!(received.location, Location::Receiving);
+        assert_eq!(received.qty, 10);
+        assert_eq!(Inventory::consignment_owner(&BOB, &sku("BOLT"), 1), None);
+        assert_eq!(
+            crate::TransferredItems::<Test>::get((ALICE, sku("BOLT"), 1)),
+            Some(BOB)
+        );
+
+        let received_transfer = transfer(0);
+        assert_eq!(received_transfer.status, TransferStatus::Received);
+        assert_eq!(received_transfer.received_at, Some(4));
+        System::assert_last_event(
+            Event::TransferReceived {
+                sender: BOB,
+                from: ALICE,
+                transfer: received_transfer,
+            }
+            .into(),
+        );
+
+        assert_noop!(
+            Inventory::receive_transfer(RuntimeOrigin::signed(BOB), 0, Employee::Dave),
+            Error::<Test>::TransferNotInTransit
+        );
+        assert_noop!(
+            Inventory::cancel_transfer(RuntimeOrigin::signed(ALICE), 0),
+            Error::<Test>::TransferNotInTransit
+        );
+    });
+}
+
+#[test]
+fn transfers_are_not_received_over_a_stocked_serial() {
+    new_test_ext().execute_with(|| {
+        ship_bolts(false);
+        assert_ok!(Inventory::inventory_insertion(
+            RuntimeOrigin::signed(CHARLIE),
+            Item {
+                location: Location::Receiving,
+                ..bolts(1, 4)
+            }
+        ));
+
+        assert_noop!(
+            Inventory::receive_transfer(RuntimeOrigin::signed(BOB), 0, Employee::Dave),
+            Error::<Test>::SerialNumberInUse
+        );
+    });
+}
+
+#[test]
+fn cancelled_transfers_return_the_stock() {
+    new_test_ext().execute_with(|| {
+        ship_bolts(false);
+
+        assert_noop!(
+            Inventory::cancel_transfer(RuntimeOrigin::signed(BOB), 0),
+            Error::<Test>::NotTransferShipper
+        );
+        assert_noop!(
+            Inventory::cancel_transfer(RuntimeOrigin::signed(ALICE), 1),
+            Error::<Test>::TransferNotFound
+        );
+
+        // Held stock stays in transit until the hold is released
+        assert_ok!(Inventory::do_place_hold(
+            &ALICE,
+            sku("BOLT"),
+            1,
+            HoldSource::Recall(0),
+            reason("Recall")
+        ));
+        assert_noop!(
+            Inventory::cancel_transfer(RuntimeOrigin::signed(ALICE), 0),
+            Error::<Test>::ItemOnHold
+        );
+        Inventory::do_release_hold(&ALICE, sku("BOLT"), 1, HoldSource::Recall(0));
+
+        assert_ok!(Inventory::cancel_transfer(RuntimeOrigin::signed(ALICE), 0));
+
+        let returned = held_item(ALICE).unwrap();
+        assert_eq!(returned.inventory_type, InventoryType::FinishedGood);
+        assert_eq!(returned.location, Location::Warehouse);
+        assert_eq!(transfer(0).status, TransferStatus::Cancelled);
+        System::assert_last_event(
+            Event::TransferCancelled {
+                sender: ALICE,
+                transfer_id: 0,
+            }
+            .into(),
+        );
+
+        assert_noop!(
+            Inventory::receive_transfer(RuntimeOrigin::signed(BOB), 0, Employee::Dave),
+            Error::<Test>::TransferNotInTransit
+        );
+    });
+}
+
+#[test]
+fn consigned_stock_can_only_go_back_to_its_owner() {
+    new_test_ext().execute_with(|| {
+        ship_bolts(true);
+        assert_ok!(Inventory::receive_transfer(
+            RuntimeOrigin::signed(BOB),
+            0,
+            Employee::Dave
+        ));
+
+        assert_eq!(
+            Inventory::consignment_owner(&BOB, &sku("BOLT"), 1),
+            Some(ALICE)
+        );
+        assert_noop!(
+            Inventory::ship_transfer(
+                RuntimeOrigin::signed(BOB),
+                CHARLIE,
+                sku("BOLT"),
+                1,
+                false,
+                Employee::Dave
+            ),
+            Error::<Test>::ConsignedStock
+        );
+        assert_noop!(
+            Inventory::ship_transfer(
+                RuntimeOrigin::signed(BOB),
+                ALICE,
+                sku("BOLT"),
+                1,
+                true,
+                Employee::Dave
+            ),
+            Error::<Test>::ConsignedStock
+        );
+
+        assert_ok!(Inventory::ship_transfer(
+            RuntimeOrigin::signed(BOB),
+            ALICE,
+            sku("BOLT"),
+            1,
+            false,
+            Employee::Dave
+        ));
+        assert_ok!(Inventory::receive_transfer(
+            RuntimeOrigin::signed(ALICE),
+            1,
+            Employee::Bob
+        ));
+
+        // Back with its owner, the stock is no longer consigned to anyone
+        assert_eq!(held_item(ALICE).unwrap().location, Location::Receiving);
+        assert_eq!(Inventory::consignment_owner(&BOB, &sku("BOLT"), 1), None);
+        assert_eq!(Inventory::consignment_owner(&ALICE, &sku("BOLT"), 1), None);
+    });
+}
+
+#[test]
+fn settling_consigned_stock_takes_ownership_of_it() {
+    new_test_ext().execute_with(|| {
+        ship_bolts(true);
+
+        // Stock is only consigned once it is received
+        assert_noop!(
+            Inventory::settle_consignment(RuntimeOrigin::signed(ALICE), BOB, sku("BOLT"), 1),
+            Error::<Test>::NotConsigned
+        );
+
+        assert_ok!(Inventory::receive_transfer(
+            RuntimeOrigin::signed(BOB),
+            0,
+            Employee::Dave
+        ));
+
+        // The holder cannot take ownership of the stock on its own, nor scrap it
+        assert_noop!(
+            Inventory::settle_consignment(RuntimeOrigin::signed(BOB), BOB, sku("BOLT"), 1),
+            Error::<Test>::NotConsignmentOwner
+        );
+        assert_noop!(
+            Inventory::inventory_scrap(
+                RuntimeOrigin::signed(BOB),
+                sku("BOLT"),
+                1,
+                ScrapDetails {
+                    issuer: Employee::Dave,
+                    reason: reason("Damaged"),
+                    equipment: Equipment::Forklift,
+                }
+            ),
+            Error::<Test>::ItemConsigned
+        );
+
+        assert_ok!(Inventory::settle_consignment(
+            RuntimeOrigin::signed(ALICE),
+            BOB,
+            sku("BOLT"),
+            1
+        ));
+
+        assert_eq!(Inventory::consignment_owner(&BOB, &sku("BOLT"), 1), None);
+        System::assert_last_event(
+            Event::ConsignmentSettled {
+                sender: ALICE,
+                holder: BOB,
+                sku: sku("BOLT"),
+                serial_number: 1,
+            }
+            .into(),
+        );
+
+        // Settled stock can go anywhere
+        assert_ok!(Inventory::ship_transfer(
+            RuntimeOrigin::signed(BOB),
+            CHARLIE,
+            sku("BOLT"),
+            1,
+            false,
+            Employee::Dave
+        ));
+    });
+}
+
+/// Bolts worth five each, ten of them in stock, with Bob and Charlie approving Alice's changes
+fn set_up_approvals(policy: ApprovalPolicy) {
+    assert_ok!(Inventory::insert_material(
+        RuntimeOrigin::signed(ALICE),
+        Material {
+            sku: sku("BOLT"),
+            unit_value: 5,
+            ..Default::default()
+        }
+    ));
+    assert_ok!(Inventory::inventory_insertion(
+        RuntimeOrigin::signed(ALICE),
+        bolts(1, 10)
+    ));
+    for approver in [BOB, CHARLIE] {
+        assert_ok!(Inventory::add_approver(
+            RuntimeOrigin::root(),
+            ALICE,
+            approver
+        ));
+    }
+    assert_ok!(Inventory::set_approval_policy(
+        RuntimeOrigin::root(),
+        ALICE,
+        policy
+    ));
+}
+
+/// Changes of more than three bolts need two approvals within five blocks
+fn qty_policy() -> ApprovalPolicy {
+    ApprovalPolicy {
+        qty_threshold: Some(3),
+        value_threshold: None,
+        required_approvals: 2,
+        expires_after: 5,
+    }
+}
+
+fn request(request_id: ApprovalRequestId) -> ApprovalRequest {
+    crate::ApprovalRequests::<Test>::get(request_id).unwrap().1
+}
+
+fn bolt_qty() -> Qty {
+    crate::Inventory::<Test>::get((ALICE, sku("BOLT"), 1))
+        .unwrap()
+        .qty
+}
+
+#[test]
+fn approval_policies_need_thresholds_and_enough_approvers() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Inventory::set_approval_policy(RuntimeOrigin::signed(ALICE), ALICE, qty_policy()),
+            DispatchError::BadOrigin
+        );
+        assert_noop!(
+            Inventory::add_approver(RuntimeOrigin::root(), ALICE, ALICE),
+            Error::<Test>::InvalidApprover
+        );
+        assert_ok!(Inventory::add_approver(RuntimeOrigin::root(), ALICE, BOB));
+        System::assert_last_event(
+            Event::ApproverAdded {
+                owner: ALICE,
+                approver: BOB,
+            }
+            .into(),
+        );
+
+        assert_noop!(
+            Inventory::set_approval_policy(RuntimeOrigin::root(), ALICE, qty_policy()),
+            Error::<Test>::NotEnoughApprovers
+        );
+        for invalid in [
+            ApprovalPolicy {
+                qty_threshold: None,
+                ..qty_policy()
+            },
+            ApprovalPolicy {
+                required_approvals: 0,
+                ..qty_policy()
+            },
+            ApprovalPolicy {
+                expires_after: 0,
+                ..qty_policy()
+            },
+        ] {
+            assert_noop!(
+                Inventory::set_approval_policy(RuntimeOrigin::root(), ALICE, invalid),
+                Error::<Test>::InvalidApprovalPolicy
+            );
+        }
+
+        assert_ok!(Inventory::add_approver(
+            RuntimeOrigin::root(),
+            ALICE,
+            CHARLIE
+        ));
+        assert_ok!(Inventory::set_approval_policy(
+            RuntimeOrigin::root(),
+            ALICE,
+            qty_policy()
+        ));
+        assert_eq!(
+            crate::ApprovalPolicies::<Test>::get(ALICE),
+            Some(qty_policy())
+        );
+        System::assert_last_event(
+            Event::ApprovalPolicySet {
+                owner: ALICE,
+                policy: qty_policy(),
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn approvers_are_kept_while_the_policy_needs_them() {
+    new_test_ext().execute_with(|| {
+        set_up_approvals(qty_policy());
+
+        assert_noop!(
+            Inventory::remove_approver(RuntimeOrigin::root(), ALICE, BOB),
+            Error::<Test>::NotEnoughApprovers
+        );
+        assert_noop!(
+            Inventory::remove_approver(RuntimeOrigin::root(), BOB, ALICE),
+            Error::<Test>::ApproverNotFound
+        );
+
+        assert_ok!(Inventory::clear_approval_policy(
+            RuntimeOrigin::root(),
+            ALICE
+        ));
+        System::assert_last_event(Event::ApprovalPolicyCleared { owner: ALICE }.into());
+        assert_noop!(
+            Inventory::clear_approval_policy(RuntimeOrigin::root(), ALICE),
+            Error::<Test>::ApprovalPolicyNotFound
+        );
+
+        assert_ok!(Inventory::remove_approver(
+            RuntimeOrigin::root(),
+            ALICE,
+            BOB
+        ));
+        System::assert_last_event(
+            Event::ApproverRemoved {
+                owner: ALICE,
+                approver: BOB,
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn changes_above_the_threshold_wait_for_approval() {
+    new_test_ext().execute_with(|| {
+        set_up_approvals(qty_policy());
+
+        // Three bolts is within the threshold
+        adjust_bolts(1, 10, 7);
+        assert_eq!(bolt_qty(), 7);
+        assert_eq!(crate::NextApprovalRequestId::<Test>::get(), 0);
+
+        adjust_bolts(1, 7, 2);
+
+        assert_eq!(bolt_qty(), 7);
+        let requested = ApprovalRequest {
+            request_id: 0,
+            sku: sku("BOLT"),
+            serial_number: 1,
+            change: PendingChange::Adjust {
+                issuer: Employee::Bob,
+                adjust_details: AdjustDetails::Quantity {
+                    original_qty: 7,
+                    new_qty: 2,
+                    reason: reason("Cycle count"),
+                },
+            },
+            item_qty: 7,
+            qty: 5,
+            value: 25,
+            required_approvals: 2,
+            approvals: 0,
+            status: ApprovalStatus::Pending,
+            created_at: 1,
+            expires_at: 6,
+        };
+        assert_eq!(request(0), requested);
+        assert_eq!(crate::RequestExpiries::<Test>::get(6).into_inner(), vec![0]);
+        System::assert_last_event(
+            Event::ApprovalRequested {
+                sender: ALICE,
+                request: requested,
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn requests_apply_once_they_have_enough_approvals() {
+    new_test_ext().execute_with(|| {
+        set_up_approvals(qty_policy());
+        adjust_bolts(1, 10, 2);
+
+        assert_ok!(Inventory::approve_request(RuntimeOrigin::signed(BOB), 0));
+
+        assert_eq!(request(0).approvals, 1);
+        assert_eq!(request(0).status, ApprovalStatus::Pending);
+        assert_eq!(bolt_qty(), 10);
+        System::assert_last_event(
+            Event::RequestApproved {
+                sender: BOB,
+                owner: ALICE,
+                request_id: 0,
+                approvals: 1,
+            }
+            .into(),
+        );
+
+        assert_noop!(
+            Inventory::approve_request(RuntimeOrigin::signed(BOB), 0),
+            Error::<Test>::AlreadyApproved
+        );
+        assert_noop!(
+            Inventory::approve_request(RuntimeOrigin::signed(ALICE), 0),
+            Error::<Test>::NotApprover
+        );
+        assert_noop!(
+            Inventory::approve_request(RuntimeOrigin::signed(BOB), 1),
+            Error::<Test>::ApprovalRequestNotFound
+        );
+
+        assert_ok!(Inventory::approve_request(
+            RuntimeOrigin::signed(CHARLIE),
+            0
+        ));
+
+        assert_eq!(bolt_qty(), 2);
+        assert_eq!(request(0).status, ApprovalStatus::Approved);
+        System::assert_last_event(
+            Event::RequestApplied {
+                owner: ALICE,
+                request_id: 0,
+            }
+            .into(),
+        );
+
+        assert_noop!(
+            Inventory::reject_request(RuntimeOrigin::signed(BOB), 0),
+            Error::<Test>::RequestNotPending
+        );
+    });
+}
+
+#[test]
+fn scraps_above_the_value_threshold_wait_for_approval() {
+    new_test_ext().execute_with(|| {
+        set_up_approvals(ApprovalPolicy {
+            qty_threshold: None,
+            value_threshold: Some(40),
+            ..qty_policy()
+        });
+        let scrap_details = ScrapDetails {
+            issuer: Employee::Bob,
+            reason: reason("Rusted"),
+            equipment: Equipment::Forklift,
+        };
+
+        assert_ok!(Inventory::inventory_scrap(
+            RuntimeOrigin::signed(ALICE),
+            sku("BOLT"),
+            1,
+            scrap_details.clone()
+        ));
+
+        assert_eq!(request(0).value, 50);
+        assert_eq!(
+            request(0).change,
+            PendingChange::Scrap(scrap_details.clone())
+        );
+        assert_eq!(bolt_qty(), 10);
+
+        for approver in [BOB, CHARLIE] {
+            assert_ok!(Inventory::approve_request(
+                RuntimeOrigin::signed(approver),
+                0
+            ));
+        }
+
+        assert!(!crate::Inventory::<Test>::contains_key((
+            ALICE,
+            sku("BOLT"),
+            1
+        )));
+        assert!(crate::ScrapInventory::<Test>::contains_key((
+            ALICE,
+            sku("BOLT"),
+            1
+        )));
+        System::assert_has_event(
+            Event::ItemScrapped {
+                sender: ALICE,
+                sku: sku("BOLT"),
+                serial_number: 1,
+                scrap_details,
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn rejected_requests_are_not_applied() {
+    new_test_ext().execute_with(|| {
+        set_up_approvals(qty_policy());
+        adjust_bolts(1, 10, 2);
+        assert_ok!(Inventory::approve_request(RuntimeOrigin::signed(BOB), 0));
+
+        assert_noop!(
+            Inventory::reject_request(RuntimeOrigin::signed(ALICE), 0),
+            Error::<Test>::NotApprover
+        );
+        assert_ok!(Inventory::reject_request(RuntimeOrigin::signed(CHARLIE), 0));
+
+        assert_eq!(request(0).status, ApprovalStatus::Rejected);
+        assert_eq!(bolt_qty(), 10);
+        System::assert_last_event(
+            Event::RequestRejected {
+                sender: CHARLIE,
+                owner: ALICE,
+                request_id: 0,
+            }
+            .into(),
+        );
+
+        assert_noop!(
+            Inventory::approve_request(RuntimeOrigin::signed(CHARLIE), 0),
+            Error::<Test>::RequestNotPending
+        );
+    });
+}
+
+#[test]
+fn pending_requests_expire() {
+    new_test_ext().execute_with(|| {
+        set_up_approvals(qty_policy());
+        adjust_bolts(1, 10, 2);
+
+        Inventory::on_initialize(5);
+        assert_eq!(request(0).status, ApprovalStatus::Pending);
+
+        Inventory::on_initialize(6);
+
+        assert_eq!(request(0).status, ApprovalStatus::Expired);
+        System::assert_last_event(
+            Event::RequestExpired {
+                owner: ALICE,
+                request_id: 0,
+            }
+            .into(),
+        );
+        assert_noop!(
+            Inventory::approve_request(RuntimeOrigin::signed(BOB), 0),
+            Error::<Test>::RequestNotPending
+        );
+    });
+}
+
+#[test]
+fn requests_for_items_changed_since_are_not_applied() {
+    new_test_ext().execute_with(|| {
+        set_up_approvals(qty_policy());
+        adjust_bolts(1, 10, 2);
+        assert_ok!(Inventory::approve_request(RuntimeOrigin::signed(BOB), 0));
+
+        adjust_bolts(1, 10, 9);
+
+        // The last approval closes the request instead of applying it
+        assert_ok!(Inventory::approve_request(
+            RuntimeOrigin::signed(CHARLIE),
+            0
+        ));
+
+        assert_eq!(bolt_qty(), 9);
+        assert_eq!(request(0).approvals, 2);
+        assert_eq!(request(0).status, ApprovalStatus::Failed);
+        System::assert_last_event(
+            Event::RequestFailed {
+                owner: ALICE,
+                request_id: 0,
+                error: Error::<Test>::ItemChanged.into(),
+            }
+            .into(),
+        );
+        assert_noop!(
+            Inventory::reject_request(RuntimeOrigin::signed(BOB), 0),
+            Error::<Test>::RequestNotPending
+        );
+    });
+}
+
+#[test]
+fn reasons_must_fit_their_bound() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(Inventory::reason("Cycle count"), Ok(reason("Cycle count")));
+        assert_eq!(
+            Inventory::reason(&"x".repeat(129)),
+            Err(Error::<Test>::ReasonTooLong.into())
+        );
+    });
+}
@@ -1,6 +1,6 @@
 use codec::{Decode, Encode};
 use frame_support::pallet_prelude::MaxEncodedLen;
-use frame_support::sp_runtime::RuntimeDebug;
+use frame_support::sp_runtime::{Percent, RuntimeDebug};
 use frame_support::traits::ConstU32;
 use frame_support::BoundedVec;
 use scale_info::TypeInfo;
@@ -14,9 +14,24 @@ pub type ProductionDate = u32;
 pub type WeightLbs = u32;
 pub type PurchaseDate = u32;
 pub type Qty = u32;
+pub type AssetId = u32;
+pub type RecipeId = u32;
+pub type RequisitionId = u32;
+pub type TransferId = u32;
+pub type ApprovalRequestId = u32;
+/// Standard value of one unit of a material
+pub type UnitValue = u64;
+pub type InventoryValue = u128;
+pub type OperationNumber = u32;
+pub type WorkCenter = BoundedVec<u8, ConstU32<32>>;
 pub type Sku = BoundedVec<u8, ConstU32<16>>;
 pub type Reason = BoundedVec<u8, ConstU32<128>>;
 
+/// Most approval requests that can expire at the same block
+pub const MAX_EXPIRING_REQUESTS: u32 = 100;
+/// Most serials followed when listing the serials split off an item
+pub const MAX_SPLIT_DESCENDANTS: usize = 100;
+
 #[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, Default, MaxEncodedLen)]
 pub enum AbcCode {
     #[default]
@@ -41,6 +56,38 @@ pub enum Equipment {
     Mixer,
 }
 
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, Default, MaxEncodedLen)]
+pub enum EquipmentStatus {
+    #[default]
+    Available,
+    InUse,
+    Down,
+    Maintenance,
+}
+
+/// An individual piece of equipment tracked in the registry
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
+pub struct EquipmentAsset {
+    pub asset_id: AssetId,
+    pub equipment_type: Equipment,
+    pub location: Location,
+    pub status: EquipmentStatus,
+    /// Number of times the asset has been locked for use
+    pub usage_count: u32,
+}
+
+/// What placed an item on quality hold
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum HoldSource {
+    Recall(u32),
+    /// Awaiting inspection after receipt against an expected receipt
+    Inspection(u32),
+    /// Packed into a handling unit, until it is unpacked or shipped
+    Packing(u64),
+    /// Reopened by a rework order, until the order is completed
+    Rework(u32),
+}
+
 #[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, Default, MaxEncodedLen)]
 pub enum InventoryType {
     #[default]
@@ -78,6 +125,35 @@ pub enum Location {
     Scrap,
     Staging,
     Packaging,
+    /// Held for the Material Review Board to decide on
+    Mrb,
+}
+
+impl Location {
+    /// Position of the location along the material flow, from receiving to shipping
+    fn flow_position(&self) -> u32 {
+        match self {
+            Location::Receiving => 0,
+            Location::Mrb => 1,
+            Location::Warehouse => 2,
+            Location::Staging => 3,
+            Location::Production => 4,
+            Location::Packaging => 5,
+            Location::Shipping => 6,
+            Location::Scrap => 7,
+        }
+    }
+
+    /// How far apart two locations are along the material flow
+    pub fn distance(&self, other: &Location) -> u32 {
+        self.flow_position().abs_diff(other.flow_position())
+    }
+
+    /// Whether stock at the location can be chosen for use. Stock awaiting the Material Review
+    /// Board or set aside as scrap cannot.
+    pub fn is_usable(&self) -> bool {
+        !matches!(self, Location::Mrb | Location::Scrap)
+    }
 }
 
 #[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, Default, MaxEncodedLen)]
@@ -106,12 +182,206 @@ pub struct Item {
     pub shelf_life: ShelfLife,
     pub cycle_count: CycleCount,
     pub created_at: u32,
+    pub production_date: ProductionDate,
     pub location: Location,
 }
 
+impl Item {
+    /// Block the item expires at, counted from its production date. Items without a shelf life
+    /// never expire.
+    pub fn expiration_date(&self) -> Option<ExpirationDate> {
+        (self.shelf_life > 0).then(|| self.production_date.saturating_add(self.shelf_life))
+    }
+}
+
+/// Order stock is picked in when several items could cover a requirement
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, Default, MaxEncodedLen)]
+pub enum PickStrategy {
+    /// First in, first out, by creation date
+    #[default]
+    Fifo,
+    /// First expired, first out. Items that never expire are picked last.
+    Fefo,
+    /// Last in, first out, by creation date
+    Lifo,
+    /// Only items of the given lot, first in first out
+    SpecificLot(LotNumber),
+    /// Items closest to where they are taken to, first in first out
+    NearestLocation,
+}
+
+impl PickStrategy {
+    /// Whether the strategy allows the item to be picked at all
+    pub fn allows(&self, item: &Item) -> bool {
+        match self {
+            PickStrategy::SpecificLot(lot_number) => item.lot_number == *lot_number,
+            _ => true,
+        }
+    }
+
+    /// Key candidate items are sorted by, lowest first, when picking for `destination`
+    pub fn sort_key(&self, item: &Item, destination: &Location) -> (u32, u32) {
+        match self {
+            PickStrategy::Fifo | PickStrategy::SpecificLot(_) => (item.created_at, 0),
+            PickStrategy::Fefo => (
+                item.expiration_date().unwrap_or(ExpirationDate::MAX),
+                item.created_at,
+            ),
+            PickStrategy::Lifo => (u32::MAX - item.created_at, 0),
+            PickStrategy::NearestLocation => (item.location.distance(destination), item.created_at),
+        }
+    }
+}
+
+/// Material master data, used to populate items produced for the SKU
 #[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
 pub struct Material {
     pub sku: Sku,
+    pub abc_code: AbcCode,
+    pub product_type: ProductType,
+    pub weight: WeightLbs,
+    pub shelf_life: ShelfLife,
+    /// Blocks needed to buy or make the SKU, used for planning
+    pub lead_time: u32,
+    /// Standard value of one unit, used to value adjustments and scraps for approval
+    pub unit_value: UnitValue,
+}
+
+/// Min/max replenishment settings for a material
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
+pub struct ReorderPolicy {
+    /// Total stock at or below which the SKU is reordered
+    pub reorder_point: Qty,
+    /// Buffer held as `InventoryType::SafetyAnticipationStock`, at most the reorder point.
+    /// Falling below it marks the reorder as urgent and tops up the reorder quantity.
+    pub safety_stock: Qty,
+    /// Quantity reordered each time the reorder point is reached
+    pub reorder_qty: Qty,
+    /// Raise a draft purchase requisition as well as the `ReorderRequired` event
+    pub create_requisition: bool,
+}
+
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
+pub enum RequisitionStatus {
+    #[default]
+    Draft,
+    /// Turned into a purchase order
+    Converted,
+    Cancelled,
+}
+
+/// A request to buy a SKU, raised when its stock reaches the reorder point
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct PurchaseRequisition {
+    pub requisition_id: RequisitionId,
+    pub sku: Sku,
+    pub qty: Qty,
+    /// Total stock of the SKU when the requisition was raised
+    pub on_hand: Qty,
+    /// Stock had fallen below the safety stock
+    pub urgent: bool,
+    pub created_at: u32,
+    pub status: RequisitionStatus,
+}
+
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
+pub enum TransferStatus {
+    #[default]
+    InTransit,
+    Received,
+    /// Called off by the shipping account before it was received
+    Cancelled,
+}
+
+/// An item shipped from one account's inventory to another's
+///
+/// The item stays in the shipping account's inventory as `InventoryType::Transit` until the
+/// receiving account receives it.
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct Transfer {
+    pub transfer_id: TransferId,
+    pub sku: Sku,
+    pub serial_number: SerialNumber,
+    pub qty: Qty,
+    /// Inventory type the item had before it was shipped, restored when it arrives
+    pub inventory_type: InventoryType,
+    /// Where the item was shipped from, which it returns to if the transfer is cancelled
+    pub from_location: Location,
+    /// The receiving account holds the item on consignment, still owned by the shipping account
+    pub consignment: bool,
+    pub status: TransferStatus,
+    pub shipped_by: Employee,
+    pub shipped_at: u32,
+    pub received_at: Option<u32>,
+}
+
+/// When an account's manual quantity adjustments and scraps need approval before they apply
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
+pub struct ApprovalPolicy {
+    /// Units added or removed above which a change needs approval
+    pub qty_threshold: Option<Qty>,
+    /// Value of the units added or removed, at the material's unit value, above which a change
+    /// needs approval
+    pub value_threshold: Option<InventoryValue>,
+    /// Approvals needed from the account's approvers
+    pub required_approvals: u8,
+    /// Blocks a request stays open for before it expires
+    pub expires_after: u32,
+}
+
+impl ApprovalPolicy {
+    /// Whether a change of `qty` units worth `value` needs approval
+    pub fn requires_approval(&self, qty: Qty, value: InventoryValue) -> bool {
+        self.qty_threshold.is_some_and(|threshold| qty > threshold)
+            || self
+                .value_threshold
+                .is_some_and(|threshold| value > threshold)
+    }
+}
+
+/// A change to an item waiting for approval
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub enum PendingChange {
+    Adjust {
+        issuer: Employee,
+        adjust_details: AdjustDetails,
+    },
+    Scrap(ScrapDetails),
+}
+
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
+pub enum ApprovalStatus {
+    #[default]
+    Pending,
+    /// Approved by enough approvers, and applied
+    Approved,
+    Rejected,
+    /// Not approved before it expired
+    Expired,
+    /// Approved by enough approvers, but could no longer be applied, e.g. because the item
+    /// changed since the change was requested
+    Failed,
+}
+
+/// A quantity adjustment or scrap above the account's approval thresholds
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct ApprovalRequest {
+    pub request_id: ApprovalRequestId,
+    pub sku: Sku,
+    pub serial_number: SerialNumber,
+    pub change: PendingChange,
+    /// Quantity of the item when the change was requested. The change is only applied if the
+    /// item still holds it.
+    pub item_qty: Qty,
+    /// Units the change adds or removes
+    pub qty: Qty,
+    /// Value of the units the change adds or removes
+    pub value: InventoryValue,
+    pub required_approvals: u8,
+    pub approvals: u8,
+    pub status: ApprovalStatus,
+    pub created_at: u32,
+    pub expires_at: u32,
 }
 
 #[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
@@ -159,14 +429,165 @@ pub struct Lot {
 pub struct Recipe {
     pub inserted_by: Employee,
     pub sku: Sku,
-    pub recipe_id: u32,
+    /// Version of the recipe for its SKU
+    pub recipe_id: RecipeId,
     pub required_components: BoundedVec<RecipeComponent, ConstU32<100>>,
     pub required_equipment: Equipment,
     pub output_quantity: u32,
+    /// Share of started units expected to come out of the process as good output
+    pub expected_yield: Percent,
+    /// Co-products and by-products made alongside `output_quantity` of the primary SKU
+    pub secondary_outputs: BoundedVec<RecipeOutput, ConstU32<10>>,
+    /// Operations the recipe is made through, in order. Empty for single-step assembly.
+    pub routing: BoundedVec<Operation, ConstU32<20>>,
+}
+
+impl Recipe {
+    /// Quantity of a component to stage so that `output_quantity` good units can be produced.
+    ///
+    /// The recipe quantity is grossed up for the component's scrap factor and then for the
+    /// recipe's expected yield, rounding up so staging never falls short.
+    pub fn required_qty(&self, component: &RecipeComponent) -> Qty {
+        let usable = 100 - component.scrap_percent.deconstruct().min(99) as u64;
+        let yield_pct = self.expected_yield.deconstruct().max(1) as u64;
+        let gross = (component.qty as u64 * 100 * 100).div_ceil(usable * yield_pct);
+
+        gross.min(Qty::MAX as u64) as Qty
+    }
+
+    /// Number of units that must be started to produce `output_quantity` at the expected yield
+    pub fn planned_input_qty(&self) -> Qty {
+        let yield_pct = self.expected_yield.deconstruct().max(1) as u64;
+        let started = (self.output_quantity as u64 * 100).div_ceil(yield_pct);
+
+        started.min(Qty::MAX as u64) as Qty
+    }
+
+    /// Whether a component is consumed by one of the recipe's operations
+    pub fn is_routed(&self, component_index: usize) -> bool {
+        self.routing
+            .iter()
+            .any(|operation| operation.components.contains(&(component_index as u8)))
+    }
+
+    /// Quantity of a secondary output made alongside `produced_qty` units of the primary SKU.
+    ///
+    /// The recipe quantity is scaled to the quantity actually produced, rounding down.
+    pub fn secondary_qty(&self, output: &RecipeOutput, produced_qty: Qty) -> Qty {
+        let scaled = output.qty as u64 * produced_qty as u64 / self.output_quantity.max(1) as u64;
+
+        scaled.min(Qty::MAX as u64) as Qty
+    }
+}
+
+/// One step of a recipe's routing
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct Operation {
+    pub operation_number: OperationNumber,
+    pub work_center: WorkCenter,
+    /// Equipment the operation needs on top of the recipe's required equipment
+    pub equipment: Option<Equipment>,
+    /// Standard labor time per unit, in minutes
+    pub standard_time: u32,
+    /// Indexes into `required_components` of the components consumed by this operation
+    pub components: BoundedVec<u8, ConstU32<100>>,
+}
+
+/// How a secondary output of a recipe is classified
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub enum OutputKind {
+    /// Made intentionally alongside the primary output, e.g. the other cuts of a cutting run
+    CoProduct,
+    /// Incidental output of the process, e.g. offcuts that go back into stock
+    ByProduct,
+}
+
+impl OutputKind {
+    /// The inventory type an output of this kind is stocked as
+    pub fn inventory_type(&self) -> InventoryType {
+        match self {
+            OutputKind::CoProduct => InventoryType::FinishedGood,
+            OutputKind::ByProduct => InventoryType::RawMaterial,
+        }
+    }
+}
+
+/// A secondary output of a recipe, made per `output_quantity` of the primary SKU
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct RecipeOutput {
+    pub sku: Sku,
+    pub qty: Qty,
+    pub kind: OutputKind,
 }
 
 #[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
 pub struct RecipeComponent {
     pub sku: Sku,
     pub qty: u32,
+    /// Share of this component lost to scrap during the process
+    pub scrap_percent: Percent,
+    /// How the component is issued to work orders
+    pub issue_mode: IssueMode,
+}
+
+/// How a recipe component gets from stock to the work orders that consume it
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
+pub enum IssueMode {
+    /// Kitted into the work order's staging area and consumed from there
+    #[default]
+    PreKitted,
+    /// Drawn from stock automatically when its operation reports units, or at assembly
+    Backflush,
+    /// Issued to the work order explicitly before assembly
+    Manual,
+}
+
+/// Stock reserved for orders held outside this pallet, such as sales order allocations
+///
+/// Inventory changes outside the flow that reserved the stock must leave it in place.
+pub trait StockAllocations<AccountId> {
+    /// The quantity of a serial allocated to orders
+    fn allocated_qty(who: &AccountId, sku: &Sku, serial_number: SerialNumber) -> Qty;
+}
+
+impl<AccountId> StockAllocations<AccountId> for () {
+    fn allocated_qty(_who: &AccountId, _sku: &Sku, _serial_number: SerialNumber) -> Qty {
+        0
+    }
+}
+
+/// Stock reserved by either of two pallets, e.g. sales order allocations and kitting pick lists
+impl<AccountId, A, B> StockAllocations<AccountId> for (A, B)
+where
+    A: StockAllocations<AccountId>,
+    B: StockAllocations<AccountId>,
+{
+    fn allocated_qty(who: &AccountId, sku: &Sku, serial_number: SerialNumber) -> Qty {
+        A::allocated_qty(who, sku, serial_number).saturating_add(B::allocated_qty(
+            who,
+            sku,
+            serial_number,
+        ))
+    }
+}
+
+/// Stock received against purchase orders, reported to pallets that plan around open purchases
+pub trait PurchaseReceipts {
+    /// A quantity of a SKU was received against a purchase order line
+    fn on_purchase_receipt(sku: &Sku, qty: Qty);
+}
+
+impl PurchaseReceipts for () {
+    fn on_purchase_receipt(_sku: &Sku, _qty: Qty) {}
+}
+
+/// Changes to items in inventory, reported to pallets that keep track of what a location holds
+pub trait StockChanges<AccountId> {
+    /// An item was moved, adjusted, split, shipped or scrapped, leaving it as `after`, or out of
+    /// the account's inventory if `None`
+    fn on_stock_changed(who: &AccountId, before: &Item, after: Option<&Item>);
+}
+
+impl<AccountId> StockChanges<AccountId> for () {
+    fn on_stock_changed(_who: &AccountId, _before: &Item, _after: Option<&Item>) {}
 }
@@ -0,0 +1,282 @@
+use crate::pallet::{
+    Config, DowntimeHistory, Error, MaintenanceOrders, MaintenancePlans,
+    NextMaintenanceOrderNumber, PlanCursor,
+};
+use crate::{pallet::Pallet, types::*};
+use frame_support::ensure;
+use frame_support::pallet_prelude::Weight;
+use frame_support::sp_runtime::{DispatchError, DispatchResult};
+use frame_support::traits::Get;
+use frame_support::BoundedVec;
+use pallet_inventory::types::{
+    AdjustDetails, AssetId, Employee, EquipmentStatus, InventoryType, Qty, Reason, SerialNumber,
+    Sku,
+};
+use pallet_inventory::{EquipmentRegistry, Inventory};
+use scale_info::prelude::vec::Vec;
+
+impl<T: Config> Pallet<T> {
+    /// Add a maintenance plan for a registered asset.
+    ///
+    /// The plan starts counting from the current block and the asset's current usage count.
+    pub fn do_create_maintenance_plan(
+        mut plan: MaintenancePlan,
+    ) -> Result<MaintenancePlan, DispatchError> {
+        ensure!(
+            !MaintenancePlans::<T>::contains_key(plan.plan_id),
+            Error::<T>::PlanAlreadyExists
+        );
+        ensure!(
+            !matches!(
+                plan.trigger,
+                MaintenanceTrigger::UsageCount(0) | MaintenanceTrigger::BlockInterval(0)
+            ),
+            Error::<T>::InvalidTrigger
+        );
+
+        let asset = EquipmentRegistry::<T>::get(plan.asset_id)
+            .ok_or(pallet_inventory::Error::<T>::EquipmentNotFound)?;
+
+        plan.last_performed_at = pallet_inventory::Pallet::<T>::current_block();
+        plan.last_usage_count = asset.usage_count;
+        plan.open_order = None;
+
+        MaintenancePlans::<T>::insert(plan.plan_id, plan.clone());
+
+        Ok(plan)
+    }
+
+    /// Raise a new maintenance order for an asset
+    pub fn do_create_maintenance_order(
+        asset_id: AssetId,
+        plan_id: Option<MaintenancePlanId>,
+        description: Reason,
+        assigned_to: Employee,
+    ) -> Result<MaintenanceOrder, DispatchError> {
+        ensure!(
+            EquipmentRegistry::<T>::contains_key(asset_id),
+            pallet_inventory::Error::<T>::EquipmentNotFound
+        );
+
+        let order_number = NextMaintenanceOrderNumber::<T>::get();
+        NextMaintenanceOrderNumber::<T>::put(order_number.saturating_add(1));
+
+        let order = MaintenanceOrder {
+            order_number,
+            asset_id,
+            plan_id,
+            description,
+            assigned_to,
+            status: MaintenanceOrderStatus::Open,
+            created_at: pallet_inventory::Pallet::<T>::current_block(),
+            started_at: None,
+            completed_at: None,
+            consumed_parts: BoundedVec::default(),
+        };
+
+        MaintenanceOrders::<T>::insert(order_number, order.clone());
+
+        Ok(order)
+    }
+
+    /// Start work on an open maintenance order, taking the asset out of service
+    pub fn do_start_maintenance(
+        order_number: MaintenanceOrderNumber,
+    ) -> Result<AssetId, DispatchError> {
+        let mut order =
+            MaintenanceOrders::<T>::get(order_number).ok_or(Error::<T>::OrderNotFound)?;
+
+        ensure!(
+            order.status == MaintenanceOrderStatus::Open,
+            Error::<T>::InvalidOrderStatus
+        );
+
+        // An asset locked by a work order must be released before it can be maintained
+        let asset = EquipmentRegistry::<T>::get(order.asset_id)
+            .ok_or(pallet_inventory::Error::<T>::EquipmentNotFound)?;
+        ensure!(
+            asset.status != EquipmentStatus::InUse,
+            Error::<T>::EquipmentInUse
+        );
+
+        pallet_inventory::Pallet::<T>::do_update_equipment_status(
+            order.asset_id,
+            EquipmentStatus::Maintenance,
+        )?;
+
+        order.status = MaintenanceOrderStatus::InProgress;
+        order.started_at = Some(pallet_inventory::Pallet::<T>::current_block());
+        MaintenanceOrders::<T>::insert(order_number, order.clone());
+
+        Ok(order.asset_id)
+    }
+
+    /// Consume MRO inventory against a maintenance order in progress
+    pub fn do_consume_mro_part(
+        who: &T::AccountId,
+        order_number: MaintenanceOrderNumber,
+        sku: Sku,
+        serial_number: SerialNumber,
+        qty: Qty,
+    ) -> Result<ConsumedPart, DispatchError> {
+        let mut order =
+            MaintenanceOrders::<T>::get(order_number).ok_or(Error::<T>::OrderNotFound)?;
+
+        ensure!(
+            order.status == MaintenanceOrderStatus::InProgress,
+            Error::<T>::InvalidOrderStatus
+        );
+
+        let item = Inventory::<T>::get((who, sku.clone(), serial_number))
+            .ok_or(pallet_inventory::Error::<T>::InventoryNotFound)?;
+
+        ensure!(
+            item.inventory_type == InventoryType::MRO,
+            Error::<T>::NotMroInventory
+        );
+        ensure!(item.qty >= qty, Error::<T>::InsufficientInventory);
+
+        // Draw the consumed quantity down from the item
+        pallet_inventory::Pallet::<T>::do_inventory_adjust(
+            who,
+            order.assigned_to.clone(),
+            item.clone(),
+            AdjustDetails::Quantity {
+                original_qty: item.qty,
+                new_qty: item.qty - qty,
+                reason: BoundedVec::try_from("Maintenance Order".as_bytes().to_vec())
+                    .map_err(|_| Error::<T>::DescriptionTooLong)?,
+            },
+        )?;
+
+        let part = ConsumedPart {
+            sku,
+            serial_number,
+            qty,
+        };
+        order
+            .consumed_parts
+            .try_push(part.clone())
+            .map_err(|_| Error::<T>::TooManyParts)?;
+        MaintenanceOrders::<T>::insert(order_number, order);
+
+        Ok(part)
+    }
+
+    /// Complete a maintenance order, return the asset to service and record the downtime
+    pub fn do_complete_maintenance(
+        order_number: MaintenanceOrderNumber,
+    ) -> Result<(AssetId, DowntimeRecord), DispatchError> {
+        let mut order =
+            MaintenanceOrders::<T>::get(order_number).ok_or(Error::<T>::OrderNotFound)?;
+
+        ensure!(
+            order.status == MaintenanceOrderStatus::InProgress,
+            Error::<T>::InvalidOrderStatus
+        );
+
+        let now = pallet_inventory::Pallet::<T>::current_block();
+
+        pallet_inventory::Pallet::<T>::do_update_equipment_status(
+            order.asset_id,
+            EquipmentStatus::Available,
+        )?;
+
+        // Record the downtime, dropping the oldest record once the history is full
+        let downtime = DowntimeRecord {
+            order_number,
+            started_at: order.started_at.unwrap_or(now),
+            ended_at: now,
+        };
+        DowntimeHistory::<T>::mutate(order.asset_id, |history| {
+            if history.is_full() {
+                history.remove(0);
+            }
+            let _ = history.try_push(downtime.clone());
+        });
+
+        // Restart the plan's interval from this maintenance
+        if let Some(plan_id) = order.plan_id {
+            let asset = EquipmentRegistry::<T>::get(order.asset_id)
+                .ok_or(pallet_inventory::Error::<T>::EquipmentNotFound)?;
+
+            MaintenancePlans::<T>::try_mutate(plan_id, |plan| -> DispatchResult {
+                let plan = plan.as_mut().ok_or(Error::<T>::PlanNotFound)?;
+                plan.last_performed_at = now;
+                plan.last_usage_count = asset.usage_count;
+                plan.open_order = None;
+                Ok(())
+            })?;
+        }
+
+        order.status = MaintenanceOrderStatus::Completed;
+        order.completed_at = Some(now);
+        MaintenanceOrders::<T>::insert(order_number, order.clone());
+
+        Ok((order.asset_id, downtime))
+    }
+
+    /// Raise a maintenance order for every plan that has come due and has none open
+    ///
+    /// At most `MAX_PLANS_PER_BLOCK` plans are checked per block, carrying on after the last plan
+    /// the previous block checked, so every plan is checked in turn. Returns the orders raised.
+    pub fn do_raise_due_maintenance(now: u32) -> (Vec<MaintenanceOrder>, Weight) {
+        let mut reads: u64 = 1;
+        let mut writes: u64 = 1;
+
+        let plans: Vec<MaintenancePlan> = match PlanCursor::<T>::get() {
+            Some(plan_id) => {
+                MaintenancePlans::<T>::iter_from(MaintenancePlans::<T>::hashed_key_for(plan_id))
+            }
+            None => MaintenancePlans::<T>::iter(),
+        }
+        .map(|(_, plan)| plan)
+        .take(MAX_PLANS_PER_BLOCK as usize)
+        .collect();
+        reads = reads.saturating_add(plans.len() as u64);
+
+        // Carry on after the last plan checked, or start over once every plan has been
+        match plans.last() {
+            Some(plan) if plans.len() == MAX_PLANS_PER_BLOCK as usize => {
+                PlanCursor::<T>::put(plan.plan_id)
+            }
+            _ => PlanCursor::<T>::kill(),
+        }
+
+        let mut orders = Vec::new();
+
+        for mut plan in plans.into_iter().filter(|plan| plan.open_order.is_none()) {
+            let Some(asset) = EquipmentRegistry::<T>::get(plan.asset_id) else {
+                continue;
+            };
+            reads = reads.saturating_add(1);
+
+            let due = match plan.trigger {
+                MaintenanceTrigger::UsageCount(count) => {
+                    asset.usage_count.saturating_sub(plan.last_usage_count) >= count
+                }
+                MaintenanceTrigger::BlockInterval(interval) => {
+                    now.saturating_sub(plan.last_performed_at) >= interval
+                }
+            };
+            if !due {
+                continue;
+            }
+
+            if let Ok(order) = Self::do_create_maintenance_order(
+                plan.asset_id,
+                Some(plan.plan_id),
+                plan.description.clone(),
+                plan.assigned_to.clone(),
+            ) {
+                plan.open_order = Some(order.order_number);
+                MaintenancePlans::<T>::insert(plan.plan_id, plan);
+                orders.push(order);
+                reads = reads.saturating_add(2);
+                writes = writes.saturating_add(3);
+            }
+        }
+
+        (orders, T::DbWeight::get().reads_writes(reads, writes))
+    }
+}
@@ -0,0 +1,299 @@
+//! # Maintenance Pallet
+//!
+//! - [`Config`]
+//! - [`Call`]
+//!
+//! ## Overview
+//!
+//! The Maintenance pallet schedules and tracks preventive maintenance for the equipment assets
+//! registered in the inventory pallet. This pallet provides functionalities to:
+//!
+//! - Define maintenance plans per asset, triggered by usage count or block interval.
+//! - Automatically raise maintenance orders when a plan comes due, checking a bounded number of
+//!   plans each block.
+//! - Consume MRO inventory for repairs against a maintenance order.
+//! - Keep a downtime history per asset.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! - `create_maintenance_plan`: Add a preventive maintenance plan for an asset.
+//! - `create_maintenance_order`: Manually raise a maintenance order, e.g. for a repair.
+//! - `start_maintenance`: Take the asset out of service and start work on an order.
+//! - `consume_mro_part`: Consume MRO inventory against an order in progress.
+//! - `complete_maintenance`: Return the asset to service and record the downtime.
+//!
+//! ## Storage
+//!
+//! - `MaintenancePlans`: Stores maintenance plans by plan ID.
+//! - `MaintenanceOrders`: Stores maintenance orders by order number.
+//! - `NextMaintenanceOrderNumber`: The number assigned to the next maintenance order.
+//! - `PlanCursor`: The last plan checked for being due, which the next block carries on after.
+//! - `DowntimeHistory`: Stores the maintenance downtime periods of each asset.
+//!
+//! ## Events
+//!
+//! - `AddMaintenancePlan`: Emitted when a maintenance plan is added.
+//! - `MaintenanceOrderCreated`: Emitted when a maintenance order is raised.
+//! - `MaintenanceStarted`: Emitted when work on a maintenance order starts.
+//! - `PartConsumed`: Emitted when MRO inventory is consumed by a maintenance order.
+//! - `MaintenanceCompleted`: Emitted when a maintenance order is completed.
+//!
+//! ## Errors
+//!
+//! - `PlanAlreadyExists`: A plan with the same ID already exists.
+//! - `PlanNotFound`: The specified plan could not be located.
+//! - `OrderNotFound`: The specified maintenance order could not be located.
+//! - `InvalidOrderStatus`: The maintenance order is not in the right status for the call.
+//! - `EquipmentInUse`: The asset is locked by a work order and cannot be maintained.
+//! - `NotMroInventory`: The consumed item is not MRO inventory.
+//! - `InsufficientInventory`: The item does not hold the quantity to consume.
+//! - `TooManyParts`: The maintenance order cannot record any more consumed parts.
+//! - `DescriptionTooLong`: The description is too long.
+//! - `InvalidTrigger`: A plan's usage count or block interval is zero.
+//!
+//! This pallet uses `no_std` for compatibility with Wasm environments, a polkadot standard.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub use pallet::*;
+
+// FRAME pallets require their own "mock runtimes" to be able to run unit tests. This module
+// contains a mock runtime specific for testing this pallet's functionality.
+#[cfg(test)]
+mod mock;
+
+// This module contains the unit tests for this pallet.
+// Learn about pallet unit testing here: https://docs.substrate.io/test/unit-testing/
+#[cfg(test)]
+mod tests;
+
+mod blogic;
+pub mod types;
+
+use crate::types::{
+    ConsumedPart, DowntimeRecord, MaintenanceOrder, MaintenanceOrderNumber, MaintenancePlan,
+    MaintenancePlanId,
+};
+use pallet_inventory::types::{AssetId, Employee, Qty, Reason, SerialNumber, Sku};
+
+#[frame_support::pallet]
+pub mod pallet {
+    use super::*;
+    use frame_support::pallet_prelude::*;
+    use frame_support::sp_runtime::SaturatedConversion;
+    use frame_system::pallet_prelude::*;
+    use pallet_inventory::pallet::Config as InventoryConfig;
+
+    #[pallet::pallet]
+    pub struct Pallet<T>(_);
+
+    /// The pallet's configuration trait.
+    ///
+    /// This trait is tightly coupled with the Inventory pallet -> [InventoryConfig]
+    #[pallet::config]
+    pub trait Config: frame_system::Config + InventoryConfig {
+        type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+    }
+
+    /// Maintenance Plans Storage
+    ///
+    /// Preventive maintenance plans, one asset each
+    #[pallet::storage]
+    pub type MaintenancePlans<T: Config> =
+        StorageMap<_, Twox64Concat, MaintenancePlanId, MaintenancePlan, OptionQuery>;
+
+    /// Maintenance Orders Storage
+    ///
+    /// Maintenance orders raised by plans or created manually
+    #[pallet::storage]
+    pub type MaintenanceOrders<T: Config> =
+        StorageMap<_, Twox64Concat, MaintenanceOrderNumber, MaintenanceOrder, OptionQuery>;
+
+    /// The number assigned to the next maintenance order
+    #[pallet::storage]
+    pub type NextMaintenanceOrderNumber<T: Config> =
+        StorageValue<_, MaintenanceOrderNumber, ValueQuery>;
+
+    /// The last plan checked for being due, which the next block carries on after
+    #[pallet::storage]
+    pub type PlanCursor<T: Config> = StorageValue<_, MaintenancePlanId, OptionQuery>;
+
+    /// Downtime History Storage
+    ///
+    /// The most recent maintenance downtime periods of each asset
+    #[pallet::storage]
+    pub type DowntimeHistory<T: Config> =
+        StorageMap<_, Twox64Concat, AssetId, BoundedVec<DowntimeRecord, ConstU32<100>>, ValueQuery>;
+
+    /// Events that functions in this pallet can emit.
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config> {
+        AddMaintenancePlan {
+            who: T::AccountId,
+            plan: MaintenancePlan,
+        },
+        /// A maintenance order has been raised, by a plan or manually
+        MaintenanceOrderCreated { order: MaintenanceOrder },
+        MaintenanceStarted {
+            who: T::AccountId,
+            order_number: MaintenanceOrderNumber,
+            asset_id: AssetId,
+        },
+        /// MRO inventory has been consumed by a maintenance order
+        PartConsumed {
+            who: T::AccountId,
+            order_number: MaintenanceOrderNumber,
+            part: ConsumedPart,
+        },
+        MaintenanceCompleted {
+            who: T::AccountId,
+            order_number: MaintenanceOrderNumber,
+            asset_id: AssetId,
+            downtime: DowntimeRecord,
+        },
+    }
+
+    /// Errors that can be returned by this pallet.
+    #[pallet::error]
+    pub enum Error<T> {
+        /// The maintenance plan already exists
+        PlanAlreadyExists,
+        /// Unable to find the defined maintenance plan
+        PlanNotFound,
+        /// Unable to find the defined maintenance order
+        OrderNotFound,
+        /// The maintenance order is not in the right status for this call
+        InvalidOrderStatus,
+        /// The asset is locked by a work order
+        EquipmentInUse,
+        /// Only MRO inventory can be consumed by maintenance
+        NotMroInventory,
+        /// The item does not hold enough quantity
+        InsufficientInventory,
+        /// The maintenance order cannot record any more consumed parts
+        TooManyParts,
+        /// The description length exceeds the maximum imposed limit
+        DescriptionTooLong,
+        /// A plan must trigger after at least one use or one block
+        InvalidTrigger,
+    }
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        /// Raise maintenance orders for the plans checked this block that have come due
+        fn on_initialize(n: BlockNumberFor<T>) -> Weight {
+            let (orders, weight) = Self::do_raise_due_maintenance(n.saturated_into::<u32>());
+
+            for order in orders {
+                Self::deposit_event(Event::MaintenanceOrderCreated { order });
+            }
+
+            weight
+        }
+    }
+
+    /// The pallet's dispatchable functions
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Add a preventive maintenance plan for an asset
+        #[pallet::call_index(0)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn create_maintenance_plan(
+            origin: OriginFor<T>,
+            plan: MaintenancePlan,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let plan = Self::do_create_maintenance_plan(plan)?;
+
+            Self::deposit_event(Event::AddMaintenancePlan { who, plan });
+
+            Ok(())
+        }
+
+        /// Manually raise a maintenance order for an asset, e.g. for a repair
+        #[pallet::call_index(1)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn create_maintenance_order(
+            origin: OriginFor<T>,
+            asset_id: AssetId,
+            description: Reason,
+            assigned_to: Employee,
+        ) -> DispatchResult {
+            ensure_signed(origin)?;
+
+            let order =
+                Self::do_create_maintenance_order(asset_id, None, description, assigned_to)?;
+
+            Self::deposit_event(Event::MaintenanceOrderCreated { order });
+
+            Ok(())
+        }
+
+        /// Take the asset out of service and start work on a maintenance order
+        #[pallet::call_index(2)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn start_maintenance(
+            origin: OriginFor<T>,
+            order_number: MaintenanceOrderNumber,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let asset_id = Self::do_start_maintenance(order_number)?;
+
+            Self::deposit_event(Event::MaintenanceStarted {
+                who,
+                order_number,
+                asset_id,
+            });
+
+            Ok(())
+        }
+
+        /// Consume MRO inventory against a maintenance order in progress
+        #[pallet::call_index(3)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn consume_mro_part(
+            origin: OriginFor<T>,
+            order_number: MaintenanceOrderNumber,
+            sku: Sku,
+            serial_number: SerialNumber,
+            qty: Qty,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let part = Self::do_consume_mro_part(&who, order_number, sku, serial_number, qty)?;
+
+            Self::deposit_event(Event::PartConsumed {
+                who,
+                order_number,
+                part,
+            });
+
+            Ok(())
+        }
+
+        /// Complete a maintenance order and return the asset to service
+        #[pallet::call_index(4)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn complete_maintenance(
+            origin: OriginFor<T>,
+            order_number: MaintenanceOrderNumber,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let (asset_id, downtime) = Self::do_complete_maintenance(order_number)?;
+
+            Self::deposit_event(Event::MaintenanceCompleted {
+                who,
+                order_number,
+                asset_id,
+                downtime,
+            });
+
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use crate as pallet_maintenance;
+use frame_support::derive_impl;
+use frame_system::EnsureRoot;
+use sp_runtime::BuildStorage;
+
+type Block = frame_system::mocking::MockBlock<Test>;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+    pub enum Test
+    {
+        System: frame_system,
+        Inventory: pallet_inventory,
+        Maintenance: pallet_maintenance,
+    }
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+impl frame_system::Config for Test {
+    type Block = Block;
+}
+
+impl pallet_inventory::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type WeightInfo = ();
+    type StockAllocations = ();
+    type StockChanges = ();
+    type ApprovalAdminOrigin = EnsureRoot<u64>;
+    type ReorderAdminOrigin = EnsureRoot<u64>;
+}
+
+impl pallet_maintenance::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    let t = frame_system::GenesisConfig::<Test>::default()
+        .build_storage()
+        .unwrap();
+
+    // Events are only recorded from block 1 on
+    let mut ext: sp_io::TestExternalities = t.into();
+    ext.execute_with(|| System::set_block_number(1));
+    ext
+}
@@ -0,0 +1,355 @@
+use crate::{mock::*, types::*, Error, Event};
+use frame_support::traits::Hooks;
+use frame_support::{assert_noop, assert_ok, BoundedVec};
+use pallet_inventory::types::{
+    Employee, Equipment, EquipmentAsset, EquipmentStatus, InventoryType, Item, Reason, Sku,
+};
+
+const ALICE: u64 = 1;
+
+fn sku(sku: &str) -> Sku {
+    BoundedVec::truncate_from(sku.as_bytes().to_vec())
+}
+
+fn reason(reason: &str) -> Reason {
+    BoundedVec::truncate_from(reason.as_bytes().to_vec())
+}
+
+/// Register a forklift under the asset ID
+fn register_asset(asset_id: u32) {
+    assert_ok!(Inventory::insert_equipment(
+        RuntimeOrigin::signed(ALICE),
+        EquipmentAsset {
+            asset_id,
+            equipment_type: Equipment::Forklift,
+            ..Default::default()
+        }
+    ));
+}
+
+fn plan(plan_id: MaintenancePlanId, trigger: MaintenanceTrigger) -> MaintenancePlan {
+    MaintenancePlan {
+        plan_id,
+        asset_id: 0,
+        trigger,
+        description: reason("Service"),
+        assigned_to: Employee::Dave,
+        last_performed_at: 0,
+        last_usage_count: 0,
+        open_order: None,
+    }
+}
+
+fn mro_part(serial_number: u32, qty: u32) -> Item {
+    Item {
+        sku: sku("FILTER"),
+        serial_number,
+        qty,
+        inventory_type: InventoryType::MRO,
+        ..Default::default()
+    }
+}
+
+/// Raise a repair order for asset 0 and start it
+fn start_repair() {
+    assert_ok!(Maintenance::create_maintenance_order(
+        RuntimeOrigin::signed(ALICE),
+        0,
+        reason("Repair"),
+        Employee::Dave
+    ));
+    assert_ok!(Maintenance::start_maintenance(
+        RuntimeOrigin::signed(ALICE),
+        0
+    ));
+}
+
+#[test]
+fn plans_need_a_registered_asset_and_a_free_id() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Maintenance::create_maintenance_plan(
+                RuntimeOrigin::signed(ALICE),
+                plan(0, MaintenanceTrigger::BlockInterval(10))
+            ),
+            pallet_inventory::Error::<Test>::EquipmentNotFound
+        );
+
+        register_asset(0);
+        assert_ok!(Maintenance::create_maintenance_plan(
+            RuntimeOrigin::signed(ALICE),
+            plan(0, MaintenanceTrigger::BlockInterval(10))
+        ));
+        assert_eq!(
+            crate::MaintenancePlans::<Test>::get(0)
+                .unwrap()
+                .last_performed_at,
+            1
+        );
+
+        assert_noop!(
+            Maintenance::create_maintenance_plan(
+                RuntimeOrigin::signed(ALICE),
+                plan(0, MaintenanceTrigger::UsageCount(5))
+            ),
+            Error::<Test>::PlanAlreadyExists
+        );
+    });
+}
+
+#[test]
+fn plans_reject_zero_triggers() {
+    new_test_ext().execute_with(|| {
+        register_asset(0);
+
+        assert_noop!(
+            Maintenance::create_maintenance_plan(
+                RuntimeOrigin::signed(ALICE),
+                plan(0, MaintenanceTrigger::UsageCount(0))
+            ),
+            Error::<Test>::InvalidTrigger
+        );
+        assert_noop!(
+            Maintenance::create_maintenance_plan(
+                RuntimeOrigin::signed(ALICE),
+                plan(0, MaintenanceTrigger::BlockInterval(0))
+            ),
+            Error::<Test>::InvalidTrigger
+        );
+    });
+}
+
+#[test]
+fn block_interval_plans_raise_one_order_when_due() {
+    new_test_ext().execute_with(|| {
+        register_asset(0);
+        assert_ok!(Maintenance::create_maintenance_plan(
+            RuntimeOrigin::signed(ALICE),
+            plan(0, MaintenanceTrigger::BlockInterval(10))
+        ));
+
+        Maintenance::on_initialize(10);
+        assert!(crate::MaintenanceOrders::<Test>::get(0).is_none());
+
+        System::set_block_number(11);
+        Maintenance::on_initialize(11);
+        let order = crate::MaintenanceOrders::<Test>::get(0).unwrap();
+        assert_eq!(order.plan_id, Some(0));
+        assert_eq!(order.status, MaintenanceOrderStatus::Open);
+        assert_eq!(
+            crate::MaintenancePlans::<Test>::get(0).unwrap().open_order,
+            Some(0)
+        );
+        System::assert_last_event(Event::MaintenanceOrderCreated { order }.into());
+
+        // The plan already has an order open
+        Maintenance::on_initialize(30);
+        assert!(crate::MaintenanceOrders::<Test>::get(1).is_none());
+    });
+}
+
+#[test]
+fn usage_count_plans_come_due_with_asset_use() {
+    new_test_ext().execute_with(|| {
+        register_asset(0);
+        assert_ok!(Maintenance::create_maintenance_plan(
+            RuntimeOrigin::signed(ALICE),
+            plan(0, MaintenanceTrigger::UsageCount(2))
+        ));
+
+        for _ in 0..2 {
+            assert_ok!(Inventory::do_lock_equipment(&Equipment::Forklift));
+            assert_ok!(Inventory::do_release_equipment(0));
+        }
+        Maintenance::on_initialize(2);
+
+        assert_eq!(
+            crate::MaintenancePlans::<Test>::get(0).unwrap().open_order,
+            Some(0)
+        );
+    });
+}
+
+#[test]
+fn maintenance_cannot_start_on_a_locked_asset() {
+    new_test_ext().execute_with(|| {
+        register_asset(0);
+        assert_ok!(Inventory::do_lock_equipment(&Equipment::Forklift));
+        assert_ok!(Maintenance::create_maintenance_order(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            reason("Repair"),
+            Employee::Dave
+        ));
+
+        assert_noop!(
+            Maintenance::start_maintenance(RuntimeOrigin::signed(ALICE), 0),
+            Error::<Test>::EquipmentInUse
+        );
+    });
+}
+
+#[test]
+fn repairs_consume_mro_parts_only() {
+    new_test_ext().execute_with(|| {
+        register_asset(0);
+        assert_ok!(Inventory::inventory_insertion(
+            RuntimeOrigin::signed(ALICE),
+            mro_part(1, 5)
+        ));
+        let mut raw_material = mro_part(2, 5);
+        raw_material.inventory_type = InventoryType::RawMaterial;
+        assert_ok!(Inventory::inventory_insertion(
+            RuntimeOrigin::signed(ALICE),
+            raw_material
+        ));
+
+        // Parts are only consumed once work has started
+        assert_ok!(Maintenance::create_maintenance_order(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            reason("Repair"),
+            Employee::Dave
+        ));
+        assert_noop!(
+            Maintenance::consume_mro_part(RuntimeOrigin::signed(ALICE), 0, sku("FILTER"), 1, 1),
+            Error::<Test>::InvalidOrderStatus
+        );
+        assert_ok!(Maintenance::start_maintenance(
+            RuntimeOrigin::signed(ALICE),
+            0
+        ));
+
+        assert_noop!(
+            Maintenance::consume_mro_part(RuntimeOrigin::signed(ALICE), 0, sku("FILTER"), 2, 1),
+            Error::<Test>::NotMroInventory
+        );
+        assert_noop!(
+            Maintenance::consume_mro_part(RuntimeOrigin::signed(ALICE), 0, sku("FILTER"), 1, 6),
+            Error::<Test>::InsufficientInventory
+        );
+
+        assert_ok!(Maintenance::consume_mro_part(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            sku("FILTER"),
+            1,
+            2
+        ));
+        assert_eq!(
+            pallet_inventory::Inventory::<Test>::get((ALICE, sku("FILTER"), 1))
+                .unwrap()
+                .qty,
+            3
+        );
+        let part = ConsumedPart {
+            sku: sku("FILTER"),
+            serial_number: 1,
+            qty: 2,
+        };
+        assert_eq!(
+            crate::MaintenanceOrders::<Test>::get(0)
+                .unwrap()
+                .consumed_parts
+                .into_inner(),
+            vec![part.clone()]
+        );
+        System::assert_last_event(
+            Event::PartConsumed {
+                who: ALICE,
+                order_number: 0,
+                part,
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn completing_maintenance_records_downtime_and_restarts_the_plan() {
+    new_test_ext().execute_with(|| {
+        register_asset(0);
+        assert_ok!(Maintenance::create_maintenance_plan(
+            RuntimeOrigin::signed(ALICE),
+            plan(0, MaintenanceTrigger::BlockInterval(10))
+        ));
+        System::set_block_number(11);
+        Maintenance::on_initialize(11);
+
+        assert_ok!(Maintenance::start_maintenance(
+            RuntimeOrigin::signed(ALICE),
+            0
+        ));
+        assert_eq!(
+            pallet_inventory::EquipmentRegistry::<Test>::get(0)
+                .unwrap()
+                .status,
+            EquipmentStatus::Maintenance
+        );
+
+        System::set_block_number(15);
+        assert_ok!(Maintenance::complete_maintenance(
+            RuntimeOrigin::signed(ALICE),
+            0
+        ));
+
+        let downtime = DowntimeRecord {
+            order_number: 0,
+            started_at: 11,
+            ended_at: 15,
+        };
+        assert_eq!(
+            crate::DowntimeHistory::<Test>::get(0).into_inner(),
+            vec![downtime.clone()]
+        );
+        assert_eq!(
+            pallet_inventory::EquipmentRegistry::<Test>::get(0)
+                .unwrap()
+                .status,
+            EquipmentStatus::Available
+        );
+        let plan = crate::MaintenancePlans::<Test>::get(0).unwrap();
+        assert_eq!(plan.last_performed_at, 15);
+        assert_eq!(plan.open_order, None);
+        System::assert_last_event(
+            Event::MaintenanceCompleted {
+                who: ALICE,
+                order_number: 0,
+                asset_id: 0,
+                downtime,
+            }
+            .into(),
+        );
+
+        assert_noop!(
+            Maintenance::complete_maintenance(RuntimeOrigin::signed(ALICE), 0),
+            Error::<Test>::InvalidOrderStatus
+        );
+    });
+}
+
+#[test]
+fn repair_orders_need_a_registered_asset() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Maintenance::create_maintenance_order(
+                RuntimeOrigin::signed(ALICE),
+                0,
+                reason("Repair"),
+                Employee::Dave
+            ),
+            pallet_inventory::Error::<Test>::EquipmentNotFound
+        );
+
+        register_asset(0);
+        start_repair();
+        assert_noop!(
+            Maintenance::start_maintenance(RuntimeOrigin::signed(ALICE), 0),
+            Error::<Test>::InvalidOrderStatus
+        );
+        assert_noop!(
+            Maintenance::start_maintenance(RuntimeOrigin::signed(ALICE), 1),
+            Error::<Test>::OrderNotFound
+        );
+    });
+}
@@ -0,0 +1,77 @@
+use codec::{Decode, Encode};
+use frame_support::pallet_prelude::ConstU32;
+use frame_support::pallet_prelude::MaxEncodedLen;
+use frame_support::BoundedVec;
+use pallet_inventory::types::{AssetId, Employee, Qty, Reason, SerialNumber, Sku};
+use scale_info::TypeInfo;
+
+pub type MaintenancePlanId = u32;
+pub type MaintenanceOrderNumber = u32;
+
+/// The most plans checked for being due in one block
+pub const MAX_PLANS_PER_BLOCK: u32 = 50;
+
+/// What causes a maintenance plan to come due
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub enum MaintenanceTrigger {
+    /// Due every time the asset has been used this many times
+    UsageCount(u32),
+    /// Due every time this many blocks have passed
+    BlockInterval(u32),
+}
+
+/// A preventive maintenance plan for a single equipment asset
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct MaintenancePlan {
+    pub plan_id: MaintenancePlanId,
+    pub asset_id: AssetId,
+    pub trigger: MaintenanceTrigger,
+    pub description: Reason,
+    pub assigned_to: Employee,
+    /// Block at which the plan was last performed
+    pub last_performed_at: u32,
+    /// Asset usage count when the plan was last performed
+    pub last_usage_count: u32,
+    /// The maintenance order currently open for this plan, if any
+    pub open_order: Option<MaintenanceOrderNumber>,
+}
+
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
+pub enum MaintenanceOrderStatus {
+    #[default]
+    Open,
+    InProgress,
+    Completed,
+}
+
+/// MRO inventory consumed by a maintenance order
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct ConsumedPart {
+    pub sku: Sku,
+    pub serial_number: SerialNumber,
+    pub qty: Qty,
+}
+
+/// A maintenance work order, either raised by a plan or created manually for a repair
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct MaintenanceOrder {
+    pub order_number: MaintenanceOrderNumber,
+    pub asset_id: AssetId,
+    /// The plan that raised this order, `None` for repairs
+    pub plan_id: Option<MaintenancePlanId>,
+    pub description: Reason,
+    pub assigned_to: Employee,
+    pub status: MaintenanceOrderStatus,
+    pub created_at: u32,
+    pub started_at: Option<u32>,
+    pub completed_at: Option<u32>,
+    pub consumed_parts: BoundedVec<ConsumedPart, ConstU32<50>>,
+}
+
+/// A period during which an asset was out of service for maintenance
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct DowntimeRecord {
+    pub order_number: MaintenanceOrderNumber,
+    pub started_at: u32,
+    pub ended_at: u32,
+}
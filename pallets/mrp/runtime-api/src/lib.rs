@@ -0,0 +1,18 @@
+//! # MRP Runtime API
+//!
+//! Runs material requirements planning against the current chain state. Planning reads every
+//! forecast, work order and staging area, so it runs here rather than on chain; the planner
+//! stores the result with the MRP pallet's `store_plan` for review and firming.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use pallet_mrp::types::PlannedOrder;
+
+sp_api::decl_runtime_apis! {
+    pub trait MrpApi {
+        /// The planned purchase and production orders for the current demand and supply
+        fn plan() -> Vec<PlannedOrder>;
+    }
+}
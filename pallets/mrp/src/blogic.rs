@@ -0,0 +1,447 @@
+use crate::pallet::{Config, Error, FirmedPurchases, Forecasts, LatestPlan, NextPlannedOrderId};
+use crate::{pallet::Pallet, types::*};
+use frame_support::ensure;
+use frame_support::pallet_prelude::ConstU32;
+use frame_support::sp_runtime::{DispatchError, SaturatedConversion};
+use frame_support::BoundedVec;
+use pallet_assembly::types::{DueDate, Priority, WorkOrder, WorkOrderNumber, WorkOrderStatus};
+use pallet_assembly::{BackflushHistory, ManualIssues, StagingArea, WorkOrders};
+use pallet_inventory::types::{
+    Employee, IssueMode, Location, PurchaseReceipts, Qty, Recipe, Sku, StockAllocations,
+};
+use pallet_inventory::{GlobalInventory, Inventory, Materials, Recipes};
+use scale_info::prelude::collections::BTreeMap;
+use scale_info::prelude::vec::Vec;
+
+impl<T: Config> Pallet<T> {
+    /// Set or clear the forecast demand for a SKU at a due date
+    pub fn do_set_forecast(sku: Sku, due_date: DueDate, qty: Qty) {
+        if qty == 0 {
+            Forecasts::<T>::remove(sku, due_date);
+        } else {
+            Forecasts::<T>::insert(sku, due_date, qty);
+        }
+    }
+
+    /// Store a plan produced by the runtime API's planning run for review
+    ///
+    /// Each order is given a fresh planned order ID and starts out planned, whatever it was
+    /// submitted with. Production orders must name a stored recipe of their SKU.
+    pub fn do_store_plan(
+        orders: BoundedVec<PlannedOrder, ConstU32<MAX_PLANNED_ORDERS>>,
+    ) -> Result<MrpPlan, DispatchError> {
+        let first_id = NextPlannedOrderId::<T>::get();
+
+        let mut orders = orders;
+        for (index, order) in orders.iter_mut().enumerate() {
+            ensure!(order.qty > 0, Error::<T>::InvalidPlannedOrder);
+            if let PlannedOrderKind::Production { recipe_id } = order.kind {
+                ensure!(
+                    Recipes::<T>::contains_key(&order.sku, recipe_id),
+                    pallet_inventory::Error::<T>::RecipeNotFound
+                );
+            }
+
+            order.planned_order_id = first_id.saturating_add(index as u32);
+            order.status = PlannedOrderStatus::Planned;
+        }
+        NextPlannedOrderId::<T>::put(first_id.saturating_add(orders.len() as u32));
+
+        let plan = MrpPlan {
+            generated_at: frame_system::Pallet::<T>::block_number().saturated_into::<u32>(),
+            orders,
+        };
+        LatestPlan::<T>::put(plan.clone());
+
+        Ok(plan)
+    }
+
+    /// Plan the purchase and production orders that cover every SKU's net requirements.
+    ///
+    /// SKUs are planned by low-level code, so every parent is planned before its components and
+    /// the demand its planned production places on them is netted too. Net requirements are
+    /// covered lot-for-lot, one order per shortfall, due when the shortfall occurs and released
+    /// a lead time earlier.
+    ///
+    /// Reads every forecast, work order and staging area, so it only runs off chain through the
+    /// runtime API, never in a dispatchable.
+    pub fn generate_plan(first_id: PlannedOrderId) -> Vec<PlannedOrder> {
+        let mut requirements: BTreeMap<Sku, Vec<(DueDate, Qty)>> = BTreeMap::new();
+        let mut receipts: BTreeMap<Sku, Vec<(DueDate, Qty)>> = BTreeMap::new();
+
+        for (sku, due_date, qty) in Forecasts::<T>::iter() {
+            requirements.entry(sku).or_default().push((due_date, qty));
+        }
+
+        let drawn = Self::drawn_components();
+        for work_order in WorkOrders::<T>::iter_values() {
+            let recipe = pallet_assembly::Pallet::<T>::work_order_recipe(&work_order).ok();
+            match work_order.status {
+                WorkOrderStatus::Completed
+                | WorkOrderStatus::Closed
+                | WorkOrderStatus::Cancelled => continue,
+                WorkOrderStatus::Planned | WorkOrderStatus::Released => {
+                    if let Some(recipe) = &recipe {
+                        let start = work_order
+                            .due_date
+                            .saturating_sub(Self::lead_time(&recipe.sku));
+                        for (sku, qty) in
+                            Self::component_requirements(recipe, work_order.planned_quantity)
+                        {
+                            requirements.entry(sku).or_default().push((start, qty));
+                        }
+                    }
+                }
+                // Kitted work orders already hold their pre-kitted components, but still draw
+                // whatever is left of their backflushed and manually issued ones from stock
+                WorkOrderStatus::Staged | WorkOrderStatus::InProgress => {
+                    if let Some(recipe) = &recipe {
+                        let start = work_order
+                            .due_date
+                            .saturating_sub(Self::lead_time(&recipe.sku));
+                        for component in recipe.required_components.iter() {
+                            if component.issue_mode == IssueMode::PreKitted {
+                                continue;
+                            }
+
+                            let outstanding =
+                                work_order.required_qty(recipe, component).saturating_sub(
+                                    drawn
+                                        .get(&(work_order.work_order_number, component.sku.clone()))
+                                        .copied()
+                                        .unwrap_or_default(),
+                                );
+                            if outstanding > 0 {
+                                requirements
+                                    .entry(component.sku.clone())
+                                    .or_default()
+                                    .push((start, outstanding));
+                            }
+                        }
+                    }
+                }
+            }
+
+            // The work order is expected to turn out the recipe's expected yield of the units it
+            // starts, in whole batches
+            let expected_qty = recipe.map_or(work_order.planned_quantity, |recipe| {
+                recipe
+                    .expected_yield
+                    .mul_floor(work_order.planned_input_qty(&recipe))
+            });
+            receipts
+                .entry(work_order.recipe_sku.clone())
+                .or_default()
+                .push((work_order.due_date, expected_qty));
+        }
+
+        for order in FirmedPurchases::<T>::iter_values() {
+            receipts
+                .entry(order.sku)
+                .or_default()
+                .push((order.due_date, order.qty));
+        }
+
+        let reserved = Self::reserved_stock();
+
+        let skus: Vec<Sku> = requirements
+            .keys()
+            .chain(receipts.keys())
+            .cloned()
+            .collect();
+        let mut planning_order: Vec<(u32, Sku)> = Self::low_level_codes(skus)
+            .into_iter()
+            .map(|(sku, level)| (level, sku))
+            .collect();
+        planning_order.sort();
+
+        let mut orders: Vec<PlannedOrder> = Vec::new();
+        for (_, sku) in planning_order {
+            let on_hand = GlobalInventory::<T>::get(&sku)
+                .unwrap_or_default()
+                .saturating_sub(reserved.get(&sku).copied().unwrap_or_default());
+
+            // Receipts on a date count before the requirements due that date
+            let mut events: Vec<(DueDate, bool, Qty)> = Vec::new();
+            for (due_date, qty) in receipts.get(&sku).into_iter().flatten() {
+                events.push((*due_date, false, *qty));
+            }
+            for (due_date, qty) in requirements.get(&sku).into_iter().flatten() {
+                events.push((*due_date, true, *qty));
+            }
+            events.sort();
+
+            let recipe = Self::latest_recipe(&sku);
+            let lead_time = Self::lead_time(&sku);
+
+            let mut projected = on_hand as i64;
+            for (due_date, is_requirement, qty) in events {
+                if is_requirement {
+                    projected -= qty as i64;
+                } else {
+                    projected += qty as i64;
+                }
+                if projected >= 0 {
+                    continue;
+                }
+
+                let shortfall = projected.unsigned_abs().min(Qty::MAX as u64) as Qty;
+                projected = 0;
+
+                if orders.len() >= MAX_PLANNED_ORDERS as usize {
+                    break;
+                }
+
+                let release_date = due_date.saturating_sub(lead_time);
+                let kind = match &recipe {
+                    Some(recipe) => {
+                        // Planned production is demand for the recipe's components
+                        for (component_sku, qty) in Self::component_requirements(recipe, shortfall)
+                        {
+                            requirements
+                                .entry(component_sku)
+                                .or_default()
+                                .push((release_date, qty));
+                        }
+                        PlannedOrderKind::Production {
+                            recipe_id: recipe.recipe_id,
+                        }
+                    }
+                    None => PlannedOrderKind::Purchase,
+                };
+
+                orders.push(PlannedOrder {
+                    planned_order_id: first_id.saturating_add(orders.len() as u32),
+                    sku: sku.clone(),
+                    qty: shortfall,
+                    due_date,
+                    release_date,
+                    kind,
+                    status: PlannedOrderStatus::Planned,
+                });
+            }
+        }
+
+        orders
+    }
+
+    /// Turn a planned production order into a planned work order
+    pub fn do_firm_production_order(
+        planned_order_id: PlannedOrderId,
+        work_order_number: WorkOrderNumber,
+        assigned_to: Employee,
+        priority: Priority,
+        output_location: Location,
+    ) -> Result<PlannedOrder, DispatchError> {
+        Self::firm_planned_order(planned_order_id, |planned_order| {
+            let PlannedOrderKind::Production { recipe_id } = planned_order.kind else {
+                return Err(Error::<T>::NotProductionOrder.into());
+            };
+
+            pallet_assembly::Pallet::<T>::do_create_work_order(WorkOrder {
+                work_order_number,
+                recipe_sku: planned_order.sku.clone(),
+                recipe_id,
+                status: WorkOrderStatus::Planned,
+                planned_quantity: planned_order.qty,
+                due_date: planned_order.due_date,
+                assigned_to,
+                priority,
+                output_location,
+            })?;
+
+            Ok(())
+        })
+    }
+
+    /// Accept a planned purchase order, keeping it as supply for later planning runs
+    pub fn do_firm_purchase_order(
+        planned_order_id: PlannedOrderId,
+    ) -> Result<PlannedOrder, DispatchError> {
+        Self::firm_planned_order(planned_order_id, |planned_order| {
+            ensure!(
+                planned_order.kind == PlannedOrderKind::Purchase,
+                Error::<T>::NotPurchaseOrder
+            );
+
+            FirmedPurchases::<T>::insert(
+                &planned_order.sku,
+                planned_order_id,
+                planned_order.clone(),
+            );
+
+            Ok(())
+        })
+    }
+
+    /// Mark an order of the latest plan as firmed once `firm` has acted on it
+    fn firm_planned_order(
+        planned_order_id: PlannedOrderId,
+        firm: impl FnOnce(&PlannedOrder) -> Result<(), DispatchError>,
+    ) -> Result<PlannedOrder, DispatchError> {
+        LatestPlan::<T>::try_mutate(|plan| {
+            let plan = plan.as_mut().ok_or(Error::<T>::NoPlan)?;
+            let planned_order = plan
+                .orders
+                .iter_mut()
+                .find(|order| order.planned_order_id == planned_order_id)
+                .ok_or(Error::<T>::PlannedOrderNotFound)?;
+            ensure!(
+                planned_order.status == PlannedOrderStatus::Planned,
+                Error::<T>::AlreadyFirmed
+            );
+
+            firm(planned_order)?;
+            planned_order.status = PlannedOrderStatus::Firmed;
+
+            Ok(planned_order.clone())
+        })
+    }
+
+    /// The lowest level each SKU appears at in the recipe structure below the given SKUs
+    fn low_level_codes(skus: Vec<Sku>) -> BTreeMap<Sku, u32> {
+        let mut levels: BTreeMap<Sku, u32> = skus.iter().cloned().map(|sku| (sku, 0)).collect();
+        let mut frontier = skus;
+
+        while let Some(sku) = frontier.pop() {
+            let level = levels.get(&sku).copied().unwrap_or_default();
+            if level >= MAX_PLANNING_LEVELS {
+                continue;
+            }
+            let Some(recipe) = Self::latest_recipe(&sku) else {
+                continue;
+            };
+
+            for component in recipe.required_components.iter() {
+                if levels
+                    .get(&component.sku)
+                    .map_or(true, |component_level| *component_level <= level)
+                {
+                    levels.insert(component.sku.clone(), level + 1);
+                    frontier.push(component.sku.clone());
+                }
+            }
+        }
+
+        levels
+    }
+
+    /// Component quantities needed to produce `qty` of a recipe's SKU, in whole batches
+    fn component_requirements(recipe: &Recipe, qty: Qty) -> Vec<(Sku, Qty)> {
+        let batches = qty.div_ceil(recipe.output_quantity.max(1));
+
+        recipe
+            .required_components
+            .iter()
+            .map(|component| {
+                (
+                    component.sku.clone(),
+                    recipe.required_qty(component).saturating_mul(batches),
+                )
+            })
+            .collect()
+    }
+
+    /// Component stock already backflushed into or issued by hand to each work order, by work
+    /// order and SKU
+    fn drawn_components() -> BTreeMap<(WorkOrderNumber, Sku), Qty> {
+        let mut drawn: BTreeMap<(WorkOrderNumber, Sku), Qty> = BTreeMap::new();
+
+        for ((_, sku, _, work_order_number), record) in BackflushHistory::<T>::iter() {
+            let qty = drawn.entry((work_order_number, sku)).or_default();
+            *qty = qty.saturating_add(record.qty);
+        }
+        for (work_order_number, issued) in ManualIssues::<T>::iter() {
+            for staged in issued.iter() {
+                let qty = drawn
+                    .entry((work_order_number, staged.sku.clone()))
+                    .or_default();
+                *qty = qty.saturating_add(staged.qty);
+            }
+        }
+
+        drawn
+    }
+
+    /// Stock kitted into staging areas or issued by hand to work orders, and stock allocated to
+    /// orders through `StockAllocations`, by SKU
+    fn reserved_stock() -> BTreeMap<Sku, Qty> {
+        let mut reserved: BTreeMap<Sku, Qty> = BTreeMap::new();
+
+        let staged = StagingArea::<T>::iter().map(|(work_order_number, (owner, kit))| {
+            (
+                owner,
+                kit.into_iter()
+                    .chain(ManualIssues::<T>::get(work_order_number)),
+            )
+        });
+        for (owner, kit) in staged {
+            for staged in kit {
+                let Some(item) =
+                    Inventory::<T>::get((&owner, staged.sku.clone(), staged.serial_number))
+                else {
+                    continue;
+                };
+                if item.location != Location::Staging {
+                    continue;
+                }
+
+                let qty = reserved.entry(staged.sku.clone()).or_default();
+                *qty = qty.saturating_add(staged.qty.min(item.qty));
+            }
+        }
+
+        for ((owner, sku, serial_number), item) in Inventory::<T>::iter() {
+            let allocated = <T as pallet_inventory::Config>::StockAllocations::allocated_qty(
+                &owner,
+                &sku,
+                serial_number,
+            )
+            .min(item.qty);
+            if allocated > 0 {
+                let qty = reserved.entry(sku).or_default();
+                *qty = qty.saturating_add(allocated);
+            }
+        }
+
+        reserved
+    }
+
+    /// The latest version of a SKU's recipe, if it is made rather than bought
+    fn latest_recipe(sku: &Sku) -> Option<Recipe> {
+        Recipes::<T>::iter_prefix_values(sku).max_by_key(|recipe| recipe.recipe_id)
+    }
+
+    /// The SKU's lead time from the material master
+    fn lead_time(sku: &Sku) -> u32 {
+        Materials::<T>::get(sku)
+            .map(|material| material.lead_time)
+            .unwrap_or_default()
+    }
+}
+
+impl<T: Config> PurchaseReceipts for Pallet<T> {
+    /// Draw the SKU's firmed purchases down by the received quantity, earliest due first, so
+    /// stock that has arrived is not counted as supply twice
+    fn on_purchase_receipt(sku: &Sku, qty: Qty) {
+        let mut firmed: Vec<PlannedOrder> = FirmedPurchases::<T>::iter_prefix_values(sku).collect();
+        firmed.sort_by_key(|order| (order.due_date, order.planned_order_id));
+
+        let mut remaining = qty;
+        for mut order in firmed {
+            if remaining == 0 {
+                break;
+            }
+
+            let received = remaining.min(order.qty);
+            remaining -= received;
+            order.qty -= received;
+
+            if order.qty == 0 {
+                FirmedPurchases::<T>::remove(sku, order.planned_order_id);
+            } else {
+                FirmedPurchases::<T>::insert(sku, order.planned_order_id, order);
+            }
+        }
+    }
+}
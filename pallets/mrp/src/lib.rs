@@ -0,0 +1,268 @@
+//! # MRP Pallet
+//!
+//! - [`Config`]
+//! - [`Call`]
+//!
+//! ## Overview
+//!
+//! The MRP pallet plans material requirements across the inventory and assembly pallets. A
+//! planning run nets gross demand against supply for every SKU, level by level down the recipe
+//! structure, and proposes purchase and production orders to cover what is short. This pallet
+//! provides functionalities to:
+//!
+//! - Record forecast demand per SKU and due date.
+//! - Net forecasts and open work order demand against on-hand stock, reservations and open
+//!   supply, using the lead times in the material master.
+//! - Explode planned production through the latest recipe of each SKU.
+//! - Keep the latest plan on chain for review, and firm its orders.
+//!
+//! Planning reads every forecast, work order and staging area, so it runs off chain through
+//! the runtime API and only the resulting plan is stored on chain. Forecasts, plans and firming
+//! are restricted to the configured `PlannerOrigin`.
+//!
+//! On-hand stock comes from `GlobalInventory`, less the stock kitted into staging areas or issued
+//! by hand to work orders, and less the stock allocated to orders through `StockAllocations`.
+//! Open work orders are supply for their output SKU, the expected yield of the units they start. Those not yet kitted
+//! are also demand for their components; kitted ones still are for whatever they have yet to
+//! backflush or be issued by hand. Firmed purchase orders count as supply in later runs, until purchase order receipts
+//! of their SKU, reported through `PurchaseReceipts`, draw them down.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! - `set_forecast`: Set the forecast demand for a SKU at a due date.
+//! - `store_plan`: Store a plan produced by the runtime API for review.
+//! - `firm_production_order`: Turn a planned production order into a work order.
+//! - `firm_purchase_order`: Accept a planned purchase order.
+//!
+//! ## Runtime API
+//!
+//! `pallet-mrp-runtime-api` runs the planning, whose result is stored with `store_plan`.
+//!
+//! ## Storage
+//!
+//! - `Forecasts`: Forecast demand by SKU and due date.
+//! - `LatestPlan`: The latest plan stored for review.
+//! - `NextPlannedOrderId`: The ID assigned to the next planned order.
+//! - `FirmedPurchases`: Firmed purchase orders by SKU, counted as supply until they are received.
+//!
+//! ## Events
+//!
+//! - `ForecastSet`: Emitted when a forecast is set or cleared.
+//! - `PlanStored`: Emitted when a new plan is stored.
+//! - `PlannedOrderFirmed`: Emitted when a planned order is firmed.
+//!
+//! ## Errors
+//!
+//! - `NoPlan`: No planning run has stored a plan yet.
+//! - `PlannedOrderNotFound`: The planned order is not part of the latest plan.
+//! - `AlreadyFirmed`: The planned order has already been firmed.
+//! - `NotProductionOrder`: The planned order is not a production order.
+//! - `NotPurchaseOrder`: The planned order is not a purchase order.
+//! - `InvalidPlannedOrder`: A stored plan holds an order for no quantity.
+//!
+//! This pallet uses `no_std` for compatibility with Wasm environments, a polkadot standard.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub use pallet::*;
+
+// FRAME pallets require their own "mock runtimes" to be able to run unit tests. This module
+// contains a mock runtime specific for testing this pallet's functionality.
+#[cfg(test)]
+mod mock;
+
+// This module contains the unit tests for this pallet.
+// Learn about pallet unit testing here: https://docs.substrate.io/test/unit-testing/
+#[cfg(test)]
+mod tests;
+
+mod blogic;
+pub mod types;
+
+use crate::types::{MrpPlan, PlannedOrder, PlannedOrderId, MAX_PLANNED_ORDERS};
+use pallet_assembly::types::{DueDate, Priority, WorkOrderNumber};
+use pallet_inventory::types::{Employee, Location, Qty, Sku};
+
+#[frame_support::pallet]
+pub mod pallet {
+    use super::*;
+    use frame_support::pallet_prelude::*;
+    use frame_system::pallet_prelude::*;
+    use pallet_assembly::pallet::Config as AssemblyConfig;
+
+    #[pallet::pallet]
+    pub struct Pallet<T>(_);
+
+    /// The pallet's configuration trait.
+    ///
+    /// This trait is tightly coupled with the Assembly pallet -> [AssemblyConfig]
+    #[pallet::config]
+    pub trait Config: frame_system::Config + AssemblyConfig {
+        type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+        /// Origin allowed to set forecasts, store plans and firm planned orders
+        type PlannerOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+    }
+
+    /// Forecasts Storage
+    ///
+    /// Forecast demand by SKU and due date
+    #[pallet::storage]
+    pub type Forecasts<T: Config> =
+        StorageDoubleMap<_, Twox64Concat, Sku, Twox64Concat, DueDate, Qty, OptionQuery>;
+
+    /// The latest plan stored for review
+    #[pallet::storage]
+    pub type LatestPlan<T: Config> = StorageValue<_, MrpPlan, OptionQuery>;
+
+    /// The ID assigned to the next planned order
+    #[pallet::storage]
+    pub type NextPlannedOrderId<T: Config> = StorageValue<_, PlannedOrderId, ValueQuery>;
+
+    /// Firmed Purchases Storage
+    ///
+    /// Firmed purchase orders by SKU, counted as supply by later planning runs until they are
+    /// received. Receipts of the SKU draw them down, earliest due first.
+    #[pallet::storage]
+    pub type FirmedPurchases<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        Sku,
+        Twox64Concat,
+        PlannedOrderId,
+        PlannedOrder,
+        OptionQuery,
+    >;
+
+    /// Events that functions in this pallet can emit.
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config> {
+        /// A forecast has been set, a quantity of zero clears it
+        ForecastSet {
+            sku: Sku,
+            due_date: DueDate,
+            qty: Qty,
+        },
+        /// A new plan has been stored for review
+        PlanStored { planned_orders: u32 },
+        PlannedOrderFirmed {
+            planned_order: PlannedOrder,
+            /// The work order created for a production order
+            work_order_number: Option<WorkOrderNumber>,
+        },
+    }
+
+    /// Errors that can be returned by this pallet.
+    #[pallet::error]
+    pub enum Error<T> {
+        /// No planning run has stored a plan yet
+        NoPlan,
+        /// The planned order is not part of the latest plan
+        PlannedOrderNotFound,
+        /// The planned order has already been firmed
+        AlreadyFirmed,
+        /// The planned order is not a production order
+        NotProductionOrder,
+        /// The planned order is not a purchase order
+        NotPurchaseOrder,
+        /// A planned order must be for some quantity
+        InvalidPlannedOrder,
+    }
+
+    /// The pallet's dispatchable functions
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Set the forecast demand for a SKU at a due date, a quantity of zero clears it
+        #[pallet::call_index(0)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn set_forecast(
+            origin: OriginFor<T>,
+            sku: Sku,
+            due_date: DueDate,
+            qty: Qty,
+        ) -> DispatchResult {
+            T::PlannerOrigin::ensure_origin(origin)?;
+
+            Self::do_set_forecast(sku.clone(), due_date, qty);
+
+            Self::deposit_event(Event::ForecastSet { sku, due_date, qty });
+
+            Ok(())
+        }
+
+        /// Store a plan produced by the runtime API's planning run for review
+        ///
+        /// Replaces the previous plan. Orders firmed from it are kept as work orders or firmed
+        /// purchases.
+        #[pallet::call_index(1)]
+        #[pallet::weight(
+            Weight::from_parts(10_000, 0)
+                + T::DbWeight::get().reads_writes(1, 2)
+                + T::DbWeight::get().reads(orders.len() as u64)
+        )]
+        pub fn store_plan(
+            origin: OriginFor<T>,
+            orders: BoundedVec<PlannedOrder, ConstU32<MAX_PLANNED_ORDERS>>,
+        ) -> DispatchResult {
+            T::PlannerOrigin::ensure_origin(origin)?;
+
+            let plan = Self::do_store_plan(orders)?;
+
+            Self::deposit_event(Event::PlanStored {
+                planned_orders: plan.orders.len() as u32,
+            });
+
+            Ok(())
+        }
+
+        /// Turn a planned production order into a work order
+        #[pallet::call_index(2)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn firm_production_order(
+            origin: OriginFor<T>,
+            planned_order_id: PlannedOrderId,
+            work_order_number: WorkOrderNumber,
+            assigned_to: Employee,
+            priority: Priority,
+            output_location: Location,
+        ) -> DispatchResult {
+            T::PlannerOrigin::ensure_origin(origin)?;
+
+            let planned_order = Self::do_firm_production_order(
+                planned_order_id,
+                work_order_number,
+                assigned_to,
+                priority,
+                output_location,
+            )?;
+
+            Self::deposit_event(Event::PlannedOrderFirmed {
+                planned_order,
+                work_order_number: Some(work_order_number),
+            });
+
+            Ok(())
+        }
+
+        /// Accept a planned purchase order
+        #[pallet::call_index(3)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn firm_purchase_order(
+            origin: OriginFor<T>,
+            planned_order_id: PlannedOrderId,
+        ) -> DispatchResult {
+            T::PlannerOrigin::ensure_origin(origin)?;
+
+            let planned_order = Self::do_firm_purchase_order(planned_order_id)?;
+
+            Self::deposit_event(Event::PlannedOrderFirmed {
+                planned_order,
+                work_order_number: None,
+            });
+
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use crate as pallet_mrp;
+use frame_support::derive_impl;
+use frame_system::EnsureRoot;
+use pallet_inventory::types::{Qty, SerialNumber, Sku, StockAllocations};
+use sp_runtime::BuildStorage;
+use std::cell::RefCell;
+
+type Block = frame_system::mocking::MockBlock<Test>;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+    pub enum Test
+    {
+        System: frame_system,
+        Inventory: pallet_inventory,
+        Assembly: pallet_assembly,
+        Mrp: pallet_mrp,
+    }
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+impl frame_system::Config for Test {
+    type Block = Block;
+}
+
+impl pallet_inventory::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type WeightInfo = ();
+    type StockAllocations = Allocations;
+    type StockChanges = ();
+    type ApprovalAdminOrigin = EnsureRoot<u64>;
+    type ReorderAdminOrigin = EnsureRoot<u64>;
+}
+
+impl pallet_assembly::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type WeightInfo = ();
+}
+
+impl pallet_mrp::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type PlannerOrigin = EnsureRoot<u64>;
+}
+
+thread_local! {
+    /// Quantities allocated to orders, by SKU and serial
+    pub static ALLOCATED: RefCell<Vec<(Sku, SerialNumber, Qty)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Stock allocations as set by the tests in `ALLOCATED`
+pub struct Allocations;
+
+impl StockAllocations<u64> for Allocations {
+    fn allocated_qty(_who: &u64, sku: &Sku, serial_number: SerialNumber) -> Qty {
+        ALLOCATED.with(|allocated| {
+            allocated
+                .borrow()
+                .iter()
+                .filter(|(allocated_sku, serial, _)| {
+                    allocated_sku == sku && *serial == serial_number
+                })
+                .map(|(_, _, qty)| qty)
+                .sum()
+        })
+    }
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    let t = frame_system::GenesisConfig::<Test>::default()
+        .build_storage()
+        .unwrap();
+
+    // Events are only recorded from block 1 on
+    let mut ext: sp_io::TestExternalities = t.into();
+    ext.execute_with(|| System::set_block_number(1));
+    ext
+}
@@ -0,0 +1,488 @@
+use crate::{mock::*, types::*, Error, Event};
+use frame_support::sp_runtime::{DispatchError, DispatchResult, Percent};
+use frame_support::{assert_noop, assert_ok, BoundedVec};
+use pallet_assembly::types::{BackflushRecord, Priority, WorkOrder, WorkOrderStatus};
+use pallet_inventory::types::{
+    Employee, IssueMode, Item, Location, Material, PurchaseReceipts, Qty, Recipe, RecipeComponent,
+    SerialNumber, Sku,
+};
+
+const ALICE: u64 = 1;
+
+fn sku(sku: &str) -> Sku {
+    BoundedVec::truncate_from(sku.as_bytes().to_vec())
+}
+
+fn stock(sku_name: &str, serial_number: SerialNumber, qty: Qty) -> Item {
+    Item {
+        sku: sku(sku_name),
+        serial_number,
+        qty,
+        ..Default::default()
+    }
+}
+
+/// A widget made from two bolts, bought in with a lead time of five blocks and made in three,
+/// with one widget and two bolts on hand and three widgets forecast at block 20
+fn set_up() {
+    assert_ok!(Inventory::insert_recipe(
+        RuntimeOrigin::signed(ALICE),
+        Recipe {
+            sku: sku("WIDGET"),
+            required_components: BoundedVec::truncate_from(vec![RecipeComponent {
+                sku: sku("BOLT"),
+                qty: 2,
+                scrap_percent: Percent::from_percent(0),
+                issue_mode: IssueMode::PreKitted,
+            }]),
+            output_quantity: 1,
+            expected_yield: Percent::from_percent(100),
+            ..Default::default()
+        }
+    ));
+    for (sku_name, lead_time) in [("WIDGET", 3), ("BOLT", 5)] {
+        assert_ok!(Inventory::insert_material(
+            RuntimeOrigin::signed(ALICE),
+            Material {
+                sku: sku(sku_name),
+                lead_time,
+                ..Default::default()
+            }
+        ));
+    }
+    for item in [stock("WIDGET", 1, 1), stock("BOLT", 2, 2)] {
+        assert_ok!(Inventory::inventory_insertion(
+            RuntimeOrigin::signed(ALICE),
+            item
+        ));
+    }
+    assert_ok!(Mrp::set_forecast(
+        RuntimeOrigin::root(),
+        sku("WIDGET"),
+        20,
+        3
+    ));
+}
+
+fn planned_order(
+    planned_order_id: PlannedOrderId,
+    sku_name: &str,
+    qty: Qty,
+    due_date: u32,
+    release_date: u32,
+    kind: PlannedOrderKind,
+) -> PlannedOrder {
+    PlannedOrder {
+        planned_order_id,
+        sku: sku(sku_name),
+        qty,
+        due_date,
+        release_date,
+        kind,
+        status: PlannedOrderStatus::Planned,
+    }
+}
+
+/// Store the plan generated from the current state
+fn store_generated_plan() {
+    let orders = Mrp::generate_plan(crate::NextPlannedOrderId::<Test>::get());
+    assert_ok!(Mrp::store_plan(
+        RuntimeOrigin::root(),
+        BoundedVec::truncate_from(orders)
+    ));
+}
+
+fn firm_production_order(planned_order_id: PlannedOrderId) -> DispatchResult {
+    Mrp::firm_production_order(
+        RuntimeOrigin::root(),
+        planned_order_id,
+        7,
+        Employee::Bob,
+        Priority::High,
+        Location::Warehouse,
+    )
+}
+
+#[test]
+fn forecasts_are_set_and_cleared() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Mrp::set_forecast(
+            RuntimeOrigin::root(),
+            sku("WIDGET"),
+            20,
+            3
+        ));
+        assert_eq!(crate::Forecasts::<Test>::get(sku("WIDGET"), 20), Some(3));
+        System::assert_last_event(
+            Event::ForecastSet {
+                sku: sku("WIDGET"),
+                due_date: 20,
+                qty: 3,
+            }
+            .into(),
+        );
+
+        assert_ok!(Mrp::set_forecast(
+            RuntimeOrigin::root(),
+            sku("WIDGET"),
+            20,
+            0
+        ));
+        assert_eq!(crate::Forecasts::<Test>::get(sku("WIDGET"), 20), None);
+    });
+}
+
+#[test]
+fn only_planners_can_plan() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Mrp::set_forecast(RuntimeOrigin::signed(ALICE), sku("WIDGET"), 20, 3),
+            DispatchError::BadOrigin
+        );
+        assert_noop!(
+            Mrp::store_plan(RuntimeOrigin::signed(ALICE), BoundedVec::default()),
+            DispatchError::BadOrigin
+        );
+        assert_noop!(
+            Mrp::firm_purchase_order(RuntimeOrigin::signed(ALICE), 0),
+            DispatchError::BadOrigin
+        );
+    });
+}
+
+#[test]
+fn planning_nets_demand_through_every_recipe_level() {
+    new_test_ext().execute_with(|| {
+        set_up();
+
+        // Two widgets short, started three blocks early, which need four bolts with two on hand
+        assert_eq!(
+            Mrp::generate_plan(0),
+            vec![
+                planned_order(
+                    0,
+                    "WIDGET",
+                    2,
+                    20,
+                    17,
+                    PlannedOrderKind::Production { recipe_id: 0 }
+                ),
+                planned_order(1, "BOLT", 2, 17, 12, PlannedOrderKind::Purchase),
+            ]
+        );
+    });
+}
+
+#[test]
+fn allocated_stock_is_not_on_hand() {
+    new_test_ext().execute_with(|| {
+        set_up();
+        ALLOCATED.with(|allocated| allocated.borrow_mut().push((sku("BOLT"), 2, 1)));
+
+        // Only one of the two bolts on hand is free for the widgets
+        assert_eq!(
+            Mrp::generate_plan(0)[1],
+            planned_order(1, "BOLT", 3, 17, 12, PlannedOrderKind::Purchase)
+        );
+    });
+}
+
+/// Drop the widget forecast and add a work order for two widgets in the given status
+fn set_up_work_order(status: WorkOrderStatus) {
+    set_up();
+    assert_ok!(Mrp::set_forecast(
+        RuntimeOrigin::root(),
+        sku("WIDGET"),
+        20,
+        0
+    ));
+    pallet_assembly::WorkOrders::<Test>::insert(
+        7,
+        WorkOrder {
+            work_order_number: 7,
+            recipe_sku: sku("WIDGET"),
+            status,
+            planned_quantity: 2,
+            due_date: 20,
+            ..Default::default()
+        },
+    );
+}
+
+#[test]
+fn kitted_work_orders_still_need_what_they_draw_from_stock() {
+    new_test_ext().execute_with(|| {
+        set_up_work_order(WorkOrderStatus::InProgress);
+
+        // Pre-kitted bolts are already held by the work order
+        assert_eq!(Mrp::generate_plan(0), vec![]);
+
+        // Backflushed bolts are not, less those already backflushed
+        pallet_inventory::Recipes::<Test>::mutate(sku("WIDGET"), 0, |recipe| {
+            recipe.as_mut().unwrap().required_components[0].issue_mode = IssueMode::Backflush;
+        });
+        pallet_assembly::BackflushHistory::<Test>::insert(
+            (ALICE, sku("BOLT"), 2, 7),
+            BackflushRecord {
+                qty: 1,
+                operation_number: None,
+                consumed_at: 1,
+            },
+        );
+        assert_eq!(
+            Mrp::generate_plan(0),
+            vec![planned_order(
+                0,
+                "BOLT",
+                1,
+                17,
+                12,
+                PlannedOrderKind::Purchase
+            )]
+        );
+    });
+}
+
+#[test]
+fn work_orders_are_supply_for_their_expected_yield() {
+    new_test_ext().execute_with(|| {
+        set_up_work_order(WorkOrderStatus::Released);
+        assert_ok!(Mrp::set_forecast(
+            RuntimeOrigin::root(),
+            sku("WIDGET"),
+            20,
+            6
+        ));
+
+        // The work order's one batch of four at a 75% yield starts six units, expected to turn
+        // out four widgets, so one more of the six forecast is short
+        pallet_inventory::Recipes::<Test>::mutate(sku("WIDGET"), 0, |recipe| {
+            let recipe = recipe.as_mut().unwrap();
+            recipe.output_quantity = 4;
+            recipe.expected_yield = Percent::from_percent(75);
+        });
+
+        let plan = Mrp::generate_plan(0);
+        assert_eq!(
+            plan[0],
+            planned_order(
+                0,
+                "WIDGET",
+                1,
+                20,
+                17,
+                PlannedOrderKind::Production { recipe_id: 0 }
+            )
+        );
+    });
+}
+
+#[test]
+fn stored_plans_are_renumbered_and_replace_the_last() {
+    new_test_ext().execute_with(|| {
+        set_up();
+        store_generated_plan();
+
+        let plan = crate::LatestPlan::<Test>::get().unwrap();
+        assert_eq!(plan.generated_at, 1);
+        assert_eq!(plan.orders.len(), 2);
+        assert_eq!(crate::NextPlannedOrderId::<Test>::get(), 2);
+        System::assert_last_event(Event::PlanStored { planned_orders: 2 }.into());
+
+        // Submitted IDs and statuses are ignored
+        let mut order = planned_order(0, "BOLT", 5, 30, 25, PlannedOrderKind::Purchase);
+        order.status = PlannedOrderStatus::Firmed;
+        assert_ok!(Mrp::store_plan(
+            RuntimeOrigin::root(),
+            BoundedVec::truncate_from(vec![order])
+        ));
+
+        let plan = crate::LatestPlan::<Test>::get().unwrap();
+        assert_eq!(
+            plan.orders.into_inner(),
+            vec![planned_order(
+                2,
+                "BOLT",
+                5,
+                30,
+                25,
+                PlannedOrderKind::Purchase
+            )]
+        );
+    });
+}
+
+#[test]
+fn plans_must_be_for_stored_recipes_and_some_quantity() {
+    new_test_ext().execute_with(|| {
+        set_up();
+
+        assert_noop!(
+            Mrp::store_plan(
+                RuntimeOrigin::root(),
+                BoundedVec::truncate_from(vec![planned_order(
+                    0,
+                    "BOLT",
+                    0,
+                    20,
+                    15,
+                    PlannedOrderKind::Purchase
+                )])
+            ),
+            Error::<Test>::InvalidPlannedOrder
+        );
+        assert_noop!(
+            Mrp::store_plan(
+                RuntimeOrigin::root(),
+                BoundedVec::truncate_from(vec![planned_order(
+                    0,
+                    "WIDGET",
+                    1,
+                    20,
+                    17,
+                    PlannedOrderKind::Production { recipe_id: 1 }
+                )])
+            ),
+            pallet_inventory::Error::<Test>::RecipeNotFound
+        );
+    });
+}
+
+#[test]
+fn firming_a_production_order_creates_a_work_order() {
+    new_test_ext().execute_with(|| {
+        set_up();
+        store_generated_plan();
+
+        assert_ok!(firm_production_order(0));
+
+        let work_order = pallet_assembly::WorkOrders::<Test>::get(7).unwrap();
+        assert_eq!(work_order.recipe_sku, sku("WIDGET"));
+        assert_eq!(work_order.status, WorkOrderStatus::Planned);
+        assert_eq!(work_order.planned_quantity, 2);
+        assert_eq!(work_order.due_date, 20);
+        assert_eq!(work_order.priority, Priority::High);
+
+        let mut firmed = planned_order(
+            0,
+            "WIDGET",
+            2,
+            20,
+            17,
+            PlannedOrderKind::Production { recipe_id: 0 },
+        );
+        firmed.status = PlannedOrderStatus::Firmed;
+        assert_eq!(crate::LatestPlan::<Test>::get().unwrap().orders[0], firmed);
+        System::assert_last_event(
+            Event::PlannedOrderFirmed {
+                planned_order: firmed,
+                work_order_number: Some(7),
+            }
+            .into(),
+        );
+
+        assert_noop!(firm_production_order(0), Error::<Test>::AlreadyFirmed);
+    });
+}
+
+#[test]
+fn firming_a_purchase_order_keeps_it_as_supply() {
+    new_test_ext().execute_with(|| {
+        set_up();
+        store_generated_plan();
+
+        assert_ok!(Mrp::firm_purchase_order(RuntimeOrigin::root(), 1));
+
+        let firmed = crate::FirmedPurchases::<Test>::get(sku("BOLT"), 1).unwrap();
+        assert_eq!(firmed.sku, sku("BOLT"));
+        assert_eq!(firmed.qty, 2);
+        assert_eq!(
+            crate::LatestPlan::<Test>::get().unwrap().orders[1].status,
+            PlannedOrderStatus::Firmed
+        );
+
+        assert_noop!(
+            Mrp::firm_purchase_order(RuntimeOrigin::root(), 1),
+            Error::<Test>::AlreadyFirmed
+        );
+    });
+}
+
+#[test]
+fn firmed_orders_are_supply_for_the_next_run() {
+    new_test_ext().execute_with(|| {
+        set_up();
+        store_generated_plan();
+
+        assert_ok!(firm_production_order(0));
+        assert_ok!(Mrp::firm_purchase_order(RuntimeOrigin::root(), 1));
+
+        // The work order covers the widgets and the firmed purchase the bolts it needs
+        assert_eq!(Mrp::generate_plan(2), vec![]);
+    });
+}
+
+#[test]
+fn purchase_receipts_draw_firmed_purchases_down() {
+    new_test_ext().execute_with(|| {
+        set_up();
+        store_generated_plan();
+        assert_ok!(firm_production_order(0));
+        assert_ok!(Mrp::firm_purchase_order(RuntimeOrigin::root(), 1));
+
+        // Receipts of other SKUs leave the firmed bolts alone
+        Mrp::on_purchase_receipt(&sku("NUT"), 5);
+        assert_eq!(
+            crate::FirmedPurchases::<Test>::get(sku("BOLT"), 1).map(|order| order.qty),
+            Some(2)
+        );
+
+        Mrp::on_purchase_receipt(&sku("BOLT"), 1);
+        assert_eq!(
+            crate::FirmedPurchases::<Test>::get(sku("BOLT"), 1).map(|order| order.qty),
+            Some(1)
+        );
+
+        // Once every bolt has arrived it is counted as stock on hand only
+        assert_ok!(Inventory::inventory_insertion(
+            RuntimeOrigin::signed(ALICE),
+            stock("BOLT", 3, 2)
+        ));
+        Mrp::on_purchase_receipt(&sku("BOLT"), 1);
+        assert_eq!(crate::FirmedPurchases::<Test>::get(sku("BOLT"), 1), None);
+
+        assert_ok!(Mrp::set_forecast(RuntimeOrigin::root(), sku("BOLT"), 20, 2));
+        assert_eq!(
+            Mrp::generate_plan(2),
+            vec![planned_order(
+                2,
+                "BOLT",
+                2,
+                20,
+                15,
+                PlannedOrderKind::Purchase
+            )]
+        );
+    });
+}
+
+#[test]
+fn planned_orders_are_firmed_by_their_kind() {
+    new_test_ext().execute_with(|| {
+        set_up();
+
+        assert_noop!(firm_production_order(0), Error::<Test>::NoPlan);
+
+        store_generated_plan();
+
+        assert_noop!(firm_production_order(1), Error::<Test>::NotProductionOrder);
+        assert_noop!(
+            Mrp::firm_purchase_order(RuntimeOrigin::root(), 0),
+            Error::<Test>::NotPurchaseOrder
+        );
+        assert_noop!(
+            Mrp::firm_purchase_order(RuntimeOrigin::root(), 2),
+            Error::<Test>::PlannedOrderNotFound
+        );
+    });
+}
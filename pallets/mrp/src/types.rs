@@ -0,0 +1,52 @@
+use codec::{Decode, Encode};
+use frame_support::pallet_prelude::ConstU32;
+use frame_support::pallet_prelude::MaxEncodedLen;
+use frame_support::BoundedVec;
+use pallet_assembly::types::DueDate;
+use pallet_inventory::types::{Qty, RecipeId, Sku};
+use scale_info::TypeInfo;
+
+pub type PlannedOrderId = u32;
+
+/// Deepest recipe level the planning run explodes
+pub const MAX_PLANNING_LEVELS: u32 = 16;
+/// Most planned orders a single planning run produces
+pub const MAX_PLANNED_ORDERS: u32 = 500;
+
+/// How a planned order would be fulfilled
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub enum PlannedOrderKind {
+    /// Bought in, the SKU has no recipe
+    Purchase,
+    /// Made through the latest version of the SKU's recipe
+    Production { recipe_id: RecipeId },
+}
+
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
+pub enum PlannedOrderStatus {
+    #[default]
+    Planned,
+    /// Accepted for execution, no longer replaced by the next planning run
+    Firmed,
+}
+
+/// An order the planning run proposes to cover a net requirement
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct PlannedOrder {
+    pub planned_order_id: PlannedOrderId,
+    pub sku: Sku,
+    pub qty: Qty,
+    /// Block by which the quantity is needed
+    pub due_date: DueDate,
+    /// Block by which the order must be placed or started, given the SKU's lead time
+    pub release_date: DueDate,
+    pub kind: PlannedOrderKind,
+    pub status: PlannedOrderStatus,
+}
+
+/// The result of the latest planning run
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct MrpPlan {
+    pub generated_at: u32,
+    pub orders: BoundedVec<PlannedOrder, ConstU32<MAX_PLANNED_ORDERS>>,
+}
@@ -0,0 +1,396 @@
+use crate::pallet::{
+    Config, Error, HandlingUnits, Manifests, NextHandlingUnitId, NextManifestId, PackedItems,
+};
+use crate::{pallet::Pallet, types::*};
+use frame_support::ensure;
+use frame_support::pallet_prelude::{ConstU32, Get};
+use frame_support::sp_runtime::{DispatchError, DispatchResult};
+use frame_support::BoundedVec;
+use pallet_inventory::types::{
+    AdjustDetails, Employee, HoldSource, Item, Location, Qty, SerialNumber, Sku, StockAllocations,
+    WeightLbs,
+};
+use pallet_inventory::Inventory;
+use pallet_sales::types::SalesOrderNumber;
+use pallet_sales::{SalesOrderAllocations, SalesOrders};
+use scale_info::prelude::vec::Vec;
+
+impl<T: Config> Pallet<T> {
+    /// Create an empty handling unit under the next serial reference
+    pub fn do_create_handling_unit(
+        who: &T::AccountId,
+        kind: HandlingUnitKind,
+        tare_weight: WeightLbs,
+    ) -> HandlingUnit {
+        let handling_unit_id = NextHandlingUnitId::<T>::get();
+        NextHandlingUnitId::<T>::put(handling_unit_id.saturating_add(1));
+
+        let handling_unit = HandlingUnit {
+            handling_unit_id,
+            sscc: sscc(
+                kind.extension_digit(),
+                T::CompanyPrefix::get(),
+                handling_unit_id,
+            ),
+            kind,
+            parent: None,
+            contents: BoundedVec::default(),
+            tare_weight,
+            gross_weight: tare_weight,
+            status: HandlingUnitStatus::Open,
+            created_at: pallet_inventory::Pallet::<T>::current_block(),
+        };
+
+        HandlingUnits::<T>::insert(handling_unit_id, (who, handling_unit.clone()));
+
+        handling_unit
+    }
+
+    /// Pack a serialized item into a handling unit, moving it to packaging
+    ///
+    /// The item is held until it is unpacked or shipped, so that the weight packed with it stays
+    /// true.
+    pub fn do_pack_item(
+        who: &T::AccountId,
+        handling_unit_id: HandlingUnitId,
+        sku: Sku,
+        serial_number: SerialNumber,
+        packed_by: Employee,
+    ) -> DispatchResult {
+        let mut handling_unit = Self::open_handling_unit(who, handling_unit_id)?;
+
+        ensure!(
+            !PackedItems::<T>::contains_key((who, sku.clone(), serial_number)),
+            Error::<T>::ItemAlreadyPacked
+        );
+
+        let item = Inventory::<T>::get((who, sku.clone(), serial_number))
+            .ok_or(pallet_inventory::Error::<T>::InventoryNotFound)?;
+        ensure!(
+            item.location.is_usable() && item.location != Location::Staging,
+            Error::<T>::ItemNotPackable
+        );
+        pallet_inventory::Pallet::<T>::ensure_not_consigned(who, &sku, serial_number)?;
+        let weight = item.weight.saturating_mul(item.qty);
+
+        // Fails if the item is on quality hold
+        if item.location != Location::Packaging {
+            pallet_inventory::Pallet::<T>::do_inventory_move(
+                who,
+                item.clone(),
+                packed_by,
+                AdjustDetails::Location {
+                    original_location: item.location.clone(),
+                    new_location: Location::Packaging,
+                    reason: pallet_inventory::Pallet::<T>::reason("Pack into handling unit")?,
+                },
+            )?;
+        }
+        pallet_inventory::Pallet::<T>::do_place_hold(
+            who,
+            sku.clone(),
+            serial_number,
+            HoldSource::Packing(handling_unit_id),
+            pallet_inventory::Pallet::<T>::reason("Packed into handling unit")?,
+        )?;
+
+        handling_unit
+            .contents
+            .try_push(PackedContent::Item {
+                sku: sku.clone(),
+                serial_number,
+                weight,
+            })
+            .map_err(|_| Error::<T>::HandlingUnitFull)?;
+        HandlingUnits::<T>::insert(handling_unit_id, (who, handling_unit));
+
+        PackedItems::<T>::insert((who, sku, serial_number), handling_unit_id);
+        Self::add_weight(handling_unit_id, weight);
+
+        Ok(())
+    }
+
+    /// Take a serialized item out of a handling unit
+    pub fn do_unpack_item(
+        who: &T::AccountId,
+        handling_unit_id: HandlingUnitId,
+        sku: Sku,
+        serial_number: SerialNumber,
+    ) -> DispatchResult {
+        let mut handling_unit = Self::open_handling_unit(who, handling_unit_id)?;
+
+        let position = handling_unit
+            .contents
+            .iter()
+            .position(|content| match content {
+                PackedContent::Item {
+                    sku: packed_sku,
+                    serial_number: packed_serial,
+                    ..
+                } => *packed_sku == sku && *packed_serial == serial_number,
+                PackedContent::Unit(_) => false,
+            })
+            .ok_or(Error::<T>::ItemNotPacked)?;
+
+        let PackedContent::Item { weight, .. } = handling_unit.contents.remove(position) else {
+            return Err(Error::<T>::ItemNotPacked.into());
+        };
+        HandlingUnits::<T>::insert(handling_unit_id, (who, handling_unit));
+
+        pallet_inventory::Pallet::<T>::do_release_hold(
+            who,
+            sku.clone(),
+            serial_number,
+            HoldSource::Packing(handling_unit_id),
+        );
+        PackedItems::<T>::remove((who, sku, serial_number));
+        Self::remove_weight(handling_unit_id, weight);
+
+        Ok(())
+    }
+
+    /// Pack a handling unit into another
+    pub fn do_pack_unit(
+        who: &T::AccountId,
+        parent_id: HandlingUnitId,
+        child_id: HandlingUnitId,
+    ) -> DispatchResult {
+        let mut parent = Self::open_handling_unit(who, parent_id)?;
+        let mut child = Self::open_handling_unit(who, child_id)?;
+
+        ensure!(child.parent.is_none(), Error::<T>::UnitAlreadyPacked);
+
+        // The parent must not be the child or sit anywhere inside it
+        let mut ancestor = Some(parent_id);
+        while let Some(unit_id) = ancestor {
+            ensure!(unit_id != child_id, Error::<T>::CircularPacking);
+            ancestor = HandlingUnits::<T>::get(unit_id).and_then(|(_, unit)| unit.parent);
+        }
+
+        parent
+            .contents
+            .try_push(PackedContent::Unit(child_id))
+            .map_err(|_| Error::<T>::HandlingUnitFull)?;
+        HandlingUnits::<T>::insert(parent_id, (who, parent));
+
+        child.parent = Some(parent_id);
+        let weight = child.gross_weight;
+        HandlingUnits::<T>::insert(child_id, (who, child));
+
+        Self::add_weight(parent_id, weight);
+
+        Ok(())
+    }
+
+    /// Take a handling unit out of the unit it is packed into
+    pub fn do_unpack_unit(
+        who: &T::AccountId,
+        parent_id: HandlingUnitId,
+        child_id: HandlingUnitId,
+    ) -> DispatchResult {
+        let mut parent = Self::open_handling_unit(who, parent_id)?;
+        let mut child = Self::open_handling_unit(who, child_id)?;
+
+        ensure!(child.parent == Some(parent_id), Error::<T>::UnitNotPacked);
+
+        parent
+            .contents
+            .retain(|content| *content != PackedContent::Unit(child_id));
+        HandlingUnits::<T>::insert(parent_id, (who, parent));
+
+        child.parent = None;
+        let weight = child.gross_weight;
+        HandlingUnits::<T>::insert(child_id, (who, child));
+
+        Self::remove_weight(parent_id, weight);
+
+        Ok(())
+    }
+
+    /// Generate a shipping manifest from top-level handling units, and ship them.
+    ///
+    /// Every item in the units, at any level, is listed against the top-level unit it ships in,
+    /// then comes out of its unit and ships. Each item must be wholly allocated to the given
+    /// sales order, or to some order if none is given.
+    pub fn do_create_manifest(
+        who: &T::AccountId,
+        units: BoundedVec<HandlingUnitId, ConstU32<MAX_MANIFEST_UNITS>>,
+        so_number: Option<SalesOrderNumber>,
+        carrier: Carrier,
+        shipped_by: Employee,
+    ) -> Result<ShippingManifest, DispatchError> {
+        ensure!(!units.is_empty(), Error::<T>::NoManifestUnits);
+
+        if let Some(so_number) = so_number {
+            let (owner, _) = SalesOrders::<T>::get(so_number)
+                .ok_or(pallet_sales::Error::<T>::SalesOrderNotFound)?;
+            ensure!(&owner == who, pallet_sales::Error::<T>::NotSalesOrderOwner);
+        }
+
+        let mut ssccs: Vec<Sscc> = Vec::new();
+        let mut lines: BoundedVec<ManifestLine, ConstU32<MAX_MANIFEST_LINES>> =
+            BoundedVec::default();
+        let mut total_weight: WeightLbs = 0;
+
+        for (index, handling_unit_id) in units.iter().enumerate() {
+            ensure!(
+                !units[..index].contains(handling_unit_id),
+                Error::<T>::DuplicateManifestUnit
+            );
+
+            let handling_unit = Self::open_handling_unit(who, *handling_unit_id)?;
+            ensure!(handling_unit.parent.is_none(), Error::<T>::NotTopLevelUnit);
+
+            total_weight = total_weight.saturating_add(handling_unit.gross_weight);
+            let sscc = handling_unit.sscc;
+            ssccs.push(sscc);
+
+            // Walk the unit and everything inside it
+            let mut pending: Vec<HandlingUnit> = Vec::from([handling_unit]);
+            while let Some(mut unit) = pending.pop() {
+                for content in unit.contents.iter() {
+                    match content {
+                        PackedContent::Item {
+                            sku, serial_number, ..
+                        } => {
+                            let item = Inventory::<T>::get((who, sku.clone(), *serial_number))
+                                .ok_or(pallet_inventory::Error::<T>::InventoryNotFound)?;
+                            ensure!(
+                                Self::allocated_qty(who, so_number, &item) >= item.qty,
+                                Error::<T>::ItemNotAllocated
+                            );
+                            lines
+                                .try_push(ManifestLine {
+                                    sscc,
+                                    sku: sku.clone(),
+                                    serial_number: *serial_number,
+                                    lot_number: item.lot_number,
+                                    qty: item.qty,
+                                })
+                                .map_err(|_| Error::<T>::TooManyManifestLines)?;
+
+                            Self::ship_item(who, unit.handling_unit_id, item, &shipped_by)?;
+                        }
+                        PackedContent::Unit(child_id) => {
+                            let (_, child) = HandlingUnits::<T>::get(child_id)
+                                .ok_or(Error::<T>::HandlingUnitNotFound)?;
+                            pending.push(child);
+                        }
+                    }
+                }
+
+                unit.status = HandlingUnitStatus::Shipped;
+                HandlingUnits::<T>::insert(unit.handling_unit_id, (who, unit));
+            }
+        }
+
+        let manifest_id = NextManifestId::<T>::get();
+        NextManifestId::<T>::put(manifest_id.saturating_add(1));
+
+        let manifest = ShippingManifest {
+            manifest_id,
+            so_number,
+            carrier,
+            // One SSCC per listed unit, so never truncated
+            units: BoundedVec::truncate_from(ssccs),
+            lines,
+            total_weight,
+            created_at: pallet_inventory::Pallet::<T>::current_block(),
+        };
+        Manifests::<T>::insert(manifest_id, (who, manifest.clone()));
+
+        Ok(manifest)
+    }
+
+    /// Take a packed item out of its unit's hold and ship it
+    ///
+    /// The item moves to shipping, where the shipment confirmation of the sales order it is
+    /// allocated to draws it down.
+    fn ship_item(
+        who: &T::AccountId,
+        handling_unit_id: HandlingUnitId,
+        item: Item,
+        shipped_by: &Employee,
+    ) -> DispatchResult {
+        let sku = item.sku.clone();
+        let serial_number = item.serial_number;
+        pallet_inventory::Pallet::<T>::ensure_not_consigned(who, &sku, serial_number)?;
+
+        pallet_inventory::Pallet::<T>::do_release_hold(
+            who,
+            sku.clone(),
+            serial_number,
+            HoldSource::Packing(handling_unit_id),
+        );
+        PackedItems::<T>::remove((who, sku, serial_number));
+
+        // Fails if the item is still on hold from elsewhere, such as a recall
+        pallet_inventory::Pallet::<T>::do_inventory_move(
+            who,
+            item.clone(),
+            shipped_by.clone(),
+            AdjustDetails::Location {
+                original_location: item.location.clone(),
+                new_location: Location::Shipping,
+                reason: pallet_inventory::Pallet::<T>::reason("Ship handling unit")?,
+            },
+        )
+    }
+
+    /// The quantity of an item allocated to the sales order, or to any order if none is given
+    fn allocated_qty(who: &T::AccountId, so_number: Option<SalesOrderNumber>, item: &Item) -> Qty {
+        match so_number {
+            Some(so_number) => SalesOrderAllocations::<T>::get(so_number)
+                .unwrap_or_default()
+                .iter()
+                .filter(|allocated| {
+                    allocated.sku == item.sku && allocated.serial_number == item.serial_number
+                })
+                .fold(0 as Qty, |total, allocated| {
+                    total.saturating_add(allocated.qty)
+                }),
+            None => T::StockAllocations::allocated_qty(who, &item.sku, item.serial_number),
+        }
+    }
+
+    /// Fetch a handling unit owned by the account that has not shipped
+    fn open_handling_unit(
+        who: &T::AccountId,
+        handling_unit_id: HandlingUnitId,
+    ) -> Result<HandlingUnit, DispatchError> {
+        let (owner, handling_unit) =
+            HandlingUnits::<T>::get(handling_unit_id).ok_or(Error::<T>::HandlingUnitNotFound)?;
+
+        ensure!(&owner == who, Error::<T>::NotHandlingUnitOwner);
+        ensure!(
+            handling_unit.status == HandlingUnitStatus::Open,
+            Error::<T>::HandlingUnitShipped
+        );
+
+        Ok(handling_unit)
+    }
+
+    /// Add weight to a handling unit and every unit it is packed into
+    fn add_weight(handling_unit_id: HandlingUnitId, weight: WeightLbs) {
+        Self::update_gross_weight(handling_unit_id, |gross| gross.saturating_add(weight));
+    }
+
+    /// Take weight off a handling unit and every unit it is packed into
+    fn remove_weight(handling_unit_id: HandlingUnitId, weight: WeightLbs) {
+        Self::update_gross_weight(handling_unit_id, |gross| gross.saturating_sub(weight));
+    }
+
+    fn update_gross_weight(
+        handling_unit_id: HandlingUnitId,
+        update: impl Fn(WeightLbs) -> WeightLbs,
+    ) {
+        let mut next = Some(handling_unit_id);
+        while let Some(unit_id) = next {
+            next = HandlingUnits::<T>::mutate(unit_id, |entry| {
+                let (_, unit) = entry.as_mut()?;
+                unit.gross_weight = update(unit.gross_weight);
+                unit.parent
+            });
+        }
+    }
+}
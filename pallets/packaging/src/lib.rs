@@ -0,0 +1,370 @@
+//! # Packaging Pallet
+//!
+//! - [`Config`]
+//! - [`Call`]
+//!
+//! ## Overview
+//!
+//! The Packaging pallet packs serialized inventory into handling units and ships them on
+//! manifests. This pallet provides functionalities to:
+//!
+//! - Create cartons and pallets, each identified by an SSCC built from the runtime's GS1
+//!   company prefix.
+//! - Pack serialized items, or other handling units, into a handling unit and unpack them.
+//! - Keep each unit's gross weight, from its tare weight and the weight of everything in it.
+//! - Generate shipping manifests listing every item in a set of packed units.
+//!
+//! Packing an item moves it to `Location::Packaging` and holds it there, so it cannot be moved,
+//! adjusted or scrapped until it is unpacked or shipped. Stock staged for a work order, awaiting
+//! review or scrapped cannot be packed, nor can stock held on consignment until its owner settles
+//! it. An item weighs its `Item::weight` per unit times its
+//! quantity. Units listed on a manifest, and every unit inside them, are shipped and can no longer
+//! be packed or unpacked. Only stock allocated to a sales order, to the manifest's order if it
+//! names one, can be listed. Its items move to `Location::Shipping`, where the order's shipment
+//! confirmation draws them down from stock.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! - `create_handling_unit`: Create an empty carton or pallet.
+//! - `pack_item`: Pack a serialized item into a handling unit.
+//! - `unpack_item`: Take a serialized item out of a handling unit.
+//! - `pack_unit`: Pack a handling unit into another.
+//! - `unpack_unit`: Take a handling unit out of the unit it is packed into.
+//! - `create_manifest`: Generate a shipping manifest from packed handling units.
+//!
+//! ## Storage
+//!
+//! - `HandlingUnits`: Handling units, and the account that owns them, by ID.
+//! - `NextHandlingUnitId`: The serial reference assigned to the next handling unit.
+//! - `PackedItems`: The handling unit each packed item is in, until it ships.
+//! - `Manifests`: Shipping manifests, and the account that created them, by ID.
+//! - `NextManifestId`: The ID assigned to the next shipping manifest.
+//!
+//! ## Events
+//!
+//! - `HandlingUnitCreated`: Emitted when a handling unit is created.
+//! - `ItemPacked`: Emitted when an item is packed into a handling unit.
+//! - `ItemUnpacked`: Emitted when an item is taken out of a handling unit.
+//! - `UnitPacked`: Emitted when a handling unit is packed into another.
+//! - `UnitUnpacked`: Emitted when a handling unit is taken out of another.
+//! - `ManifestCreated`: Emitted when a shipping manifest is generated.
+//!
+//! ## Errors
+//!
+//! - `HandlingUnitNotFound`: The specified handling unit could not be located.
+//! - `NotHandlingUnitOwner`: Only the account that created the handling unit can use it.
+//! - `HandlingUnitShipped`: The handling unit has already shipped.
+//! - `HandlingUnitFull`: The handling unit cannot hold any more contents.
+//! - `ItemAlreadyPacked`: The item is already packed into a handling unit.
+//! - `ItemNotPacked`: The item is not packed into the handling unit.
+//! - `UnitAlreadyPacked`: The handling unit is already packed into another.
+//! - `UnitNotPacked`: The handling unit is not packed into the given unit.
+//! - `CircularPacking`: A handling unit cannot be packed into itself or its own contents.
+//! - `NotTopLevelUnit`: Only units not packed into another can be listed on a manifest.
+//! - `NoManifestUnits`: The manifest lists no handling units.
+//! - `DuplicateManifestUnit`: A handling unit is listed on the manifest twice.
+//! - `TooManyManifestLines`: The units hold more items than a manifest can list.
+//! - `ItemNotPackable`: Stock staged, awaiting review or scrapped cannot be packed.
+//! - `ItemNotAllocated`: A listed item is not wholly allocated to the manifest's sales order.
+//!
+//! This pallet uses `no_std` for compatibility with Wasm environments, a polkadot standard.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub use pallet::*;
+
+// FRAME pallets require their own "mock runtimes" to be able to run unit tests. This module
+// contains a mock runtime specific for testing this pallet's functionality.
+#[cfg(test)]
+mod mock;
+
+// This module contains the unit tests for this pallet.
+// Learn about pallet unit testing here: https://docs.substrate.io/test/unit-testing/
+#[cfg(test)]
+mod tests;
+
+mod blogic;
+pub mod types;
+
+use crate::types::{
+    Carrier, HandlingUnitId, HandlingUnitKind, ManifestId, ShippingManifest, Sscc,
+    MAX_MANIFEST_LINES, MAX_MANIFEST_UNITS,
+};
+use pallet_inventory::types::{Employee, SerialNumber, Sku, WeightLbs};
+use pallet_sales::types::SalesOrderNumber;
+
+#[frame_support::pallet]
+pub mod pallet {
+    use super::*;
+    use crate::types::HandlingUnit;
+    use frame_support::pallet_prelude::*;
+    use frame_system::pallet_prelude::*;
+    use pallet_sales::pallet::Config as SalesConfig;
+
+    #[pallet::pallet]
+    pub struct Pallet<T>(_);
+
+    /// The pallet's configuration trait.
+    ///
+    /// This trait is tightly coupled with the Sales pallet -> [SalesConfig]
+    #[pallet::config]
+    pub trait Config: frame_system::Config + SalesConfig {
+        type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+        /// The 7-digit GS1 company prefix SSCCs are issued under
+        #[pallet::constant]
+        type CompanyPrefix: Get<u64>;
+    }
+
+    /// Handling Units Storage
+    ///
+    /// Handling units by ID, with the account whose inventory is packed into them
+    #[pallet::storage]
+    pub type HandlingUnits<T: Config> =
+        StorageMap<_, Twox64Concat, HandlingUnitId, (T::AccountId, HandlingUnit), OptionQuery>;
+
+    /// The serial reference assigned to the next handling unit
+    #[pallet::storage]
+    pub type NextHandlingUnitId<T: Config> = StorageValue<_, HandlingUnitId, ValueQuery>;
+
+    /// Packed Items Storage
+    ///
+    /// The handling unit each packed item is in, so that no item is packed twice
+    #[pallet::storage]
+    pub type PackedItems<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, T::AccountId>,
+            NMapKey<Blake2_128Concat, Sku>,
+            NMapKey<Blake2_128Concat, SerialNumber>,
+        ),
+        HandlingUnitId,
+        OptionQuery,
+    >;
+
+    /// Manifests Storage
+    ///
+    /// Shipping manifests by ID, with the account that shipped them
+    #[pallet::storage]
+    pub type Manifests<T: Config> =
+        StorageMap<_, Twox64Concat, ManifestId, (T::AccountId, ShippingManifest), OptionQuery>;
+
+    /// The ID assigned to the next shipping manifest
+    #[pallet::storage]
+    pub type NextManifestId<T: Config> = StorageValue<_, ManifestId, ValueQuery>;
+
+    /// Events that functions in this pallet can emit.
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config> {
+        HandlingUnitCreated {
+            who: T::AccountId,
+            handling_unit_id: HandlingUnitId,
+            sscc: Sscc,
+        },
+        ItemPacked {
+            who: T::AccountId,
+            handling_unit_id: HandlingUnitId,
+            sku: Sku,
+            serial_number: SerialNumber,
+        },
+        ItemUnpacked {
+            who: T::AccountId,
+            handling_unit_id: HandlingUnitId,
+            sku: Sku,
+            serial_number: SerialNumber,
+        },
+        UnitPacked {
+            who: T::AccountId,
+            parent: HandlingUnitId,
+            child: HandlingUnitId,
+        },
+        UnitUnpacked {
+            who: T::AccountId,
+            parent: HandlingUnitId,
+            child: HandlingUnitId,
+        },
+        /// A shipping manifest has been generated and its units shipped
+        ManifestCreated {
+            who: T::AccountId,
+            manifest: ShippingManifest,
+        },
+    }
+
+    /// Errors that can be returned by this pallet.
+    #[pallet::error]
+    pub enum Error<T> {
+        /// The handling unit was not found
+        HandlingUnitNotFound,
+        /// The handling unit belongs to another account
+        NotHandlingUnitOwner,
+        /// The handling unit has already shipped
+        HandlingUnitShipped,
+        /// The handling unit cannot hold any more contents
+        HandlingUnitFull,
+        /// The item is already packed into a handling unit
+        ItemAlreadyPacked,
+        /// The item is not packed into the handling unit
+        ItemNotPacked,
+        /// The handling unit is already packed into another
+        UnitAlreadyPacked,
+        /// The handling unit is not packed into the given unit
+        UnitNotPacked,
+        /// A handling unit cannot be packed into itself or its own contents
+        CircularPacking,
+        /// The handling unit is packed into another unit
+        NotTopLevelUnit,
+        /// The manifest lists no handling units
+        NoManifestUnits,
+        /// A handling unit is listed on the manifest more than once
+        DuplicateManifestUnit,
+        /// The units hold more items than a manifest can list
+        TooManyManifestLines,
+        /// Stock staged for a work order, awaiting review or scrapped cannot be packed
+        ItemNotPackable,
+        /// Only stock allocated to a sales order, to the manifest's if it names one, can ship
+        ItemNotAllocated,
+    }
+
+    /// The pallet's dispatchable functions
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Create an empty carton or pallet
+        #[pallet::call_index(0)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn create_handling_unit(
+            origin: OriginFor<T>,
+            kind: HandlingUnitKind,
+            tare_weight: WeightLbs,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let handling_unit = Self::do_create_handling_unit(&who, kind, tare_weight);
+
+            Self::deposit_event(Event::HandlingUnitCreated {
+                who,
+                handling_unit_id: handling_unit.handling_unit_id,
+                sscc: handling_unit.sscc,
+            });
+
+            Ok(())
+        }
+
+        /// Pack a serialized item into a handling unit, moving it to packaging
+        #[pallet::call_index(1)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn pack_item(
+            origin: OriginFor<T>,
+            handling_unit_id: HandlingUnitId,
+            sku: Sku,
+            serial_number: SerialNumber,
+            packed_by: Employee,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            Self::do_pack_item(
+                &who,
+                handling_unit_id,
+                sku.clone(),
+                serial_number,
+                packed_by,
+            )?;
+
+            Self::deposit_event(Event::ItemPacked {
+                who,
+                handling_unit_id,
+                sku,
+                serial_number,
+            });
+
+            Ok(())
+        }
+
+        /// Take a serialized item out of a handling unit
+        ///
+        /// The item stays in packaging.
+        #[pallet::call_index(2)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn unpack_item(
+            origin: OriginFor<T>,
+            handling_unit_id: HandlingUnitId,
+            sku: Sku,
+            serial_number: SerialNumber,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            Self::do_unpack_item(&who, handling_unit_id, sku.clone(), serial_number)?;
+
+            Self::deposit_event(Event::ItemUnpacked {
+                who,
+                handling_unit_id,
+                sku,
+                serial_number,
+            });
+
+            Ok(())
+        }
+
+        /// Pack a handling unit into another, e.g. a carton onto a pallet
+        #[pallet::call_index(3)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn pack_unit(
+            origin: OriginFor<T>,
+            parent: HandlingUnitId,
+            child: HandlingUnitId,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            Self::do_pack_unit(&who, parent, child)?;
+
+            Self::deposit_event(Event::UnitPacked { who, parent, child });
+
+            Ok(())
+        }
+
+        /// Take a handling unit out of the unit it is packed into
+        #[pallet::call_index(4)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn unpack_unit(
+            origin: OriginFor<T>,
+            parent: HandlingUnitId,
+            child: HandlingUnitId,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            Self::do_unpack_unit(&who, parent, child)?;
+
+            Self::deposit_event(Event::UnitUnpacked { who, parent, child });
+
+            Ok(())
+        }
+
+        /// Generate a shipping manifest from packed handling units
+        ///
+        /// Lists every item in the units at any level, and ships the units and their items. Only
+        /// stock allocated to a sales order can ship, left for its shipment confirmation to draw
+        /// down.
+        #[pallet::call_index(5)]
+        #[pallet::weight(
+            Weight::from_parts(10_000, 0)
+                + T::DbWeight::get().reads_writes(2, 2)
+                + T::DbWeight::get().reads_writes(1, 1).saturating_mul(MAX_MANIFEST_UNITS as u64)
+                + T::DbWeight::get().reads_writes(6, 8).saturating_mul(MAX_MANIFEST_LINES as u64)
+        )]
+        pub fn create_manifest(
+            origin: OriginFor<T>,
+            units: BoundedVec<HandlingUnitId, ConstU32<MAX_MANIFEST_UNITS>>,
+            so_number: Option<SalesOrderNumber>,
+            carrier: Carrier,
+            shipped_by: Employee,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let manifest = Self::do_create_manifest(&who, units, so_number, carrier, shipped_by)?;
+
+            Self::deposit_event(Event::ManifestCreated { who, manifest });
+
+            Ok(())
+        }
+    }
+}
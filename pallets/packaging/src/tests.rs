@@ -0,0 +1,605 @@
+use crate::{mock::*, types::*, Error, Event};
+use frame_support::pallet_prelude::ConstU32;
+use frame_support::{assert_noop, assert_ok, BoundedVec};
+use pallet_inventory::types::{
+    AdjustDetails, Employee, HoldSource, InventoryType, Item, Location, Qty, SerialNumber, Sku,
+    WeightLbs,
+};
+use pallet_sales::types::{Customer, SalesOrderLine};
+
+const ALICE: u64 = 1;
+const BOB: u64 = 2;
+const COMPANY_PREFIX: u64 = 614141;
+
+fn sku(sku: &str) -> Sku {
+    BoundedVec::truncate_from(sku.as_bytes().to_vec())
+}
+
+fn carrier() -> Carrier {
+    BoundedVec::truncate_from(b"Freight Co".to_vec())
+}
+
+/// Stock two finished widgets of lot 9 under the serial, weighing three pounds each
+fn stock_widgets(serial_number: SerialNumber) {
+    assert_ok!(Inventory::inventory_insertion(
+        RuntimeOrigin::signed(ALICE),
+        Item {
+            sku: sku("WIDGET"),
+            lot_number: 9,
+            serial_number,
+            qty: 2,
+            weight: 3,
+            inventory_type: InventoryType::FinishedGood,
+            ..Default::default()
+        }
+    ));
+}
+
+/// Take a sales order 0 from Alice for the widgets in stock, allocate it and pick it
+fn allocate_widgets(qty: Qty) {
+    assert_ok!(Sales::add_customer(
+        RuntimeOrigin::root(),
+        Customer::default()
+    ));
+    assert_ok!(Sales::create_sales_order(
+        RuntimeOrigin::signed(ALICE),
+        0,
+        0,
+        BoundedVec::truncate_from(vec![SalesOrderLine {
+            sku: sku("WIDGET"),
+            qty,
+            ..Default::default()
+        }])
+    ));
+    assert_ok!(Sales::allocate_sales_order(RuntimeOrigin::signed(ALICE), 0));
+    assert_ok!(Sales::pick_sales_order(
+        RuntimeOrigin::signed(ALICE),
+        0,
+        Employee::Bob
+    ));
+}
+
+/// Create a handling unit for Alice, returning its ID
+fn create_unit(kind: HandlingUnitKind, tare_weight: WeightLbs) -> HandlingUnitId {
+    let handling_unit_id = crate::NextHandlingUnitId::<Test>::get();
+    assert_ok!(Packaging::create_handling_unit(
+        RuntimeOrigin::signed(ALICE),
+        kind,
+        tare_weight
+    ));
+    handling_unit_id
+}
+
+fn pack(handling_unit_id: HandlingUnitId, serial_number: SerialNumber) {
+    assert_ok!(Packaging::pack_item(
+        RuntimeOrigin::signed(ALICE),
+        handling_unit_id,
+        sku("WIDGET"),
+        serial_number,
+        Employee::Bob
+    ));
+}
+
+fn unit(handling_unit_id: HandlingUnitId) -> HandlingUnit {
+    crate::HandlingUnits::<Test>::get(handling_unit_id)
+        .unwrap()
+        .1
+}
+
+fn units(units: Vec<HandlingUnitId>) -> BoundedVec<HandlingUnitId, ConstU32<MAX_MANIFEST_UNITS>> {
+    BoundedVec::truncate_from(units)
+}
+
+/// A carton holding widgets 1 packed on a pallet next to widgets 2, all allocated to sales
+/// order 0
+fn set_up_pallet() -> (HandlingUnitId, HandlingUnitId) {
+    stock_widgets(1);
+    stock_widgets(2);
+    allocate_widgets(4);
+    let carton = create_unit(HandlingUnitKind::Carton, 1);
+    let pallet = create_unit(HandlingUnitKind::Pallet, 20);
+    pack(carton, 1);
+    pack(pallet, 2);
+    assert_ok!(Packaging::pack_unit(
+        RuntimeOrigin::signed(ALICE),
+        pallet,
+        carton
+    ));
+    (carton, pallet)
+}
+
+#[test]
+fn ssccs_carry_a_gs1_check_digit() {
+    assert_eq!(&sscc(1, 614141, 123456789), b"106141411234567897");
+    assert_eq!(&sscc(0, 614141, 123456789), b"006141411234567890");
+    // Serial references beyond nine digits are truncated
+    assert_eq!(sscc(0, 614141, 1_000_000_001), sscc(0, 614141, 1));
+}
+
+#[test]
+fn handling_units_take_the_next_serial_reference() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(create_unit(HandlingUnitKind::Carton, 1), 0);
+        System::assert_last_event(
+            Event::HandlingUnitCreated {
+                who: ALICE,
+                handling_unit_id: 0,
+                sscc: sscc(0, COMPANY_PREFIX, 0),
+            }
+            .into(),
+        );
+
+        assert_eq!(create_unit(HandlingUnitKind::Pallet, 20), 1);
+        let pallet = unit(1);
+        assert_eq!(pallet.sscc, sscc(1, COMPANY_PREFIX, 1));
+        assert_eq!(pallet.gross_weight, 20);
+        assert_eq!(pallet.status, HandlingUnitStatus::Open);
+        assert_eq!(pallet.created_at, 1);
+    });
+}
+
+#[test]
+fn packing_an_item_moves_it_to_packaging_and_adds_its_weight() {
+    new_test_ext().execute_with(|| {
+        stock_widgets(1);
+        let carton = create_unit(HandlingUnitKind::Carton, 1);
+
+        pack(carton, 1);
+
+        assert_eq!(unit(carton).gross_weight, 7);
+        assert_eq!(
+            unit(carton).contents.into_inner(),
+            vec![PackedContent::Item {
+                sku: sku("WIDGET"),
+                serial_number: 1,
+                weight: 6,
+            }]
+        );
+        assert_eq!(
+            pallet_inventory::Inventory::<Test>::get((ALICE, sku("WIDGET"), 1))
+                .unwrap()
+                .location,
+            Location::Packaging
+        );
+        assert_eq!(
+            crate::PackedItems::<Test>::get((ALICE, sku("WIDGET"), 1)),
+            Some(carton)
+        );
+        System::assert_last_event(
+            Event::ItemPacked {
+                who: ALICE,
+                handling_unit_id: carton,
+                sku: sku("WIDGET"),
+                serial_number: 1,
+            }
+            .into(),
+        );
+
+        assert_noop!(
+            Packaging::pack_item(
+                RuntimeOrigin::signed(ALICE),
+                carton,
+                sku("WIDGET"),
+                1,
+                Employee::Bob
+            ),
+            Error::<Test>::ItemAlreadyPacked
+        );
+
+        assert_ok!(Packaging::unpack_item(
+            RuntimeOrigin::signed(ALICE),
+            carton,
+            sku("WIDGET"),
+            1
+        ));
+        assert_eq!(unit(carton).gross_weight, 1);
+        assert!(unit(carton).contents.is_empty());
+        assert_eq!(
+            crate::PackedItems::<Test>::get((ALICE, sku("WIDGET"), 1)),
+            None
+        );
+        assert_noop!(
+            Packaging::unpack_item(RuntimeOrigin::signed(ALICE), carton, sku("WIDGET"), 1),
+            Error::<Test>::ItemNotPacked
+        );
+    });
+}
+
+#[test]
+fn packed_items_stay_as_packed_until_unpacked() {
+    new_test_ext().execute_with(|| {
+        stock_widgets(1);
+        let carton = create_unit(HandlingUnitKind::Carton, 1);
+        pack(carton, 1);
+
+        assert!(pallet_inventory::QualityHolds::<Test>::contains_key((
+            ALICE,
+            sku("WIDGET"),
+            1,
+            HoldSource::Packing(carton)
+        )));
+        let move_out = || {
+            Inventory::inventory_move(
+                RuntimeOrigin::signed(ALICE),
+                sku("WIDGET"),
+                1,
+                Employee::Bob,
+                AdjustDetails::Location {
+                    original_location: Location::Packaging,
+                    new_location: Location::Warehouse,
+                    reason: BoundedVec::truncate_from(b"Return to stock".to_vec()),
+                },
+            )
+        };
+        assert_noop!(move_out(), pallet_inventory::Error::<Test>::ItemOnHold);
+        assert_noop!(
+            Inventory::inventory_adjust(
+                RuntimeOrigin::signed(ALICE),
+                Employee::Bob,
+                sku("WIDGET"),
+                1,
+                AdjustDetails::Quantity {
+                    original_qty: 2,
+                    new_qty: 5,
+                    reason: BoundedVec::truncate_from(b"Cycle count".to_vec()),
+                }
+            ),
+            pallet_inventory::Error::<Test>::ItemOnHold
+        );
+
+        assert_ok!(Packaging::unpack_item(
+            RuntimeOrigin::signed(ALICE),
+            carton,
+            sku("WIDGET"),
+            1
+        ));
+        assert_ok!(move_out());
+    });
+}
+
+#[test]
+fn items_are_packed_by_the_unit_owner_from_their_inventory() {
+    new_test_ext().execute_with(|| {
+        stock_widgets(1);
+        let carton = create_unit(HandlingUnitKind::Carton, 1);
+
+        assert_noop!(
+            Packaging::pack_item(
+                RuntimeOrigin::signed(BOB),
+                carton,
+                sku("WIDGET"),
+                1,
+                Employee::Bob
+            ),
+            Error::<Test>::NotHandlingUnitOwner
+        );
+        assert_noop!(
+            Packaging::pack_item(
+                RuntimeOrigin::signed(ALICE),
+                1,
+                sku("WIDGET"),
+                1,
+                Employee::Bob
+            ),
+            Error::<Test>::HandlingUnitNotFound
+        );
+        assert_noop!(
+            Packaging::pack_item(
+                RuntimeOrigin::signed(ALICE),
+                carton,
+                sku("WIDGET"),
+                2,
+                Employee::Bob
+            ),
+            pallet_inventory::Error::<Test>::InventoryNotFound
+        );
+
+        // Stock staged for a work order or awaiting review stays where it is
+        for location in [Location::Staging, Location::Mrb] {
+            let original_location =
+                pallet_inventory::Inventory::<Test>::get((ALICE, sku("WIDGET"), 1))
+                    .unwrap()
+                    .location;
+            assert_ok!(Inventory::inventory_move(
+                RuntimeOrigin::signed(ALICE),
+                sku("WIDGET"),
+                1,
+                Employee::Bob,
+                AdjustDetails::Location {
+                    original_location,
+                    new_location: location,
+                    reason: BoundedVec::truncate_from(b"Set aside".to_vec()),
+                }
+            ));
+            assert_noop!(
+                Packaging::pack_item(
+                    RuntimeOrigin::signed(ALICE),
+                    carton,
+                    sku("WIDGET"),
+                    1,
+                    Employee::Bob
+                ),
+                Error::<Test>::ItemNotPackable
+            );
+        }
+    });
+}
+
+#[test]
+fn nested_units_carry_their_weight_up_to_every_parent() {
+    new_test_ext().execute_with(|| {
+        let (carton, pallet) = set_up_pallet();
+
+        // 20 for the pallet, 6 for widgets 2 and 7 for the carton of widgets 1
+        assert_eq!(unit(pallet).gross_weight, 33);
+        assert_eq!(unit(carton).parent, Some(pallet));
+        System::assert_last_event(
+            Event::UnitPacked {
+                who: ALICE,
+                parent: pallet,
+                child: carton,
+            }
+            .into(),
+        );
+
+        stock_widgets(3);
+        pack(carton, 3);
+        assert_eq!(unit(carton).gross_weight, 13);
+        assert_eq!(unit(pallet).gross_weight, 39);
+
+        assert_ok!(Packaging::unpack_unit(
+            RuntimeOrigin::signed(ALICE),
+            pallet,
+            carton
+        ));
+        assert_eq!(unit(carton).parent, None);
+        assert_eq!(unit(pallet).gross_weight, 26);
+        assert_noop!(
+            Packaging::unpack_unit(RuntimeOrigin::signed(ALICE), pallet, carton),
+            Error::<Test>::UnitNotPacked
+        );
+    });
+}
+
+#[test]
+fn units_cannot_be_packed_into_themselves() {
+    new_test_ext().execute_with(|| {
+        let (carton, pallet) = set_up_pallet();
+
+        assert_noop!(
+            Packaging::pack_unit(RuntimeOrigin::signed(ALICE), carton, pallet),
+            Error::<Test>::CircularPacking
+        );
+        assert_noop!(
+            Packaging::pack_unit(RuntimeOrigin::signed(ALICE), pallet, pallet),
+            Error::<Test>::CircularPacking
+        );
+
+        let other_pallet = create_unit(HandlingUnitKind::Pallet, 20);
+        assert_noop!(
+            Packaging::pack_unit(RuntimeOrigin::signed(ALICE), other_pallet, carton),
+            Error::<Test>::UnitAlreadyPacked
+        );
+    });
+}
+
+#[test]
+fn manifests_list_every_item_against_its_top_level_unit() {
+    new_test_ext().execute_with(|| {
+        let (carton, pallet) = set_up_pallet();
+        let pallet_sscc = unit(pallet).sscc;
+
+        assert_ok!(Packaging::create_manifest(
+            RuntimeOrigin::signed(ALICE),
+            units(vec![pallet]),
+            None,
+            carrier(),
+            Employee::Bob
+        ));
+
+        let manifest = ShippingManifest {
+            manifest_id: 0,
+            so_number: None,
+            carrier: carrier(),
+            units: BoundedVec::truncate_from(vec![pallet_sscc]),
+            lines: BoundedVec::truncate_from(vec![
+                ManifestLine {
+                    sscc: pallet_sscc,
+                    sku: sku("WIDGET"),
+                    serial_number: 2,
+                    lot_number: 9,
+                    qty: 2,
+                },
+                ManifestLine {
+                    sscc: pallet_sscc,
+                    sku: sku("WIDGET"),
+                    serial_number: 1,
+                    lot_number: 9,
+                    qty: 2,
+                },
+            ]),
+            total_weight: 33,
+            created_at: 1,
+        };
+        assert_eq!(
+            crate::Manifests::<Test>::get(0),
+            Some((ALICE, manifest.clone()))
+        );
+        assert_eq!(crate::NextManifestId::<Test>::get(), 1);
+        System::assert_last_event(
+            Event::ManifestCreated {
+                who: ALICE,
+                manifest,
+            }
+            .into(),
+        );
+
+        // Shipped units are sealed, at every level
+        assert_eq!(unit(pallet).status, HandlingUnitStatus::Shipped);
+        assert_eq!(unit(carton).status, HandlingUnitStatus::Shipped);
+        assert_noop!(
+            Packaging::unpack_item(RuntimeOrigin::signed(ALICE), carton, sku("WIDGET"), 1),
+            Error::<Test>::HandlingUnitShipped
+        );
+
+        // Their items come out of packaging, left for the order's shipment confirmation
+        for serial_number in [1, 2] {
+            let item =
+                pallet_inventory::Inventory::<Test>::get((ALICE, sku("WIDGET"), serial_number))
+                    .unwrap();
+            assert_eq!(item.location, Location::Shipping);
+            assert_eq!(item.qty, 2);
+            assert!(!Inventory::is_on_hold(
+                &ALICE,
+                &sku("WIDGET"),
+                serial_number
+            ));
+            assert_eq!(
+                crate::PackedItems::<Test>::get((ALICE, sku("WIDGET"), serial_number)),
+                None
+            );
+        }
+        assert_eq!(
+            pallet_inventory::GlobalInventory::<Test>::get(sku("WIDGET")),
+            Some(4)
+        );
+
+        assert_ok!(Sales::pack_sales_order(RuntimeOrigin::signed(ALICE), 0));
+        assert_ok!(Sales::confirm_shipment(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            Employee::Bob
+        ));
+        assert_eq!(
+            pallet_inventory::GlobalInventory::<Test>::get(sku("WIDGET")),
+            Some(0)
+        );
+    });
+}
+
+#[test]
+fn manifests_only_ship_allocated_stock() {
+    new_test_ext().execute_with(|| {
+        // Widgets 1 are only partly allocated to the order
+        stock_widgets(1);
+        stock_widgets(2);
+        allocate_widgets(3);
+        let carton = create_unit(HandlingUnitKind::Carton, 1);
+        pack(carton, 1);
+        pack(carton, 2);
+
+        for so_number in [None, Some(0)] {
+            assert_noop!(
+                Packaging::create_manifest(
+                    RuntimeOrigin::signed(ALICE),
+                    units(vec![carton]),
+                    so_number,
+                    carrier(),
+                    Employee::Bob
+                ),
+                Error::<Test>::ItemNotAllocated
+            );
+        }
+
+        assert_ok!(Packaging::unpack_item(
+            RuntimeOrigin::signed(ALICE),
+            carton,
+            sku("WIDGET"),
+            1
+        ));
+        assert_ok!(Packaging::create_manifest(
+            RuntimeOrigin::signed(ALICE),
+            units(vec![carton]),
+            Some(0),
+            carrier(),
+            Employee::Bob
+        ));
+    });
+}
+
+#[test]
+fn manifests_ship_distinct_top_level_units() {
+    new_test_ext().execute_with(|| {
+        let (carton, pallet) = set_up_pallet();
+
+        assert_noop!(
+            Packaging::create_manifest(
+                RuntimeOrigin::signed(ALICE),
+                units(vec![]),
+                None,
+                carrier(),
+                Employee::Bob
+            ),
+            Error::<Test>::NoManifestUnits
+        );
+        assert_noop!(
+            Packaging::create_manifest(
+                RuntimeOrigin::signed(ALICE),
+                units(vec![pallet, pallet]),
+                None,
+                carrier(),
+                Employee::Bob
+            ),
+            Error::<Test>::DuplicateManifestUnit
+        );
+        assert_noop!(
+            Packaging::create_manifest(
+                RuntimeOrigin::signed(ALICE),
+                units(vec![carton]),
+                None,
+                carrier(),
+                Employee::Bob
+            ),
+            Error::<Test>::NotTopLevelUnit
+        );
+        assert_noop!(
+            Packaging::create_manifest(
+                RuntimeOrigin::signed(BOB),
+                units(vec![pallet]),
+                None,
+                carrier(),
+                Employee::Bob
+            ),
+            Error::<Test>::NotHandlingUnitOwner
+        );
+    });
+}
+
+#[test]
+fn manifests_fulfil_the_shippers_own_sales_orders() {
+    new_test_ext().execute_with(|| {
+        let (_, pallet) = set_up_pallet();
+
+        assert_noop!(
+            Packaging::create_manifest(
+                RuntimeOrigin::signed(ALICE),
+                units(vec![pallet]),
+                Some(1),
+                carrier(),
+                Employee::Bob
+            ),
+            pallet_sales::Error::<Test>::SalesOrderNotFound
+        );
+
+        assert_ok!(Sales::create_sales_order(
+            RuntimeOrigin::signed(BOB),
+            1,
+            0,
+            BoundedVec::truncate_from(vec![SalesOrderLine {
+                sku: sku("WIDGET"),
+                qty: 4,
+                ..Default::default()
+            }])
+        ));
+        assert_noop!(
+            Packaging::create_manifest(
+                RuntimeOrigin::signed(ALICE),
+                units(vec![pallet]),
+                Some(1),
+                carrier(),
+                Employee::Bob
+            ),
+            pallet_sales::Error::<Test>::NotSalesOrderOwner
+        );
+    });
+}
@@ -0,0 +1,134 @@
+use codec::{Decode, Encode};
+use frame_support::pallet_prelude::{ConstU32, MaxEncodedLen};
+use frame_support::BoundedVec;
+use pallet_inventory::types::{LotNumber, Qty, SerialNumber, Sku, WeightLbs};
+use pallet_sales::types::SalesOrderNumber;
+use scale_info::TypeInfo;
+
+/// Serial reference of a handling unit, unique across the chain
+pub type HandlingUnitId = u64;
+pub type ManifestId = u32;
+/// Serial Shipping Container Code, as 18 ASCII digits
+pub type Sscc = [u8; 18];
+pub type Carrier = BoundedVec<u8, ConstU32<32>>;
+
+/// Most items and units a single handling unit can contain
+pub const MAX_UNIT_CONTENTS: u32 = 100;
+/// Most top-level units a single manifest can ship
+pub const MAX_MANIFEST_UNITS: u32 = 50;
+/// Most items a single manifest can list
+pub const MAX_MANIFEST_LINES: u32 = 500;
+
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
+pub enum HandlingUnitKind {
+    #[default]
+    Carton,
+    Pallet,
+}
+
+impl HandlingUnitKind {
+    /// SSCC extension digit identifying the kind of unit
+    pub fn extension_digit(&self) -> u8 {
+        match self {
+            HandlingUnitKind::Carton => 0,
+            HandlingUnitKind::Pallet => 1,
+        }
+    }
+}
+
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
+pub enum HandlingUnitStatus {
+    #[default]
+    Open,
+    /// Listed on a shipping manifest, can no longer be packed or unpacked
+    Shipped,
+}
+
+/// Something packed into a handling unit
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub enum PackedContent {
+    /// A serialized inventory item, with the weight it added when packed
+    Item {
+        sku: Sku,
+        serial_number: SerialNumber,
+        weight: WeightLbs,
+    },
+    /// Another handling unit, e.g. a carton on a pallet
+    Unit(HandlingUnitId),
+}
+
+/// A carton or pallet holding serialized items or other handling units
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct HandlingUnit {
+    pub handling_unit_id: HandlingUnitId,
+    pub sscc: Sscc,
+    pub kind: HandlingUnitKind,
+    /// The unit this one is packed into, if any
+    pub parent: Option<HandlingUnitId>,
+    pub contents: BoundedVec<PackedContent, ConstU32<MAX_UNIT_CONTENTS>>,
+    /// Weight of the empty unit
+    pub tare_weight: WeightLbs,
+    /// Tare weight plus everything packed into the unit, at any level
+    pub gross_weight: WeightLbs,
+    pub status: HandlingUnitStatus,
+    pub created_at: u32,
+}
+
+/// An item shipped on a manifest, with the top-level unit it ships in
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct ManifestLine {
+    pub sscc: Sscc,
+    pub sku: Sku,
+    pub serial_number: SerialNumber,
+    pub lot_number: LotNumber,
+    pub qty: Qty,
+}
+
+/// The contents of a shipment, generated from its packed handling units
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct ShippingManifest {
+    pub manifest_id: ManifestId,
+    /// The sales order the shipment fulfils, if any
+    pub so_number: Option<SalesOrderNumber>,
+    pub carrier: Carrier,
+    /// SSCCs of the top-level units shipped
+    pub units: BoundedVec<Sscc, ConstU32<MAX_MANIFEST_UNITS>>,
+    pub lines: BoundedVec<ManifestLine, ConstU32<MAX_MANIFEST_LINES>>,
+    pub total_weight: WeightLbs,
+    pub created_at: u32,
+}
+
+/// Build an SSCC from its extension digit, a 7-digit GS1 company prefix and a serial reference.
+///
+/// The serial reference is truncated to the 9 digits left between the prefix and the check
+/// digit, which is calculated with the GS1 mod-10 algorithm.
+pub fn sscc(extension_digit: u8, company_prefix: u64, serial_reference: u64) -> Sscc {
+    let body: u64 = (extension_digit as u64 % 10) * 10u64.pow(16)
+        + (company_prefix % 10u64.pow(7)) * 10u64.pow(9)
+        + serial_reference % 10u64.pow(9);
+
+    let mut digits = [b'0'; 18];
+    let mut remaining = body;
+    for digit in digits[..17].iter_mut().rev() {
+        *digit = b'0' + (remaining % 10) as u8;
+        remaining /= 10;
+    }
+
+    // Weights alternate 3, 1, ... from the rightmost digit of the body
+    let sum: u32 = digits[..17]
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(position, digit)| {
+            let value = (digit - b'0') as u32;
+            if position % 2 == 0 {
+                value * 3
+            } else {
+                value
+            }
+        })
+        .sum();
+    digits[17] = b'0' + ((10 - sum % 10) % 10) as u8;
+
+    digits
+}
@@ -0,0 +1,547 @@
+use crate::pallet::{
+    Config, Error, NextPickListId, NextWaveId, PickDiscrepancies, PickLists, ReservedSerials,
+    SourcePickLists, Waves,
+};
+use crate::{pallet::Pallet, types::*};
+use frame_support::ensure;
+use frame_support::pallet_prelude::ConstU32;
+use frame_support::sp_runtime::DispatchError;
+use frame_support::BoundedVec;
+use pallet_assembly::types::{StagedItem, StagingKit, WorkOrderNumber, WorkOrderStatus};
+use pallet_assembly::WorkOrders;
+use pallet_inventory::types::{
+    AdjustDetails, Employee, IssueMode, Item, Location, PickStrategy, Qty, SerialNumber, Sku,
+    StockAllocations,
+};
+use pallet_inventory::Inventory;
+use pallet_sales::types::{AllocatedItem, SalesOrderNumber, SalesOrderStatus};
+use pallet_sales::{SalesOrderAllocations, SalesOrders};
+use scale_info::prelude::vec::Vec;
+
+impl<T: Config> Pallet<T> {
+    /// Generate a pick list for a sales order's allocation, allocating an open order first
+    pub fn do_create_sales_pick_list(
+        who: &T::AccountId,
+        so_number: SalesOrderNumber,
+        strategy: Option<PickStrategy>,
+    ) -> Result<PickList, DispatchError> {
+        let source = PickSource::SalesOrder(so_number);
+        ensure!(
+            !SourcePickLists::<T>::contains_key(&source),
+            Error::<T>::PickListExists
+        );
+
+        let (owner, sales_order) =
+            SalesOrders::<T>::get(so_number).ok_or(pallet_sales::Error::<T>::SalesOrderNotFound)?;
+        ensure!(&owner == who, pallet_sales::Error::<T>::NotSalesOrderOwner);
+
+        let allocation = match sales_order.status {
+            SalesOrderStatus::Open => pallet_sales::Pallet::<T>::do_allocate_sales_order(
+                who,
+                so_number,
+                strategy.as_ref(),
+            )?,
+            SalesOrderStatus::Allocated => {
+                SalesOrderAllocations::<T>::get(so_number).unwrap_or_default()
+            }
+            _ => return Err(pallet_sales::Error::<T>::InvalidSalesOrderStatus.into()),
+        };
+
+        let mut lines: BoundedVec<PickLine, ConstU32<MAX_PICK_LINES>> = BoundedVec::default();
+        for allocated in allocation.iter() {
+            let item = Inventory::<T>::get((who, allocated.sku.clone(), allocated.serial_number))
+                .ok_or(pallet_inventory::Error::<T>::InventoryNotFound)?;
+            lines
+                .try_push(Self::pick_line(&item, allocated.qty, allocated.line_index))
+                .map_err(|_| Error::<T>::TooManyPickLines)?;
+        }
+
+        Ok(Self::insert_pick_list(who, source, strategy, lines))
+    }
+
+    /// Generate a pick list for the pre-kitted components of a released work order
+    ///
+    /// The chosen stock is reserved against other kitting lists until it is picked.
+    pub fn do_create_kitting_pick_list(
+        who: &T::AccountId,
+        work_order_number: WorkOrderNumber,
+        strategy: Option<PickStrategy>,
+    ) -> Result<PickList, DispatchError> {
+        let source = PickSource::WorkOrder(work_order_number);
+        ensure!(
+            !SourcePickLists::<T>::contains_key(&source),
+            Error::<T>::PickListExists
+        );
+
+        let work_order = WorkOrders::<T>::get(work_order_number)
+            .ok_or(pallet_assembly::Error::<T>::WorkOrderNotFound)?;
+        ensure!(
+            work_order.status == WorkOrderStatus::Released,
+            pallet_assembly::Error::<T>::WorkOrderNotReleased
+        );
+        let recipe = pallet_assembly::Pallet::<T>::work_order_recipe(&work_order)?;
+
+        let mut lines: BoundedVec<PickLine, ConstU32<MAX_PICK_LINES>> = BoundedVec::default();
+        for (component_index, component) in recipe.required_components.iter().enumerate() {
+            if component.issue_mode != IssueMode::PreKitted {
+                continue;
+            }
+
+            let required_qty = work_order.required_qty(&recipe, component);
+            let picked = pallet_inventory::Pallet::<T>::pick_stock(
+                Self::kitting_candidates(who, &component.sku, &lines),
+                required_qty,
+                &Self::strategy(strategy.as_ref(), &component.sku),
+                &Location::Staging,
+            );
+
+            let picked_qty = picked
+                .iter()
+                .fold(0 as Qty, |total, (_, qty)| total.saturating_add(*qty));
+            ensure!(
+                picked_qty >= required_qty,
+                pallet_assembly::Error::<T>::InsufficientInventory
+            );
+
+            for (item, qty) in picked {
+                lines
+                    .try_push(Self::pick_line(&item, qty, component_index as u8))
+                    .map_err(|_| Error::<T>::TooManyPickLines)?;
+            }
+        }
+
+        let pick_list = Self::insert_pick_list(who, source, strategy, lines);
+        for line in pick_list.lines.iter() {
+            ReservedSerials::<T>::insert(
+                (who, line.sku.clone(), line.serial_number),
+                pick_list.pick_list_id,
+            );
+        }
+
+        Ok(pick_list)
+    }
+
+    /// Group open pick lists into a wave
+    pub fn do_create_wave(
+        who: &T::AccountId,
+        pick_lists: BoundedVec<PickListId, ConstU32<MAX_WAVE_LISTS>>,
+    ) -> Result<Wave, DispatchError> {
+        ensure!(!pick_lists.is_empty(), Error::<T>::NoPickLists);
+
+        let wave_id = NextWaveId::<T>::get();
+
+        for (index, pick_list_id) in pick_lists.iter().enumerate() {
+            ensure!(
+                !pick_lists[..index].contains(pick_list_id),
+                Error::<T>::DuplicatePickList
+            );
+
+            let mut pick_list = Self::open_pick_list(who, *pick_list_id)?;
+            ensure!(pick_list.wave.is_none(), Error::<T>::AlreadyInWave);
+
+            pick_list.wave = Some(wave_id);
+            PickLists::<T>::insert(pick_list_id, (who, pick_list));
+        }
+
+        NextWaveId::<T>::put(wave_id.saturating_add(1));
+
+        let wave = Wave {
+            wave_id,
+            pick_lists,
+            status: WaveStatus::Open,
+            created_at: pallet_inventory::Pallet::<T>::current_block(),
+        };
+        Waves::<T>::insert(wave_id, (who, wave.clone()));
+
+        Ok(wave)
+    }
+
+    /// Confirm the quantity picked for a line, moving the item to the list's destination.
+    ///
+    /// Only the picked quantity moves: picking part of an item splits it off to a new serial
+    /// first, which the line then records.
+    ///
+    /// Picking less than the line asks for records the shortfall as a discrepancy on the item,
+    /// leaving its quantity to be corrected through an approved inventory adjustment, and adds
+    /// replacement stock to the list, chosen as the list's stock was. Once no line is left
+    /// open the list completes, marking its sales order picked or staging its work order.
+    /// Returns the updated list, and the list's wave if it completed with it.
+    pub fn do_confirm_pick(
+        who: &T::AccountId,
+        pick_list_id: PickListId,
+        line_index: PickLineIndex,
+        picked_qty: Qty,
+        picked_by: Employee,
+    ) -> Result<(PickList, Option<WaveId>), DispatchError> {
+        let mut pick_list = Self::open_pick_list(who, pick_list_id)?;
+
+        let line = pick_list
+            .lines
+            .get(line_index as usize)
+            .cloned()
+            .ok_or(Error::<T>::LineNotFound)?;
+        ensure!(
+            line.status == PickLineStatus::Open,
+            Error::<T>::LineAlreadyConfirmed
+        );
+        ensure!(picked_qty <= line.qty, Error::<T>::OverPick);
+
+        let short_qty = line.qty - picked_qty;
+        let destination = pick_list.source.destination();
+        let mut picked_serial = line.serial_number;
+
+        match Inventory::<T>::get((who, line.sku.clone(), line.serial_number)) {
+            // The item may already be at the destination, picked for another line
+            Some(item) if item.location == line.from_location || item.location == destination => {
+                if short_qty > 0 {
+                    PickDiscrepancies::<T>::mutate(
+                        (who, line.sku.clone(), line.serial_number),
+                        |discrepancy| {
+                            *discrepancy =
+                                Some(discrepancy.unwrap_or_default().saturating_add(short_qty))
+                        },
+                    );
+                }
+
+                // Fails if the item is on quality hold
+                if picked_qty > 0 && item.location != destination {
+                    let picked = if picked_qty < item.qty {
+                        Self::split_pick(who, &pick_list.source, &line, &item, picked_qty)?
+                    } else {
+                        item
+                    };
+
+                    pallet_inventory::Pallet::<T>::do_inventory_move(
+                        who,
+                        picked.clone(),
+                        picked_by.clone(),
+                        AdjustDetails::Location {
+                            original_location: picked.location.clone(),
+                            new_location: destination.clone(),
+                            reason: pallet_inventory::Pallet::<T>::reason("Pick")?,
+                        },
+                    )?;
+                    picked_serial = picked.serial_number;
+                }
+            }
+            // Nothing to correct if the item is gone, as long as nothing was picked from it
+            _ => ensure!(picked_qty == 0, Error::<T>::ItemMoved),
+        }
+
+        ReservedSerials::<T>::remove((who, line.sku.clone(), line.serial_number));
+
+        let confirmed = &mut pick_list.lines[line_index as usize];
+        confirmed.serial_number = picked_serial;
+        confirmed.picked_qty = picked_qty;
+        confirmed.status = if short_qty > 0 {
+            PickLineStatus::Short
+        } else {
+            PickLineStatus::Picked
+        };
+
+        if short_qty > 0 {
+            Self::replace_short_pick(who, &mut pick_list, &line, short_qty)?;
+        }
+
+        if pick_list
+            .lines
+            .iter()
+            .all(|line| line.status != PickLineStatus::Open)
+        {
+            Self::complete_pick_list(who, &mut pick_list, &picked_by)?;
+        }
+
+        PickLists::<T>::insert(pick_list_id, (who, pick_list.clone()));
+
+        let completed_wave = pick_list.wave.filter(|wave_id| {
+            pick_list.status == PickListStatus::Completed && Self::complete_wave(*wave_id)
+        });
+
+        Ok((pick_list, completed_wave))
+    }
+
+    /// Split the picked quantity off an item, leaving the rest where it is
+    ///
+    /// A sales order's allocation follows the picked quantity to the new serial.
+    fn split_pick(
+        who: &T::AccountId,
+        source: &PickSource,
+        line: &PickLine,
+        item: &Item,
+        picked_qty: Qty,
+    ) -> Result<Item, DispatchError> {
+        let split = pallet_inventory::Pallet::<T>::do_split_item(who, item, picked_qty)?;
+
+        if let PickSource::SalesOrder(so_number) = source {
+            pallet_sales::Pallet::<T>::do_reduce_allocation(
+                who,
+                *so_number,
+                &AllocatedItem {
+                    line_index: line.source_line,
+                    sku: line.sku.clone(),
+                    serial_number: line.serial_number,
+                    qty: picked_qty,
+                },
+            )?;
+            pallet_sales::Pallet::<T>::do_add_allocation(
+                who,
+                *so_number,
+                AllocatedItem {
+                    line_index: line.source_line,
+                    sku: split.sku.clone(),
+                    serial_number: split.serial_number,
+                    qty: picked_qty,
+                },
+            )?;
+        }
+
+        Ok(split)
+    }
+
+    /// Add replacement stock for a short pick to the list, recording whatever is not covered
+    fn replace_short_pick(
+        who: &T::AccountId,
+        pick_list: &mut PickList,
+        line: &PickLine,
+        short_qty: Qty,
+    ) -> Result<(), DispatchError> {
+        let candidates = match pick_list.source {
+            PickSource::SalesOrder(so_number) => {
+                pallet_sales::Pallet::<T>::do_reduce_allocation(
+                    who,
+                    so_number,
+                    &AllocatedItem {
+                        line_index: line.source_line,
+                        sku: line.sku.clone(),
+                        serial_number: line.serial_number,
+                        qty: short_qty,
+                    },
+                )?;
+
+                pallet_sales::Pallet::<T>::allocatable_items(who, &line.sku)
+                    .into_iter()
+                    .filter(|(item, _)| {
+                        !Self::on_list(&pick_list.lines, item) && !Self::has_discrepancy(who, item)
+                    })
+                    .collect()
+            }
+            PickSource::WorkOrder(_) => Self::kitting_candidates(who, &line.sku, &pick_list.lines),
+        };
+
+        let mut covered_qty: Qty = 0;
+        for (item, qty) in pallet_inventory::Pallet::<T>::pick_stock(
+            candidates,
+            short_qty,
+            &Self::strategy(pick_list.strategy.as_ref(), &line.sku),
+            &pick_list.source.destination(),
+        ) {
+            match pick_list.source {
+                PickSource::SalesOrder(so_number) => {
+                    pallet_sales::Pallet::<T>::do_add_allocation(
+                        who,
+                        so_number,
+                        AllocatedItem {
+                            line_index: line.source_line,
+                            sku: item.sku.clone(),
+                            serial_number: item.serial_number,
+                            qty,
+                        },
+                    )?;
+                }
+                PickSource::WorkOrder(_) => {
+                    ReservedSerials::<T>::insert(
+                        (who, item.sku.clone(), item.serial_number),
+                        pick_list.pick_list_id,
+                    );
+                }
+            }
+
+            pick_list
+                .lines
+                .try_push(Self::pick_line(&item, qty, line.source_line))
+                .map_err(|_| Error::<T>::TooManyPickLines)?;
+            covered_qty = covered_qty.saturating_add(qty);
+        }
+
+        pick_list.short_qty = pick_list
+            .short_qty
+            .saturating_add(short_qty.saturating_sub(covered_qty));
+
+        Ok(())
+    }
+
+    /// Hand a fully confirmed pick list over to its sales order or work order
+    fn complete_pick_list(
+        who: &T::AccountId,
+        pick_list: &mut PickList,
+        picked_by: &Employee,
+    ) -> Result<(), DispatchError> {
+        match pick_list.source {
+            PickSource::SalesOrder(so_number) => {
+                // Every allocated item is already in packaging
+                pallet_sales::Pallet::<T>::do_pick_sales_order(who, so_number, picked_by)?;
+            }
+            PickSource::WorkOrder(work_order_number) => {
+                let work_order = WorkOrders::<T>::get(work_order_number)
+                    .ok_or(pallet_assembly::Error::<T>::WorkOrderNotFound)?;
+                ensure!(
+                    work_order.status == WorkOrderStatus::Released,
+                    pallet_assembly::Error::<T>::WorkOrderNotReleased
+                );
+
+                let mut kit = StagingKit::default();
+                for line in pick_list.lines.iter().filter(|line| line.picked_qty > 0) {
+                    kit.try_push(StagedItem {
+                        sku: line.sku.clone(),
+                        serial_number: line.serial_number,
+                        qty: line.picked_qty,
+                    })
+                    .map_err(|_| pallet_assembly::Error::<T>::TooManyStagedItems)?;
+                }
+
+                let recipe = pallet_assembly::Pallet::<T>::work_order_recipe(&work_order)?;
+                pallet_assembly::Pallet::<T>::lock_work_order_equipment(&work_order, &recipe)?;
+                pallet_assembly::Pallet::<T>::do_stage_kit(who, &work_order, kit);
+            }
+        }
+
+        pick_list.status = PickListStatus::Completed;
+        SourcePickLists::<T>::remove(&pick_list.source);
+
+        Ok(())
+    }
+
+    /// Complete a wave once none of its pick lists is open, returning whether it completed
+    fn complete_wave(wave_id: WaveId) -> bool {
+        Waves::<T>::mutate(wave_id, |entry| {
+            let Some((_, wave)) = entry.as_mut() else {
+                return false;
+            };
+
+            let any_open = wave
+                .pick_lists
+                .iter()
+                .filter_map(PickLists::<T>::get)
+                .any(|(_, pick_list)| pick_list.status == PickListStatus::Open);
+            if any_open {
+                return false;
+            }
+
+            wave.status = WaveStatus::Completed;
+            true
+        })
+    }
+
+    /// Stock of a SKU that can be kitted and is not already chosen for a kitting list
+    fn kitting_candidates(who: &T::AccountId, sku: &Sku, lines: &[PickLine]) -> Vec<(Item, Qty)> {
+        pallet_assembly::Pallet::<T>::kitting_candidates(who, sku)
+            .into_iter()
+            .filter(|(item, _)| {
+                !Self::on_list(lines, item)
+                    && !Self::has_discrepancy(who, item)
+                    && !ReservedSerials::<T>::contains_key((
+                        who,
+                        item.sku.clone(),
+                        item.serial_number,
+                    ))
+            })
+            .collect()
+    }
+
+    /// The list's strategy, or else the material's own
+    fn strategy(strategy: Option<&PickStrategy>, sku: &Sku) -> PickStrategy {
+        strategy
+            .cloned()
+            .unwrap_or_else(|| pallet_inventory::Pallet::<T>::pick_strategy(sku))
+    }
+
+    /// Whether an item has been short-picked and not yet counted
+    fn has_discrepancy(who: &T::AccountId, item: &Item) -> bool {
+        PickDiscrepancies::<T>::contains_key((who, item.sku.clone(), item.serial_number))
+    }
+
+    /// Whether an item already has a line on the list
+    fn on_list(lines: &[PickLine], item: &Item) -> bool {
+        lines
+            .iter()
+            .any(|line| line.sku == item.sku && line.serial_number == item.serial_number)
+    }
+
+    fn pick_line(item: &Item, qty: Qty, source_line: u8) -> PickLine {
+        PickLine {
+            sku: item.sku.clone(),
+            serial_number: item.serial_number,
+            qty,
+            from_location: item.location.clone(),
+            source_line,
+            picked_qty: 0,
+            status: PickLineStatus::Open,
+        }
+    }
+
+    /// Store a new pick list as the open list of its source
+    fn insert_pick_list(
+        who: &T::AccountId,
+        source: PickSource,
+        strategy: Option<PickStrategy>,
+        lines: BoundedVec<PickLine, ConstU32<MAX_PICK_LINES>>,
+    ) -> PickList {
+        let pick_list_id = NextPickListId::<T>::get();
+        NextPickListId::<T>::put(pick_list_id.saturating_add(1));
+
+        let pick_list = PickList {
+            pick_list_id,
+            source: source.clone(),
+            strategy,
+            lines,
+            wave: None,
+            short_qty: 0,
+            status: PickListStatus::Open,
+            created_at: pallet_inventory::Pallet::<T>::current_block(),
+        };
+        PickLists::<T>::insert(pick_list_id, (who, pick_list.clone()));
+        SourcePickLists::<T>::insert(source, pick_list_id);
+
+        pick_list
+    }
+
+    /// Fetch an open pick list of the account
+    fn open_pick_list(
+        who: &T::AccountId,
+        pick_list_id: PickListId,
+    ) -> Result<PickList, DispatchError> {
+        let (owner, pick_list) =
+            PickLists::<T>::get(pick_list_id).ok_or(Error::<T>::PickListNotFound)?;
+
+        ensure!(&owner == who, Error::<T>::NotPickListOwner);
+        ensure!(
+            pick_list.status == PickListStatus::Open,
+            Error::<T>::PickListCompleted
+        );
+
+        Ok(pick_list)
+    }
+}
+
+impl<T: Config> StockAllocations<T::AccountId> for Pallet<T> {
+    fn allocated_qty(who: &T::AccountId, sku: &Sku, serial_number: SerialNumber) -> Qty {
+        let Some(pick_list_id) = ReservedSerials::<T>::get((who, sku.clone(), serial_number))
+        else {
+            return 0;
+        };
+
+        PickLists::<T>::get(pick_list_id)
+            .map(|(_, pick_list)| {
+                pick_list
+                    .lines
+                    .iter()
+                    .filter(|line| {
+                        line.status == PickLineStatus::Open
+                            && &line.sku == sku
+                            && line.serial_number == serial_number
+                    })
+                    .fold(0 as Qty, |total, line| total.saturating_add(line.qty))
+            })
+            .unwrap_or_default()
+    }
+}
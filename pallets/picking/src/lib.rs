@@ -0,0 +1,390 @@
+//! # Picking Pallet
+//!
+//! - [`Config`]
+//! - [`Call`]
+//!
+//! ## Overview
+//!
+//! The Picking pallet directs picking of stock for sales orders and work-order kitting. This
+//! pallet provides functionalities to:
+//!
+//! - Generate pick lists for sales orders, from their allocation, and for the pre-kitted
+//!   components of work orders, choosing stock with each material's pick strategy or one given
+//!   for the list.
+//! - Group pick lists into waves to be picked together.
+//! - Confirm picks per serial, moving picked items to packaging or staging.
+//! - Handle short picks by recording the missing quantity as a discrepancy and picking
+//!   replacement stock.
+//!
+//! A sales order that is still open is allocated when its pick list is generated. Stock chosen
+//! for a kitting list is reserved until it is picked, and the pallet reports the reservation to
+//! the inventory pallet as allocated stock through `StockAllocations`, so it is not scrapped,
+//! moved or kitted elsewhere in the meantime. Picking part of a serial splits the picked
+//! quantity off to a new serial before moving it, and the line, and a sales order's allocation,
+//! follow the new serial. Once every line of a list is picked or
+//! short-picked, a sales order is marked picked and a work order is staged with the kit that was
+//! actually picked. A short pick that no other stock can replace
+//! is recorded as the list's short quantity.
+//!
+//! A short pick does not change the item's quantity. The missing quantity is recorded against the
+//! serial as a pick discrepancy, to be counted and corrected through the inventory pallet's
+//! `inventory_adjust`, under the account's approval policy, and then resolved. Until then the
+//! serial is not chosen as kitting stock or as a replacement for a short pick.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! - `create_sales_pick_list`: Generate a pick list for a sales order.
+//! - `create_kitting_pick_list`: Generate a pick list to kit a released work order.
+//! - `create_wave`: Group open pick lists into a wave.
+//! - `confirm_pick`: Confirm the quantity picked for a pick list line.
+//! - `resolve_pick_discrepancy`: Clear a serial's pick discrepancy once it has been counted.
+//!
+//! ## Storage
+//!
+//! - `PickLists`: Pick lists, and the account whose stock they pick, by ID.
+//! - `NextPickListId`: The ID assigned to the next pick list.
+//! - `SourcePickLists`: The open pick list of each sales order and work order.
+//! - `ReservedSerials`: Stock chosen for an open kitting list.
+//! - `Waves`: Waves, and the account that created them, by ID.
+//! - `NextWaveId`: The ID assigned to the next wave.
+//! - `PickDiscrepancies`: The quantity short-picked from each serial that has not been resolved.
+//!
+//! ## Events
+//!
+//! - `PickListCreated`: Emitted when a pick list is generated.
+//! - `WaveCreated`: Emitted when pick lists are grouped into a wave.
+//! - `PickConfirmed`: Emitted when a pick list line is picked or short-picked.
+//! - `PickListCompleted`: Emitted when every line of a pick list has been confirmed.
+//! - `WaveCompleted`: Emitted when every pick list in a wave has been completed.
+//! - `PickDiscrepancyResolved`: Emitted when a serial's pick discrepancy is cleared.
+//!
+//! ## Errors
+//!
+//! - `PickListNotFound`: The specified pick list could not be located.
+//! - `NotPickListOwner`: Only the account whose stock a pick list picks can work it.
+//! - `PickListExists`: The sales order or work order already has an open pick list.
+//! - `PickListCompleted`: The pick list has already been completed.
+//! - `TooManyPickLines`: The pick list cannot hold any more lines.
+//! - `LineNotFound`: The pick list has no line at the given index.
+//! - `LineAlreadyConfirmed`: The line has already been picked or short-picked.
+//! - `OverPick`: More was picked than the line asks for.
+//! - `ItemMoved`: The item is no longer where the line picks it from.
+//! - `NoPickLists`: The wave groups no pick lists.
+//! - `DuplicatePickList`: A pick list is grouped into the wave twice.
+//! - `AlreadyInWave`: The pick list is already part of a wave.
+//! - `WaveNotFound`: The specified wave could not be located.
+//! - `DiscrepancyNotFound`: The serial has no pick discrepancy.
+//!
+//! This pallet uses `no_std` for compatibility with Wasm environments, a polkadot standard.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub use pallet::*;
+
+// FRAME pallets require their own "mock runtimes" to be able to run unit tests. This module
+// contains a mock runtime specific for testing this pallet's functionality.
+#[cfg(test)]
+mod mock;
+
+// This module contains the unit tests for this pallet.
+// Learn about pallet unit testing here: https://docs.substrate.io/test/unit-testing/
+#[cfg(test)]
+mod tests;
+
+mod blogic;
+pub mod types;
+
+use crate::types::{PickLineIndex, PickList, PickListId, PickSource, WaveId, MAX_WAVE_LISTS};
+use pallet_assembly::types::WorkOrderNumber;
+use pallet_inventory::types::{Employee, PickStrategy, Qty, SerialNumber, Sku};
+use pallet_sales::types::SalesOrderNumber;
+
+#[frame_support::pallet]
+pub mod pallet {
+    use super::*;
+    use crate::types::{PickListStatus, Wave};
+    use frame_support::pallet_prelude::*;
+    use frame_system::pallet_prelude::*;
+    use pallet_assembly::pallet::Config as AssemblyConfig;
+    use pallet_sales::pallet::Config as SalesConfig;
+
+    #[pallet::pallet]
+    pub struct Pallet<T>(_);
+
+    /// The pallet's configuration trait.
+    ///
+    /// This trait is tightly coupled with the Sales and Assembly pallets -> [SalesConfig],
+    /// [AssemblyConfig]
+    #[pallet::config]
+    pub trait Config: frame_system::Config + SalesConfig + AssemblyConfig {
+        type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+    }
+
+    /// Pick Lists Storage
+    ///
+    /// Pick lists by ID, with the account whose stock they pick
+    #[pallet::storage]
+    pub type PickLists<T: Config> =
+        StorageMap<_, Twox64Concat, PickListId, (T::AccountId, PickList), OptionQuery>;
+
+    /// The ID assigned to the next pick list
+    #[pallet::storage]
+    pub type NextPickListId<T: Config> = StorageValue<_, PickListId, ValueQuery>;
+
+    /// The open pick list of each sales order and work order, so that neither is picked twice
+    #[pallet::storage]
+    pub type SourcePickLists<T: Config> =
+        StorageMap<_, Blake2_128Concat, PickSource, PickListId, OptionQuery>;
+
+    /// Reserved Serials Storage
+    ///
+    /// Stock chosen for an open kitting list, which other kitting lists cannot choose
+    #[pallet::storage]
+    pub type ReservedSerials<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, T::AccountId>,
+            NMapKey<Blake2_128Concat, Sku>,
+            NMapKey<Blake2_128Concat, SerialNumber>,
+        ),
+        PickListId,
+        OptionQuery,
+    >;
+
+    /// Waves Storage
+    ///
+    /// Waves by ID, with the account that created them
+    #[pallet::storage]
+    pub type Waves<T: Config> =
+        StorageMap<_, Twox64Concat, WaveId, (T::AccountId, Wave), OptionQuery>;
+
+    /// The ID assigned to the next wave
+    #[pallet::storage]
+    pub type NextWaveId<T: Config> = StorageValue<_, WaveId, ValueQuery>;
+
+    /// Pick Discrepancies Storage
+    ///
+    /// The quantity short-picked from each serial, left in its stock until it is counted
+    #[pallet::storage]
+    pub type PickDiscrepancies<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, T::AccountId>,
+            NMapKey<Blake2_128Concat, Sku>,
+            NMapKey<Blake2_128Concat, SerialNumber>,
+        ),
+        Qty,
+        OptionQuery,
+    >;
+
+    /// Events that functions in this pallet can emit.
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config> {
+        PickListCreated {
+            who: T::AccountId,
+            pick_list: PickList,
+        },
+        WaveCreated {
+            who: T::AccountId,
+            wave_id: WaveId,
+            pick_lists: BoundedVec<PickListId, ConstU32<MAX_WAVE_LISTS>>,
+        },
+        /// A line was picked, short by `short_qty` if any, into the serial the line now names
+        PickConfirmed {
+            who: T::AccountId,
+            pick_list_id: PickListId,
+            line_index: PickLineIndex,
+            sku: Sku,
+            serial_number: SerialNumber,
+            picked_qty: Qty,
+            short_qty: Qty,
+        },
+        /// Every line of a pick list has been confirmed
+        PickListCompleted {
+            who: T::AccountId,
+            pick_list_id: PickListId,
+            source: PickSource,
+            /// Quantity short-picked that no other stock could replace
+            short_qty: Qty,
+        },
+        WaveCompleted {
+            who: T::AccountId,
+            wave_id: WaveId,
+        },
+        /// A serial's pick discrepancy has been counted and cleared
+        PickDiscrepancyResolved {
+            who: T::AccountId,
+            sku: Sku,
+            serial_number: SerialNumber,
+            short_qty: Qty,
+        },
+    }
+
+    /// Errors that can be returned by this pallet.
+    #[pallet::error]
+    pub enum Error<T> {
+        /// The pick list was not found
+        PickListNotFound,
+        /// The pick list belongs to another account
+        NotPickListOwner,
+        /// The sales order or work order already has an open pick list
+        PickListExists,
+        /// The pick list has already been completed
+        PickListCompleted,
+        /// The pick list cannot hold any more lines
+        TooManyPickLines,
+        /// The pick list has no line at the given index
+        LineNotFound,
+        /// The line has already been picked or short-picked
+        LineAlreadyConfirmed,
+        /// More was picked than the line asks for
+        OverPick,
+        /// The item is no longer where the line picks it from
+        ItemMoved,
+        /// The wave groups no pick lists
+        NoPickLists,
+        /// A pick list is grouped into the wave more than once
+        DuplicatePickList,
+        /// The pick list is already part of a wave
+        AlreadyInWave,
+        /// The wave was not found
+        WaveNotFound,
+        /// The serial has no pick discrepancy
+        DiscrepancyNotFound,
+    }
+
+    /// The pallet's dispatchable functions
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Generate a pick list for a sales order
+        ///
+        /// An open sales order is allocated first, with the given strategy or else each material's
+        /// own; an allocated one is picked as allocated.
+        #[pallet::call_index(0)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn create_sales_pick_list(
+            origin: OriginFor<T>,
+            so_number: SalesOrderNumber,
+            strategy: Option<PickStrategy>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let pick_list = Self::do_create_sales_pick_list(&who, so_number, strategy)?;
+
+            Self::deposit_event(Event::PickListCreated { who, pick_list });
+
+            Ok(())
+        }
+
+        /// Generate a pick list to kit the pre-kitted components of a released work order
+        ///
+        /// Stock is chosen with the given strategy, or else each material's own.
+        #[pallet::call_index(1)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn create_kitting_pick_list(
+            origin: OriginFor<T>,
+            work_order_number: WorkOrderNumber,
+            strategy: Option<PickStrategy>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let pick_list = Self::do_create_kitting_pick_list(&who, work_order_number, strategy)?;
+
+            Self::deposit_event(Event::PickListCreated { who, pick_list });
+
+            Ok(())
+        }
+
+        /// Group open pick lists into a wave
+        #[pallet::call_index(2)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn create_wave(
+            origin: OriginFor<T>,
+            pick_lists: BoundedVec<PickListId, ConstU32<MAX_WAVE_LISTS>>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let wave = Self::do_create_wave(&who, pick_lists)?;
+
+            Self::deposit_event(Event::WaveCreated {
+                who,
+                wave_id: wave.wave_id,
+                pick_lists: wave.pick_lists,
+            });
+
+            Ok(())
+        }
+
+        /// Confirm the quantity picked for a pick list line
+        ///
+        /// Picking less than the line asks for is a short pick: the shortfall is recorded as a
+        /// discrepancy on the item and replacement stock is added to the list where there is any.
+        #[pallet::call_index(3)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn confirm_pick(
+            origin: OriginFor<T>,
+            pick_list_id: PickListId,
+            line_index: PickLineIndex,
+            picked_qty: Qty,
+            picked_by: Employee,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let (pick_list, completed_wave) =
+                Self::do_confirm_pick(&who, pick_list_id, line_index, picked_qty, picked_by)?;
+
+            let line = &pick_list.lines[line_index as usize];
+            Self::deposit_event(Event::PickConfirmed {
+                who: who.clone(),
+                pick_list_id,
+                line_index,
+                sku: line.sku.clone(),
+                serial_number: line.serial_number,
+                picked_qty,
+                short_qty: line.qty.saturating_sub(picked_qty),
+            });
+
+            if pick_list.status == PickListStatus::Completed {
+                Self::deposit_event(Event::PickListCompleted {
+                    who: who.clone(),
+                    pick_list_id,
+                    source: pick_list.source,
+                    short_qty: pick_list.short_qty,
+                });
+            }
+
+            if let Some(wave_id) = completed_wave {
+                Self::deposit_event(Event::WaveCompleted { who, wave_id });
+            }
+
+            Ok(())
+        }
+
+        /// Clear a serial's pick discrepancy once its stock has been counted
+        ///
+        /// Any correction to the item's quantity is made through the inventory pallet.
+        #[pallet::call_index(4)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn resolve_pick_discrepancy(
+            origin: OriginFor<T>,
+            sku: Sku,
+            serial_number: SerialNumber,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let short_qty = PickDiscrepancies::<T>::take((&who, sku.clone(), serial_number))
+                .ok_or(Error::<T>::DiscrepancyNotFound)?;
+
+            Self::deposit_event(Event::PickDiscrepancyResolved {
+                who,
+                sku,
+                serial_number,
+                short_qty,
+            });
+
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,717 @@
+use crate::{mock::*, types::*, Error, Event};
+use frame_support::pallet_prelude::ConstU32;
+use frame_support::sp_runtime::{DispatchResult, Percent};
+use frame_support::{assert_noop, assert_ok, BoundedVec};
+use pallet_assembly::types::{StagedItem, WorkOrder, WorkOrderNumber, WorkOrderStatus};
+use pallet_inventory::types::{
+    Employee, Equipment, EquipmentAsset, InventoryType, IssueMode, Item, Location, Qty, Recipe,
+    RecipeComponent, ScrapDetails, SerialNumber, Sku, StockAllocations,
+};
+use pallet_sales::types::{
+    AllocatedItem, Customer, SalesOrderLine, SalesOrderNumber, SalesOrderStatus, MAX_SO_LINES,
+};
+
+const ALICE: u64 = 1;
+const BOB: u64 = 2;
+
+fn sku(sku: &str) -> Sku {
+    BoundedVec::truncate_from(sku.as_bytes().to_vec())
+}
+
+/// Stock of a SKU in the warehouse, created in the order of its serial number
+fn stock(sku_name: &str, serial_number: SerialNumber, qty: Qty) -> Item {
+    Item {
+        sku: sku(sku_name),
+        serial_number,
+        qty,
+        inventory_type: InventoryType::FinishedGood,
+        created_at: serial_number,
+        ..Default::default()
+    }
+}
+
+fn insert_stock(items: Vec<Item>) {
+    for item in items {
+        assert_ok!(Inventory::inventory_insertion(
+            RuntimeOrigin::signed(ALICE),
+            item
+        ));
+    }
+}
+
+/// Add customer 0 and stock Alice's warehouse with widgets
+fn set_up_sales(widgets: Vec<(SerialNumber, Qty)>) {
+    assert_ok!(Sales::add_customer(
+        RuntimeOrigin::root(),
+        Customer {
+            customer_id: 0,
+            name: BoundedVec::truncate_from(b"Globex".to_vec()),
+            credit_limit: None,
+            credit_hold: false,
+        }
+    ));
+    insert_stock(
+        widgets
+            .into_iter()
+            .map(|(serial_number, qty)| stock("WIDGET", serial_number, qty))
+            .collect(),
+    );
+}
+
+fn create_sales_order(who: u64, so_number: SalesOrderNumber, qty: Qty) {
+    assert_ok!(Sales::create_sales_order(
+        RuntimeOrigin::signed(who),
+        so_number,
+        0,
+        BoundedVec::<_, ConstU32<MAX_SO_LINES>>::truncate_from(vec![SalesOrderLine {
+            sku: sku("WIDGET"),
+            qty,
+            unit_price: 100,
+            due_date: 20,
+            ..Default::default()
+        }])
+    ));
+}
+
+fn pick_list(pick_list_id: PickListId) -> PickList {
+    crate::PickLists::<Test>::get(pick_list_id).unwrap().1
+}
+
+fn line(sku_name: &str, serial_number: SerialNumber, qty: Qty, source_line: u8) -> PickLine {
+    PickLine {
+        sku: sku(sku_name),
+        serial_number,
+        qty,
+        from_location: Location::Warehouse,
+        source_line,
+        picked_qty: 0,
+        status: PickLineStatus::Open,
+    }
+}
+
+fn confirm(pick_list_id: PickListId, line_index: PickLineIndex, picked_qty: Qty) -> DispatchResult {
+    Picking::confirm_pick(
+        RuntimeOrigin::signed(ALICE),
+        pick_list_id,
+        line_index,
+        picked_qty,
+        Employee::Charlie,
+    )
+}
+
+fn location(sku_name: &str, serial_number: SerialNumber) -> Location {
+    pallet_inventory::Inventory::<Test>::get((ALICE, sku(sku_name), serial_number))
+        .unwrap()
+        .location
+}
+
+fn so_status(so_number: SalesOrderNumber) -> SalesOrderStatus {
+    pallet_sales::SalesOrders::<Test>::get(so_number)
+        .unwrap()
+        .1
+        .status
+}
+
+fn waves(pick_lists: Vec<PickListId>) -> BoundedVec<PickListId, ConstU32<MAX_WAVE_LISTS>> {
+    BoundedVec::truncate_from(pick_lists)
+}
+
+/// A widget kitted from two bolts, with an asset to build it on and bolts in the warehouse
+fn set_up_kitting(bolts: Vec<(SerialNumber, Qty)>) {
+    let recipe = Recipe {
+        sku: sku("WIDGET"),
+        required_components: BoundedVec::truncate_from(vec![RecipeComponent {
+            sku: sku("BOLT"),
+            qty: 2,
+            scrap_percent: Percent::from_percent(0),
+            issue_mode: IssueMode::PreKitted,
+        }]),
+        output_quantity: 1,
+        expected_yield: Percent::from_percent(100),
+        ..Default::default()
+    };
+    assert_ok!(Inventory::insert_recipe(
+        RuntimeOrigin::signed(ALICE),
+        recipe.clone()
+    ));
+    assert_ok!(Inventory::insert_equipment(
+        RuntimeOrigin::signed(ALICE),
+        EquipmentAsset {
+            asset_id: 0,
+            equipment_type: recipe.required_equipment,
+            ..Default::default()
+        }
+    ));
+    insert_stock(
+        bolts
+            .into_iter()
+            .map(|(serial_number, qty)| stock("BOLT", serial_number, qty))
+            .collect(),
+    );
+}
+
+fn create_work_order(work_order_number: WorkOrderNumber, planned_quantity: Qty, release: bool) {
+    assert_ok!(Assembly::create_work_order(
+        RuntimeOrigin::signed(ALICE),
+        WorkOrder {
+            work_order_number,
+            recipe_sku: sku("WIDGET"),
+            recipe_id: 0,
+            planned_quantity,
+            ..Default::default()
+        }
+    ));
+    if release {
+        assert_ok!(Assembly::update_work_order_status(
+            RuntimeOrigin::signed(ALICE),
+            work_order_number,
+            WorkOrderStatus::Released
+        ));
+    }
+}
+
+#[test]
+fn sales_pick_lists_pick_the_allocation_oldest_first() {
+    new_test_ext().execute_with(|| {
+        set_up_sales(vec![(1, 3), (2, 5)]);
+        create_sales_order(ALICE, 0, 6);
+
+        assert_ok!(Picking::create_sales_pick_list(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            None
+        ));
+
+        // The open sales order is allocated first
+        assert_eq!(so_status(0), SalesOrderStatus::Allocated);
+        let created = pick_list(0);
+        assert_eq!(
+            created.lines.clone().into_inner(),
+            vec![line("WIDGET", 1, 3, 0), line("WIDGET", 2, 3, 0)]
+        );
+        assert_eq!(created.source, PickSource::SalesOrder(0));
+        assert_eq!(created.status, PickListStatus::Open);
+        assert_eq!(
+            crate::SourcePickLists::<Test>::get(PickSource::SalesOrder(0)),
+            Some(0)
+        );
+        System::assert_last_event(
+            Event::PickListCreated {
+                who: ALICE,
+                pick_list: created,
+            }
+            .into(),
+        );
+
+        assert_noop!(
+            Picking::create_sales_pick_list(RuntimeOrigin::signed(ALICE), 0, None),
+            Error::<Test>::PickListExists
+        );
+    });
+}
+
+#[test]
+fn sales_pick_lists_need_an_open_or_allocated_order_of_the_account() {
+    new_test_ext().execute_with(|| {
+        set_up_sales(vec![(1, 3)]);
+        create_sales_order(ALICE, 0, 3);
+
+        assert_noop!(
+            Picking::create_sales_pick_list(RuntimeOrigin::signed(ALICE), 1, None),
+            pallet_sales::Error::<Test>::SalesOrderNotFound
+        );
+        assert_noop!(
+            Picking::create_sales_pick_list(RuntimeOrigin::signed(BOB), 0, None),
+            pallet_sales::Error::<Test>::NotSalesOrderOwner
+        );
+
+        create_sales_order(ALICE, 1, 4);
+        assert_noop!(
+            Picking::create_sales_pick_list(RuntimeOrigin::signed(ALICE), 1, None),
+            pallet_sales::Error::<Test>::InsufficientInventory
+        );
+
+        assert_ok!(Sales::cancel_sales_order(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            Employee::Bob
+        ));
+        assert_noop!(
+            Picking::create_sales_pick_list(RuntimeOrigin::signed(ALICE), 0, None),
+            pallet_sales::Error::<Test>::InvalidSalesOrderStatus
+        );
+    });
+}
+
+#[test]
+fn confirmed_picks_complete_the_list_and_pick_the_order() {
+    new_test_ext().execute_with(|| {
+        set_up_sales(vec![(1, 3), (2, 5)]);
+        create_sales_order(ALICE, 0, 6);
+        assert_ok!(Picking::create_sales_pick_list(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            None
+        ));
+
+        assert_ok!(confirm(0, 0, 3));
+
+        assert_eq!(location("WIDGET", 1), Location::Packaging);
+        assert_eq!(pick_list(0).lines[0].status, PickLineStatus::Picked);
+        assert_eq!(pick_list(0).status, PickListStatus::Open);
+        System::assert_last_event(
+            Event::PickConfirmed {
+                who: ALICE,
+                pick_list_id: 0,
+                line_index: 0,
+                sku: sku("WIDGET"),
+                serial_number: 1,
+                picked_qty: 3,
+                short_qty: 0,
+            }
+            .into(),
+        );
+
+        // Only the three picked widgets are split off serial 2 and moved
+        assert_ok!(confirm(0, 1, 3));
+
+        assert_eq!(location("WIDGET", 2), Location::Warehouse);
+        assert_eq!(location("WIDGET", 3), Location::Packaging);
+        assert_eq!(
+            pallet_inventory::Inventory::<Test>::get((ALICE, sku("WIDGET"), 2))
+                .unwrap()
+                .qty,
+            2
+        );
+        assert_eq!(pick_list(0).lines[1].serial_number, 3);
+        assert_eq!(
+            pallet_sales::SalesOrderAllocations::<Test>::get(0)
+                .unwrap()
+                .into_inner(),
+            vec![
+                AllocatedItem {
+                    line_index: 0,
+                    sku: sku("WIDGET"),
+                    serial_number: 1,
+                    qty: 3,
+                },
+                AllocatedItem {
+                    line_index: 0,
+                    sku: sku("WIDGET"),
+                    serial_number: 3,
+                    qty: 3,
+                },
+            ]
+        );
+        assert_eq!(pick_list(0).status, PickListStatus::Completed);
+        assert_eq!(so_status(0), SalesOrderStatus::Picked);
+        assert!(!crate::SourcePickLists::<Test>::contains_key(
+            PickSource::SalesOrder(0)
+        ));
+        System::assert_last_event(
+            Event::PickListCompleted {
+                who: ALICE,
+                pick_list_id: 0,
+                source: PickSource::SalesOrder(0),
+                short_qty: 0,
+            }
+            .into(),
+        );
+
+        assert_noop!(confirm(0, 0, 3), Error::<Test>::PickListCompleted);
+    });
+}
+
+#[test]
+fn short_picks_are_replaced_from_other_stock() {
+    new_test_ext().execute_with(|| {
+        set_up_sales(vec![(1, 3), (2, 5), (3, 4)]);
+        create_sales_order(ALICE, 0, 6);
+        assert_ok!(Picking::create_sales_pick_list(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            None
+        ));
+        assert_ok!(confirm(0, 0, 3));
+
+        // Only one of the three widgets is found on serial 2, and is split off to serial 4
+        assert_ok!(confirm(0, 1, 1));
+
+        assert_eq!(
+            crate::PickDiscrepancies::<Test>::get((ALICE, sku("WIDGET"), 2)),
+            Some(2)
+        );
+        assert_eq!(location("WIDGET", 2), Location::Warehouse);
+        assert_eq!(location("WIDGET", 4), Location::Packaging);
+        let short_picked = pick_list(0);
+        assert_eq!(short_picked.lines[1].status, PickLineStatus::Short);
+        assert_eq!(short_picked.lines[1].serial_number, 4);
+        assert_eq!(short_picked.lines[1].picked_qty, 1);
+        assert_eq!(short_picked.lines[2], line("WIDGET", 3, 2, 0));
+        assert_eq!(short_picked.short_qty, 0);
+        assert_eq!(
+            pallet_sales::SalesOrderAllocations::<Test>::get(0)
+                .unwrap()
+                .into_inner(),
+            vec![
+                AllocatedItem {
+                    line_index: 0,
+                    sku: sku("WIDGET"),
+                    serial_number: 1,
+                    qty: 3,
+                },
+                AllocatedItem {
+                    line_index: 0,
+                    sku: sku("WIDGET"),
+                    serial_number: 4,
+                    qty: 1,
+                },
+                AllocatedItem {
+                    line_index: 0,
+                    sku: sku("WIDGET"),
+                    serial_number: 3,
+                    qty: 2,
+                },
+            ]
+        );
+        System::assert_last_event(
+            Event::PickConfirmed {
+                who: ALICE,
+                pick_list_id: 0,
+                line_index: 1,
+                sku: sku("WIDGET"),
+                serial_number: 4,
+                picked_qty: 1,
+                short_qty: 2,
+            }
+            .into(),
+        );
+
+        assert_ok!(confirm(0, 2, 2));
+
+        assert_eq!(location("WIDGET", 5), Location::Packaging);
+        assert_eq!(so_status(0), SalesOrderStatus::Picked);
+    });
+}
+
+#[test]
+fn short_picks_without_replacement_stay_short() {
+    new_test_ext().execute_with(|| {
+        set_up_sales(vec![(1, 3), (2, 5)]);
+        create_sales_order(ALICE, 0, 6);
+        assert_ok!(Picking::create_sales_pick_list(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            None
+        ));
+        assert_ok!(confirm(0, 0, 3));
+
+        // Nothing is found, so nothing is moved
+        assert_ok!(confirm(0, 1, 0));
+
+        assert_eq!(location("WIDGET", 2), Location::Warehouse);
+        let completed = pick_list(0);
+        assert_eq!(completed.lines.len(), 2);
+        assert_eq!(completed.short_qty, 3);
+        assert_eq!(completed.status, PickListStatus::Completed);
+        System::assert_last_event(
+            Event::PickListCompleted {
+                who: ALICE,
+                pick_list_id: 0,
+                source: PickSource::SalesOrder(0),
+                short_qty: 3,
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn pick_discrepancies_are_resolved_once_counted() {
+    new_test_ext().execute_with(|| {
+        set_up_sales(vec![(1, 3), (2, 5)]);
+        create_sales_order(ALICE, 0, 6);
+        assert_ok!(Picking::create_sales_pick_list(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            None
+        ));
+        assert_ok!(confirm(0, 1, 1));
+
+        assert_noop!(
+            Picking::resolve_pick_discrepancy(RuntimeOrigin::signed(BOB), sku("WIDGET"), 2),
+            Error::<Test>::DiscrepancyNotFound
+        );
+        assert_ok!(Picking::resolve_pick_discrepancy(
+            RuntimeOrigin::signed(ALICE),
+            sku("WIDGET"),
+            2
+        ));
+        assert!(!crate::PickDiscrepancies::<Test>::contains_key((
+            ALICE,
+            sku("WIDGET"),
+            2
+        )));
+        System::assert_last_event(
+            Event::PickDiscrepancyResolved {
+                who: ALICE,
+                sku: sku("WIDGET"),
+                serial_number: 2,
+                short_qty: 2,
+            }
+            .into(),
+        );
+
+        assert_noop!(
+            Picking::resolve_pick_discrepancy(RuntimeOrigin::signed(ALICE), sku("WIDGET"), 2),
+            Error::<Test>::DiscrepancyNotFound
+        );
+    });
+}
+
+#[test]
+fn picks_are_confirmed_once_up_to_the_line_quantity() {
+    new_test_ext().execute_with(|| {
+        set_up_sales(vec![(1, 3), (2, 5)]);
+        create_sales_order(ALICE, 0, 6);
+        assert_ok!(Picking::create_sales_pick_list(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            None
+        ));
+
+        assert_noop!(confirm(0, 0, 4), Error::<Test>::OverPick);
+        assert_noop!(confirm(0, 2, 1), Error::<Test>::LineNotFound);
+        assert_noop!(confirm(1, 0, 1), Error::<Test>::PickListNotFound);
+        assert_noop!(
+            Picking::confirm_pick(RuntimeOrigin::signed(BOB), 0, 0, 3, Employee::Charlie),
+            Error::<Test>::NotPickListOwner
+        );
+
+        assert_ok!(confirm(0, 0, 3));
+        assert_noop!(confirm(0, 0, 3), Error::<Test>::LineAlreadyConfirmed);
+    });
+}
+
+#[test]
+fn waves_complete_with_their_last_pick_list() {
+    new_test_ext().execute_with(|| {
+        set_up_sales(vec![(1, 3), (2, 5)]);
+        create_sales_order(ALICE, 0, 3);
+        create_sales_order(ALICE, 1, 4);
+        for so_number in [0, 1] {
+            assert_ok!(Picking::create_sales_pick_list(
+                RuntimeOrigin::signed(ALICE),
+                so_number,
+                None
+            ));
+        }
+
+        assert_ok!(Picking::create_wave(
+            RuntimeOrigin::signed(ALICE),
+            waves(vec![0, 1])
+        ));
+
+        assert_eq!(pick_list(0).wave, Some(0));
+        assert_eq!(pick_list(1).wave, Some(0));
+        let (owner, wave) = crate::Waves::<Test>::get(0).unwrap();
+        assert_eq!(owner, ALICE);
+        assert_eq!(wave.status, WaveStatus::Open);
+        assert_eq!(crate::NextWaveId::<Test>::get(), 1);
+        System::assert_last_event(
+            Event::WaveCreated {
+                who: ALICE,
+                wave_id: 0,
+                pick_lists: waves(vec![0, 1]),
+            }
+            .into(),
+        );
+
+        assert_ok!(confirm(0, 0, 3));
+        assert_eq!(
+            crate::Waves::<Test>::get(0).unwrap().1.status,
+            WaveStatus::Open
+        );
+
+        assert_ok!(confirm(1, 0, 4));
+        assert_eq!(
+            crate::Waves::<Test>::get(0).unwrap().1.status,
+            WaveStatus::Completed
+        );
+        System::assert_last_event(
+            Event::WaveCompleted {
+                who: ALICE,
+                wave_id: 0,
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn waves_group_open_pick_lists_once() {
+    new_test_ext().execute_with(|| {
+        set_up_sales(vec![(1, 3), (2, 5)]);
+        create_sales_order(ALICE, 0, 3);
+        create_sales_order(ALICE, 1, 4);
+        for so_number in [0, 1] {
+            assert_ok!(Picking::create_sales_pick_list(
+                RuntimeOrigin::signed(ALICE),
+                so_number,
+                None
+            ));
+        }
+
+        assert_noop!(
+            Picking::create_wave(RuntimeOrigin::signed(ALICE), waves(vec![])),
+            Error::<Test>::NoPickLists
+        );
+        assert_noop!(
+            Picking::create_wave(RuntimeOrigin::signed(ALICE), waves(vec![0, 0])),
+            Error::<Test>::DuplicatePickList
+        );
+        assert_noop!(
+            Picking::create_wave(RuntimeOrigin::signed(BOB), waves(vec![0])),
+            Error::<Test>::NotPickListOwner
+        );
+        assert_noop!(
+            Picking::create_wave(RuntimeOrigin::signed(ALICE), waves(vec![2])),
+            Error::<Test>::PickListNotFound
+        );
+
+        assert_ok!(Picking::create_wave(
+            RuntimeOrigin::signed(ALICE),
+            waves(vec![0])
+        ));
+        assert_noop!(
+            Picking::create_wave(RuntimeOrigin::signed(ALICE), waves(vec![1, 0])),
+            Error::<Test>::AlreadyInWave
+        );
+
+        assert_ok!(confirm(1, 0, 4));
+        assert_noop!(
+            Picking::create_wave(RuntimeOrigin::signed(ALICE), waves(vec![1])),
+            Error::<Test>::PickListCompleted
+        );
+    });
+}
+
+#[test]
+fn kitting_pick_lists_reserve_their_stock() {
+    new_test_ext().execute_with(|| {
+        set_up_kitting(vec![(1, 3), (2, 5)]);
+        create_work_order(0, 3, true);
+        create_work_order(1, 1, true);
+
+        assert_ok!(Picking::create_kitting_pick_list(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            None
+        ));
+
+        assert_eq!(
+            pick_list(0).lines.into_inner(),
+            vec![line("BOLT", 1, 3, 0), line("BOLT", 2, 3, 0)]
+        );
+        assert_eq!(
+            crate::ReservedSerials::<Test>::get((ALICE, sku("BOLT"), 2)),
+            Some(0)
+        );
+
+        // Both serials are reserved for the first work order
+        assert_noop!(
+            Picking::create_kitting_pick_list(RuntimeOrigin::signed(ALICE), 1, None),
+            pallet_assembly::Error::<Test>::InsufficientInventory
+        );
+
+        // The reservation is reported as allocated stock, which cannot be scrapped, and only
+        // the rest of serial 2 can be kitted elsewhere
+        assert_eq!(
+            <Picking as StockAllocations<u64>>::allocated_qty(&ALICE, &sku("BOLT"), 2),
+            3
+        );
+        assert_noop!(
+            Inventory::inventory_scrap(
+                RuntimeOrigin::signed(ALICE),
+                sku("BOLT"),
+                1,
+                ScrapDetails {
+                    issuer: Employee::Charlie,
+                    reason: BoundedVec::truncate_from(b"Rusted".to_vec()),
+                    equipment: Equipment::Forklift,
+                }
+            ),
+            pallet_inventory::Error::<Test>::ItemAllocated
+        );
+        assert_eq!(
+            pallet_assembly::Pallet::<Test>::kitting_candidates(&ALICE, &sku("BOLT"))
+                .into_iter()
+                .map(|(item, qty)| (item.serial_number, qty))
+                .collect::<Vec<_>>(),
+            vec![(2, 2)]
+        );
+    });
+}
+
+#[test]
+fn completed_kitting_lists_stage_the_work_order() {
+    new_test_ext().execute_with(|| {
+        set_up_kitting(vec![(1, 3), (2, 5)]);
+        create_work_order(0, 3, true);
+        assert_ok!(Picking::create_kitting_pick_list(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            None
+        ));
+
+        assert_ok!(confirm(0, 0, 3));
+        assert_ok!(confirm(0, 1, 3));
+
+        // The two bolts left on serial 2 stay in the warehouse
+        assert_eq!(location("BOLT", 1), Location::Staging);
+        assert_eq!(location("BOLT", 2), Location::Warehouse);
+        assert_eq!(location("BOLT", 3), Location::Staging);
+        assert!(!crate::ReservedSerials::<Test>::contains_key((
+            ALICE,
+            sku("BOLT"),
+            1
+        )));
+        assert_eq!(
+            pallet_assembly::WorkOrders::<Test>::get(0).unwrap().status,
+            WorkOrderStatus::Staged
+        );
+        let (_, kit) = pallet_assembly::StagingArea::<Test>::get(0).unwrap();
+        assert_eq!(
+            kit.into_inner(),
+            vec![
+                StagedItem {
+                    sku: sku("BOLT"),
+                    serial_number: 1,
+                    qty: 3,
+                },
+                StagedItem {
+                    sku: sku("BOLT"),
+                    serial_number: 3,
+                    qty: 3,
+                },
+            ]
+        );
+    });
+}
+
+#[test]
+fn kitting_pick_lists_need_a_released_work_order() {
+    new_test_ext().execute_with(|| {
+        set_up_kitting(vec![(1, 3), (2, 5)]);
+        create_work_order(0, 3, false);
+
+        assert_noop!(
+            Picking::create_kitting_pick_list(RuntimeOrigin::signed(ALICE), 1, None),
+            pallet_assembly::Error::<Test>::WorkOrderNotFound
+        );
+        assert_noop!(
+            Picking::create_kitting_pick_list(RuntimeOrigin::signed(ALICE), 0, None),
+            pallet_assembly::Error::<Test>::WorkOrderNotReleased
+        );
+    });
+}
@@ -0,0 +1,100 @@
+use codec::{Decode, Encode};
+use frame_support::pallet_prelude::{ConstU32, MaxEncodedLen};
+use frame_support::BoundedVec;
+use pallet_assembly::types::WorkOrderNumber;
+use pallet_inventory::types::{Location, PickStrategy, Qty, SerialNumber, Sku};
+use pallet_sales::types::SalesOrderNumber;
+use scale_info::TypeInfo;
+
+pub type PickListId = u32;
+pub type WaveId = u32;
+/// Index of a line within its pick list
+pub type PickLineIndex = u8;
+
+/// Most lines a single pick list can hold, including lines added to replace short picks
+pub const MAX_PICK_LINES: u32 = 200;
+/// Most pick lists a single wave can group
+pub const MAX_WAVE_LISTS: u32 = 50;
+
+/// What a pick list is picked for
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub enum PickSource {
+    /// Outbound picking of a sales order's allocation
+    SalesOrder(SalesOrderNumber),
+    /// Kitting of a work order's pre-kitted components
+    WorkOrder(WorkOrderNumber),
+}
+
+impl PickSource {
+    /// Where picked items are taken to
+    pub fn destination(&self) -> Location {
+        match self {
+            PickSource::SalesOrder(_) => Location::Packaging,
+            PickSource::WorkOrder(_) => Location::Staging,
+        }
+    }
+}
+
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
+pub enum PickLineStatus {
+    #[default]
+    Open,
+    Picked,
+    /// Fewer units were found than the line asked for
+    Short,
+}
+
+/// A quantity to pick from a serialized item
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct PickLine {
+    pub sku: Sku,
+    pub serial_number: SerialNumber,
+    pub qty: Qty,
+    /// Where the item was when the line was created
+    pub from_location: Location,
+    /// The sales order line, or recipe component, the line is picked for
+    pub source_line: u8,
+    pub picked_qty: Qty,
+    pub status: PickLineStatus,
+}
+
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
+pub enum PickListStatus {
+    #[default]
+    Open,
+    /// Every line has been picked or short-picked
+    Completed,
+}
+
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct PickList {
+    pub pick_list_id: PickListId,
+    pub source: PickSource,
+    /// Strategy overriding each material's own when choosing stock, including replacements
+    /// for short picks
+    pub strategy: Option<PickStrategy>,
+    pub lines: BoundedVec<PickLine, ConstU32<MAX_PICK_LINES>>,
+    /// The wave the list is picked in, if any
+    pub wave: Option<WaveId>,
+    /// Quantity short-picked that no other stock could replace
+    pub short_qty: Qty,
+    pub status: PickListStatus,
+    pub created_at: u32,
+}
+
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
+pub enum WaveStatus {
+    #[default]
+    Open,
+    /// Every pick list in the wave has been completed
+    Completed,
+}
+
+/// Pick lists released to the floor to be picked together
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct Wave {
+    pub wave_id: WaveId,
+    pub pick_lists: BoundedVec<PickListId, ConstU32<MAX_WAVE_LISTS>>,
+    pub status: WaveStatus,
+    pub created_at: u32,
+}
@@ -0,0 +1,261 @@
+use crate::pallet::{
+    Config, Error, PoApprovers, PoReceipts, PurchaseOrderApprovals, PurchaseOrders, Suppliers,
+};
+use crate::{pallet::Pallet, types::*};
+use frame_support::ensure;
+use frame_support::pallet_prelude::ConstU32;
+use frame_support::sp_runtime::{DispatchError, DispatchResult};
+use frame_support::storage::with_storage_layer;
+use frame_support::BoundedVec;
+use pallet_inventory::types::{
+    Employee, InventoryType, Item, Location, LotNumber, Material, PurchaseReceipts, Qty,
+    RequisitionStatus, SerialNumber, Sku,
+};
+use pallet_inventory::{Inventory, InventoryLocale, Materials, PurchaseRequisitions};
+
+impl<T: Config> Pallet<T> {
+    /// Add a supplier to the supplier master
+    pub fn do_add_supplier(supplier: Supplier) -> DispatchResult {
+        ensure!(
+            !Suppliers::<T>::contains_key(supplier.supplier_id),
+            Error::<T>::SupplierAlreadyExists
+        );
+
+        Suppliers::<T>::insert(supplier.supplier_id, supplier);
+
+        Ok(())
+    }
+
+    /// Update an existing supplier, returning the supplier as it was
+    pub fn do_update_supplier(supplier: Supplier) -> Result<Supplier, DispatchError> {
+        Suppliers::<T>::try_mutate(supplier.supplier_id, |existing| {
+            let existing = existing.as_mut().ok_or(Error::<T>::SupplierNotFound)?;
+            Ok(core::mem::replace(existing, supplier))
+        })
+    }
+
+    /// Raise a draft purchase order, converting the requisitions its lines were raised from
+    pub fn do_create_purchase_order(
+        who: &T::AccountId,
+        po_number: PurchaseOrderNumber,
+        supplier_id: SupplierId,
+        mut lines: BoundedVec<PurchaseOrderLine, ConstU32<MAX_PO_LINES>>,
+    ) -> Result<PurchaseOrder, DispatchError> {
+        ensure!(
+            !PurchaseOrders::<T>::contains_key(po_number),
+            Error::<T>::PurchaseOrderAlreadyExists
+        );
+
+        let supplier = Suppliers::<T>::get(supplier_id).ok_or(Error::<T>::SupplierNotFound)?;
+        ensure!(supplier.active, Error::<T>::SupplierInactive);
+        ensure!(!lines.is_empty(), Error::<T>::NoPurchaseOrderLines);
+
+        for line in lines.iter_mut() {
+            ensure!(line.qty > 0, Error::<T>::InvalidPurchaseOrderLine);
+            line.received_qty = 0;
+
+            if let Some(requisition_id) = line.requisition_id {
+                let requisition = PurchaseRequisitions::<T>::get(requisition_id)
+                    .ok_or(pallet_inventory::Error::<T>::RequisitionNotFound)?;
+                ensure!(
+                    requisition.sku == line.sku,
+                    Error::<T>::InvalidPurchaseOrderLine
+                );
+
+                // Fails if the requisition was already converted or cancelled
+                pallet_inventory::Pallet::<T>::do_close_requisition(
+                    requisition_id,
+                    RequisitionStatus::Converted,
+                )?;
+            }
+        }
+
+        let purchase_order = PurchaseOrder {
+            po_number,
+            supplier_id,
+            lines,
+            status: PurchaseOrderStatus::Draft,
+            created_at: pallet_inventory::Pallet::<T>::current_block(),
+            approved_at: None,
+        };
+
+        PurchaseOrders::<T>::insert(po_number, (who, purchase_order.clone()));
+
+        Ok(purchase_order)
+    }
+
+    /// Approve a draft purchase order raised by another account, as a PO approver
+    pub fn do_approve_purchase_order(
+        who: &T::AccountId,
+        po_number: PurchaseOrderNumber,
+    ) -> Result<PurchaseOrder, DispatchError> {
+        let (owner, mut purchase_order) =
+            PurchaseOrders::<T>::get(po_number).ok_or(Error::<T>::PurchaseOrderNotFound)?;
+
+        ensure!(
+            PoApprovers::<T>::contains_key(who),
+            Error::<T>::NotPoApprover
+        );
+        ensure!(&owner != who, Error::<T>::SelfApproval);
+        ensure!(
+            purchase_order.status == PurchaseOrderStatus::Draft,
+            Error::<T>::PurchaseOrderNotDraft
+        );
+
+        purchase_order.status = PurchaseOrderStatus::Approved;
+        purchase_order.approved_at = Some(pallet_inventory::Pallet::<T>::current_block());
+
+        PurchaseOrders::<T>::insert(po_number, (owner, purchase_order.clone()));
+        PurchaseOrderApprovals::<T>::insert(po_number, who);
+
+        Ok(purchase_order)
+    }
+
+    /// Cancel a purchase order that has not been received against
+    ///
+    /// Requisitions converted by the order go back to draft, so the stock they ask for can be
+    /// ordered again.
+    pub fn do_cancel_purchase_order(
+        who: &T::AccountId,
+        po_number: PurchaseOrderNumber,
+    ) -> DispatchResult {
+        let mut purchase_order = Self::owned_purchase_order(who, po_number)?;
+
+        ensure!(
+            matches!(
+                purchase_order.status,
+                PurchaseOrderStatus::Draft | PurchaseOrderStatus::Approved
+            ),
+            Error::<T>::CannotCancel
+        );
+
+        with_storage_layer(|| {
+            for requisition_id in purchase_order
+                .lines
+                .iter()
+                .filter_map(|line| line.requisition_id)
+            {
+                pallet_inventory::Pallet::<T>::do_reopen_requisition(requisition_id)?;
+            }
+
+            purchase_order.status = PurchaseOrderStatus::Cancelled;
+            PurchaseOrders::<T>::insert(po_number, (who, purchase_order));
+
+            Ok(())
+        })
+    }
+
+    /// Receive stock against a purchase order line into `Location::Receiving`
+    pub fn do_receive_po_line(
+        who: &T::AccountId,
+        po_number: PurchaseOrderNumber,
+        line_index: LineIndex,
+        serial_number: SerialNumber,
+        qty: Qty,
+        supplier_lot: LotNumber,
+        received_by: Employee,
+    ) -> Result<PoReceipt, DispatchError> {
+        let mut purchase_order = Self::owned_purchase_order(who, po_number)?;
+
+        ensure!(
+            matches!(
+                purchase_order.status,
+                PurchaseOrderStatus::Approved | PurchaseOrderStatus::PartiallyReceived
+            ),
+            Error::<T>::PurchaseOrderNotApproved
+        );
+
+        let line = purchase_order
+            .lines
+            .get_mut(line_index as usize)
+            .ok_or(Error::<T>::LineNotFound)?;
+        ensure!(qty > 0 && qty <= line.open_qty(), Error::<T>::OverReceipt);
+
+        Self::do_receive_into_receiving(
+            who,
+            line.sku.clone(),
+            serial_number,
+            qty,
+            supplier_lot,
+            received_by.clone(),
+        )?;
+
+        line.received_qty = line.received_qty.saturating_add(qty);
+        T::PurchaseReceipts::on_purchase_receipt(&line.sku, qty);
+
+        purchase_order.status = if purchase_order.lines.iter().all(|line| line.open_qty() == 0) {
+            PurchaseOrderStatus::Received
+        } else {
+            PurchaseOrderStatus::PartiallyReceived
+        };
+        PurchaseOrders::<T>::insert(po_number, (who, purchase_order));
+
+        let receipt = PoReceipt {
+            qty,
+            supplier_lot,
+            received_by,
+            received_at: pallet_inventory::Pallet::<T>::current_block(),
+        };
+        PoReceipts::<T>::insert((po_number, line_index, serial_number), receipt.clone());
+
+        Ok(receipt)
+    }
+
+    /// Insert a supplier delivery into `Location::Receiving` as raw material
+    ///
+    /// Item master data is taken from the SKU's material, when there is one.
+    pub fn do_receive_into_receiving(
+        who: &T::AccountId,
+        sku: Sku,
+        serial_number: SerialNumber,
+        qty: Qty,
+        supplier_lot: LotNumber,
+        received_by: Employee,
+    ) -> DispatchResult {
+        // Inserting would silently overwrite an item already stocked under the serial
+        ensure!(
+            !Inventory::<T>::contains_key((who, sku.clone(), serial_number))
+                && !InventoryLocale::<T>::get(Location::Receiving)
+                    .is_some_and(|items| items.contains_key(&serial_number)),
+            Error::<T>::SerialNumberInUse
+        );
+
+        let now = pallet_inventory::Pallet::<T>::current_block();
+        let material = Materials::<T>::get(&sku).unwrap_or(Material {
+            sku: sku.clone(),
+            ..Default::default()
+        });
+
+        let item = Item {
+            moved_by: received_by,
+            sku,
+            lot_number: supplier_lot,
+            serial_number,
+            abc_code: material.abc_code.clone(),
+            inventory_type: InventoryType::RawMaterial,
+            product_type: material.product_type.clone(),
+            qty,
+            weight: material.weight,
+            shelf_life: material.shelf_life,
+            cycle_count: 0,
+            created_at: now,
+            production_date: now,
+            location: Location::Receiving,
+            material,
+        };
+        pallet_inventory::Pallet::<T>::do_inventory_insertion(who, item)
+    }
+
+    /// Fetch a purchase order raised by the account
+    fn owned_purchase_order(
+        who: &T::AccountId,
+        po_number: PurchaseOrderNumber,
+    ) -> Result<PurchaseOrder, DispatchError> {
+        let (owner, purchase_order) =
+            PurchaseOrders::<T>::get(po_number).ok_or(Error::<T>::PurchaseOrderNotFound)?;
+
+        ensure!(&owner == who, Error::<T>::NotPurchaseOrderOwner);
+
+        Ok(purchase_order)
+    }
+}
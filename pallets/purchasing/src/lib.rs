@@ -0,0 +1,414 @@
+//! # Purchasing Pallet
+//!
+//! - [`Config`]
+//! - [`Call`]
+//!
+//! ## Overview
+//!
+//! The Purchasing pallet buys stock from suppliers and receives it into the inventory pallet.
+//! This pallet provides functionalities to:
+//!
+//! - Manage supplier master data.
+//! - Raise purchase orders with lines by SKU, quantity, price and due date, optionally
+//!   converting the draft purchase requisitions raised by reorder policies.
+//! - Approve purchase orders before they can be received against.
+//! - Receive purchase order lines into `Location::Receiving`, with the supplier lot recorded as
+//!   the lot number of the received item.
+//!
+//! A purchase order must be approved by one of the authorized PO approvers, other than the
+//! account that raised it. The supplier master and the approvers are managed by the configured
+//! `PurchasingAdminOrigin`. Lines can
+//! be received in several deliveries, each under its own serial, but never beyond the ordered
+//! quantity. Every receipt is reported to the configured `PurchaseReceipts`. Cancelling a
+//! purchase order returns the requisitions it converted to draft.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! - `add_supplier`: Add a supplier to the supplier master.
+//! - `update_supplier`: Update an existing supplier.
+//! - `create_purchase_order`: Raise a draft purchase order with a supplier.
+//! - `approve_purchase_order`: Approve a draft purchase order.
+//! - `cancel_purchase_order`: Cancel a purchase order that has not been received against.
+//! - `receive_po_line`: Receive stock against an approved purchase order line.
+//! - `add_po_approver`: Authorize an account to approve purchase orders.
+//! - `remove_po_approver`: Withdraw an account's authority to approve purchase orders.
+//!
+//! ## Storage
+//!
+//! - `Suppliers`: Supplier master data by supplier ID.
+//! - `PurchaseOrders`: Purchase orders, and the account that raised them, by PO number.
+//! - `PurchaseOrderApprovals`: The account that approved each purchase order.
+//! - `PoReceipts`: Stock received against each purchase order line, by serial number.
+//! - `PoApprovers`: The accounts authorized to approve purchase orders.
+//!
+//! ## Events
+//!
+//! - `SupplierAdded`: Emitted when a supplier is added.
+//! - `SupplierUpdated`: Emitted when a supplier is updated.
+//! - `PurchaseOrderCreated`: Emitted when a purchase order is raised.
+//! - `PurchaseOrderApproved`: Emitted when a purchase order is approved.
+//! - `PurchaseOrderCancelled`: Emitted when a purchase order is cancelled.
+//! - `PoLineReceived`: Emitted when stock is received against a purchase order line.
+//! - `PoApproverAdded`: Emitted when an account is authorized to approve purchase orders.
+//! - `PoApproverRemoved`: Emitted when an account can no longer approve purchase orders.
+//!
+//! ## Errors
+//!
+//! - `SupplierAlreadyExists`: Attempted to add a supplier ID that already exists.
+//! - `SupplierNotFound`: The specified supplier could not be located.
+//! - `SupplierInactive`: The supplier cannot be sent new purchase orders.
+//! - `PurchaseOrderAlreadyExists`: Attempted to raise a PO number that already exists.
+//! - `PurchaseOrderNotFound`: The specified purchase order could not be located.
+//! - `NotPurchaseOrderOwner`: Only the account that raised the purchase order can manage it.
+//! - `InvalidPurchaseOrderLine`: A line has no quantity or does not match its requisition.
+//! - `NoPurchaseOrderLines`: The purchase order has no lines.
+//! - `PurchaseOrderNotDraft`: The purchase order has already been approved or cancelled.
+//! - `SelfApproval`: The account that raised a purchase order cannot approve it.
+//! - `NotPoApprover`: The account is not authorized to approve purchase orders.
+//! - `PoApproverNotFound`: The account is not a PO approver.
+//! - `PurchaseOrderNotApproved`: The purchase order is not open for receiving.
+//! - `CannotCancel`: The purchase order has already been received against.
+//! - `LineNotFound`: The purchase order has no line at the given index.
+//! - `OverReceipt`: The receipt exceeds the quantity still open on the line.
+//! - `SerialNumberInUse`: The serial number is already in inventory or at receiving.
+//!
+//! This pallet uses `no_std` for compatibility with Wasm environments, a polkadot standard.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub use pallet::*;
+
+// FRAME pallets require their own "mock runtimes" to be able to run unit tests. This module
+// contains a mock runtime specific for testing this pallet's functionality.
+#[cfg(test)]
+mod mock;
+
+// This module contains the unit tests for this pallet.
+// Learn about pallet unit testing here: https://docs.substrate.io/test/unit-testing/
+#[cfg(test)]
+mod tests;
+
+mod blogic;
+pub mod types;
+
+use crate::types::{
+    LineIndex, PoReceipt, PurchaseOrder, PurchaseOrderLine, PurchaseOrderNumber, Supplier,
+    SupplierId, MAX_PO_LINES,
+};
+use pallet_inventory::types::{Employee, LotNumber, PurchaseReceipts, Qty, SerialNumber};
+
+#[frame_support::pallet]
+pub mod pallet {
+    use super::*;
+    use frame_support::pallet_prelude::*;
+    use frame_system::pallet_prelude::*;
+    use pallet_inventory::pallet::Config as InventoryConfig;
+
+    #[pallet::pallet]
+    pub struct Pallet<T>(_);
+
+    /// The pallet's configuration trait.
+    ///
+    /// This trait is tightly coupled with the Inventory pallet -> [InventoryConfig]
+    #[pallet::config]
+    pub trait Config: frame_system::Config + InventoryConfig {
+        type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+        /// Origin allowed to manage the supplier master and the PO approvers
+        type PurchasingAdminOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+        /// Pallets told of stock received against purchase order lines, such as MRP drawing
+        /// down its firmed purchases
+        type PurchaseReceipts: PurchaseReceipts;
+    }
+
+    /// Suppliers Storage
+    ///
+    /// Supplier master data by supplier ID
+    #[pallet::storage]
+    pub type Suppliers<T: Config> = StorageMap<_, Twox64Concat, SupplierId, Supplier, OptionQuery>;
+
+    /// Purchase Orders Storage
+    ///
+    /// Purchase orders by PO number, with the account whose inventory they are received into
+    #[pallet::storage]
+    pub type PurchaseOrders<T: Config> = StorageMap<
+        _,
+        Twox64Concat,
+        PurchaseOrderNumber,
+        (T::AccountId, PurchaseOrder),
+        OptionQuery,
+    >;
+
+    /// The account that approved each purchase order
+    #[pallet::storage]
+    pub type PurchaseOrderApprovals<T: Config> =
+        StorageMap<_, Twox64Concat, PurchaseOrderNumber, T::AccountId, OptionQuery>;
+
+    /// PO Receipts Storage
+    ///
+    /// Stock received against each purchase order line, by the serial it was received under
+    #[pallet::storage]
+    pub type PoReceipts<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Twox64Concat, PurchaseOrderNumber>,
+            NMapKey<Twox64Concat, LineIndex>,
+            NMapKey<Blake2_128Concat, SerialNumber>,
+        ),
+        PoReceipt,
+        OptionQuery,
+    >;
+
+    /// The accounts authorized to approve purchase orders
+    #[pallet::storage]
+    pub type PoApprovers<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, (), OptionQuery>;
+
+    /// Events that functions in this pallet can emit.
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config> {
+        SupplierAdded {
+            supplier: Supplier,
+        },
+        SupplierUpdated {
+            original_supplier: Supplier,
+            new_supplier: Supplier,
+        },
+        PurchaseOrderCreated {
+            who: T::AccountId,
+            purchase_order: PurchaseOrder,
+        },
+        PurchaseOrderApproved {
+            who: T::AccountId,
+            po_number: PurchaseOrderNumber,
+            total_value: u128,
+        },
+        PurchaseOrderCancelled {
+            who: T::AccountId,
+            po_number: PurchaseOrderNumber,
+        },
+        /// Stock has been received into `Location::Receiving` against a purchase order line
+        PoLineReceived {
+            who: T::AccountId,
+            po_number: PurchaseOrderNumber,
+            line_index: LineIndex,
+            serial_number: SerialNumber,
+            receipt: PoReceipt,
+        },
+        PoApproverAdded {
+            approver: T::AccountId,
+        },
+        PoApproverRemoved {
+            approver: T::AccountId,
+        },
+    }
+
+    /// Errors that can be returned by this pallet.
+    #[pallet::error]
+    pub enum Error<T> {
+        /// The supplier already exists
+        SupplierAlreadyExists,
+        /// The supplier was not found
+        SupplierNotFound,
+        /// The supplier is inactive
+        SupplierInactive,
+        /// A purchase order with the same number already exists
+        PurchaseOrderAlreadyExists,
+        /// The purchase order was not found
+        PurchaseOrderNotFound,
+        /// The purchase order was raised by another account
+        NotPurchaseOrderOwner,
+        /// Every line must order a quantity, of the SKU its requisition asks for
+        InvalidPurchaseOrderLine,
+        /// The purchase order has no lines
+        NoPurchaseOrderLines,
+        /// The purchase order is no longer a draft
+        PurchaseOrderNotDraft,
+        /// A purchase order cannot be approved by the account that raised it
+        SelfApproval,
+        /// The account is not authorized to approve purchase orders
+        NotPoApprover,
+        /// The account is not a PO approver
+        PoApproverNotFound,
+        /// The purchase order is not approved, or is already fully received or cancelled
+        PurchaseOrderNotApproved,
+        /// The purchase order has already been received against
+        CannotCancel,
+        /// The purchase order has no line at the given index
+        LineNotFound,
+        /// The receipt exceeds the quantity still open on the line
+        OverReceipt,
+        /// The serial number is already in inventory or at receiving
+        SerialNumberInUse,
+    }
+
+    /// The pallet's dispatchable functions
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Add a supplier to the supplier master
+        #[pallet::call_index(0)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn add_supplier(origin: OriginFor<T>, supplier: Supplier) -> DispatchResult {
+            T::PurchasingAdminOrigin::ensure_origin(origin)?;
+
+            Self::do_add_supplier(supplier.clone())?;
+
+            Self::deposit_event(Event::SupplierAdded { supplier });
+
+            Ok(())
+        }
+
+        /// Update an existing supplier
+        #[pallet::call_index(1)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn update_supplier(origin: OriginFor<T>, supplier: Supplier) -> DispatchResult {
+            T::PurchasingAdminOrigin::ensure_origin(origin)?;
+
+            let original_supplier = Self::do_update_supplier(supplier.clone())?;
+
+            Self::deposit_event(Event::SupplierUpdated {
+                original_supplier,
+                new_supplier: supplier,
+            });
+
+            Ok(())
+        }
+
+        /// Raise a draft purchase order with a supplier
+        ///
+        /// Lines that reference a draft purchase requisition convert it. Received quantities
+        /// start at zero whatever is submitted.
+        #[pallet::call_index(2)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn create_purchase_order(
+            origin: OriginFor<T>,
+            po_number: PurchaseOrderNumber,
+            supplier_id: SupplierId,
+            lines: BoundedVec<PurchaseOrderLine, ConstU32<MAX_PO_LINES>>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let purchase_order =
+                Self::do_create_purchase_order(&who, po_number, supplier_id, lines)?;
+
+            Self::deposit_event(Event::PurchaseOrderCreated {
+                who,
+                purchase_order,
+            });
+
+            Ok(())
+        }
+
+        /// Approve a draft purchase order, so that it can be received against
+        ///
+        /// Only a PO approver other than the account that raised the order can approve it.
+        #[pallet::call_index(3)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn approve_purchase_order(
+            origin: OriginFor<T>,
+            po_number: PurchaseOrderNumber,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let purchase_order = Self::do_approve_purchase_order(&who, po_number)?;
+
+            Self::deposit_event(Event::PurchaseOrderApproved {
+                who,
+                po_number,
+                total_value: purchase_order.total_value(),
+            });
+
+            Ok(())
+        }
+
+        /// Cancel a purchase order that has not been received against
+        ///
+        /// The requisitions its lines converted go back to draft.
+        #[pallet::call_index(4)]
+        #[pallet::weight(
+            Weight::from_parts(10_000, 0)
+                + T::DbWeight::get().reads_writes(1, 1)
+                + T::DbWeight::get().reads_writes(2, 2).saturating_mul(MAX_PO_LINES as u64)
+        )]
+        pub fn cancel_purchase_order(
+            origin: OriginFor<T>,
+            po_number: PurchaseOrderNumber,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            Self::do_cancel_purchase_order(&who, po_number)?;
+
+            Self::deposit_event(Event::PurchaseOrderCancelled { who, po_number });
+
+            Ok(())
+        }
+
+        /// Receive stock against an approved purchase order line
+        ///
+        /// The stock is inserted into inventory at `Location::Receiving` under the given serial,
+        /// with the supplier lot as its lot number.
+        #[pallet::call_index(5)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn receive_po_line(
+            origin: OriginFor<T>,
+            po_number: PurchaseOrderNumber,
+            line_index: LineIndex,
+            serial_number: SerialNumber,
+            qty: Qty,
+            supplier_lot: LotNumber,
+            received_by: Employee,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let receipt = Self::do_receive_po_line(
+                &who,
+                po_number,
+                line_index,
+                serial_number,
+                qty,
+                supplier_lot,
+                received_by,
+            )?;
+
+            Self::deposit_event(Event::PoLineReceived {
+                who,
+                po_number,
+                line_index,
+                serial_number,
+                receipt,
+            });
+
+            Ok(())
+        }
+
+        /// Authorize an account to approve purchase orders
+        #[pallet::call_index(6)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn add_po_approver(origin: OriginFor<T>, approver: T::AccountId) -> DispatchResult {
+            T::PurchasingAdminOrigin::ensure_origin(origin)?;
+
+            PoApprovers::<T>::insert(&approver, ());
+
+            Self::deposit_event(Event::PoApproverAdded { approver });
+
+            Ok(())
+        }
+
+        /// Withdraw an account's authority to approve purchase orders
+        ///
+        /// Purchase orders it has already approved stay approved.
+        #[pallet::call_index(7)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn remove_po_approver(origin: OriginFor<T>, approver: T::AccountId) -> DispatchResult {
+            T::PurchasingAdminOrigin::ensure_origin(origin)?;
+
+            PoApprovers::<T>::take(&approver).ok_or(Error::<T>::PoApproverNotFound)?;
+
+            Self::deposit_event(Event::PoApproverRemoved { approver });
+
+            Ok(())
+        }
+    }
+}
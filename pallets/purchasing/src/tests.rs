@@ -0,0 +1,512 @@
+use crate::{mock::*, types::*, Error, Event};
+use frame_support::pallet_prelude::ConstU32;
+use frame_support::sp_runtime::{DispatchError, DispatchResult};
+use frame_support::{assert_noop, assert_ok, BoundedVec};
+use pallet_inventory::types::{
+    Employee, InventoryType, Location, Material, Qty, ReorderPolicy, RequisitionStatus, Sku,
+};
+
+const ALICE: u64 = 1;
+const BOB: u64 = 2;
+const CHARLIE: u64 = 3;
+
+fn sku(sku: &str) -> Sku {
+    BoundedVec::truncate_from(sku.as_bytes().to_vec())
+}
+
+fn supplier(supplier_id: SupplierId, active: bool) -> Supplier {
+    Supplier {
+        supplier_id,
+        name: BoundedVec::truncate_from(b"Acme Fasteners".to_vec()),
+        lead_time: 10,
+        active,
+    }
+}
+
+fn line(sku_name: &str, qty: Qty, unit_price: UnitPrice) -> PurchaseOrderLine {
+    PurchaseOrderLine {
+        sku: sku(sku_name),
+        qty,
+        unit_price,
+        due_date: 20,
+        ..Default::default()
+    }
+}
+
+fn lines(lines: Vec<PurchaseOrderLine>) -> BoundedVec<PurchaseOrderLine, ConstU32<MAX_PO_LINES>> {
+    BoundedVec::truncate_from(lines)
+}
+
+/// Add supplier 0 and raise purchase order 0 with it as Alice, with Bob as a PO approver
+fn set_up(po_lines: Vec<PurchaseOrderLine>) {
+    assert_ok!(Purchasing::add_supplier(
+        RuntimeOrigin::root(),
+        supplier(0, true)
+    ));
+    assert_ok!(Purchasing::add_po_approver(RuntimeOrigin::root(), BOB));
+    assert_ok!(Purchasing::create_purchase_order(
+        RuntimeOrigin::signed(ALICE),
+        0,
+        0,
+        lines(po_lines)
+    ));
+}
+
+/// Raise purchase order 0 for ten bolts and have Bob approve it
+fn set_up_approved() {
+    set_up(vec![line("BOLT", 10, 25)]);
+    assert_ok!(Purchasing::approve_purchase_order(
+        RuntimeOrigin::signed(BOB),
+        0
+    ));
+}
+
+fn receive(serial_number: u32, qty: Qty) -> DispatchResult {
+    Purchasing::receive_po_line(
+        RuntimeOrigin::signed(ALICE),
+        0,
+        0,
+        serial_number,
+        qty,
+        77,
+        Employee::Charlie,
+    )
+}
+
+fn status(po_number: PurchaseOrderNumber) -> PurchaseOrderStatus {
+    crate::PurchaseOrders::<Test>::get(po_number)
+        .unwrap()
+        .1
+        .status
+}
+
+#[test]
+fn suppliers_are_managed_by_the_admin_origin() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Purchasing::add_supplier(RuntimeOrigin::signed(ALICE), supplier(0, true)),
+            DispatchError::BadOrigin
+        );
+
+        assert_ok!(Purchasing::add_supplier(
+            RuntimeOrigin::root(),
+            supplier(0, true)
+        ));
+        assert_eq!(crate::Suppliers::<Test>::get(0), Some(supplier(0, true)));
+        assert_noop!(
+            Purchasing::add_supplier(RuntimeOrigin::root(), supplier(0, false)),
+            Error::<Test>::SupplierAlreadyExists
+        );
+
+        assert_ok!(Purchasing::update_supplier(
+            RuntimeOrigin::root(),
+            supplier(0, false)
+        ));
+        System::assert_last_event(
+            Event::SupplierUpdated {
+                original_supplier: supplier(0, true),
+                new_supplier: supplier(0, false),
+            }
+            .into(),
+        );
+        assert_noop!(
+            Purchasing::update_supplier(RuntimeOrigin::root(), supplier(1, true)),
+            Error::<Test>::SupplierNotFound
+        );
+    });
+}
+
+#[test]
+fn purchase_orders_start_as_drafts_with_nothing_received() {
+    new_test_ext().execute_with(|| {
+        let mut received = line("BOLT", 10, 25);
+        received.received_qty = 4;
+        set_up(vec![received, line("NUT", 20, 5)]);
+
+        let (owner, purchase_order) = crate::PurchaseOrders::<Test>::get(0).unwrap();
+        assert_eq!(owner, ALICE);
+        assert_eq!(purchase_order.status, PurchaseOrderStatus::Draft);
+        assert_eq!(purchase_order.created_at, 1);
+        assert_eq!(purchase_order.lines[0].received_qty, 0);
+        assert_eq!(purchase_order.total_value(), 350);
+        System::assert_last_event(
+            Event::PurchaseOrderCreated {
+                who: ALICE,
+                purchase_order,
+            }
+            .into(),
+        );
+
+        assert_noop!(
+            Purchasing::create_purchase_order(
+                RuntimeOrigin::signed(ALICE),
+                0,
+                0,
+                lines(vec![line("BOLT", 1, 25)])
+            ),
+            Error::<Test>::PurchaseOrderAlreadyExists
+        );
+    });
+}
+
+#[test]
+fn purchase_orders_need_an_active_supplier_and_lines() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Purchasing::create_purchase_order(
+                RuntimeOrigin::signed(ALICE),
+                0,
+                0,
+                lines(vec![line("BOLT", 10, 25)])
+            ),
+            Error::<Test>::SupplierNotFound
+        );
+
+        assert_ok!(Purchasing::add_supplier(
+            RuntimeOrigin::root(),
+            supplier(0, true)
+        ));
+        assert_ok!(Purchasing::add_supplier(
+            RuntimeOrigin::root(),
+            supplier(1, false)
+        ));
+
+        assert_noop!(
+            Purchasing::create_purchase_order(
+                RuntimeOrigin::signed(ALICE),
+                0,
+                1,
+                lines(vec![line("BOLT", 10, 25)])
+            ),
+            Error::<Test>::SupplierInactive
+        );
+        assert_noop!(
+            Purchasing::create_purchase_order(RuntimeOrigin::signed(ALICE), 0, 0, lines(vec![])),
+            Error::<Test>::NoPurchaseOrderLines
+        );
+        assert_noop!(
+            Purchasing::create_purchase_order(
+                RuntimeOrigin::signed(ALICE),
+                0,
+                0,
+                lines(vec![line("BOLT", 0, 25)])
+            ),
+            Error::<Test>::InvalidPurchaseOrderLine
+        );
+    });
+}
+
+#[test]
+fn lines_convert_the_requisitions_they_were_raised_from() {
+    new_test_ext().execute_with(|| {
+        // Bolts without stock are reordered straight away
+        assert_ok!(Inventory::insert_material(
+            RuntimeOrigin::signed(ALICE),
+            Material {
+                sku: sku("BOLT"),
+                ..Default::default()
+            }
+        ));
+        assert_ok!(Inventory::set_reorder_policy(
+            RuntimeOrigin::root(),
+            sku("BOLT"),
+            ReorderPolicy {
+                reorder_point: 10,
+                safety_stock: 0,
+                reorder_qty: 20,
+                create_requisition: true,
+            }
+        ));
+        assert_ok!(Purchasing::add_supplier(
+            RuntimeOrigin::root(),
+            supplier(0, true)
+        ));
+
+        let mut requisitioned = line("NUT", 20, 5);
+        requisitioned.requisition_id = Some(0);
+        assert_noop!(
+            Purchasing::create_purchase_order(
+                RuntimeOrigin::signed(ALICE),
+                0,
+                0,
+                lines(vec![requisitioned.clone()])
+            ),
+            Error::<Test>::InvalidPurchaseOrderLine
+        );
+
+        requisitioned.sku = sku("BOLT");
+        assert_ok!(Purchasing::create_purchase_order(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            0,
+            lines(vec![requisitioned.clone()])
+        ));
+        assert_eq!(
+            pallet_inventory::PurchaseRequisitions::<Test>::get(0)
+                .unwrap()
+                .status,
+            RequisitionStatus::Converted
+        );
+        assert_eq!(
+            pallet_inventory::OpenRequisitions::<Test>::get(sku("BOLT")),
+            None
+        );
+
+        assert_noop!(
+            Purchasing::create_purchase_order(
+                RuntimeOrigin::signed(ALICE),
+                1,
+                0,
+                lines(vec![requisitioned.clone()])
+            ),
+            pallet_inventory::Error::<Test>::RequisitionNotDraft
+        );
+        requisitioned.requisition_id = Some(1);
+        assert_noop!(
+            Purchasing::create_purchase_order(
+                RuntimeOrigin::signed(ALICE),
+                1,
+                0,
+                lines(vec![requisitioned.clone()])
+            ),
+            pallet_inventory::Error::<Test>::RequisitionNotFound
+        );
+
+        // Cancelling the order reopens the requisition, to be converted again
+        assert_ok!(Purchasing::cancel_purchase_order(
+            RuntimeOrigin::signed(ALICE),
+            0
+        ));
+        assert_eq!(
+            pallet_inventory::PurchaseRequisitions::<Test>::get(0)
+                .unwrap()
+                .status,
+            RequisitionStatus::Draft
+        );
+        assert_eq!(
+            pallet_inventory::OpenRequisitions::<Test>::get(sku("BOLT")),
+            Some(0)
+        );
+
+        requisitioned.requisition_id = Some(0);
+        assert_ok!(Purchasing::create_purchase_order(
+            RuntimeOrigin::signed(ALICE),
+            1,
+            0,
+            lines(vec![requisitioned])
+        ));
+    });
+}
+
+#[test]
+fn purchase_orders_are_approved_by_another_po_approver() {
+    new_test_ext().execute_with(|| {
+        set_up(vec![line("BOLT", 10, 25)]);
+
+        assert_noop!(
+            Purchasing::approve_purchase_order(RuntimeOrigin::signed(CHARLIE), 0),
+            Error::<Test>::NotPoApprover
+        );
+        assert_ok!(Purchasing::add_po_approver(RuntimeOrigin::root(), ALICE));
+        assert_noop!(
+            Purchasing::approve_purchase_order(RuntimeOrigin::signed(ALICE), 0),
+            Error::<Test>::SelfApproval
+        );
+        assert_noop!(
+            Purchasing::approve_purchase_order(RuntimeOrigin::signed(BOB), 1),
+            Error::<Test>::PurchaseOrderNotFound
+        );
+
+        System::set_block_number(4);
+        assert_ok!(Purchasing::approve_purchase_order(
+            RuntimeOrigin::signed(BOB),
+            0
+        ));
+
+        let (_, purchase_order) = crate::PurchaseOrders::<Test>::get(0).unwrap();
+        assert_eq!(purchase_order.status, PurchaseOrderStatus::Approved);
+        assert_eq!(purchase_order.approved_at, Some(4));
+        assert_eq!(crate::PurchaseOrderApprovals::<Test>::get(0), Some(BOB));
+        System::assert_last_event(
+            Event::PurchaseOrderApproved {
+                who: BOB,
+                po_number: 0,
+                total_value: 250,
+            }
+            .into(),
+        );
+
+        assert_noop!(
+            Purchasing::approve_purchase_order(RuntimeOrigin::signed(BOB), 0),
+            Error::<Test>::PurchaseOrderNotDraft
+        );
+    });
+}
+
+#[test]
+fn po_approvers_are_managed_by_the_admin_origin() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Purchasing::add_po_approver(RuntimeOrigin::signed(ALICE), BOB),
+            DispatchError::BadOrigin
+        );
+        assert_ok!(Purchasing::add_po_approver(RuntimeOrigin::root(), BOB));
+        assert!(crate::PoApprovers::<Test>::contains_key(BOB));
+
+        assert_noop!(
+            Purchasing::remove_po_approver(RuntimeOrigin::signed(ALICE), BOB),
+            DispatchError::BadOrigin
+        );
+        assert_ok!(Purchasing::remove_po_approver(RuntimeOrigin::root(), BOB));
+        assert!(!crate::PoApprovers::<Test>::contains_key(BOB));
+        System::assert_last_event(Event::PoApproverRemoved { approver: BOB }.into());
+
+        assert_noop!(
+            Purchasing::remove_po_approver(RuntimeOrigin::root(), BOB),
+            Error::<Test>::PoApproverNotFound
+        );
+    });
+}
+
+#[test]
+fn receiving_stocks_the_supplier_lot_at_receiving() {
+    new_test_ext().execute_with(|| {
+        set_up_approved();
+
+        assert_ok!(receive(1, 4));
+
+        let item = pallet_inventory::Inventory::<Test>::get((ALICE, sku("BOLT"), 1)).unwrap();
+        assert_eq!(item.qty, 4);
+        assert_eq!(item.lot_number, 77);
+        assert_eq!(item.location, Location::Receiving);
+        assert_eq!(item.inventory_type, InventoryType::RawMaterial);
+        assert_eq!(item.moved_by, Employee::Charlie);
+        assert_eq!(
+            pallet_inventory::GlobalInventory::<Test>::get(sku("BOLT")),
+            Some(4)
+        );
+
+        let receipt = PoReceipt {
+            qty: 4,
+            supplier_lot: 77,
+            received_by: Employee::Charlie,
+            received_at: 1,
+        };
+        assert_eq!(
+            crate::PoReceipts::<Test>::get((0, 0, 1)),
+            Some(receipt.clone())
+        );
+        assert_eq!(status(0), PurchaseOrderStatus::PartiallyReceived);
+        System::assert_last_event(
+            Event::PoLineReceived {
+                who: ALICE,
+                po_number: 0,
+                line_index: 0,
+                serial_number: 1,
+                receipt,
+            }
+            .into(),
+        );
+
+        assert_ok!(receive(2, 6));
+        assert_eq!(status(0), PurchaseOrderStatus::Received);
+        assert_noop!(receive(3, 1), Error::<Test>::PurchaseOrderNotApproved);
+    });
+}
+
+#[test]
+fn receipts_are_limited_to_the_open_line_quantity() {
+    new_test_ext().execute_with(|| {
+        set_up_approved();
+        assert_ok!(receive(1, 4));
+
+        assert_noop!(receive(2, 7), Error::<Test>::OverReceipt);
+        assert_noop!(receive(2, 0), Error::<Test>::OverReceipt);
+        assert_noop!(receive(1, 1), Error::<Test>::SerialNumberInUse);
+        assert_noop!(
+            Purchasing::receive_po_line(
+                RuntimeOrigin::signed(ALICE),
+                0,
+                1,
+                2,
+                1,
+                77,
+                Employee::Charlie
+            ),
+            Error::<Test>::LineNotFound
+        );
+        assert_noop!(
+            Purchasing::receive_po_line(
+                RuntimeOrigin::signed(BOB),
+                0,
+                0,
+                2,
+                1,
+                77,
+                Employee::Charlie
+            ),
+            Error::<Test>::NotPurchaseOrderOwner
+        );
+    });
+}
+
+#[test]
+fn only_approved_purchase_orders_are_received() {
+    new_test_ext().execute_with(|| {
+        set_up(vec![line("BOLT", 10, 25)]);
+
+        assert_noop!(receive(1, 4), Error::<Test>::PurchaseOrderNotApproved);
+    });
+}
+
+#[test]
+fn purchase_orders_are_cancelled_until_received_against() {
+    new_test_ext().execute_with(|| {
+        set_up(vec![line("BOLT", 10, 25)]);
+
+        assert_noop!(
+            Purchasing::cancel_purchase_order(RuntimeOrigin::signed(BOB), 0),
+            Error::<Test>::NotPurchaseOrderOwner
+        );
+        assert_ok!(Purchasing::cancel_purchase_order(
+            RuntimeOrigin::signed(ALICE),
+            0
+        ));
+        assert_eq!(status(0), PurchaseOrderStatus::Cancelled);
+        System::assert_last_event(
+            Event::PurchaseOrderCancelled {
+                who: ALICE,
+                po_number: 0,
+            }
+            .into(),
+        );
+        assert_noop!(
+            Purchasing::cancel_purchase_order(RuntimeOrigin::signed(ALICE), 0),
+            Error::<Test>::CannotCancel
+        );
+
+        assert_ok!(Purchasing::create_purchase_order(
+            RuntimeOrigin::signed(ALICE),
+            1,
+            0,
+            lines(vec![line("BOLT", 10, 25)])
+        ));
+        assert_ok!(Purchasing::approve_purchase_order(
+            RuntimeOrigin::signed(BOB),
+            1
+        ));
+        assert_ok!(Purchasing::receive_po_line(
+            RuntimeOrigin::signed(ALICE),
+            1,
+            0,
+            1,
+            4,
+            77,
+            Employee::Charlie
+        ));
+        assert_noop!(
+            Purchasing::cancel_purchase_order(RuntimeOrigin::signed(ALICE), 1),
+            Error::<Test>::CannotCancel
+        );
+    });
+}
@@ -0,0 +1,87 @@
+use codec::{Decode, Encode};
+use frame_support::pallet_prelude::{ConstU32, MaxEncodedLen};
+use frame_support::BoundedVec;
+use pallet_inventory::types::{Employee, LotNumber, Qty, RequisitionId, Sku};
+use scale_info::TypeInfo;
+
+pub type SupplierId = u32;
+pub type PurchaseOrderNumber = u32;
+/// Index of a line within its purchase order
+pub type LineIndex = u8;
+/// Price per unit, in the smallest unit of the purchasing currency
+pub type UnitPrice = u64;
+pub type SupplierName = BoundedVec<u8, ConstU32<64>>;
+
+/// Most lines a single purchase order can hold
+pub const MAX_PO_LINES: u32 = 50;
+
+/// Supplier master data
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
+pub struct Supplier {
+    pub supplier_id: SupplierId,
+    pub name: SupplierName,
+    /// Blocks the supplier usually takes to deliver
+    pub lead_time: u32,
+    /// Inactive suppliers cannot be sent new purchase orders
+    pub active: bool,
+}
+
+/// A SKU ordered on a purchase order
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
+pub struct PurchaseOrderLine {
+    pub sku: Sku,
+    pub qty: Qty,
+    pub unit_price: UnitPrice,
+    /// Block by which the supplier should deliver
+    pub due_date: u32,
+    pub received_qty: Qty,
+    /// The draft purchase requisition the line was raised from, converted with the order
+    pub requisition_id: Option<RequisitionId>,
+}
+
+impl PurchaseOrderLine {
+    /// Quantity still to be delivered
+    pub fn open_qty(&self) -> Qty {
+        self.qty.saturating_sub(self.received_qty)
+    }
+}
+
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
+pub enum PurchaseOrderStatus {
+    #[default]
+    Draft,
+    /// Approved and sent to the supplier, ready to receive against
+    Approved,
+    PartiallyReceived,
+    Received,
+    Cancelled,
+}
+
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct PurchaseOrder {
+    pub po_number: PurchaseOrderNumber,
+    pub supplier_id: SupplierId,
+    pub lines: BoundedVec<PurchaseOrderLine, ConstU32<MAX_PO_LINES>>,
+    pub status: PurchaseOrderStatus,
+    pub created_at: u32,
+    pub approved_at: Option<u32>,
+}
+
+impl PurchaseOrder {
+    /// Total value of the order, across every line
+    pub fn total_value(&self) -> u128 {
+        self.lines.iter().fold(0u128, |total, line| {
+            total.saturating_add((line.unit_price as u128).saturating_mul(line.qty as u128))
+        })
+    }
+}
+
+/// Stock received against a purchase order line under a single serial
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct PoReceipt {
+    pub qty: Qty,
+    /// The supplier's lot, recorded as the lot number of the received item
+    pub supplier_lot: LotNumber,
+    pub received_by: Employee,
+    pub received_at: u32,
+}
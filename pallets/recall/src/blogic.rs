@@ -0,0 +1,247 @@
+use crate::pallet::{Config, Error, NextRecallId, RecallItems, Recalls};
+use crate::{pallet::Pallet, types::*};
+use frame_support::ensure;
+use frame_support::sp_runtime::{DispatchError, DispatchResult};
+use pallet_inventory::types::{HoldSource, LotNumber, Reason, SerialNumber, Sku};
+use pallet_inventory::{Inventory, TransferredItems};
+use scale_info::prelude::vec::Vec;
+
+/// An item in an account's inventory
+type HeldItem<T> = (<T as frame_system::Config>::AccountId, Sku, SerialNumber);
+
+/// An item a recall reaches, whether to walk its genealogy in the account holding it, and the
+/// lot it must belong to for a lot recall
+type RecallCandidate<T> = (HeldItem<T>, bool, Option<LotNumber>);
+
+impl<T: Config> Pallet<T> {
+    /// Open a recall and place every affected item on hold
+    ///
+    /// The affected items are the suspect stock itself plus every assembly it went into, at any
+    /// level, that is still in inventory, whether in the account's own or in the accounts it was
+    /// transferred to.
+    pub fn do_initiate_recall(
+        who: &T::AccountId,
+        source: RecallSource,
+        reason: Reason,
+    ) -> Result<Recall, DispatchError> {
+        let recall_id = NextRecallId::<T>::get();
+        NextRecallId::<T>::put(recall_id.saturating_add(1));
+
+        let affected = Self::affected_items(who, &source)?;
+
+        for (holder, sku, serial_number) in affected.iter() {
+            pallet_inventory::Pallet::<T>::do_place_hold(
+                holder,
+                sku.clone(),
+                *serial_number,
+                HoldSource::Recall(recall_id),
+                reason.clone(),
+            )?;
+            RecallItems::<T>::insert(
+                (recall_id, holder, sku.clone(), *serial_number),
+                RecallItem::default(),
+            );
+        }
+
+        let recall = Recall {
+            recall_id,
+            source,
+            reason,
+            status: RecallStatus::Open,
+            opened_at: pallet_inventory::Pallet::<T>::current_block(),
+            closed_at: None,
+            affected_items: affected.len() as u32,
+            resolved_items: 0,
+        };
+
+        Recalls::<T>::insert(recall_id, (who, recall.clone()));
+
+        Ok(recall)
+    }
+
+    /// Release or scrap an item held by a recall in the `holder` account's inventory
+    pub fn do_resolve_recall_item(
+        who: &T::AccountId,
+        recall_id: RecallId,
+        holder: &T::AccountId,
+        sku: Sku,
+        serial_number: SerialNumber,
+        resolution: RecallResolution,
+    ) -> Result<RecallItem, DispatchError> {
+        let mut recall = Self::open_recall(who, recall_id)?;
+
+        let mut item = RecallItems::<T>::get((recall_id, holder, sku.clone(), serial_number))
+            .ok_or(Error::<T>::ItemNotAffected)?;
+        ensure!(
+            item.status == RecallItemStatus::OnHold,
+            Error::<T>::ItemAlreadyResolved
+        );
+
+        pallet_inventory::Pallet::<T>::do_release_hold(
+            holder,
+            sku.clone(),
+            serial_number,
+            HoldSource::Recall(recall_id),
+        );
+
+        item.status = match resolution {
+            RecallResolution::Release => RecallItemStatus::Released,
+            RecallResolution::Scrap(details) => {
+                // Fails if the item is still held by another source
+                pallet_inventory::Pallet::<T>::do_inventory_scrap(
+                    holder,
+                    sku.clone(),
+                    serial_number,
+                    details,
+                )?;
+                RecallItemStatus::Scrapped
+            }
+        };
+        item.resolved_at = Some(pallet_inventory::Pallet::<T>::current_block());
+        RecallItems::<T>::insert((recall_id, holder, sku, serial_number), item.clone());
+
+        recall.resolved_items = recall.resolved_items.saturating_add(1);
+        Recalls::<T>::insert(recall_id, (who, recall));
+
+        Ok(item)
+    }
+
+    /// Close a recall once none of its items are on hold any more
+    pub fn do_close_recall(who: &T::AccountId, recall_id: RecallId) -> DispatchResult {
+        let mut recall = Self::open_recall(who, recall_id)?;
+
+        ensure!(
+            recall.resolved_items >= recall.affected_items,
+            Error::<T>::UnresolvedItems
+        );
+
+        recall.status = RecallStatus::Closed;
+        recall.closed_at = Some(pallet_inventory::Pallet::<T>::current_block());
+        Recalls::<T>::insert(recall_id, (who, recall));
+
+        Ok(())
+    }
+
+    /// Fetch a recall that is still open and owned by the account
+    fn open_recall(who: &T::AccountId, recall_id: RecallId) -> Result<Recall, DispatchError> {
+        let (owner, recall) = Recalls::<T>::get(recall_id).ok_or(Error::<T>::RecallNotFound)?;
+
+        ensure!(&owner == who, Error::<T>::NotRecallOwner);
+        ensure!(
+            recall.status == RecallStatus::Open,
+            Error::<T>::RecallClosed
+        );
+
+        Ok(recall)
+    }
+
+    /// The suspect stock and every assembly it went into that is still in inventory
+    ///
+    /// Serials split off a suspect serial are suspect too, and items received by other accounts
+    /// are followed there, along with the assemblies those accounts built from them. Items drawn
+    /// down to nothing are left out. Fails if more than `MAX_RECALL_ITEMS` items of the lot or
+    /// serial, and assemblies they went into, would be reached.
+    fn affected_items(
+        who: &T::AccountId,
+        source: &RecallSource,
+    ) -> Result<Vec<HeldItem<T>>, DispatchError> {
+        let held = |sku: &Sku, serial_number: SerialNumber| -> HeldItem<T> {
+            (who.clone(), sku.clone(), serial_number)
+        };
+
+        let (mut frontier, links): (Vec<RecallCandidate<T>>, _) = match source {
+            RecallSource::Lot { sku, lot_number } => {
+                // Only serials of the lot count toward the limit, stock of other lots is skipped
+                let scanned: Vec<_> = Inventory::<T>::iter_prefix((who, sku.clone()))
+                    .filter(|(_, item)| item.lot_number == *lot_number)
+                    .map(|(serial_number, _)| serial_number)
+                    .take(MAX_RECALL_ITEMS as usize + 1)
+                    .collect();
+                // Shipped serials are checked against the lot of the stock their receiver holds
+                let shipped: Vec<_> = TransferredItems::<T>::iter_prefix((who, sku.clone()))
+                    .filter(|(serial_number, receiver)| {
+                        Inventory::<T>::get((receiver, sku.clone(), *serial_number))
+                            .map_or(true, |item| item.lot_number == *lot_number)
+                    })
+                    .map(|(serial_number, _)| serial_number)
+                    .take(MAX_RECALL_ITEMS as usize + 1)
+                    .collect();
+                ensure!(
+                    scanned.len() + shipped.len() <= MAX_RECALL_ITEMS as usize,
+                    Error::<T>::TooManyAffectedItems
+                );
+
+                // Lot genealogy already covers every level in the account
+                (
+                    scanned
+                        .into_iter()
+                        .chain(shipped)
+                        .map(|serial_number| (held(sku, serial_number), false, Some(*lot_number)))
+                        .collect(),
+                    pallet_assembly::Pallet::<T>::where_used_lot(who, sku.clone(), *lot_number),
+                )
+            }
+            RecallSource::Serial { sku, serial_number } => (
+                core::iter::once(*serial_number)
+                    .chain(pallet_inventory::Pallet::<T>::split_descendants(
+                        who,
+                        sku,
+                        *serial_number,
+                    ))
+                    .map(|serial_number| (held(sku, serial_number), false, None))
+                    .collect(),
+                pallet_assembly::Pallet::<T>::where_used_serial(who, sku.clone(), *serial_number),
+            ),
+        };
+        frontier.extend(links.into_iter().map(|link| {
+            (
+                held(&link.parent.sku, link.parent.serial_number),
+                false,
+                None,
+            )
+        }));
+
+        let mut reached: Vec<HeldItem<T>> = Vec::new();
+        let mut affected: Vec<HeldItem<T>> = Vec::new();
+        while let Some((candidate, walk_genealogy, lot_number)) = frontier.pop() {
+            if reached.contains(&candidate) {
+                continue;
+            }
+            ensure!(
+                reached.len() < MAX_RECALL_ITEMS as usize,
+                Error::<T>::TooManyAffectedItems
+            );
+            reached.push(candidate.clone());
+
+            let (holder, sku, serial_number) = candidate.clone();
+            if let Some(item) = Inventory::<T>::get((&holder, sku.clone(), serial_number)) {
+                if lot_number.is_some_and(|lot_number| item.lot_number != lot_number) {
+                    continue;
+                }
+                // Assemblies already shipped or consumed further up are no longer in inventory
+                if item.qty > 0 {
+                    affected.push(candidate);
+                }
+                if walk_genealogy {
+                    for link in pallet_assembly::Pallet::<T>::where_used_serial(
+                        &holder,
+                        sku.clone(),
+                        serial_number,
+                    ) {
+                        let parent = (holder.clone(), link.parent.sku, link.parent.serial_number);
+                        frontier.push((parent, false, None));
+                    }
+                }
+            }
+
+            // Stock received by another account is followed there
+            if let Some(receiver) =
+                TransferredItems::<T>::get((&holder, sku.clone(), serial_number))
+            {
+                frontier.push(((receiver, sku, serial_number), true, lot_number));
+            }
+        }
+
+        Ok(affected)
+    }
+}
@@ -0,0 +1,236 @@
+//! # Recall Pallet
+//!
+//! - [`Config`]
+//! - [`Call`]
+//!
+//! ## Overview
+//!
+//! The Recall pallet freezes stock affected by a suspect lot or serial. It walks the assembly
+//! genealogy recorded by the assembly pallet to find every affected item still in inventory,
+//! following stock transferred to other accounts into their inventory.
+//! This pallet provides functionalities to:
+//!
+//! - Open a recall from a lot or a serial number.
+//! - Place the suspect stock, and every assembly it went into, on quality hold.
+//! - Resolve each affected item by releasing or scrapping it.
+//! - Close the recall once every affected item is resolved.
+//!
+//! Items on hold cannot be moved, adjusted, consumed by assembly or scrapped until released.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! - `initiate_recall`: Open a recall and place every affected item on hold.
+//! - `resolve_recall_item`: Release or scrap an item affected by a recall in the account holding it.
+//! - `close_recall`: Close a recall whose affected items are all resolved.
+//!
+//! ## Storage
+//!
+//! - `Recalls`: Stores recalls, and the account that opened them, by recall ID.
+//! - `NextRecallId`: The ID assigned to the next recall.
+//! - `RecallItems`: Stores the resolution status of each item affected by a recall, by the
+//!   account holding it.
+//!
+//! ## Events
+//!
+//! - `RecallInitiated`: Emitted when a recall is opened.
+//! - `RecallItemResolved`: Emitted when an affected item is released or scrapped.
+//! - `RecallClosed`: Emitted when a recall is closed.
+//!
+//! ## Errors
+//!
+//! - `RecallNotFound`: The specified recall could not be located.
+//! - `NotRecallOwner`: Only the account that opened the recall can manage it.
+//! - `RecallClosed`: The recall is already closed.
+//! - `ItemNotAffected`: The item is not affected by the recall.
+//! - `ItemAlreadyResolved`: The item has already been released or scrapped.
+//! - `UnresolvedItems`: The recall still has items on hold.
+//! - `TooManyAffectedItems`: The recall would reach or hold more than `MAX_RECALL_ITEMS` items.
+//!
+//! This pallet uses `no_std` for compatibility with Wasm environments, a polkadot standard.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub use pallet::*;
+
+// FRAME pallets require their own "mock runtimes" to be able to run unit tests. This module
+// contains a mock runtime specific for testing this pallet's functionality.
+#[cfg(test)]
+mod mock;
+
+// This module contains the unit tests for this pallet.
+// Learn about pallet unit testing here: https://docs.substrate.io/test/unit-testing/
+#[cfg(test)]
+mod tests;
+
+mod blogic;
+pub mod types;
+
+use crate::types::{
+    Recall, RecallId, RecallItem, RecallResolution, RecallSource, MAX_RECALL_ITEMS,
+};
+use pallet_inventory::types::{Reason, SerialNumber, Sku};
+
+#[frame_support::pallet]
+pub mod pallet {
+    use super::*;
+    use frame_support::pallet_prelude::*;
+    use frame_system::pallet_prelude::*;
+    use pallet_assembly::pallet::Config as AssemblyConfig;
+
+    #[pallet::pallet]
+    pub struct Pallet<T>(_);
+
+    /// The pallet's configuration trait.
+    ///
+    /// This trait is tightly coupled with the Assembly pallet -> [AssemblyConfig]
+    #[pallet::config]
+    pub trait Config: frame_system::Config + AssemblyConfig {
+        type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+    }
+
+    /// Recalls Storage
+    ///
+    /// Recalls by ID, with the account whose inventory they hold
+    #[pallet::storage]
+    pub type Recalls<T: Config> =
+        StorageMap<_, Twox64Concat, RecallId, (T::AccountId, Recall), OptionQuery>;
+
+    /// The ID assigned to the next recall
+    #[pallet::storage]
+    pub type NextRecallId<T: Config> = StorageValue<_, RecallId, ValueQuery>;
+
+    /// Recall Items Storage
+    ///
+    /// The resolution status of each item affected by a recall, keyed by the account whose
+    /// inventory holds it
+    #[pallet::storage]
+    pub type RecallItems<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Twox64Concat, RecallId>,
+            NMapKey<Blake2_128Concat, T::AccountId>,
+            NMapKey<Blake2_128Concat, Sku>,
+            NMapKey<Blake2_128Concat, SerialNumber>,
+        ),
+        RecallItem,
+        OptionQuery,
+    >;
+
+    /// Events that functions in this pallet can emit.
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config> {
+        /// A recall has been opened and its affected items placed on hold
+        RecallInitiated { who: T::AccountId, recall: Recall },
+        RecallItemResolved {
+            who: T::AccountId,
+            recall_id: RecallId,
+            holder: T::AccountId,
+            sku: Sku,
+            serial_number: SerialNumber,
+            item: RecallItem,
+        },
+        RecallClosed {
+            who: T::AccountId,
+            recall_id: RecallId,
+        },
+    }
+
+    /// Errors that can be returned by this pallet.
+    #[pallet::error]
+    pub enum Error<T> {
+        /// Unable to find the defined recall
+        RecallNotFound,
+        /// The recall was opened by another account
+        NotRecallOwner,
+        /// The recall is already closed
+        RecallClosed,
+        /// The item is not affected by the recall
+        ItemNotAffected,
+        /// The item has already been released or scrapped
+        ItemAlreadyResolved,
+        /// The recall still has items on hold
+        UnresolvedItems,
+        /// The recall would reach or hold more items than a single recall can
+        TooManyAffectedItems,
+    }
+
+    /// The pallet's dispatchable functions
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Open a recall from a suspect lot or serial
+        ///
+        /// Every matching item still in inventory, and every assembly it went into, is placed on
+        /// quality hold. Weighed for the most items a recall can scan and hold.
+        #[pallet::call_index(0)]
+        #[pallet::weight(
+            Weight::from_parts(10_000, 0)
+                + T::DbWeight::get().reads(3 * MAX_RECALL_ITEMS as u64)
+                + T::DbWeight::get().writes(2 * MAX_RECALL_ITEMS as u64 + 2)
+        )]
+        pub fn initiate_recall(
+            origin: OriginFor<T>,
+            source: RecallSource,
+            reason: Reason,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let recall = Self::do_initiate_recall(&who, source, reason)?;
+
+            Self::deposit_event(Event::RecallInitiated { who, recall });
+
+            Ok(())
+        }
+
+        /// Release or scrap an item affected by a recall
+        ///
+        /// `holder` is the account whose inventory holds the item, which is the recall owner's
+        /// own unless the item was transferred.
+        #[pallet::call_index(1)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn resolve_recall_item(
+            origin: OriginFor<T>,
+            recall_id: RecallId,
+            holder: T::AccountId,
+            sku: Sku,
+            serial_number: SerialNumber,
+            resolution: RecallResolution,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let item = Self::do_resolve_recall_item(
+                &who,
+                recall_id,
+                &holder,
+                sku.clone(),
+                serial_number,
+                resolution,
+            )?;
+
+            Self::deposit_event(Event::RecallItemResolved {
+                who,
+                recall_id,
+                holder,
+                sku,
+                serial_number,
+                item,
+            });
+
+            Ok(())
+        }
+
+        /// Close a recall once every affected item is resolved
+        #[pallet::call_index(2)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn close_recall(origin: OriginFor<T>, recall_id: RecallId) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            Self::do_close_recall(&who, recall_id)?;
+
+            Self::deposit_event(Event::RecallClosed { who, recall_id });
+
+            Ok(())
+        }
+    }
+}
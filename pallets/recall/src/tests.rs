@@ -0,0 +1,494 @@
+use crate::{
+    mock::*,
+    types::{
+        RecallItem, RecallItemStatus, RecallResolution, RecallSource, RecallStatus,
+        MAX_RECALL_ITEMS,
+    },
+    Error, Event,
+};
+use frame_support::sp_runtime::Percent;
+use frame_support::{assert_noop, assert_ok, BoundedVec};
+use pallet_assembly::types::WorkOrder;
+use pallet_inventory::types::{
+    AdjustDetails, Employee, Equipment, EquipmentAsset, HoldSource, IssueMode, Item, Location,
+    Reason, Recipe, RecipeComponent, ScrapDetails, SerialNumber, Sku,
+};
+
+const ALICE: u64 = 1;
+const BOB: u64 = 2;
+
+fn sku(sku: &str) -> Sku {
+    BoundedVec::truncate_from(sku.as_bytes().to_vec())
+}
+
+fn reason(reason: &str) -> Reason {
+    BoundedVec::truncate_from(reason.as_bytes().to_vec())
+}
+
+fn scrap_details() -> ScrapDetails {
+    ScrapDetails {
+        issuer: Employee::Bob,
+        reason: reason("Failed inspection"),
+        equipment: Equipment::Forklift,
+    }
+}
+
+fn bolt(serial_number: SerialNumber, lot_number: u32) -> Item {
+    Item {
+        sku: sku("BOLT"),
+        lot_number,
+        serial_number,
+        qty: 1,
+        created_at: serial_number,
+        ..Default::default()
+    }
+}
+
+/// Assemble widget 100 from one of the two bolts of serial 1, lot 9, kitted under serial 3,
+/// leaving the other and bolt 2 of lot 8 in stock
+fn build_widget() {
+    assert_ok!(Inventory::insert_recipe(
+        RuntimeOrigin::signed(ALICE),
+        Recipe {
+            sku: sku("WIDGET"),
+            required_components: BoundedVec::truncate_from(vec![RecipeComponent {
+                sku: sku("BOLT"),
+                qty: 1,
+                scrap_percent: Percent::from_percent(0),
+                issue_mode: IssueMode::PreKitted,
+            }]),
+            output_quantity: 1,
+            expected_yield: Percent::from_percent(100),
+            ..Default::default()
+        }
+    ));
+    assert_ok!(Inventory::insert_equipment(
+        RuntimeOrigin::signed(ALICE),
+        EquipmentAsset::default()
+    ));
+    for item in [
+        Item {
+            qty: 2,
+            ..bolt(1, 9)
+        },
+        bolt(2, 8),
+    ] {
+        assert_ok!(Inventory::inventory_insertion(
+            RuntimeOrigin::signed(ALICE),
+            item
+        ));
+    }
+
+    let work_order = WorkOrder {
+        recipe_sku: sku("WIDGET"),
+        planned_quantity: 1,
+        ..Default::default()
+    };
+    assert_ok!(Assembly::create_work_order(
+        RuntimeOrigin::signed(ALICE),
+        work_order.clone()
+    ));
+    assert_ok!(Assembly::update_work_order_status(
+        RuntimeOrigin::signed(ALICE),
+        0,
+        pallet_assembly::types::WorkOrderStatus::Released
+    ));
+    assert_ok!(Assembly::prepare_staging_area(
+        RuntimeOrigin::signed(ALICE),
+        work_order.clone()
+    ));
+    assert_ok!(Assembly::assemble_product(
+        RuntimeOrigin::signed(ALICE),
+        work_order,
+        100,
+        1
+    ));
+}
+
+fn recall_lot_9() {
+    assert_ok!(Recall::initiate_recall(
+        RuntimeOrigin::signed(ALICE),
+        RecallSource::Lot {
+            sku: sku("BOLT"),
+            lot_number: 9,
+        },
+        reason("Supplier lot failed testing")
+    ));
+}
+
+fn recall_item(sku_name: &str, serial_number: SerialNumber) -> Option<RecallItem> {
+    held_recall_item(ALICE, sku_name, serial_number)
+}
+
+fn held_recall_item(
+    holder: u64,
+    sku_name: &str,
+    serial_number: SerialNumber,
+) -> Option<RecallItem> {
+    crate::RecallItems::<Test>::get((0, holder, sku(sku_name), serial_number))
+}
+
+#[test]
+fn lot_recall_holds_the_lot_and_every_assembly_it_went_into() {
+    new_test_ext().execute_with(|| {
+        build_widget();
+        recall_lot_9();
+
+        let (owner, recall) = crate::Recalls::<Test>::get(0).unwrap();
+        assert_eq!(owner, ALICE);
+        assert_eq!(recall.status, RecallStatus::Open);
+        assert_eq!(recall.affected_items, 2);
+        System::assert_last_event(Event::RecallInitiated { who: ALICE, recall }.into());
+
+        assert_eq!(recall_item("BOLT", 1), Some(RecallItem::default()));
+        assert_eq!(recall_item("WIDGET", 100), Some(RecallItem::default()));
+        assert_eq!(recall_item("BOLT", 2), None);
+        // The kitted bolt was consumed, nothing of it is left to hold
+        assert_eq!(recall_item("BOLT", 3), None);
+        assert!(Inventory::is_on_hold(&ALICE, &sku("WIDGET"), 100));
+        assert!(!Inventory::is_on_hold(&ALICE, &sku("BOLT"), 2));
+
+        // Held stock cannot be moved
+        assert_noop!(
+            Inventory::inventory_move(
+                RuntimeOrigin::signed(ALICE),
+                sku("WIDGET"),
+                100,
+                Employee::Bob,
+                AdjustDetails::Location {
+                    original_location: Location::Warehouse,
+                    new_location: Location::Shipping,
+                    reason: reason("Ship"),
+                }
+            ),
+            pallet_inventory::Error::<Test>::ItemOnHold
+        );
+    });
+}
+
+#[test]
+fn serial_recall_holds_only_that_serial_and_its_assemblies() {
+    new_test_ext().execute_with(|| {
+        build_widget();
+
+        assert_ok!(Recall::initiate_recall(
+            RuntimeOrigin::signed(ALICE),
+            RecallSource::Serial {
+                sku: sku("BOLT"),
+                serial_number: 2,
+            },
+            reason("Damaged")
+        ));
+
+        assert_eq!(crate::Recalls::<Test>::get(0).unwrap().1.affected_items, 1);
+        assert_eq!(recall_item("BOLT", 2), Some(RecallItem::default()));
+        assert_eq!(recall_item("WIDGET", 100), None);
+    });
+}
+
+#[test]
+fn serial_recall_follows_the_serials_split_off_it() {
+    new_test_ext().execute_with(|| {
+        build_widget();
+
+        assert_ok!(Recall::initiate_recall(
+            RuntimeOrigin::signed(ALICE),
+            RecallSource::Serial {
+                sku: sku("BOLT"),
+                serial_number: 1,
+            },
+            reason("Damaged")
+        ));
+
+        assert_eq!(crate::Recalls::<Test>::get(0).unwrap().1.affected_items, 2);
+        assert_eq!(recall_item("BOLT", 1), Some(RecallItem::default()));
+        assert_eq!(recall_item("WIDGET", 100), Some(RecallItem::default()));
+    });
+}
+
+#[test]
+fn recalls_follow_stock_transferred_to_other_accounts() {
+    new_test_ext().execute_with(|| {
+        build_widget();
+        for (transfer_id, item_sku, serial_number) in [(0, "WIDGET", 100), (1, "BOLT", 1)] {
+            assert_ok!(Inventory::ship_transfer(
+                RuntimeOrigin::signed(ALICE),
+                BOB,
+                sku(item_sku),
+                serial_number,
+                false,
+                Employee::Bob
+            ));
+            assert_ok!(Inventory::receive_transfer(
+                RuntimeOrigin::signed(BOB),
+                transfer_id,
+                Employee::Bob
+            ));
+        }
+        recall_lot_9();
+
+        assert_eq!(crate::Recalls::<Test>::get(0).unwrap().1.affected_items, 2);
+        assert_eq!(recall_item("WIDGET", 100), None);
+        assert_eq!(
+            held_recall_item(BOB, "WIDGET", 100),
+            Some(RecallItem::default())
+        );
+        assert_eq!(
+            held_recall_item(BOB, "BOLT", 1),
+            Some(RecallItem::default())
+        );
+        assert!(Inventory::is_on_hold(&BOB, &sku("WIDGET"), 100));
+
+        // The recall owner works the items wherever they are held
+        assert_noop!(
+            Recall::resolve_recall_item(
+                RuntimeOrigin::signed(ALICE),
+                0,
+                ALICE,
+                sku("WIDGET"),
+                100,
+                RecallResolution::Release
+            ),
+            Error::<Test>::ItemNotAffected
+        );
+        assert_ok!(Recall::resolve_recall_item(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            BOB,
+            sku("WIDGET"),
+            100,
+            RecallResolution::Scrap(scrap_details())
+        ));
+        assert_eq!(
+            pallet_inventory::Inventory::<Test>::get((BOB, sku("WIDGET"), 100)),
+            None
+        );
+    });
+}
+
+#[test]
+fn recalls_scan_a_bounded_number_of_items() {
+    new_test_ext().execute_with(|| {
+        for serial_number in 0..=MAX_RECALL_ITEMS {
+            assert_ok!(Inventory::inventory_insertion(
+                RuntimeOrigin::signed(ALICE),
+                bolt(serial_number, 9)
+            ));
+        }
+
+        assert_noop!(
+            Recall::initiate_recall(
+                RuntimeOrigin::signed(ALICE),
+                RecallSource::Lot {
+                    sku: sku("BOLT"),
+                    lot_number: 9,
+                },
+                reason("Supplier lot failed testing")
+            ),
+            Error::<Test>::TooManyAffectedItems
+        );
+    });
+}
+
+#[test]
+fn lot_recalls_only_count_stock_of_the_lot() {
+    new_test_ext().execute_with(|| {
+        for serial_number in 0..=MAX_RECALL_ITEMS {
+            assert_ok!(Inventory::inventory_insertion(
+                RuntimeOrigin::signed(ALICE),
+                bolt(serial_number, 8)
+            ));
+        }
+        assert_ok!(Inventory::inventory_insertion(
+            RuntimeOrigin::signed(ALICE),
+            bolt(MAX_RECALL_ITEMS + 1, 9)
+        ));
+
+        recall_lot_9();
+
+        assert!(recall_item("BOLT", MAX_RECALL_ITEMS + 1).is_some());
+        assert_eq!(recall_item("BOLT", 0), None);
+    });
+}
+
+#[test]
+fn released_items_come_off_hold() {
+    new_test_ext().execute_with(|| {
+        build_widget();
+        recall_lot_9();
+
+        assert_ok!(Recall::resolve_recall_item(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            ALICE,
+            sku("WIDGET"),
+            100,
+            RecallResolution::Release
+        ));
+
+        let item = RecallItem {
+            status: RecallItemStatus::Released,
+            resolved_at: Some(1),
+        };
+        assert_eq!(recall_item("WIDGET", 100), Some(item.clone()));
+        assert!(!Inventory::is_on_hold(&ALICE, &sku("WIDGET"), 100));
+        assert!(!pallet_inventory::QualityHolds::<Test>::contains_key((
+            ALICE,
+            sku("WIDGET"),
+            100,
+            HoldSource::Recall(0)
+        )));
+        assert_eq!(crate::Recalls::<Test>::get(0).unwrap().1.resolved_items, 1);
+        System::assert_last_event(
+            Event::RecallItemResolved {
+                who: ALICE,
+                recall_id: 0,
+                holder: ALICE,
+                sku: sku("WIDGET"),
+                serial_number: 100,
+                item,
+            }
+            .into(),
+        );
+
+        assert_noop!(
+            Recall::resolve_recall_item(
+                RuntimeOrigin::signed(ALICE),
+                0,
+                ALICE,
+                sku("WIDGET"),
+                100,
+                RecallResolution::Release
+            ),
+            Error::<Test>::ItemAlreadyResolved
+        );
+    });
+}
+
+#[test]
+fn scrapped_items_leave_inventory() {
+    new_test_ext().execute_with(|| {
+        build_widget();
+        recall_lot_9();
+
+        assert_ok!(Recall::resolve_recall_item(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            ALICE,
+            sku("WIDGET"),
+            100,
+            RecallResolution::Scrap(scrap_details())
+        ));
+
+        assert_eq!(
+            recall_item("WIDGET", 100).unwrap().status,
+            RecallItemStatus::Scrapped
+        );
+        assert_eq!(
+            pallet_inventory::Inventory::<Test>::get((ALICE, sku("WIDGET"), 100)),
+            None
+        );
+        assert!(pallet_inventory::ScrapInventory::<Test>::contains_key((
+            ALICE,
+            sku("WIDGET"),
+            100
+        )));
+    });
+}
+
+#[test]
+fn only_affected_items_can_be_resolved() {
+    new_test_ext().execute_with(|| {
+        build_widget();
+        recall_lot_9();
+
+        assert_noop!(
+            Recall::resolve_recall_item(
+                RuntimeOrigin::signed(ALICE),
+                0,
+                ALICE,
+                sku("BOLT"),
+                2,
+                RecallResolution::Release
+            ),
+            Error::<Test>::ItemNotAffected
+        );
+    });
+}
+
+#[test]
+fn recall_closes_once_every_item_is_resolved() {
+    new_test_ext().execute_with(|| {
+        build_widget();
+        recall_lot_9();
+
+        assert_ok!(Recall::resolve_recall_item(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            ALICE,
+            sku("WIDGET"),
+            100,
+            RecallResolution::Release
+        ));
+        assert_noop!(
+            Recall::close_recall(RuntimeOrigin::signed(ALICE), 0),
+            Error::<Test>::UnresolvedItems
+        );
+
+        assert_ok!(Recall::resolve_recall_item(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            ALICE,
+            sku("BOLT"),
+            1,
+            RecallResolution::Release
+        ));
+        System::set_block_number(3);
+        assert_ok!(Recall::close_recall(RuntimeOrigin::signed(ALICE), 0));
+
+        let (_, recall) = crate::Recalls::<Test>::get(0).unwrap();
+        assert_eq!(recall.status, RecallStatus::Closed);
+        assert_eq!(recall.closed_at, Some(3));
+        System::assert_last_event(
+            Event::RecallClosed {
+                who: ALICE,
+                recall_id: 0,
+            }
+            .into(),
+        );
+
+        assert_noop!(
+            Recall::close_recall(RuntimeOrigin::signed(ALICE), 0),
+            Error::<Test>::RecallClosed
+        );
+    });
+}
+
+#[test]
+fn recalls_are_worked_only_by_their_owner() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Recall::close_recall(RuntimeOrigin::signed(ALICE), 0),
+            Error::<Test>::RecallNotFound
+        );
+
+        build_widget();
+        recall_lot_9();
+
+        assert_noop!(
+            Recall::resolve_recall_item(
+                RuntimeOrigin::signed(BOB),
+                0,
+                ALICE,
+                sku("WIDGET"),
+                100,
+                RecallResolution::Release
+            ),
+            Error::<Test>::NotRecallOwner
+        );
+        assert_noop!(
+            Recall::close_recall(RuntimeOrigin::signed(BOB), 0),
+            Error::<Test>::NotRecallOwner
+        );
+    });
+}
@@ -0,0 +1,67 @@
+use codec::{Decode, Encode};
+use frame_support::pallet_prelude::MaxEncodedLen;
+use pallet_inventory::types::{LotNumber, Reason, ScrapDetails, SerialNumber, Sku};
+use scale_info::TypeInfo;
+
+pub type RecallId = u32;
+
+/// Most items a recall scans for suspect stock, and most it places on hold
+pub const MAX_RECALL_ITEMS: u32 = 500;
+
+/// The suspect stock a recall starts from
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub enum RecallSource {
+    /// Every item of a lot, e.g. a suspect supplier lot
+    Lot { sku: Sku, lot_number: LotNumber },
+    /// A single serialized item
+    Serial {
+        sku: Sku,
+        serial_number: SerialNumber,
+    },
+}
+
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
+pub enum RecallStatus {
+    #[default]
+    Open,
+    Closed,
+}
+
+/// A recall, holding every affected item until each one is resolved
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct Recall {
+    pub recall_id: RecallId,
+    pub source: RecallSource,
+    pub reason: Reason,
+    pub status: RecallStatus,
+    pub opened_at: u32,
+    pub closed_at: Option<u32>,
+    /// Number of items placed on hold by the recall
+    pub affected_items: u32,
+    /// Number of affected items released or scrapped so far
+    pub resolved_items: u32,
+}
+
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
+pub enum RecallItemStatus {
+    #[default]
+    OnHold,
+    Released,
+    Scrapped,
+}
+
+/// The state of a single item affected by a recall
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
+pub struct RecallItem {
+    pub status: RecallItemStatus,
+    pub resolved_at: Option<u32>,
+}
+
+/// How an affected item is resolved
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub enum RecallResolution {
+    /// The item passed inspection and goes back into circulation
+    Release,
+    /// The item is scrapped
+    Scrap(ScrapDetails),
+}
@@ -0,0 +1,414 @@
+use crate::pallet::{
+    BinOccupancy, Bins, Config, Error, ExpectedReceipts, ItemBins, NextPutawayTaskId,
+    PendingInspections, PutawayRules, PutawayTasks,
+};
+use crate::{pallet::Pallet, types::*};
+use frame_support::ensure;
+use frame_support::pallet_prelude::ConstU32;
+use frame_support::sp_runtime::{DispatchError, DispatchResult};
+use frame_support::BoundedVec;
+use pallet_inventory::types::{
+    AdjustDetails, Employee, HoldSource, Item, Location, LotNumber, Qty, SerialNumber, Sku,
+    StockChanges,
+};
+use pallet_inventory::Inventory;
+use pallet_purchasing::types::{LineIndex, PurchaseOrderNumber, SupplierId};
+use pallet_purchasing::{PurchaseOrders, Suppliers};
+
+impl<T: Config> Pallet<T> {
+    /// Add a storage bin
+    pub fn do_add_bin(bin: Bin) -> DispatchResult {
+        ensure!(
+            !Bins::<T>::contains_key(bin.bin_id),
+            Error::<T>::BinAlreadyExists
+        );
+        ensure!(
+            bin.capacity > 0 && bin.location != Location::Receiving,
+            Error::<T>::InvalidBin
+        );
+
+        Bins::<T>::insert(bin.bin_id, bin);
+
+        Ok(())
+    }
+
+    /// Set the bins a material prefers to be put away to, clearing the rule when empty
+    pub fn do_set_putaway_rule(
+        sku: Sku,
+        bins: BoundedVec<BinId, ConstU32<MAX_PREFERRED_BINS>>,
+    ) -> DispatchResult {
+        for bin_id in bins.iter() {
+            ensure!(Bins::<T>::contains_key(bin_id), Error::<T>::BinNotFound);
+        }
+
+        if bins.is_empty() {
+            PutawayRules::<T>::remove(&sku);
+        } else {
+            PutawayRules::<T>::insert(&sku, bins);
+        }
+
+        Ok(())
+    }
+
+    /// Record an expected receipt, checking its lines against the purchase order if given
+    pub fn do_create_expected_receipt(
+        who: &T::AccountId,
+        asn_id: AsnId,
+        supplier_id: SupplierId,
+        po_number: Option<PurchaseOrderNumber>,
+        mut lines: BoundedVec<AsnLine, ConstU32<MAX_ASN_LINES>>,
+        expected_at: u32,
+    ) -> Result<ExpectedReceipt, DispatchError> {
+        ensure!(
+            !ExpectedReceipts::<T>::contains_key(asn_id),
+            Error::<T>::AsnAlreadyExists
+        );
+        ensure!(
+            Suppliers::<T>::contains_key(supplier_id),
+            pallet_purchasing::Error::<T>::SupplierNotFound
+        );
+        ensure!(!lines.is_empty(), Error::<T>::NoAsnLines);
+
+        let purchase_order = match po_number {
+            Some(po_number) => {
+                let (owner, purchase_order) = PurchaseOrders::<T>::get(po_number)
+                    .ok_or(pallet_purchasing::Error::<T>::PurchaseOrderNotFound)?;
+                ensure!(
+                    &owner == who,
+                    pallet_purchasing::Error::<T>::NotPurchaseOrderOwner
+                );
+                ensure!(
+                    purchase_order.supplier_id == supplier_id,
+                    Error::<T>::InvalidAsnLine
+                );
+                Some(purchase_order)
+            }
+            None => None,
+        };
+
+        for line in lines.iter_mut() {
+            ensure!(line.expected_qty > 0, Error::<T>::InvalidAsnLine);
+            line.received_qty = 0;
+
+            if let Some(po_line) = line.po_line {
+                let po_line = purchase_order
+                    .as_ref()
+                    .and_then(|purchase_order| purchase_order.lines.get(po_line as usize))
+                    .ok_or(Error::<T>::InvalidAsnLine)?;
+                ensure!(po_line.sku == line.sku, Error::<T>::InvalidAsnLine);
+            }
+        }
+
+        let expected_receipt = ExpectedReceipt {
+            asn_id,
+            supplier_id,
+            po_number,
+            lines,
+            expected_at,
+            status: AsnStatus::Expected,
+            created_at: pallet_inventory::Pallet::<T>::current_block(),
+        };
+
+        ExpectedReceipts::<T>::insert(asn_id, (who, expected_receipt.clone()));
+
+        Ok(expected_receipt)
+    }
+
+    /// Receive stock against an ASN line into `Location::Receiving`
+    ///
+    /// Lines referencing a purchase order line are received through it. Returns the line, and
+    /// the putaway task unless the stock is held for inspection.
+    pub fn do_receive_asn_line(
+        who: &T::AccountId,
+        asn_id: AsnId,
+        line_index: LineIndex,
+        serial_number: SerialNumber,
+        qty: Qty,
+        supplier_lot: LotNumber,
+        received_by: Employee,
+    ) -> Result<(AsnLine, Option<PutawayTask>), DispatchError> {
+        let (owner, mut expected_receipt) =
+            ExpectedReceipts::<T>::get(asn_id).ok_or(Error::<T>::AsnNotFound)?;
+
+        ensure!(&owner == who, Error::<T>::NotAsnOwner);
+        ensure!(
+            matches!(
+                expected_receipt.status,
+                AsnStatus::Expected | AsnStatus::PartiallyReceived
+            ),
+            Error::<T>::AsnNotOpen
+        );
+
+        let line = expected_receipt
+            .lines
+            .get_mut(line_index as usize)
+            .ok_or(Error::<T>::LineNotFound)?;
+        ensure!(qty > 0 && qty <= line.open_qty(), Error::<T>::OverReceipt);
+
+        match (expected_receipt.po_number, line.po_line) {
+            (Some(po_number), Some(po_line)) => {
+                pallet_purchasing::Pallet::<T>::do_receive_po_line(
+                    who,
+                    po_number,
+                    po_line,
+                    serial_number,
+                    qty,
+                    supplier_lot,
+                    received_by,
+                )?;
+            }
+            _ => {
+                pallet_purchasing::Pallet::<T>::do_receive_into_receiving(
+                    who,
+                    line.sku.clone(),
+                    serial_number,
+                    qty,
+                    supplier_lot,
+                    received_by,
+                )?;
+            }
+        }
+
+        line.received_qty = line.received_qty.saturating_add(qty);
+        let line = line.clone();
+
+        expected_receipt.status = if expected_receipt
+            .lines
+            .iter()
+            .all(|line| line.open_qty() == 0)
+        {
+            AsnStatus::Received
+        } else {
+            AsnStatus::PartiallyReceived
+        };
+        ExpectedReceipts::<T>::insert(asn_id, (who, expected_receipt));
+
+        let task = if line.inspect {
+            pallet_inventory::Pallet::<T>::do_place_hold(
+                who,
+                line.sku.clone(),
+                serial_number,
+                HoldSource::Inspection(asn_id),
+                pallet_inventory::Pallet::<T>::reason("Awaiting receiving inspection")?,
+            )?;
+            PendingInspections::<T>::insert((who, line.sku.clone(), serial_number), asn_id);
+            None
+        } else {
+            Some(Self::create_putaway_task(
+                who,
+                asn_id,
+                line.sku.clone(),
+                serial_number,
+            )?)
+        };
+
+        Ok((line, task))
+    }
+
+    /// Release an item held for inspection, returning its putaway task if it passed.
+    ///
+    /// Failed items are moved to `Location::Mrb` for the Material Review Board.
+    pub fn do_record_inspection(
+        who: &T::AccountId,
+        sku: Sku,
+        serial_number: SerialNumber,
+        passed: bool,
+        inspected_by: Employee,
+    ) -> Result<Option<PutawayTask>, DispatchError> {
+        let asn_id = PendingInspections::<T>::take((who, sku.clone(), serial_number))
+            .ok_or(Error::<T>::InspectionNotPending)?;
+
+        pallet_inventory::Pallet::<T>::do_release_hold(
+            who,
+            sku.clone(),
+            serial_number,
+            HoldSource::Inspection(asn_id),
+        );
+
+        if passed {
+            return Ok(Some(Self::create_putaway_task(
+                who,
+                asn_id,
+                sku,
+                serial_number,
+            )?));
+        }
+
+        let item = Self::received_item(who, &sku, serial_number)?;
+        // Fails if another source still holds the item
+        pallet_inventory::Pallet::<T>::do_inventory_move(
+            who,
+            item,
+            inspected_by,
+            AdjustDetails::Location {
+                original_location: Location::Receiving,
+                new_location: Location::Mrb,
+                reason: pallet_inventory::Pallet::<T>::reason("Failed receiving inspection")?,
+            },
+        )?;
+
+        Ok(None)
+    }
+
+    /// Put a received item away to a bin that accepts it and has room for it
+    pub fn do_confirm_putaway(
+        who: &T::AccountId,
+        task_id: PutawayTaskId,
+        bin_id: BinId,
+        moved_by: Employee,
+    ) -> Result<PutawayTask, DispatchError> {
+        let (owner, mut task) =
+            PutawayTasks::<T>::get(task_id).ok_or(Error::<T>::PutawayTaskNotFound)?;
+
+        ensure!(&owner == who, Error::<T>::NotPutawayTaskOwner);
+        ensure!(
+            task.status == PutawayStatus::Open,
+            Error::<T>::PutawayCompleted
+        );
+
+        let bin = Bins::<T>::get(bin_id).ok_or(Error::<T>::BinNotFound)?;
+        let item = Self::received_item(who, &task.sku, task.serial_number)?;
+
+        ensure!(bin.accepts(&item), Error::<T>::BinRejectsItem);
+        ensure!(Self::free_capacity(&bin) >= item.qty, Error::<T>::BinFull);
+        let qty = item.qty;
+
+        // Fails if the item is on quality hold
+        pallet_inventory::Pallet::<T>::do_inventory_move(
+            who,
+            item,
+            moved_by,
+            AdjustDetails::Location {
+                original_location: Location::Receiving,
+                new_location: bin.location.clone(),
+                reason: pallet_inventory::Pallet::<T>::reason("Putaway")?,
+            },
+        )?;
+
+        ItemBins::<T>::insert((who, task.sku.clone(), task.serial_number), bin_id);
+        BinOccupancy::<T>::mutate(bin_id, |occupied| *occupied = occupied.saturating_add(qty));
+
+        task.status = PutawayStatus::Completed;
+        task.bin = Some(bin_id);
+        task.completed_at = Some(pallet_inventory::Pallet::<T>::current_block());
+        PutawayTasks::<T>::insert(task_id, (who, task.clone()));
+
+        Ok(task)
+    }
+
+    /// Close an expected receipt that will not be received in full
+    pub fn do_close_expected_receipt(who: &T::AccountId, asn_id: AsnId) -> DispatchResult {
+        let (owner, mut expected_receipt) =
+            ExpectedReceipts::<T>::get(asn_id).ok_or(Error::<T>::AsnNotFound)?;
+
+        ensure!(&owner == who, Error::<T>::NotAsnOwner);
+        ensure!(
+            matches!(
+                expected_receipt.status,
+                AsnStatus::Expected | AsnStatus::PartiallyReceived
+            ),
+            Error::<T>::AsnNotOpen
+        );
+
+        expected_receipt.status = AsnStatus::Closed;
+        ExpectedReceipts::<T>::insert(asn_id, (who, expected_receipt));
+
+        Ok(())
+    }
+
+    /// Suggest a bin for an item
+    ///
+    /// The material's preferred bins are tried in order. Failing those, the accepting bin with
+    /// the least room left that still fits the item, among the first `MAX_BINS_SEARCHED` bins, is
+    /// suggested, to keep bins consolidated.
+    pub fn suggest_bin(item: &Item) -> Option<BinId> {
+        let preferred = PutawayRules::<T>::get(&item.sku)
+            .into_iter()
+            .filter_map(Bins::<T>::get)
+            .find(|bin| bin.accepts(item) && Self::free_capacity(bin) >= item.qty);
+        if let Some(bin) = preferred {
+            return Some(bin.bin_id);
+        }
+
+        Bins::<T>::iter_values()
+            .take(MAX_BINS_SEARCHED as usize)
+            .filter(|bin| bin.accepts(item))
+            .map(|bin| (Self::free_capacity(&bin), bin.bin_id))
+            .filter(|(free, _)| *free >= item.qty)
+            .min()
+            .map(|(_, bin_id)| bin_id)
+    }
+
+    /// Room left in a bin, counting the items put away to it that are still at its location
+    pub fn free_capacity(bin: &Bin) -> Qty {
+        bin.capacity
+            .saturating_sub(BinOccupancy::<T>::get(bin.bin_id))
+    }
+
+    /// Create a putaway task for a received item, with a suggested bin
+    fn create_putaway_task(
+        who: &T::AccountId,
+        asn_id: AsnId,
+        sku: Sku,
+        serial_number: SerialNumber,
+    ) -> Result<PutawayTask, DispatchError> {
+        let item = Self::received_item(who, &sku, serial_number)?;
+
+        let task_id = NextPutawayTaskId::<T>::get();
+        NextPutawayTaskId::<T>::put(task_id.saturating_add(1));
+
+        let task = PutawayTask {
+            task_id,
+            asn_id,
+            sku,
+            serial_number,
+            qty: item.qty,
+            suggested_bin: Self::suggest_bin(&item),
+            bin: None,
+            status: PutawayStatus::Open,
+            created_at: pallet_inventory::Pallet::<T>::current_block(),
+            completed_at: None,
+        };
+        PutawayTasks::<T>::insert(task_id, (who, task.clone()));
+
+        Ok(task)
+    }
+
+    /// Fetch an item that is still at receiving
+    fn received_item(
+        who: &T::AccountId,
+        sku: &Sku,
+        serial_number: SerialNumber,
+    ) -> Result<Item, DispatchError> {
+        let item = Inventory::<T>::get((who, sku.clone(), serial_number))
+            .ok_or(pallet_inventory::Error::<T>::InventoryNotFound)?;
+
+        ensure!(
+            item.location == Location::Receiving,
+            Error::<T>::ItemNotAtReceiving
+        );
+
+        Ok(item)
+    }
+}
+
+impl<T: Config> StockChanges<T::AccountId> for Pallet<T> {
+    /// Keep the occupancy of the bin an item was put away to in step with the item, until it
+    /// leaves the bin's location
+    fn on_stock_changed(who: &T::AccountId, before: &Item, after: Option<&Item>) {
+        let key = (who, before.sku.clone(), before.serial_number);
+        let Some(bin_id) = ItemBins::<T>::get(key.clone()) else {
+            return;
+        };
+
+        let still_in_bin = after.filter(|item| item.location == before.location);
+        BinOccupancy::<T>::mutate(bin_id, |occupied| {
+            *occupied = occupied
+                .saturating_sub(before.qty)
+                .saturating_add(still_in_bin.map_or(0, |item| item.qty));
+        });
+        if still_in_bin.is_none() {
+            ItemBins::<T>::remove(key);
+        }
+    }
+}
@@ -0,0 +1,464 @@
+//! # Receiving Pallet
+//!
+//! - [`Config`]
+//! - [`Call`]
+//!
+//! ## Overview
+//!
+//! The Receiving pallet runs the inbound flow from a supplier's shipping notice to stock in a
+//! storage bin. This pallet provides functionalities to:
+//!
+//! - Record expected receipts (ASNs) from suppliers, optionally against a purchase order.
+//! - Receive ASN lines into `Location::Receiving`, through the purchase order line when one is
+//!   referenced.
+//! - Hold received stock for inspection, then pass it or send it to the Material Review Board.
+//! - Suggest a storage bin for each received item, from the bin's material rules, the
+//!   material's preferred bins and the room left in each bin.
+//! - Confirm putaway, moving the item from receiving to the bin's location.
+//!
+//! A putaway task is created as soon as an item is received, or once it passes inspection. The
+//! bin a task suggests is only a suggestion; any bin that accepts the item and has room can be
+//! confirmed. A bin's occupancy is the quantity of the items put away to it that are still at
+//! its location, kept up to date from the inventory pallet's `StockChanges`. Beyond a material's
+//! preferred bins, only the first `MAX_BINS_SEARCHED` bins are searched for a suggestion.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! - `add_bin`: Add a storage bin.
+//! - `set_putaway_rule`: Set the bins a material prefers to be put away to.
+//! - `create_expected_receipt`: Record an ASN from a supplier.
+//! - `receive_asn_line`: Receive stock against an ASN line into receiving.
+//! - `record_inspection`: Pass or fail a received item held for inspection.
+//! - `confirm_putaway`: Put a received item away to a bin.
+//! - `close_expected_receipt`: Close an ASN that will not be received in full.
+//!
+//! ## Storage
+//!
+//! - `Bins`: Storage bins by bin ID.
+//! - `PutawayRules`: The bins each material prefers, in order.
+//! - `ExpectedReceipts`: Expected receipts, and the account receiving them, by ASN number.
+//! - `PendingInspections`: Received items held for inspection, with their ASN.
+//! - `PutawayTasks`: Putaway tasks, and the account whose item they move, by task ID.
+//! - `NextPutawayTaskId`: The ID assigned to the next putaway task.
+//! - `ItemBins`: The bin each put away item is still in.
+//! - `BinOccupancy`: The quantity of the items put away to each bin that is still in it.
+//!
+//! ## Events
+//!
+//! - `BinAdded`: Emitted when a storage bin is added.
+//! - `PutawayRuleSet`: Emitted when a material's preferred bins are set.
+//! - `ExpectedReceiptCreated`: Emitted when an ASN is recorded.
+//! - `AsnLineReceived`: Emitted when stock is received against an ASN line.
+//! - `InspectionRecorded`: Emitted when a held item passes or fails inspection.
+//! - `PutawayTaskCreated`: Emitted when a received item is ready to be put away.
+//! - `PutawayConfirmed`: Emitted when an item is put away to a bin.
+//! - `ExpectedReceiptClosed`: Emitted when an ASN is closed.
+//!
+//! ## Errors
+//!
+//! - `BinAlreadyExists`: Attempted to add a bin ID that already exists.
+//! - `BinNotFound`: The specified bin could not be located.
+//! - `InvalidBin`: A bin needs capacity and cannot sit at receiving.
+//! - `AsnAlreadyExists`: Attempted to record an ASN number that already exists.
+//! - `AsnNotFound`: The specified expected receipt could not be located.
+//! - `NotAsnOwner`: Only the account that recorded the ASN can receive against it.
+//! - `NoAsnLines`: The ASN has no lines.
+//! - `InvalidAsnLine`: A line has no quantity or does not match its purchase order line.
+//! - `AsnNotOpen`: The ASN has been received in full or closed.
+//! - `LineNotFound`: The ASN has no line at the given index.
+//! - `OverReceipt`: The receipt exceeds the quantity still expected on the line.
+//! - `InspectionNotPending`: The item is not held for inspection.
+//! - `PutawayTaskNotFound`: The specified putaway task could not be located.
+//! - `NotPutawayTaskOwner`: Only the account whose item it moves can confirm a putaway task.
+//! - `PutawayCompleted`: The putaway task has already been confirmed.
+//! - `ItemNotAtReceiving`: The item is no longer at receiving.
+//! - `BinRejectsItem`: The bin's material rules do not allow the item.
+//! - `BinFull`: The bin does not have room for the item.
+//!
+//! This pallet uses `no_std` for compatibility with Wasm environments, a polkadot standard.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub use pallet::*;
+
+// FRAME pallets require their own "mock runtimes" to be able to run unit tests. This module
+// contains a mock runtime specific for testing this pallet's functionality.
+#[cfg(test)]
+mod mock;
+
+// This module contains the unit tests for this pallet.
+// Learn about pallet unit testing here: https://docs.substrate.io/test/unit-testing/
+#[cfg(test)]
+mod tests;
+
+mod blogic;
+pub mod types;
+
+use crate::types::{
+    AsnId, AsnLine, Bin, BinId, PutawayTask, PutawayTaskId, MAX_ASN_LINES, MAX_PREFERRED_BINS,
+};
+use pallet_inventory::types::{Employee, LotNumber, Qty, SerialNumber, Sku};
+use pallet_purchasing::types::{LineIndex, PurchaseOrderNumber, SupplierId};
+
+#[frame_support::pallet]
+pub mod pallet {
+    use super::*;
+    use crate::types::ExpectedReceipt;
+    use frame_support::pallet_prelude::*;
+    use frame_system::pallet_prelude::*;
+    use pallet_purchasing::pallet::Config as PurchasingConfig;
+
+    #[pallet::pallet]
+    pub struct Pallet<T>(_);
+
+    /// The pallet's configuration trait.
+    ///
+    /// This trait is tightly coupled with the Purchasing pallet -> [PurchasingConfig]
+    #[pallet::config]
+    pub trait Config: frame_system::Config + PurchasingConfig {
+        type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+    }
+
+    /// Bins Storage
+    ///
+    /// Storage bins by bin ID
+    #[pallet::storage]
+    pub type Bins<T: Config> = StorageMap<_, Twox64Concat, BinId, Bin, OptionQuery>;
+
+    /// Putaway Rules Storage
+    ///
+    /// The bins a material prefers to be put away to, tried in order before any other bin
+    #[pallet::storage]
+    pub type PutawayRules<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        Sku,
+        BoundedVec<BinId, ConstU32<MAX_PREFERRED_BINS>>,
+        ValueQuery,
+    >;
+
+    /// Expected Receipts Storage
+    ///
+    /// Expected receipts by ASN number, with the account whose inventory they are received into
+    #[pallet::storage]
+    pub type ExpectedReceipts<T: Config> =
+        StorageMap<_, Twox64Concat, AsnId, (T::AccountId, ExpectedReceipt), OptionQuery>;
+
+    /// Pending Inspections Storage
+    ///
+    /// Received items on hold for inspection, with the ASN they were received against
+    #[pallet::storage]
+    pub type PendingInspections<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, T::AccountId>,
+            NMapKey<Blake2_128Concat, Sku>,
+            NMapKey<Blake2_128Concat, SerialNumber>,
+        ),
+        AsnId,
+        OptionQuery,
+    >;
+
+    /// Putaway Tasks Storage
+    ///
+    /// Putaway tasks by ID, with the account whose item they move
+    #[pallet::storage]
+    pub type PutawayTasks<T: Config> =
+        StorageMap<_, Twox64Concat, PutawayTaskId, (T::AccountId, PutawayTask), OptionQuery>;
+
+    /// The ID assigned to the next putaway task
+    #[pallet::storage]
+    pub type NextPutawayTaskId<T: Config> = StorageValue<_, PutawayTaskId, ValueQuery>;
+
+    /// The bin each put away item is still in, until it leaves the bin's location
+    #[pallet::storage]
+    pub type ItemBins<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, T::AccountId>,
+            NMapKey<Blake2_128Concat, Sku>,
+            NMapKey<Blake2_128Concat, SerialNumber>,
+        ),
+        BinId,
+        OptionQuery,
+    >;
+
+    /// Bin Occupancy Storage
+    ///
+    /// The quantity of the items put away to each bin that is still in it, used to work out how
+    /// much room is left in it
+    #[pallet::storage]
+    pub type BinOccupancy<T: Config> = StorageMap<_, Twox64Concat, BinId, Qty, ValueQuery>;
+
+    /// Events that functions in this pallet can emit.
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config> {
+        BinAdded {
+            who: T::AccountId,
+            bin: Bin,
+        },
+        PutawayRuleSet {
+            who: T::AccountId,
+            sku: Sku,
+            bins: BoundedVec<BinId, ConstU32<MAX_PREFERRED_BINS>>,
+        },
+        ExpectedReceiptCreated {
+            who: T::AccountId,
+            asn_id: AsnId,
+            supplier_id: SupplierId,
+        },
+        AsnLineReceived {
+            who: T::AccountId,
+            asn_id: AsnId,
+            line_index: LineIndex,
+            sku: Sku,
+            serial_number: SerialNumber,
+            qty: Qty,
+            /// The item is on hold until it passes inspection
+            inspect: bool,
+        },
+        /// A held item passed inspection, or failed and was moved to the MRB
+        InspectionRecorded {
+            who: T::AccountId,
+            sku: Sku,
+            serial_number: SerialNumber,
+            passed: bool,
+        },
+        PutawayTaskCreated {
+            who: T::AccountId,
+            task: PutawayTask,
+        },
+        PutawayConfirmed {
+            who: T::AccountId,
+            task_id: PutawayTaskId,
+            bin_id: BinId,
+            suggested_bin: Option<BinId>,
+        },
+        ExpectedReceiptClosed {
+            who: T::AccountId,
+            asn_id: AsnId,
+        },
+    }
+
+    /// Errors that can be returned by this pallet.
+    #[pallet::error]
+    pub enum Error<T> {
+        /// A bin with this ID already exists
+        BinAlreadyExists,
+        /// The bin was not found
+        BinNotFound,
+        /// A bin needs capacity and cannot sit at receiving
+        InvalidBin,
+        /// An ASN with this number already exists
+        AsnAlreadyExists,
+        /// The expected receipt was not found
+        AsnNotFound,
+        /// The expected receipt belongs to another account
+        NotAsnOwner,
+        /// The ASN has no lines
+        NoAsnLines,
+        /// A line has no quantity or does not match its purchase order line
+        InvalidAsnLine,
+        /// The ASN has been received in full or closed
+        AsnNotOpen,
+        /// The ASN has no line at the given index
+        LineNotFound,
+        /// The receipt exceeds the quantity still expected on the line
+        OverReceipt,
+        /// The item is not held for inspection
+        InspectionNotPending,
+        /// The putaway task was not found
+        PutawayTaskNotFound,
+        /// The putaway task belongs to another account
+        NotPutawayTaskOwner,
+        /// The putaway task has already been confirmed
+        PutawayCompleted,
+        /// The item is no longer at receiving
+        ItemNotAtReceiving,
+        /// The bin's material rules do not allow the item
+        BinRejectsItem,
+        /// The bin does not have room for the item
+        BinFull,
+    }
+
+    /// The pallet's dispatchable functions
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Add a storage bin
+        #[pallet::call_index(0)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn add_bin(origin: OriginFor<T>, bin: Bin) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            Self::do_add_bin(bin.clone())?;
+
+            Self::deposit_event(Event::BinAdded { who, bin });
+
+            Ok(())
+        }
+
+        /// Set the bins a material prefers to be put away to, in order
+        ///
+        /// An empty list clears the rule.
+        #[pallet::call_index(1)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn set_putaway_rule(
+            origin: OriginFor<T>,
+            sku: Sku,
+            bins: BoundedVec<BinId, ConstU32<MAX_PREFERRED_BINS>>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            Self::do_set_putaway_rule(sku.clone(), bins.clone())?;
+
+            Self::deposit_event(Event::PutawayRuleSet { who, sku, bins });
+
+            Ok(())
+        }
+
+        /// Record an advance shipping notice from a supplier
+        #[pallet::call_index(2)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn create_expected_receipt(
+            origin: OriginFor<T>,
+            asn_id: AsnId,
+            supplier_id: SupplierId,
+            po_number: Option<PurchaseOrderNumber>,
+            lines: BoundedVec<AsnLine, ConstU32<MAX_ASN_LINES>>,
+            expected_at: u32,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            Self::do_create_expected_receipt(
+                &who,
+                asn_id,
+                supplier_id,
+                po_number,
+                lines,
+                expected_at,
+            )?;
+
+            Self::deposit_event(Event::ExpectedReceiptCreated {
+                who,
+                asn_id,
+                supplier_id,
+            });
+
+            Ok(())
+        }
+
+        /// Receive stock against an ASN line into receiving
+        ///
+        /// Lines marked for inspection are put on hold, otherwise a putaway task is created.
+        #[pallet::call_index(3)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn receive_asn_line(
+            origin: OriginFor<T>,
+            asn_id: AsnId,
+            line_index: LineIndex,
+            serial_number: SerialNumber,
+            qty: Qty,
+            supplier_lot: LotNumber,
+            received_by: Employee,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let (line, task) = Self::do_receive_asn_line(
+                &who,
+                asn_id,
+                line_index,
+                serial_number,
+                qty,
+                supplier_lot,
+                received_by,
+            )?;
+
+            Self::deposit_event(Event::AsnLineReceived {
+                who: who.clone(),
+                asn_id,
+                line_index,
+                sku: line.sku,
+                serial_number,
+                qty,
+                inspect: line.inspect,
+            });
+
+            if let Some(task) = task {
+                Self::deposit_event(Event::PutawayTaskCreated { who, task });
+            }
+
+            Ok(())
+        }
+
+        /// Pass or fail a received item held for inspection
+        ///
+        /// Passed items get a putaway task; failed items are moved to the MRB.
+        #[pallet::call_index(4)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn record_inspection(
+            origin: OriginFor<T>,
+            sku: Sku,
+            serial_number: SerialNumber,
+            passed: bool,
+            inspected_by: Employee,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let task =
+                Self::do_record_inspection(&who, sku.clone(), serial_number, passed, inspected_by)?;
+
+            Self::deposit_event(Event::InspectionRecorded {
+                who: who.clone(),
+                sku,
+                serial_number,
+                passed,
+            });
+
+            if let Some(task) = task {
+                Self::deposit_event(Event::PutawayTaskCreated { who, task });
+            }
+
+            Ok(())
+        }
+
+        /// Put a received item away to a bin, moving it to the bin's location
+        ///
+        /// The bin does not have to be the one the task suggested.
+        #[pallet::call_index(5)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn confirm_putaway(
+            origin: OriginFor<T>,
+            task_id: PutawayTaskId,
+            bin_id: BinId,
+            moved_by: Employee,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let task = Self::do_confirm_putaway(&who, task_id, bin_id, moved_by)?;
+
+            Self::deposit_event(Event::PutawayConfirmed {
+                who,
+                task_id,
+                bin_id,
+                suggested_bin: task.suggested_bin,
+            });
+
+            Ok(())
+        }
+
+        /// Close an ASN that will not be received in full
+        #[pallet::call_index(6)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn close_expected_receipt(origin: OriginFor<T>, asn_id: AsnId) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            Self::do_close_expected_receipt(&who, asn_id)?;
+
+            Self::deposit_event(Event::ExpectedReceiptClosed { who, asn_id });
+
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,673 @@
+use crate::{mock::*, types::*, Error, Event};
+use frame_support::pallet_prelude::ConstU32;
+use frame_support::sp_runtime::DispatchResult;
+use frame_support::{assert_noop, assert_ok, BoundedVec};
+use pallet_inventory::types::{
+    AbcCode, AdjustDetails, Employee, Item, Location, Qty, SerialNumber, Sku,
+};
+use pallet_purchasing::types::{PurchaseOrderLine, Supplier};
+
+const ALICE: u64 = 1;
+const BOB: u64 = 2;
+
+fn sku(sku: &str) -> Sku {
+    BoundedVec::truncate_from(sku.as_bytes().to_vec())
+}
+
+fn bin(bin_id: BinId, capacity: Qty) -> Bin {
+    Bin {
+        bin_id,
+        code: BoundedVec::truncate_from(b"A-01".to_vec()),
+        location: Location::Warehouse,
+        capacity,
+        ..Default::default()
+    }
+}
+
+fn add_bins(bins: Vec<Bin>) {
+    for bin in bins {
+        assert_ok!(Receiving::add_bin(RuntimeOrigin::signed(ALICE), bin));
+    }
+}
+
+fn asn_line(expected_qty: Qty, inspect: bool) -> AsnLine {
+    AsnLine {
+        sku: sku("BOLT"),
+        expected_qty,
+        inspect,
+        ..Default::default()
+    }
+}
+
+fn asn_lines(lines: Vec<AsnLine>) -> BoundedVec<AsnLine, ConstU32<MAX_ASN_LINES>> {
+    BoundedVec::truncate_from(lines)
+}
+
+fn add_supplier() {
+    assert_ok!(Purchasing::add_supplier(
+        RuntimeOrigin::root(),
+        Supplier {
+            supplier_id: 0,
+            active: true,
+            ..Default::default()
+        }
+    ));
+}
+
+/// Record ASN 0 from supplier 0 for Alice, with no purchase order
+fn set_up_asn(lines: Vec<AsnLine>) {
+    add_supplier();
+    assert_ok!(Receiving::create_expected_receipt(
+        RuntimeOrigin::signed(ALICE),
+        0,
+        0,
+        None,
+        asn_lines(lines),
+        10
+    ));
+}
+
+fn receive(serial_number: SerialNumber, qty: Qty) -> DispatchResult {
+    Receiving::receive_asn_line(
+        RuntimeOrigin::signed(ALICE),
+        0,
+        0,
+        serial_number,
+        qty,
+        77,
+        Employee::Charlie,
+    )
+}
+
+fn item(serial_number: SerialNumber) -> Item {
+    pallet_inventory::Inventory::<Test>::get((ALICE, sku("BOLT"), serial_number)).unwrap()
+}
+
+fn task(task_id: PutawayTaskId) -> PutawayTask {
+    crate::PutawayTasks::<Test>::get(task_id).unwrap().1
+}
+
+fn asn_status() -> AsnStatus {
+    crate::ExpectedReceipts::<Test>::get(0).unwrap().1.status
+}
+
+#[test]
+fn bins_need_capacity_outside_receiving() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Receiving::add_bin(RuntimeOrigin::signed(ALICE), bin(0, 10)));
+        assert_eq!(crate::Bins::<Test>::get(0), Some(bin(0, 10)));
+        System::assert_last_event(
+            Event::BinAdded {
+                who: ALICE,
+                bin: bin(0, 10),
+            }
+            .into(),
+        );
+
+        assert_noop!(
+            Receiving::add_bin(RuntimeOrigin::signed(ALICE), bin(0, 5)),
+            Error::<Test>::BinAlreadyExists
+        );
+        assert_noop!(
+            Receiving::add_bin(RuntimeOrigin::signed(ALICE), bin(1, 0)),
+            Error::<Test>::InvalidBin
+        );
+        assert_noop!(
+            Receiving::add_bin(
+                RuntimeOrigin::signed(ALICE),
+                Bin {
+                    location: Location::Receiving,
+                    ..bin(1, 10)
+                }
+            ),
+            Error::<Test>::InvalidBin
+        );
+    });
+}
+
+#[test]
+fn putaway_rules_name_existing_bins() {
+    new_test_ext().execute_with(|| {
+        add_bins(vec![bin(0, 10)]);
+
+        assert_noop!(
+            Receiving::set_putaway_rule(
+                RuntimeOrigin::signed(ALICE),
+                sku("BOLT"),
+                BoundedVec::truncate_from(vec![0, 1])
+            ),
+            Error::<Test>::BinNotFound
+        );
+
+        assert_ok!(Receiving::set_putaway_rule(
+            RuntimeOrigin::signed(ALICE),
+            sku("BOLT"),
+            BoundedVec::truncate_from(vec![0])
+        ));
+        assert_eq!(
+            crate::PutawayRules::<Test>::get(sku("BOLT")).into_inner(),
+            vec![0]
+        );
+
+        assert_ok!(Receiving::set_putaway_rule(
+            RuntimeOrigin::signed(ALICE),
+            sku("BOLT"),
+            BoundedVec::default()
+        ));
+        assert!(!crate::PutawayRules::<Test>::contains_key(sku("BOLT")));
+    });
+}
+
+#[test]
+fn expected_receipts_need_a_supplier_and_lines() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Receiving::create_expected_receipt(
+                RuntimeOrigin::signed(ALICE),
+                0,
+                0,
+                None,
+                asn_lines(vec![asn_line(10, false)]),
+                10
+            ),
+            pallet_purchasing::Error::<Test>::SupplierNotFound
+        );
+
+        set_up_asn(vec![asn_line(10, false)]);
+        let (owner, expected_receipt) = crate::ExpectedReceipts::<Test>::get(0).unwrap();
+        assert_eq!(owner, ALICE);
+        assert_eq!(expected_receipt.status, AsnStatus::Expected);
+        assert_eq!(expected_receipt.created_at, 1);
+        System::assert_last_event(
+            Event::ExpectedReceiptCreated {
+                who: ALICE,
+                asn_id: 0,
+                supplier_id: 0,
+            }
+            .into(),
+        );
+
+        assert_noop!(
+            Receiving::create_expected_receipt(
+                RuntimeOrigin::signed(ALICE),
+                0,
+                0,
+                None,
+                asn_lines(vec![asn_line(10, false)]),
+                10
+            ),
+            Error::<Test>::AsnAlreadyExists
+        );
+        assert_noop!(
+            Receiving::create_expected_receipt(
+                RuntimeOrigin::signed(ALICE),
+                1,
+                0,
+                None,
+                asn_lines(vec![]),
+                10
+            ),
+            Error::<Test>::NoAsnLines
+        );
+        assert_noop!(
+            Receiving::create_expected_receipt(
+                RuntimeOrigin::signed(ALICE),
+                1,
+                0,
+                None,
+                asn_lines(vec![asn_line(0, false)]),
+                10
+            ),
+            Error::<Test>::InvalidAsnLine
+        );
+
+        // A purchase order line can only be named with its purchase order
+        let mut po_line = asn_line(10, false);
+        po_line.po_line = Some(0);
+        assert_noop!(
+            Receiving::create_expected_receipt(
+                RuntimeOrigin::signed(ALICE),
+                1,
+                0,
+                None,
+                asn_lines(vec![po_line]),
+                10
+            ),
+            Error::<Test>::InvalidAsnLine
+        );
+    });
+}
+
+#[test]
+fn receipts_without_inspection_get_a_putaway_task() {
+    new_test_ext().execute_with(|| {
+        add_bins(vec![bin(0, 10), bin(1, 5)]);
+        set_up_asn(vec![asn_line(10, false)]);
+
+        assert_ok!(receive(1, 4));
+
+        assert_eq!(item(1).location, Location::Receiving);
+        assert_eq!(item(1).lot_number, 77);
+        assert_eq!(asn_status(), AsnStatus::PartiallyReceived);
+
+        // The fullest bin the bolts fit in is suggested
+        let created = PutawayTask {
+            task_id: 0,
+            asn_id: 0,
+            sku: sku("BOLT"),
+            serial_number: 1,
+            qty: 4,
+            suggested_bin: Some(1),
+            bin: None,
+            status: PutawayStatus::Open,
+            created_at: 1,
+            completed_at: None,
+        };
+        assert_eq!(task(0), created);
+        System::assert_has_event(
+            Event::AsnLineReceived {
+                who: ALICE,
+                asn_id: 0,
+                line_index: 0,
+                sku: sku("BOLT"),
+                serial_number: 1,
+                qty: 4,
+                inspect: false,
+            }
+            .into(),
+        );
+        System::assert_last_event(
+            Event::PutawayTaskCreated {
+                who: ALICE,
+                task: created,
+            }
+            .into(),
+        );
+
+        assert_ok!(receive(2, 6));
+        assert_eq!(asn_status(), AsnStatus::Received);
+        assert_eq!(task(1).suggested_bin, Some(0));
+    });
+}
+
+#[test]
+fn preferred_bins_are_suggested_first() {
+    new_test_ext().execute_with(|| {
+        add_bins(vec![bin(0, 10), bin(1, 5)]);
+        assert_ok!(Receiving::set_putaway_rule(
+            RuntimeOrigin::signed(ALICE),
+            sku("BOLT"),
+            BoundedVec::truncate_from(vec![0])
+        ));
+        set_up_asn(vec![asn_line(10, false)]);
+
+        assert_ok!(receive(1, 4));
+
+        assert_eq!(task(0).suggested_bin, Some(0));
+    });
+}
+
+#[test]
+fn confirming_putaway_moves_the_item_into_the_bin() {
+    new_test_ext().execute_with(|| {
+        add_bins(vec![
+            bin(0, 5),
+            Bin {
+                location: Location::Production,
+                ..bin(1, 10)
+            },
+        ]);
+        set_up_asn(vec![asn_line(10, false)]);
+        assert_ok!(receive(1, 4));
+
+        // Any bin with room can be chosen over the suggestion
+        System::set_block_number(3);
+        assert_ok!(Receiving::confirm_putaway(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            1,
+            Employee::Bob
+        ));
+
+        assert_eq!(item(1).location, Location::Production);
+        assert_eq!(
+            crate::ItemBins::<Test>::get((ALICE, sku("BOLT"), 1)),
+            Some(1)
+        );
+        assert_eq!(
+            Receiving::free_capacity(&crate::Bins::<Test>::get(1).unwrap()),
+            6
+        );
+        let completed = task(0);
+        assert_eq!(completed.status, PutawayStatus::Completed);
+        assert_eq!(completed.bin, Some(1));
+        assert_eq!(completed.completed_at, Some(3));
+        System::assert_last_event(
+            Event::PutawayConfirmed {
+                who: ALICE,
+                task_id: 0,
+                bin_id: 1,
+                suggested_bin: Some(0),
+            }
+            .into(),
+        );
+
+        assert_noop!(
+            Receiving::confirm_putaway(RuntimeOrigin::signed(ALICE), 0, 1, Employee::Bob),
+            Error::<Test>::PutawayCompleted
+        );
+    });
+}
+
+#[test]
+fn bins_free_up_as_put_away_stock_leaves_them() {
+    new_test_ext().execute_with(|| {
+        add_bins(vec![bin(0, 10)]);
+        set_up_asn(vec![asn_line(10, false)]);
+        assert_ok!(receive(1, 4));
+        assert_ok!(Receiving::confirm_putaway(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            0,
+            Employee::Bob
+        ));
+        let free_capacity = || Receiving::free_capacity(&crate::Bins::<Test>::get(0).unwrap());
+        assert_eq!(crate::BinOccupancy::<Test>::get(0), 4);
+        assert_eq!(free_capacity(), 6);
+
+        assert_ok!(Inventory::inventory_adjust(
+            RuntimeOrigin::signed(ALICE),
+            Employee::Bob,
+            sku("BOLT"),
+            1,
+            AdjustDetails::Quantity {
+                original_qty: 4,
+                new_qty: 3,
+                reason: BoundedVec::truncate_from(b"Cycle count".to_vec()),
+            }
+        ));
+        assert_eq!(free_capacity(), 7);
+
+        assert_ok!(Inventory::inventory_move(
+            RuntimeOrigin::signed(ALICE),
+            sku("BOLT"),
+            1,
+            Employee::Bob,
+            AdjustDetails::Location {
+                original_location: Location::Warehouse,
+                new_location: Location::Production,
+                reason: BoundedVec::truncate_from(b"Issue".to_vec()),
+            }
+        ));
+        assert_eq!(free_capacity(), 10);
+        assert_eq!(crate::ItemBins::<Test>::get((ALICE, sku("BOLT"), 1)), None);
+    });
+}
+
+#[test]
+fn putaway_respects_bin_rules_and_capacity() {
+    new_test_ext().execute_with(|| {
+        add_bins(vec![
+            bin(0, 3),
+            Bin {
+                abc_code: Some(AbcCode::C),
+                ..bin(1, 10)
+            },
+        ]);
+        set_up_asn(vec![asn_line(10, false)]);
+        assert_ok!(receive(1, 4));
+
+        // Neither bin can take the bolts
+        assert_eq!(task(0).suggested_bin, None);
+        assert_noop!(
+            Receiving::confirm_putaway(RuntimeOrigin::signed(ALICE), 0, 0, Employee::Bob),
+            Error::<Test>::BinFull
+        );
+        assert_noop!(
+            Receiving::confirm_putaway(RuntimeOrigin::signed(ALICE), 0, 1, Employee::Bob),
+            Error::<Test>::BinRejectsItem
+        );
+        assert_noop!(
+            Receiving::confirm_putaway(RuntimeOrigin::signed(ALICE), 0, 2, Employee::Bob),
+            Error::<Test>::BinNotFound
+        );
+        assert_noop!(
+            Receiving::confirm_putaway(RuntimeOrigin::signed(BOB), 0, 0, Employee::Bob),
+            Error::<Test>::NotPutawayTaskOwner
+        );
+        assert_noop!(
+            Receiving::confirm_putaway(RuntimeOrigin::signed(ALICE), 1, 0, Employee::Bob),
+            Error::<Test>::PutawayTaskNotFound
+        );
+    });
+}
+
+#[test]
+fn inspected_lines_are_held_until_they_pass() {
+    new_test_ext().execute_with(|| {
+        add_bins(vec![bin(0, 10)]);
+        set_up_asn(vec![asn_line(10, true)]);
+
+        assert_ok!(receive(1, 4));
+
+        assert_eq!(crate::NextPutawayTaskId::<Test>::get(), 0);
+        assert!(Inventory::is_on_hold(&ALICE, &sku("BOLT"), 1));
+        assert_eq!(
+            crate::PendingInspections::<Test>::get((ALICE, sku("BOLT"), 1)),
+            Some(0)
+        );
+
+        assert_ok!(Receiving::record_inspection(
+            RuntimeOrigin::signed(ALICE),
+            sku("BOLT"),
+            1,
+            true,
+            Employee::Dave
+        ));
+
+        assert!(!Inventory::is_on_hold(&ALICE, &sku("BOLT"), 1));
+        assert_eq!(task(0).suggested_bin, Some(0));
+        System::assert_has_event(
+            Event::InspectionRecorded {
+                who: ALICE,
+                sku: sku("BOLT"),
+                serial_number: 1,
+                passed: true,
+            }
+            .into(),
+        );
+
+        assert_noop!(
+            Receiving::record_inspection(
+                RuntimeOrigin::signed(ALICE),
+                sku("BOLT"),
+                1,
+                true,
+                Employee::Dave
+            ),
+            Error::<Test>::InspectionNotPending
+        );
+    });
+}
+
+#[test]
+fn failed_inspections_go_to_the_mrb() {
+    new_test_ext().execute_with(|| {
+        set_up_asn(vec![asn_line(10, true)]);
+        assert_ok!(receive(1, 4));
+
+        assert_ok!(Receiving::record_inspection(
+            RuntimeOrigin::signed(ALICE),
+            sku("BOLT"),
+            1,
+            false,
+            Employee::Dave
+        ));
+
+        assert_eq!(item(1).location, Location::Mrb);
+        assert!(!Inventory::is_on_hold(&ALICE, &sku("BOLT"), 1));
+        assert_eq!(crate::NextPutawayTaskId::<Test>::get(), 0);
+        System::assert_last_event(
+            Event::InspectionRecorded {
+                who: ALICE,
+                sku: sku("BOLT"),
+                serial_number: 1,
+                passed: false,
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn receipts_are_limited_to_open_lines_of_open_asns() {
+    new_test_ext().execute_with(|| {
+        set_up_asn(vec![asn_line(10, false)]);
+        assert_ok!(receive(1, 4));
+
+        assert_noop!(receive(2, 7), Error::<Test>::OverReceipt);
+        assert_noop!(receive(2, 0), Error::<Test>::OverReceipt);
+        assert_noop!(
+            receive(1, 1),
+            pallet_purchasing::Error::<Test>::SerialNumberInUse
+        );
+        assert_noop!(
+            Receiving::receive_asn_line(
+                RuntimeOrigin::signed(ALICE),
+                0,
+                1,
+                2,
+                1,
+                77,
+                Employee::Charlie
+            ),
+            Error::<Test>::LineNotFound
+        );
+        assert_noop!(
+            Receiving::receive_asn_line(
+                RuntimeOrigin::signed(BOB),
+                0,
+                0,
+                2,
+                1,
+                77,
+                Employee::Charlie
+            ),
+            Error::<Test>::NotAsnOwner
+        );
+        assert_noop!(
+            Receiving::receive_asn_line(
+                RuntimeOrigin::signed(ALICE),
+                1,
+                0,
+                2,
+                1,
+                77,
+                Employee::Charlie
+            ),
+            Error::<Test>::AsnNotFound
+        );
+
+        assert_noop!(
+            Receiving::close_expected_receipt(RuntimeOrigin::signed(BOB), 0),
+            Error::<Test>::NotAsnOwner
+        );
+        assert_ok!(Receiving::close_expected_receipt(
+            RuntimeOrigin::signed(ALICE),
+            0
+        ));
+        assert_eq!(asn_status(), AsnStatus::Closed);
+        System::assert_last_event(
+            Event::ExpectedReceiptClosed {
+                who: ALICE,
+                asn_id: 0,
+            }
+            .into(),
+        );
+
+        assert_noop!(receive(2, 1), Error::<Test>::AsnNotOpen);
+        assert_noop!(
+            Receiving::close_expected_receipt(RuntimeOrigin::signed(ALICE), 0),
+            Error::<Test>::AsnNotOpen
+        );
+    });
+}
+
+#[test]
+fn asn_lines_against_a_purchase_order_receive_through_it() {
+    new_test_ext().execute_with(|| {
+        add_supplier();
+        assert_ok!(Purchasing::add_po_approver(RuntimeOrigin::root(), BOB));
+        assert_ok!(Purchasing::create_purchase_order(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            0,
+            BoundedVec::truncate_from(vec![PurchaseOrderLine {
+                sku: sku("BOLT"),
+                qty: 10,
+                ..Default::default()
+            }])
+        ));
+        assert_ok!(Purchasing::approve_purchase_order(
+            RuntimeOrigin::signed(BOB),
+            0
+        ));
+
+        let mut po_line = asn_line(10, false);
+        po_line.po_line = Some(0);
+
+        // Lines must match the purchase order, which must be the account's own
+        assert_noop!(
+            Receiving::create_expected_receipt(
+                RuntimeOrigin::signed(BOB),
+                0,
+                0,
+                Some(0),
+                asn_lines(vec![po_line.clone()]),
+                10
+            ),
+            pallet_purchasing::Error::<Test>::NotPurchaseOrderOwner
+        );
+        assert_noop!(
+            Receiving::create_expected_receipt(
+                RuntimeOrigin::signed(ALICE),
+                0,
+                0,
+                Some(1),
+                asn_lines(vec![po_line.clone()]),
+                10
+            ),
+            pallet_purchasing::Error::<Test>::PurchaseOrderNotFound
+        );
+        assert_noop!(
+            Receiving::create_expected_receipt(
+                RuntimeOrigin::signed(ALICE),
+                0,
+                0,
+                Some(0),
+                asn_lines(vec![AsnLine {
+                    sku: sku("NUT"),
+                    ..po_line.clone()
+                }]),
+                10
+            ),
+            Error::<Test>::InvalidAsnLine
+        );
+
+        assert_ok!(Receiving::create_expected_receipt(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            0,
+            Some(0),
+            asn_lines(vec![po_line]),
+            10
+        ));
+        assert_ok!(receive(1, 4));
+
+        let (_, purchase_order) = pallet_purchasing::PurchaseOrders::<Test>::get(0).unwrap();
+        assert_eq!(purchase_order.lines[0].received_qty, 4);
+        assert!(pallet_purchasing::PoReceipts::<Test>::contains_key((
+            0, 0, 1
+        )));
+    });
+}
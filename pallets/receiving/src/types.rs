@@ -0,0 +1,114 @@
+use codec::{Decode, Encode};
+use frame_support::pallet_prelude::{ConstU32, MaxEncodedLen};
+use frame_support::BoundedVec;
+use pallet_inventory::types::{AbcCode, Item, Location, ProductType, Qty, SerialNumber, Sku};
+use pallet_purchasing::types::{LineIndex, PurchaseOrderNumber, SupplierId};
+use scale_info::TypeInfo;
+
+/// Advance shipping notice number, as given by the supplier
+pub type AsnId = u32;
+pub type BinId = u32;
+pub type PutawayTaskId = u32;
+pub type BinCode = BoundedVec<u8, ConstU32<16>>;
+
+/// Most lines a single expected receipt can hold
+pub const MAX_ASN_LINES: u32 = 50;
+/// Most bins a material can prefer for putaway
+pub const MAX_PREFERRED_BINS: u32 = 10;
+/// Most bins searched for room when a material's preferred bins are full
+pub const MAX_BINS_SEARCHED: u32 = 100;
+
+/// A storage bin that received stock can be put away to
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
+pub struct Bin {
+    pub bin_id: BinId,
+    pub code: BinCode,
+    /// The location the bin sits in, which put away items are moved to
+    pub location: Location,
+    /// Most units the bin can hold
+    pub capacity: Qty,
+    /// Only accept materials of this ABC class
+    pub abc_code: Option<AbcCode>,
+    /// Only accept materials of this product type
+    pub product_type: Option<ProductType>,
+}
+
+impl Bin {
+    /// Whether the bin's material rules allow the item to be stored in it
+    pub fn accepts(&self, item: &Item) -> bool {
+        self.abc_code
+            .as_ref()
+            .map_or(true, |abc_code| *abc_code == item.abc_code)
+            && self
+                .product_type
+                .as_ref()
+                .map_or(true, |product_type| *product_type == item.product_type)
+    }
+}
+
+/// A SKU a supplier has announced it is shipping
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
+pub struct AsnLine {
+    pub sku: Sku,
+    pub expected_qty: Qty,
+    pub received_qty: Qty,
+    /// The purchase order line the delivery is received against, if any
+    pub po_line: Option<LineIndex>,
+    /// Hold received stock for inspection before it can be put away
+    pub inspect: bool,
+}
+
+impl AsnLine {
+    /// Quantity still expected
+    pub fn open_qty(&self) -> Qty {
+        self.expected_qty.saturating_sub(self.received_qty)
+    }
+}
+
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
+pub enum AsnStatus {
+    #[default]
+    Expected,
+    PartiallyReceived,
+    Received,
+    /// Closed before every line was received in full
+    Closed,
+}
+
+/// An expected receipt, announced by the supplier's advance shipping notice
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct ExpectedReceipt {
+    pub asn_id: AsnId,
+    pub supplier_id: SupplierId,
+    /// The purchase order the shipment is delivered against, if any
+    pub po_number: Option<PurchaseOrderNumber>,
+    pub lines: BoundedVec<AsnLine, ConstU32<MAX_ASN_LINES>>,
+    /// Block the shipment is expected to arrive by
+    pub expected_at: u32,
+    pub status: AsnStatus,
+    pub created_at: u32,
+}
+
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
+pub enum PutawayStatus {
+    #[default]
+    Open,
+    Completed,
+}
+
+/// Moving a received item from receiving into a storage bin
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct PutawayTask {
+    pub task_id: PutawayTaskId,
+    pub asn_id: AsnId,
+    pub sku: Sku,
+    pub serial_number: SerialNumber,
+    pub qty: Qty,
+    /// The bin suggested when the task was created, if any had room
+    pub suggested_bin: Option<BinId>,
+    /// The bin the item was actually put away to
+    pub bin: Option<BinId>,
+    pub status: PutawayStatus,
+    pub created_at: u32,
+    pub completed_at: Option<u32>,
+}
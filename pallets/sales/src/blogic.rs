@@ -0,0 +1,486 @@
+use crate::pallet::{
+    AllocatedStock, Config, CustomerExposure, Customers, Error, SalesOrderAllocations, SalesOrders,
+};
+use crate::{pallet::Pallet, types::*};
+use frame_support::ensure;
+use frame_support::pallet_prelude::ConstU32;
+use frame_support::sp_runtime::{DispatchError, DispatchResult};
+use frame_support::BoundedVec;
+use pallet_inventory::types::{
+    AdjustDetails, Employee, InventoryType, Item, Location, PickStrategy, Qty, SerialNumber, Sku,
+    StockAllocations,
+};
+use pallet_inventory::Inventory;
+use scale_info::prelude::vec::Vec;
+
+impl<T: Config> Pallet<T> {
+    /// Add a customer to the customer master
+    pub fn do_add_customer(customer: Customer) -> DispatchResult {
+        ensure!(
+            !Customers::<T>::contains_key(customer.customer_id),
+            Error::<T>::CustomerAlreadyExists
+        );
+
+        Customers::<T>::insert(customer.customer_id, customer);
+
+        Ok(())
+    }
+
+    /// Update an existing customer, returning the customer as it was
+    pub fn do_update_customer(customer: Customer) -> Result<Customer, DispatchError> {
+        Customers::<T>::try_mutate(customer.customer_id, |existing| {
+            let existing = existing.as_mut().ok_or(Error::<T>::CustomerNotFound)?;
+            Ok(core::mem::replace(existing, customer))
+        })
+    }
+
+    /// Take a sales order, holding it for credit if the customer cannot take it on
+    pub fn do_create_sales_order(
+        who: &T::AccountId,
+        so_number: SalesOrderNumber,
+        customer_id: CustomerId,
+        mut lines: BoundedVec<SalesOrderLine, ConstU32<MAX_SO_LINES>>,
+    ) -> Result<SalesOrder, DispatchError> {
+        ensure!(
+            !SalesOrders::<T>::contains_key(so_number),
+            Error::<T>::SalesOrderAlreadyExists
+        );
+
+        let customer = Customers::<T>::get(customer_id).ok_or(Error::<T>::CustomerNotFound)?;
+        ensure!(!lines.is_empty(), Error::<T>::NoSalesOrderLines);
+
+        for line in lines.iter_mut() {
+            ensure!(line.qty > 0, Error::<T>::InvalidSalesOrderLine);
+            line.shipped_qty = 0;
+        }
+
+        let mut sales_order = SalesOrder {
+            so_number,
+            customer_id,
+            lines,
+            status: SalesOrderStatus::Open,
+            created_at: pallet_inventory::Pallet::<T>::current_block(),
+            shipped_at: None,
+        };
+
+        let exposure =
+            CustomerExposure::<T>::get(customer_id).saturating_add(sales_order.total_value());
+        if customer.credit_hold || customer.credit_limit.is_some_and(|limit| exposure > limit) {
+            sales_order.status = SalesOrderStatus::CreditHold;
+        }
+
+        CustomerExposure::<T>::insert(customer_id, exposure);
+        SalesOrders::<T>::insert(so_number, (who, sales_order.clone()));
+
+        Ok(sales_order)
+    }
+
+    /// Release a sales order held for credit.
+    ///
+    /// The customer must be off credit hold, and their exposure, which already counts the held
+    /// order, must be within their credit limit.
+    pub fn do_release_credit_hold(
+        who: &T::AccountId,
+        so_number: SalesOrderNumber,
+    ) -> DispatchResult {
+        let mut sales_order =
+            Self::owned_sales_order(who, so_number, SalesOrderStatus::CreditHold)?;
+
+        let customer =
+            Customers::<T>::get(sales_order.customer_id).ok_or(Error::<T>::CustomerNotFound)?;
+        ensure!(!customer.credit_hold, Error::<T>::CustomerOnCreditHold);
+        let exposure = CustomerExposure::<T>::get(sales_order.customer_id);
+        ensure!(
+            !customer.credit_limit.is_some_and(|limit| exposure > limit),
+            Error::<T>::CreditLimitExceeded
+        );
+
+        sales_order.status = SalesOrderStatus::Open;
+        SalesOrders::<T>::insert(so_number, (who, sales_order));
+
+        Ok(())
+    }
+
+    /// Reserve finished goods in the warehouse for every line of an open sales order.
+    ///
+    /// Items are allocated in the given strategy's order, or else in each material's own pick
+    /// order. Stock on quality hold, or already allocated to another sales order, is skipped.
+    /// Fails unless every line can be covered in full.
+    pub fn do_allocate_sales_order(
+        who: &T::AccountId,
+        so_number: SalesOrderNumber,
+        strategy: Option<&PickStrategy>,
+    ) -> Result<Allocation, DispatchError> {
+        let mut sales_order = Self::owned_sales_order(who, so_number, SalesOrderStatus::Open)?;
+
+        let mut allocation = Allocation::default();
+        for (line_index, line) in sales_order.lines.iter().enumerate() {
+            let strategy = strategy
+                .cloned()
+                .unwrap_or_else(|| pallet_inventory::Pallet::<T>::pick_strategy(&line.sku));
+            let picked = pallet_inventory::Pallet::<T>::pick_stock(
+                Self::allocatable_items(who, &line.sku),
+                line.qty,
+                &strategy,
+                &Location::Packaging,
+            );
+
+            let picked_qty = picked
+                .iter()
+                .fold(0 as Qty, |total, (_, qty)| total.saturating_add(*qty));
+            ensure!(picked_qty >= line.qty, Error::<T>::InsufficientInventory);
+
+            for (item, qty) in picked {
+                // Later lines of the same SKU see this line's allocation
+                AllocatedStock::<T>::mutate((who, item.sku.clone(), item.serial_number), |total| {
+                    *total = total.saturating_add(qty)
+                });
+
+                allocation
+                    .try_push(AllocatedItem {
+                        line_index: line_index as LineIndex,
+                        sku: item.sku,
+                        serial_number: item.serial_number,
+                        qty,
+                    })
+                    .map_err(|_| Error::<T>::TooManyAllocations)?;
+            }
+        }
+
+        SalesOrderAllocations::<T>::insert(so_number, allocation.clone());
+        sales_order.status = SalesOrderStatus::Allocated;
+        SalesOrders::<T>::insert(so_number, (who, sales_order));
+
+        Ok(allocation)
+    }
+
+    /// Move every item allocated to a sales order to packaging
+    pub fn do_pick_sales_order(
+        who: &T::AccountId,
+        so_number: SalesOrderNumber,
+        picked_by: &Employee,
+    ) -> DispatchResult {
+        let mut sales_order = Self::owned_sales_order(who, so_number, SalesOrderStatus::Allocated)?;
+
+        for allocated in SalesOrderAllocations::<T>::get(so_number)
+            .unwrap_or_default()
+            .iter()
+        {
+            let item = Self::allocated_item(who, allocated)?;
+            if item.location != Location::Packaging {
+                Self::move_item(
+                    who,
+                    item,
+                    Location::Packaging,
+                    picked_by,
+                    "Pick sales order",
+                )?;
+            }
+        }
+
+        sales_order.status = SalesOrderStatus::Picked;
+        SalesOrders::<T>::insert(so_number, (who, sales_order));
+
+        Ok(())
+    }
+
+    /// Mark the picked items of a sales order as packed
+    pub fn do_pack_sales_order(who: &T::AccountId, so_number: SalesOrderNumber) -> DispatchResult {
+        let mut sales_order = Self::owned_sales_order(who, so_number, SalesOrderStatus::Picked)?;
+
+        sales_order.status = SalesOrderStatus::Packed;
+        SalesOrders::<T>::insert(so_number, (who, sales_order));
+
+        Ok(())
+    }
+
+    /// Ship a packed sales order.
+    ///
+    /// Each allocated item moves to shipping and the allocated quantity is drawn down from it.
+    /// Whatever is left of an item once the order has shipped goes back to the warehouse.
+    pub fn do_confirm_shipment(
+        who: &T::AccountId,
+        so_number: SalesOrderNumber,
+        shipped_by: &Employee,
+    ) -> DispatchResult {
+        let mut sales_order = Self::owned_sales_order(who, so_number, SalesOrderStatus::Packed)?;
+        let allocation = SalesOrderAllocations::<T>::take(so_number).unwrap_or_default();
+
+        let mut shipped: Vec<(Sku, SerialNumber)> = Vec::new();
+        for allocated in allocation.iter() {
+            let mut item = Self::allocated_item(who, allocated)?;
+            ensure!(item.qty >= allocated.qty, Error::<T>::InsufficientInventory);
+            pallet_inventory::Pallet::<T>::ensure_not_consigned(
+                who,
+                &item.sku,
+                item.serial_number,
+            )?;
+
+            if item.location != Location::Shipping {
+                Self::move_item(
+                    who,
+                    item.clone(),
+                    Location::Shipping,
+                    shipped_by,
+                    "Ship sales order",
+                )?;
+                item.location = Location::Shipping;
+                item.moved_by = shipped_by.clone();
+            }
+
+            pallet_inventory::Pallet::<T>::do_inventory_adjust(
+                who,
+                shipped_by.clone(),
+                item.clone(),
+                AdjustDetails::Quantity {
+                    original_qty: item.qty,
+                    new_qty: item.qty - allocated.qty,
+                    reason: pallet_inventory::Pallet::<T>::reason("Ship sales order")?,
+                },
+            )?;
+
+            Self::release_allocated_stock(who, allocated);
+            if let Some(line) = sales_order.lines.get_mut(allocated.line_index as usize) {
+                line.shipped_qty = line.shipped_qty.saturating_add(allocated.qty);
+            }
+
+            let key = (allocated.sku.clone(), allocated.serial_number);
+            if !shipped.contains(&key) {
+                shipped.push(key);
+            }
+        }
+
+        // Return what the order did not take
+        for (sku, serial_number) in shipped {
+            let Some(item) = Inventory::<T>::get((who, sku, serial_number)) else {
+                continue;
+            };
+            if item.qty > 0 && item.location == Location::Shipping {
+                Self::move_item(
+                    who,
+                    item,
+                    Location::Warehouse,
+                    shipped_by,
+                    "Return unshipped stock",
+                )?;
+            }
+        }
+
+        Self::reduce_exposure(&sales_order);
+        sales_order.status = SalesOrderStatus::Shipped;
+        sales_order.shipped_at = Some(pallet_inventory::Pallet::<T>::current_block());
+        SalesOrders::<T>::insert(so_number, (who, sales_order));
+
+        Ok(())
+    }
+
+    /// Cancel a sales order that has not shipped, returning picked items to the warehouse
+    pub fn do_cancel_sales_order(
+        who: &T::AccountId,
+        so_number: SalesOrderNumber,
+        returned_by: &Employee,
+    ) -> DispatchResult {
+        let (owner, mut sales_order) =
+            SalesOrders::<T>::get(so_number).ok_or(Error::<T>::SalesOrderNotFound)?;
+
+        ensure!(&owner == who, Error::<T>::NotSalesOrderOwner);
+        ensure!(
+            !matches!(
+                sales_order.status,
+                SalesOrderStatus::Shipped | SalesOrderStatus::Cancelled
+            ),
+            Error::<T>::InvalidSalesOrderStatus
+        );
+
+        for allocated in SalesOrderAllocations::<T>::take(so_number)
+            .unwrap_or_default()
+            .iter()
+        {
+            Self::release_allocated_stock(who, allocated);
+
+            let Some(item) =
+                Inventory::<T>::get((who, allocated.sku.clone(), allocated.serial_number))
+            else {
+                continue;
+            };
+            if item.location == Location::Packaging {
+                Self::move_item(
+                    who,
+                    item,
+                    Location::Warehouse,
+                    returned_by,
+                    "Return cancelled sales order",
+                )?;
+            }
+        }
+
+        Self::reduce_exposure(&sales_order);
+        sales_order.status = SalesOrderStatus::Cancelled;
+        SalesOrders::<T>::insert(so_number, (who, sales_order));
+
+        Ok(())
+    }
+
+    /// Finished goods of a SKU in the warehouse, with the quantity of each still unallocated
+    pub fn allocatable_items(who: &T::AccountId, sku: &Sku) -> Vec<(Item, Qty)> {
+        Inventory::<T>::iter_prefix((who, sku.clone()))
+            .map(|(_, item)| item)
+            .filter(|item| {
+                item.location == Location::Warehouse
+                    && item.inventory_type == InventoryType::FinishedGood
+            })
+            // Consigned stock is not the account's to sell until it is settled
+            .filter(|item| {
+                !pallet_inventory::Pallet::<T>::is_on_hold(who, &item.sku, item.serial_number)
+                    && pallet_inventory::Pallet::<T>::consignment_owner(
+                        who,
+                        &item.sku,
+                        item.serial_number,
+                    )
+                    .is_none()
+            })
+            .filter_map(|item| {
+                // Also leaves out stock reserved elsewhere, such as for kitting
+                let allocated = <T as pallet_inventory::Config>::StockAllocations::allocated_qty(
+                    who,
+                    &item.sku,
+                    item.serial_number,
+                );
+                let available = item.qty.saturating_sub(allocated);
+                (available > 0).then_some((item, available))
+            })
+            .collect()
+    }
+
+    /// Take a short-picked quantity off an allocated sales order
+    ///
+    /// `short` names the allocation and the quantity that could not be picked.
+    pub fn do_reduce_allocation(
+        who: &T::AccountId,
+        so_number: SalesOrderNumber,
+        short: &AllocatedItem,
+    ) -> DispatchResult {
+        Self::owned_sales_order(who, so_number, SalesOrderStatus::Allocated)?;
+
+        let mut allocation = SalesOrderAllocations::<T>::get(so_number).unwrap_or_default();
+        let position = allocation
+            .iter()
+            .position(|allocated| {
+                allocated.line_index == short.line_index
+                    && allocated.sku == short.sku
+                    && allocated.serial_number == short.serial_number
+                    && allocated.qty >= short.qty
+            })
+            .ok_or(Error::<T>::AllocationNotFound)?;
+
+        allocation[position].qty -= short.qty;
+        if allocation[position].qty == 0 {
+            allocation.remove(position);
+        }
+        SalesOrderAllocations::<T>::insert(so_number, allocation);
+
+        Self::release_allocated_stock(who, short);
+
+        Ok(())
+    }
+
+    /// Allocate more stock to an allocated sales order, e.g. to replace a short pick
+    pub fn do_add_allocation(
+        who: &T::AccountId,
+        so_number: SalesOrderNumber,
+        allocated: AllocatedItem,
+    ) -> DispatchResult {
+        let sales_order = Self::owned_sales_order(who, so_number, SalesOrderStatus::Allocated)?;
+        ensure!(
+            sales_order
+                .lines
+                .get(allocated.line_index as usize)
+                .is_some_and(|line| line.sku == allocated.sku),
+            Error::<T>::AllocationNotFound
+        );
+
+        let mut allocation = SalesOrderAllocations::<T>::get(so_number).unwrap_or_default();
+        allocation
+            .try_push(allocated.clone())
+            .map_err(|_| Error::<T>::TooManyAllocations)?;
+        SalesOrderAllocations::<T>::insert(so_number, allocation);
+
+        AllocatedStock::<T>::mutate(
+            (who, allocated.sku.clone(), allocated.serial_number),
+            |total| *total = total.saturating_add(allocated.qty),
+        );
+
+        Ok(())
+    }
+
+    /// Fetch a sales order taken by the account, at the given status
+    fn owned_sales_order(
+        who: &T::AccountId,
+        so_number: SalesOrderNumber,
+        status: SalesOrderStatus,
+    ) -> Result<SalesOrder, DispatchError> {
+        let (owner, sales_order) =
+            SalesOrders::<T>::get(so_number).ok_or(Error::<T>::SalesOrderNotFound)?;
+
+        ensure!(&owner == who, Error::<T>::NotSalesOrderOwner);
+        ensure!(
+            sales_order.status == status,
+            Error::<T>::InvalidSalesOrderStatus
+        );
+
+        Ok(sales_order)
+    }
+
+    /// Fetch the inventory item an allocation reserves
+    fn allocated_item(
+        who: &T::AccountId,
+        allocated: &AllocatedItem,
+    ) -> Result<Item, DispatchError> {
+        Inventory::<T>::get((who, allocated.sku.clone(), allocated.serial_number))
+            .ok_or(pallet_inventory::Error::<T>::InventoryNotFound.into())
+    }
+
+    /// Give an allocated quantity back to the serial's unallocated stock
+    fn release_allocated_stock(who: &T::AccountId, allocated: &AllocatedItem) {
+        AllocatedStock::<T>::mutate_exists(
+            (who, allocated.sku.clone(), allocated.serial_number),
+            |total| {
+                let remaining = total.unwrap_or_default().saturating_sub(allocated.qty);
+                *total = (remaining > 0).then_some(remaining);
+            },
+        );
+    }
+
+    /// Take a sales order's value off its customer's exposure
+    fn reduce_exposure(sales_order: &SalesOrder) {
+        CustomerExposure::<T>::mutate(sales_order.customer_id, |exposure| {
+            *exposure = exposure.saturating_sub(sales_order.total_value())
+        });
+    }
+
+    /// Move an item to a new location
+    fn move_item(
+        who: &T::AccountId,
+        item: Item,
+        location: Location,
+        moved_by: &Employee,
+        reason: &str,
+    ) -> DispatchResult {
+        pallet_inventory::Pallet::<T>::do_inventory_move(
+            who,
+            item.clone(),
+            moved_by.clone(),
+            AdjustDetails::Location {
+                original_location: item.location,
+                new_location: location,
+                reason: pallet_inventory::Pallet::<T>::reason(reason)?,
+            },
+        )
+    }
+}
+
+impl<T: Config> StockAllocations<T::AccountId> for Pallet<T> {
+    fn allocated_qty(who: &T::AccountId, sku: &Sku, serial_number: SerialNumber) -> Qty {
+        AllocatedStock::<T>::get((who, sku.clone(), serial_number))
+    }
+}
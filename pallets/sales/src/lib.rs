@@ -0,0 +1,450 @@
+//! # Sales Pallet
+//!
+//! - [`Config`]
+//! - [`Call`]
+//!
+//! ## Overview
+//!
+//! The Sales pallet takes customer orders and fulfils them from finished goods held by the
+//! inventory pallet. This pallet provides functionalities to:
+//!
+//! - Manage customer master data, including credit limits and credit holds, through the
+//!   configured `SalesAdminOrigin`.
+//! - Take sales orders with lines by SKU, quantity, price and due date.
+//! - Hold orders for credit, and release them once cleared.
+//! - Allocate finished goods in the warehouse to every line, in each material's pick order.
+//! - Pick allocated items to `Location::Packaging`, pack them and confirm the shipment.
+//!
+//! A new order is held for credit if its customer is on credit hold, or if it would take the
+//! customer's unshipped orders over their credit limit. A held order can only be released once
+//! the customer is off credit hold and their unshipped orders are within the limit. Confirming a shipment moves the picked
+//! items to `Location::Shipping` and draws the shipped quantity down from stock. Whatever is
+//! left of a partly shipped item goes back to the warehouse. Stock held on consignment is not
+//! allocated or shipped until its owner settles it.
+//!
+//! Allocated stock is reported to the inventory pallet through `StockAllocations`, so that
+//! moves, adjustments, scraps and work orders elsewhere leave it for the sales order.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! - `add_customer`: Add a customer to the customer master.
+//! - `update_customer`: Update an existing customer.
+//! - `set_customer_credit_hold`: Place a customer on credit hold, or lift it.
+//! - `create_sales_order`: Take a sales order from a customer.
+//! - `release_credit_hold`: Release a sales order held for credit.
+//! - `allocate_sales_order`: Reserve finished goods for every line of a sales order.
+//! - `pick_sales_order`: Pick the allocated items to packaging.
+//! - `pack_sales_order`: Mark the picked items as packed.
+//! - `confirm_shipment`: Ship a packed sales order, drawing the shipped stock down.
+//! - `cancel_sales_order`: Cancel a sales order that has not shipped.
+//!
+//! ## Storage
+//!
+//! - `Customers`: Customer master data by customer ID.
+//! - `CustomerExposure`: The value of each customer's unshipped orders.
+//! - `SalesOrders`: Sales orders, and the account that took them, by SO number.
+//! - `SalesOrderAllocations`: The serials allocated to each sales order.
+//! - `AllocatedStock`: The quantity of each serial allocated to sales orders.
+//!
+//! ## Events
+//!
+//! - `CustomerAdded`: Emitted when a customer is added.
+//! - `CustomerUpdated`: Emitted when a customer is updated.
+//! - `CustomerCreditHoldSet`: Emitted when a customer's credit hold is placed or lifted.
+//! - `SalesOrderCreated`: Emitted when a sales order is taken.
+//! - `CreditHoldReleased`: Emitted when a sales order held for credit is released.
+//! - `SalesOrderAllocated`: Emitted when finished goods are allocated to a sales order.
+//! - `SalesOrderPicked`: Emitted when a sales order's items are picked.
+//! - `SalesOrderPacked`: Emitted when a sales order's items are packed.
+//! - `ShipmentConfirmed`: Emitted when a sales order ships.
+//! - `SalesOrderCancelled`: Emitted when a sales order is cancelled.
+//!
+//! ## Errors
+//!
+//! - `CustomerAlreadyExists`: Attempted to add a customer ID that already exists.
+//! - `CustomerNotFound`: The specified customer could not be located.
+//! - `CustomerOnCreditHold`: The customer is on credit hold.
+//! - `CreditLimitExceeded`: The customer's unshipped orders are over their credit limit.
+//! - `SalesOrderAlreadyExists`: Attempted to take an SO number that already exists.
+//! - `SalesOrderNotFound`: The specified sales order could not be located.
+//! - `NotSalesOrderOwner`: Only the account that took the sales order can manage it.
+//! - `InvalidSalesOrderLine`: A line has no quantity.
+//! - `NoSalesOrderLines`: The sales order has no lines.
+//! - `InvalidSalesOrderStatus`: The sales order is not at the right step for the call.
+//! - `InsufficientInventory`: Not enough unallocated finished goods to cover a line.
+//! - `TooManyAllocations`: The sales order would be allocated too many serials.
+//! - `AllocationNotFound`: The sales order has no such allocation to change.
+//!
+//! This pallet uses `no_std` for compatibility with Wasm environments, a polkadot standard.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub use pallet::*;
+
+// FRAME pallets require their own "mock runtimes" to be able to run unit tests. This module
+// contains a mock runtime specific for testing this pallet's functionality.
+#[cfg(test)]
+mod mock;
+
+// This module contains the unit tests for this pallet.
+// Learn about pallet unit testing here: https://docs.substrate.io/test/unit-testing/
+#[cfg(test)]
+mod tests;
+
+mod blogic;
+pub mod types;
+
+use crate::types::{
+    Allocation, Customer, CustomerId, SalesOrder, SalesOrderLine, SalesOrderNumber,
+    MAX_ALLOCATIONS, MAX_SO_LINES,
+};
+use pallet_inventory::types::{Employee, Qty, SerialNumber, Sku};
+
+#[frame_support::pallet]
+pub mod pallet {
+    use super::*;
+    use frame_support::pallet_prelude::*;
+    use frame_system::pallet_prelude::*;
+    use pallet_inventory::pallet::Config as InventoryConfig;
+
+    #[pallet::pallet]
+    pub struct Pallet<T>(_);
+
+    /// The pallet's configuration trait.
+    ///
+    /// This trait is tightly coupled with the Inventory pallet -> [InventoryConfig]
+    #[pallet::config]
+    pub trait Config: frame_system::Config + InventoryConfig {
+        type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+        /// Origin allowed to manage the customer master
+        type SalesAdminOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+    }
+
+    /// Customers Storage
+    ///
+    /// Customer master data by customer ID
+    #[pallet::storage]
+    pub type Customers<T: Config> = StorageMap<_, Twox64Concat, CustomerId, Customer, OptionQuery>;
+
+    /// The value of each customer's orders that have not shipped or been cancelled
+    #[pallet::storage]
+    pub type CustomerExposure<T: Config> =
+        StorageMap<_, Twox64Concat, CustomerId, u128, ValueQuery>;
+
+    /// Sales Orders Storage
+    ///
+    /// Sales orders by SO number, with the account whose inventory fulfils them
+    #[pallet::storage]
+    pub type SalesOrders<T: Config> =
+        StorageMap<_, Twox64Concat, SalesOrderNumber, (T::AccountId, SalesOrder), OptionQuery>;
+
+    /// Sales Order Allocations Storage
+    ///
+    /// The serials and quantities allocated to each sales order until it ships or is cancelled
+    #[pallet::storage]
+    pub type SalesOrderAllocations<T: Config> =
+        StorageMap<_, Twox64Concat, SalesOrderNumber, Allocation, OptionQuery>;
+
+    /// Allocated Stock Storage
+    ///
+    /// The quantity of each serial allocated to sales orders, which cannot be allocated again
+    #[pallet::storage]
+    pub type AllocatedStock<T: Config> = StorageNMap<
+        _,
+        (
+            NMapKey<Blake2_128Concat, T::AccountId>,
+            NMapKey<Blake2_128Concat, Sku>,
+            NMapKey<Blake2_128Concat, SerialNumber>,
+        ),
+        Qty,
+        ValueQuery,
+    >;
+
+    /// Events that functions in this pallet can emit.
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config> {
+        CustomerAdded {
+            customer: Customer,
+        },
+        CustomerUpdated {
+            original_customer: Customer,
+            new_customer: Customer,
+        },
+        CustomerCreditHoldSet {
+            customer_id: CustomerId,
+            credit_hold: bool,
+        },
+        /// A sales order has been taken, held for credit if its status says so
+        SalesOrderCreated {
+            who: T::AccountId,
+            sales_order: SalesOrder,
+        },
+        CreditHoldReleased {
+            who: T::AccountId,
+            so_number: SalesOrderNumber,
+        },
+        SalesOrderAllocated {
+            who: T::AccountId,
+            so_number: SalesOrderNumber,
+            allocation: Allocation,
+        },
+        SalesOrderPicked {
+            who: T::AccountId,
+            so_number: SalesOrderNumber,
+            picked_by: Employee,
+        },
+        SalesOrderPacked {
+            who: T::AccountId,
+            so_number: SalesOrderNumber,
+        },
+        /// A sales order has shipped and its stock has been drawn down
+        ShipmentConfirmed {
+            who: T::AccountId,
+            so_number: SalesOrderNumber,
+            shipped_by: Employee,
+        },
+        SalesOrderCancelled {
+            who: T::AccountId,
+            so_number: SalesOrderNumber,
+        },
+    }
+
+    /// Errors that can be returned by this pallet.
+    #[pallet::error]
+    pub enum Error<T> {
+        /// The customer already exists
+        CustomerAlreadyExists,
+        /// The customer was not found
+        CustomerNotFound,
+        /// The customer is on credit hold
+        CustomerOnCreditHold,
+        /// The customer's unshipped orders are over their credit limit
+        CreditLimitExceeded,
+        /// A sales order with the same number already exists
+        SalesOrderAlreadyExists,
+        /// The sales order was not found
+        SalesOrderNotFound,
+        /// The sales order was taken by another account
+        NotSalesOrderOwner,
+        /// Every line must order a quantity
+        InvalidSalesOrderLine,
+        /// The sales order has no lines
+        NoSalesOrderLines,
+        /// The sales order is not at the right step for the call
+        InvalidSalesOrderStatus,
+        /// Not enough unallocated finished goods in the warehouse to cover a line
+        InsufficientInventory,
+        /// The sales order would be allocated too many serials
+        TooManyAllocations,
+        /// The sales order has no such allocation to change
+        AllocationNotFound,
+    }
+
+    /// The pallet's dispatchable functions
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Add a customer to the customer master
+        #[pallet::call_index(0)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn add_customer(origin: OriginFor<T>, customer: Customer) -> DispatchResult {
+            T::SalesAdminOrigin::ensure_origin(origin)?;
+
+            Self::do_add_customer(customer.clone())?;
+
+            Self::deposit_event(Event::CustomerAdded { customer });
+
+            Ok(())
+        }
+
+        /// Update an existing customer
+        #[pallet::call_index(1)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn update_customer(origin: OriginFor<T>, customer: Customer) -> DispatchResult {
+            T::SalesAdminOrigin::ensure_origin(origin)?;
+
+            let original_customer = Self::do_update_customer(customer.clone())?;
+
+            Self::deposit_event(Event::CustomerUpdated {
+                original_customer,
+                new_customer: customer,
+            });
+
+            Ok(())
+        }
+
+        /// Place a customer on credit hold, or lift it
+        ///
+        /// Orders already held stay held until they are released.
+        #[pallet::call_index(2)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn set_customer_credit_hold(
+            origin: OriginFor<T>,
+            customer_id: CustomerId,
+            credit_hold: bool,
+        ) -> DispatchResult {
+            T::SalesAdminOrigin::ensure_origin(origin)?;
+
+            Customers::<T>::try_mutate(customer_id, |customer| -> DispatchResult {
+                let customer = customer.as_mut().ok_or(Error::<T>::CustomerNotFound)?;
+                customer.credit_hold = credit_hold;
+                Ok(())
+            })?;
+
+            Self::deposit_event(Event::CustomerCreditHoldSet {
+                customer_id,
+                credit_hold,
+            });
+
+            Ok(())
+        }
+
+        /// Take a sales order from a customer
+        ///
+        /// Shipped quantities start at zero whatever is submitted.
+        #[pallet::call_index(3)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn create_sales_order(
+            origin: OriginFor<T>,
+            so_number: SalesOrderNumber,
+            customer_id: CustomerId,
+            lines: BoundedVec<SalesOrderLine, ConstU32<MAX_SO_LINES>>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let sales_order = Self::do_create_sales_order(&who, so_number, customer_id, lines)?;
+
+            Self::deposit_event(Event::SalesOrderCreated { who, sales_order });
+
+            Ok(())
+        }
+
+        /// Release a sales order held for credit
+        ///
+        /// The customer must be off credit hold, with their unshipped orders within their limit.
+        #[pallet::call_index(4)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn release_credit_hold(
+            origin: OriginFor<T>,
+            so_number: SalesOrderNumber,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            Self::do_release_credit_hold(&who, so_number)?;
+
+            Self::deposit_event(Event::CreditHoldReleased { who, so_number });
+
+            Ok(())
+        }
+
+        /// Reserve finished goods in the warehouse for every line of a sales order
+        #[pallet::call_index(5)]
+        #[pallet::weight(
+            Weight::from_parts(10_000, 0)
+                + T::DbWeight::get().reads_writes(2, 2)
+                + T::DbWeight::get().reads(2).saturating_mul(MAX_SO_LINES as u64)
+                + T::DbWeight::get().reads_writes(3, 1).saturating_mul(MAX_ALLOCATIONS as u64)
+        )]
+        pub fn allocate_sales_order(
+            origin: OriginFor<T>,
+            so_number: SalesOrderNumber,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let allocation = Self::do_allocate_sales_order(&who, so_number, None)?;
+
+            Self::deposit_event(Event::SalesOrderAllocated {
+                who,
+                so_number,
+                allocation,
+            });
+
+            Ok(())
+        }
+
+        /// Pick the allocated items of a sales order to packaging
+        #[pallet::call_index(6)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn pick_sales_order(
+            origin: OriginFor<T>,
+            so_number: SalesOrderNumber,
+            picked_by: Employee,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            Self::do_pick_sales_order(&who, so_number, &picked_by)?;
+
+            Self::deposit_event(Event::SalesOrderPicked {
+                who,
+                so_number,
+                picked_by,
+            });
+
+            Ok(())
+        }
+
+        /// Mark the picked items of a sales order as packed
+        #[pallet::call_index(7)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn pack_sales_order(
+            origin: OriginFor<T>,
+            so_number: SalesOrderNumber,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            Self::do_pack_sales_order(&who, so_number)?;
+
+            Self::deposit_event(Event::SalesOrderPacked { who, so_number });
+
+            Ok(())
+        }
+
+        /// Ship a packed sales order
+        ///
+        /// The packed items move to shipping and the shipped quantity is drawn down from stock.
+        #[pallet::call_index(8)]
+        #[pallet::weight(
+            Weight::from_parts(10_000, 0)
+                + T::DbWeight::get().reads_writes(2, 2)
+                + T::DbWeight::get().reads_writes(6, 8).saturating_mul(MAX_ALLOCATIONS as u64)
+        )]
+        pub fn confirm_shipment(
+            origin: OriginFor<T>,
+            so_number: SalesOrderNumber,
+            shipped_by: Employee,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            Self::do_confirm_shipment(&who, so_number, &shipped_by)?;
+
+            Self::deposit_event(Event::ShipmentConfirmed {
+                who,
+                so_number,
+                shipped_by,
+            });
+
+            Ok(())
+        }
+
+        /// Cancel a sales order that has not shipped
+        ///
+        /// Picked items go back to the warehouse and the allocation is released.
+        #[pallet::call_index(9)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn cancel_sales_order(
+            origin: OriginFor<T>,
+            so_number: SalesOrderNumber,
+            returned_by: Employee,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            Self::do_cancel_sales_order(&who, so_number, &returned_by)?;
+
+            Self::deposit_event(Event::SalesOrderCancelled { who, so_number });
+
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,543 @@
+use crate::{mock::*, types::*, Error, Event};
+use frame_support::pallet_prelude::ConstU32;
+use frame_support::sp_runtime::DispatchError;
+use frame_support::{assert_noop, assert_ok, BoundedVec};
+use pallet_inventory::types::{
+    AdjustDetails, Employee, Equipment, InventoryType, Item, Location, Qty, ScrapDetails,
+    SerialNumber, Sku,
+};
+
+const ALICE: u64 = 1;
+const BOB: u64 = 2;
+
+fn sku(sku: &str) -> Sku {
+    BoundedVec::truncate_from(sku.as_bytes().to_vec())
+}
+
+fn customer(customer_id: CustomerId, credit_limit: Option<u128>) -> Customer {
+    Customer {
+        customer_id,
+        name: BoundedVec::truncate_from(b"Globex".to_vec()),
+        credit_limit,
+        credit_hold: false,
+    }
+}
+
+fn line(qty: Qty, unit_price: UnitPrice) -> SalesOrderLine {
+    SalesOrderLine {
+        sku: sku("WIDGET"),
+        qty,
+        unit_price,
+        due_date: 20,
+        ..Default::default()
+    }
+}
+
+fn lines(lines: Vec<SalesOrderLine>) -> BoundedVec<SalesOrderLine, ConstU32<MAX_SO_LINES>> {
+    BoundedVec::truncate_from(lines)
+}
+
+/// Finished widgets in the warehouse, created in the order of their serial number
+fn widgets(serial_number: SerialNumber, qty: Qty) -> Item {
+    Item {
+        sku: sku("WIDGET"),
+        serial_number,
+        qty,
+        inventory_type: InventoryType::FinishedGood,
+        created_at: serial_number,
+        ..Default::default()
+    }
+}
+
+/// Add customer 0 with the given credit limit and stock Alice's warehouse
+fn set_up(credit_limit: Option<u128>, items: Vec<Item>) {
+    assert_ok!(Sales::add_customer(
+        RuntimeOrigin::root(),
+        customer(0, credit_limit)
+    ));
+    for item in items {
+        assert_ok!(Inventory::inventory_insertion(
+            RuntimeOrigin::signed(ALICE),
+            item
+        ));
+    }
+}
+
+fn create(so_number: SalesOrderNumber, so_lines: Vec<SalesOrderLine>) {
+    assert_ok!(Sales::create_sales_order(
+        RuntimeOrigin::signed(ALICE),
+        so_number,
+        0,
+        lines(so_lines)
+    ));
+}
+
+/// Take sales order 0 for six widgets against eight in stock, and allocate it
+fn set_up_allocated() {
+    set_up(None, vec![widgets(1, 3), widgets(2, 5)]);
+    create(0, vec![line(6, 100)]);
+    assert_ok!(Sales::allocate_sales_order(RuntimeOrigin::signed(ALICE), 0));
+}
+
+fn status(so_number: SalesOrderNumber) -> SalesOrderStatus {
+    crate::SalesOrders::<Test>::get(so_number).unwrap().1.status
+}
+
+fn item(serial_number: SerialNumber) -> Item {
+    pallet_inventory::Inventory::<Test>::get((ALICE, sku("WIDGET"), serial_number)).unwrap()
+}
+
+fn allocated(serial_number: SerialNumber, qty: Qty) -> AllocatedItem {
+    AllocatedItem {
+        line_index: 0,
+        sku: sku("WIDGET"),
+        serial_number,
+        qty,
+    }
+}
+
+#[test]
+fn customers_are_managed_by_the_admin_origin() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Sales::add_customer(RuntimeOrigin::signed(ALICE), customer(0, None)),
+            DispatchError::BadOrigin
+        );
+
+        assert_ok!(Sales::add_customer(
+            RuntimeOrigin::root(),
+            customer(0, None)
+        ));
+        assert_noop!(
+            Sales::add_customer(RuntimeOrigin::root(), customer(0, None)),
+            Error::<Test>::CustomerAlreadyExists
+        );
+
+        assert_ok!(Sales::update_customer(
+            RuntimeOrigin::root(),
+            customer(0, Some(500))
+        ));
+        System::assert_last_event(
+            Event::CustomerUpdated {
+                original_customer: customer(0, None),
+                new_customer: customer(0, Some(500)),
+            }
+            .into(),
+        );
+        assert_noop!(
+            Sales::update_customer(RuntimeOrigin::root(), customer(1, None)),
+            Error::<Test>::CustomerNotFound
+        );
+
+        assert_noop!(
+            Sales::set_customer_credit_hold(RuntimeOrigin::signed(ALICE), 0, true),
+            DispatchError::BadOrigin
+        );
+        assert_noop!(
+            Sales::set_customer_credit_hold(RuntimeOrigin::root(), 1, true),
+            Error::<Test>::CustomerNotFound
+        );
+    });
+}
+
+#[test]
+fn sales_orders_need_a_customer_and_lines() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Sales::create_sales_order(
+                RuntimeOrigin::signed(ALICE),
+                0,
+                0,
+                lines(vec![line(1, 100)])
+            ),
+            Error::<Test>::CustomerNotFound
+        );
+
+        set_up(None, vec![]);
+
+        assert_noop!(
+            Sales::create_sales_order(RuntimeOrigin::signed(ALICE), 0, 0, lines(vec![])),
+            Error::<Test>::NoSalesOrderLines
+        );
+        assert_noop!(
+            Sales::create_sales_order(
+                RuntimeOrigin::signed(ALICE),
+                0,
+                0,
+                lines(vec![line(0, 100)])
+            ),
+            Error::<Test>::InvalidSalesOrderLine
+        );
+
+        let mut shipped = line(2, 100);
+        shipped.shipped_qty = 2;
+        create(0, vec![shipped]);
+
+        let (owner, sales_order) = crate::SalesOrders::<Test>::get(0).unwrap();
+        assert_eq!(owner, ALICE);
+        assert_eq!(sales_order.status, SalesOrderStatus::Open);
+        assert_eq!(sales_order.lines[0].shipped_qty, 0);
+        assert_eq!(crate::CustomerExposure::<Test>::get(0), 200);
+        System::assert_last_event(
+            Event::SalesOrderCreated {
+                who: ALICE,
+                sales_order,
+            }
+            .into(),
+        );
+
+        assert_noop!(
+            Sales::create_sales_order(
+                RuntimeOrigin::signed(ALICE),
+                0,
+                0,
+                lines(vec![line(1, 100)])
+            ),
+            Error::<Test>::SalesOrderAlreadyExists
+        );
+    });
+}
+
+#[test]
+fn orders_over_the_credit_limit_are_held_until_it_is_freed() {
+    new_test_ext().execute_with(|| {
+        set_up(Some(1_000), vec![]);
+
+        create(0, vec![line(6, 100)]);
+        create(1, vec![line(6, 100)]);
+        assert_eq!(status(0), SalesOrderStatus::Open);
+        assert_eq!(status(1), SalesOrderStatus::CreditHold);
+        assert_eq!(crate::CustomerExposure::<Test>::get(0), 1_200);
+
+        assert_noop!(
+            Sales::allocate_sales_order(RuntimeOrigin::signed(ALICE), 1),
+            Error::<Test>::InvalidSalesOrderStatus
+        );
+        assert_noop!(
+            Sales::release_credit_hold(RuntimeOrigin::signed(ALICE), 1),
+            Error::<Test>::CreditLimitExceeded
+        );
+
+        assert_ok!(Sales::cancel_sales_order(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            Employee::Bob
+        ));
+        assert_eq!(crate::CustomerExposure::<Test>::get(0), 600);
+
+        assert_ok!(Sales::release_credit_hold(RuntimeOrigin::signed(ALICE), 1));
+        assert_eq!(status(1), SalesOrderStatus::Open);
+        System::assert_last_event(
+            Event::CreditHoldReleased {
+                who: ALICE,
+                so_number: 1,
+            }
+            .into(),
+        );
+
+        assert_noop!(
+            Sales::release_credit_hold(RuntimeOrigin::signed(ALICE), 1),
+            Error::<Test>::InvalidSalesOrderStatus
+        );
+    });
+}
+
+#[test]
+fn customers_on_credit_hold_have_every_order_held() {
+    new_test_ext().execute_with(|| {
+        set_up(None, vec![]);
+        assert_ok!(Sales::set_customer_credit_hold(
+            RuntimeOrigin::root(),
+            0,
+            true
+        ));
+        System::assert_last_event(
+            Event::CustomerCreditHoldSet {
+                customer_id: 0,
+                credit_hold: true,
+            }
+            .into(),
+        );
+
+        create(0, vec![line(1, 100)]);
+        assert_eq!(status(0), SalesOrderStatus::CreditHold);
+        assert_noop!(
+            Sales::release_credit_hold(RuntimeOrigin::signed(ALICE), 0),
+            Error::<Test>::CustomerOnCreditHold
+        );
+        assert_noop!(
+            Sales::release_credit_hold(RuntimeOrigin::signed(BOB), 0),
+            Error::<Test>::NotSalesOrderOwner
+        );
+
+        assert_ok!(Sales::set_customer_credit_hold(
+            RuntimeOrigin::root(),
+            0,
+            false
+        ));
+        assert_ok!(Sales::release_credit_hold(RuntimeOrigin::signed(ALICE), 0));
+        assert_eq!(status(0), SalesOrderStatus::Open);
+    });
+}
+
+#[test]
+fn allocation_reserves_finished_goods_first_in_first_out() {
+    new_test_ext().execute_with(|| {
+        let mut raw_material = widgets(3, 10);
+        raw_material.inventory_type = InventoryType::RawMaterial;
+        set_up(None, vec![widgets(2, 5), widgets(1, 3), raw_material]);
+        create(0, vec![line(6, 100)]);
+
+        assert_ok!(Sales::allocate_sales_order(RuntimeOrigin::signed(ALICE), 0));
+
+        let allocation: Allocation =
+            BoundedVec::truncate_from(vec![allocated(1, 3), allocated(2, 3)]);
+        assert_eq!(
+            crate::SalesOrderAllocations::<Test>::get(0),
+            Some(allocation.clone())
+        );
+        assert_eq!(
+            crate::AllocatedStock::<Test>::get((ALICE, sku("WIDGET"), 2)),
+            3
+        );
+        assert_eq!(status(0), SalesOrderStatus::Allocated);
+        System::assert_last_event(
+            Event::SalesOrderAllocated {
+                who: ALICE,
+                so_number: 0,
+                allocation,
+            }
+            .into(),
+        );
+
+        // Only two unallocated finished widgets are left
+        create(1, vec![line(3, 100)]);
+        assert_noop!(
+            Sales::allocate_sales_order(RuntimeOrigin::signed(ALICE), 1),
+            Error::<Test>::InsufficientInventory
+        );
+        assert_noop!(
+            Sales::allocate_sales_order(RuntimeOrigin::signed(ALICE), 0),
+            Error::<Test>::InvalidSalesOrderStatus
+        );
+        assert_noop!(
+            Sales::allocate_sales_order(RuntimeOrigin::signed(ALICE), 2),
+            Error::<Test>::SalesOrderNotFound
+        );
+    });
+}
+
+#[test]
+fn consigned_stock_is_allocated_only_once_settled() {
+    new_test_ext().execute_with(|| {
+        set_up(None, vec![]);
+        assert_ok!(Inventory::inventory_insertion(
+            RuntimeOrigin::signed(BOB),
+            widgets(1, 4)
+        ));
+        assert_ok!(Inventory::ship_transfer(
+            RuntimeOrigin::signed(BOB),
+            ALICE,
+            sku("WIDGET"),
+            1,
+            true,
+            Employee::Bob
+        ));
+        assert_ok!(Inventory::receive_transfer(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            Employee::Bob
+        ));
+        assert_ok!(Inventory::inventory_move(
+            RuntimeOrigin::signed(ALICE),
+            sku("WIDGET"),
+            1,
+            Employee::Bob,
+            AdjustDetails::Location {
+                original_location: Location::Receiving,
+                new_location: Location::Warehouse,
+                reason: BoundedVec::truncate_from(b"Put away".to_vec()),
+            }
+        ));
+        create(0, vec![line(4, 100)]);
+
+        // The widgets still belong to Bob
+        assert_noop!(
+            Sales::allocate_sales_order(RuntimeOrigin::signed(ALICE), 0),
+            Error::<Test>::InsufficientInventory
+        );
+
+        assert_ok!(Inventory::settle_consignment(
+            RuntimeOrigin::signed(BOB),
+            ALICE,
+            sku("WIDGET"),
+            1
+        ));
+        assert_ok!(Sales::allocate_sales_order(RuntimeOrigin::signed(ALICE), 0));
+    });
+}
+
+#[test]
+fn allocated_stock_cannot_be_taken_through_inventory() {
+    new_test_ext().execute_with(|| {
+        set_up_allocated();
+
+        assert_noop!(
+            Inventory::inventory_scrap(
+                RuntimeOrigin::signed(ALICE),
+                sku("WIDGET"),
+                1,
+                ScrapDetails {
+                    issuer: Employee::Bob,
+                    reason: BoundedVec::truncate_from(b"Dented".to_vec()),
+                    equipment: Equipment::Forklift,
+                }
+            ),
+            pallet_inventory::Error::<Test>::ItemAllocated
+        );
+        assert_noop!(
+            Inventory::inventory_adjust(
+                RuntimeOrigin::signed(ALICE),
+                Employee::Bob,
+                sku("WIDGET"),
+                2,
+                AdjustDetails::Quantity {
+                    original_qty: 5,
+                    new_qty: 2,
+                    reason: BoundedVec::truncate_from(b"Cycle count".to_vec()),
+                }
+            ),
+            pallet_inventory::Error::<Test>::ItemAllocated
+        );
+
+        // What is left unallocated can still be adjusted away
+        assert_ok!(Inventory::inventory_adjust(
+            RuntimeOrigin::signed(ALICE),
+            Employee::Bob,
+            sku("WIDGET"),
+            2,
+            AdjustDetails::Quantity {
+                original_qty: 5,
+                new_qty: 3,
+                reason: BoundedVec::truncate_from(b"Cycle count".to_vec()),
+            }
+        ));
+    });
+}
+
+#[test]
+fn shipment_draws_down_stock_through_packaging_to_shipping() {
+    new_test_ext().execute_with(|| {
+        set_up_allocated();
+
+        assert_noop!(
+            Sales::pack_sales_order(RuntimeOrigin::signed(ALICE), 0),
+            Error::<Test>::InvalidSalesOrderStatus
+        );
+        assert_noop!(
+            Sales::pick_sales_order(RuntimeOrigin::signed(BOB), 0, Employee::Bob),
+            Error::<Test>::NotSalesOrderOwner
+        );
+
+        assert_ok!(Sales::pick_sales_order(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            Employee::Bob
+        ));
+        assert_eq!(status(0), SalesOrderStatus::Picked);
+        assert_eq!(item(1).location, Location::Packaging);
+        assert_eq!(item(2).location, Location::Packaging);
+
+        assert_noop!(
+            Sales::confirm_shipment(RuntimeOrigin::signed(ALICE), 0, Employee::Dave),
+            Error::<Test>::InvalidSalesOrderStatus
+        );
+        assert_ok!(Sales::pack_sales_order(RuntimeOrigin::signed(ALICE), 0));
+        assert_eq!(status(0), SalesOrderStatus::Packed);
+
+        System::set_block_number(5);
+        assert_ok!(Sales::confirm_shipment(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            Employee::Dave
+        ));
+
+        // Serial 1 shipped in full, what serial 2 had over went back to the warehouse
+        assert_eq!(item(1).qty, 0);
+        assert_eq!(item(1).location, Location::Shipping);
+        assert_eq!(item(2).qty, 2);
+        assert_eq!(item(2).location, Location::Warehouse);
+        assert_eq!(
+            pallet_inventory::GlobalInventory::<Test>::get(sku("WIDGET")),
+            Some(2)
+        );
+
+        let (_, sales_order) = crate::SalesOrders::<Test>::get(0).unwrap();
+        assert_eq!(sales_order.status, SalesOrderStatus::Shipped);
+        assert_eq!(sales_order.shipped_at, Some(5));
+        assert_eq!(sales_order.lines[0].shipped_qty, 6);
+        assert_eq!(crate::SalesOrderAllocations::<Test>::get(0), None);
+        assert_eq!(
+            crate::AllocatedStock::<Test>::get((ALICE, sku("WIDGET"), 2)),
+            0
+        );
+        assert_eq!(crate::CustomerExposure::<Test>::get(0), 0);
+        System::assert_last_event(
+            Event::ShipmentConfirmed {
+                who: ALICE,
+                so_number: 0,
+                shipped_by: Employee::Dave,
+            }
+            .into(),
+        );
+
+        assert_noop!(
+            Sales::cancel_sales_order(RuntimeOrigin::signed(ALICE), 0, Employee::Bob),
+            Error::<Test>::InvalidSalesOrderStatus
+        );
+    });
+}
+
+#[test]
+fn cancelling_returns_picked_stock_and_releases_the_allocation() {
+    new_test_ext().execute_with(|| {
+        set_up_allocated();
+        assert_ok!(Sales::pick_sales_order(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            Employee::Bob
+        ));
+
+        assert_noop!(
+            Sales::cancel_sales_order(RuntimeOrigin::signed(BOB), 0, Employee::Bob),
+            Error::<Test>::NotSalesOrderOwner
+        );
+        assert_ok!(Sales::cancel_sales_order(
+            RuntimeOrigin::signed(ALICE),
+            0,
+            Employee::Bob
+        ));
+
+        assert_eq!(status(0), SalesOrderStatus::Cancelled);
+        assert_eq!(item(1).location, Location::Warehouse);
+        assert_eq!(item(2).location, Location::Warehouse);
+        assert_eq!(crate::SalesOrderAllocations::<Test>::get(0), None);
+        assert_eq!(
+            crate::AllocatedStock::<Test>::get((ALICE, sku("WIDGET"), 1)),
+            0
+        );
+        assert_eq!(crate::CustomerExposure::<Test>::get(0), 0);
+        System::assert_last_event(
+            Event::SalesOrderCancelled {
+                who: ALICE,
+                so_number: 0,
+            }
+            .into(),
+        );
+
+        assert_noop!(
+            Sales::cancel_sales_order(RuntimeOrigin::signed(ALICE), 0, Employee::Bob),
+            Error::<Test>::InvalidSalesOrderStatus
+        );
+    });
+}
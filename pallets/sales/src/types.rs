@@ -0,0 +1,86 @@
+use codec::{Decode, Encode};
+use frame_support::pallet_prelude::{ConstU32, MaxEncodedLen};
+use frame_support::BoundedVec;
+use pallet_inventory::types::{Qty, SerialNumber, Sku};
+use scale_info::TypeInfo;
+
+pub type CustomerId = u32;
+pub type SalesOrderNumber = u32;
+/// Index of a line within its sales order
+pub type LineIndex = u8;
+/// Price per unit, in the smallest unit of the sales currency
+pub type UnitPrice = u64;
+pub type CustomerName = BoundedVec<u8, ConstU32<64>>;
+
+/// Most lines a single sales order can hold
+pub const MAX_SO_LINES: u32 = 50;
+/// Most serials a single sales order can be allocated
+pub const MAX_ALLOCATIONS: u32 = 200;
+
+/// Customer master data
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
+pub struct Customer {
+    pub customer_id: CustomerId,
+    pub name: CustomerName,
+    /// Most the customer may have on unshipped orders, unlimited if not set
+    pub credit_limit: Option<u128>,
+    /// Every new order of the customer is held, and held orders cannot be released
+    pub credit_hold: bool,
+}
+
+/// A SKU ordered on a sales order
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
+pub struct SalesOrderLine {
+    pub sku: Sku,
+    pub qty: Qty,
+    pub unit_price: UnitPrice,
+    /// Block by which the customer expects the shipment
+    pub due_date: u32,
+    pub shipped_qty: Qty,
+}
+
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug, Default)]
+pub enum SalesOrderStatus {
+    #[default]
+    Open,
+    /// Held for credit, must be released before stock is allocated
+    CreditHold,
+    /// Finished goods are reserved for every line
+    Allocated,
+    /// The allocated items have been picked to packaging
+    Picked,
+    Packed,
+    Shipped,
+    Cancelled,
+}
+
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct SalesOrder {
+    pub so_number: SalesOrderNumber,
+    pub customer_id: CustomerId,
+    pub lines: BoundedVec<SalesOrderLine, ConstU32<MAX_SO_LINES>>,
+    pub status: SalesOrderStatus,
+    pub created_at: u32,
+    pub shipped_at: Option<u32>,
+}
+
+impl SalesOrder {
+    /// Total value of the order, across every line
+    pub fn total_value(&self) -> u128 {
+        self.lines.iter().fold(0u128, |total, line| {
+            total.saturating_add((line.unit_price as u128).saturating_mul(line.qty as u128))
+        })
+    }
+}
+
+/// Finished goods reserved from a single serial for a sales order line
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct AllocatedItem {
+    pub line_index: LineIndex,
+    pub sku: Sku,
+    pub serial_number: SerialNumber,
+    pub qty: Qty,
+}
+
+/// Every serial allocated to a single sales order
+pub type Allocation = BoundedVec<AllocatedItem, ConstU32<MAX_ALLOCATIONS>>;
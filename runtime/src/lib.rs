@@ -39,6 +39,7 @@ use frame_support::{
     traits::VariantCountOf,
 };
 pub use frame_system::Call as SystemCall;
+use frame_system::EnsureRoot;
 pub use pallet_balances::Call as BalancesCall;
 pub use pallet_timestamp::Call as TimestampCall;
 use pallet_transaction_payment::{ConstFeeMultiplier, FungibleAdapter, Multiplier};
@@ -50,6 +51,14 @@ pub use sp_runtime::{Perbill, Permill};
 pub use pallet_inventory;
 
 pub use pallet_assembly;
+pub use pallet_maintenance;
+pub use pallet_mrp;
+pub use pallet_packaging;
+pub use pallet_picking;
+pub use pallet_purchasing;
+pub use pallet_recall;
+pub use pallet_receiving;
+pub use pallet_sales;
 
 /// An index to a block.
 pub type BlockNumber = u32;
@@ -310,6 +319,10 @@ impl pallet_sudo::Config for Runtime {
 impl pallet_inventory::Config for Runtime {
     type RuntimeEvent = RuntimeEvent;
     type WeightInfo = pallet_inventory::weights::SubstrateWeight<Runtime>;
+    type StockAllocations = (Sales, Picking);
+    type StockChanges = Receiving;
+    type ApprovalAdminOrigin = EnsureRoot<AccountId>;
+    type ReorderAdminOrigin = EnsureRoot<AccountId>;
 }
 
 impl pallet_assembly::Config for Runtime {
@@ -317,6 +330,43 @@ impl pallet_assembly::Config for Runtime {
     type WeightInfo = pallet_assembly::weights::SubstrateWeight<Runtime>;
 }
 
+impl pallet_maintenance::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+}
+
+impl pallet_recall::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+}
+
+impl pallet_mrp::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type PlannerOrigin = EnsureRoot<AccountId>;
+}
+
+impl pallet_purchasing::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type PurchasingAdminOrigin = EnsureRoot<AccountId>;
+    type PurchaseReceipts = Mrp;
+}
+
+impl pallet_sales::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type SalesAdminOrigin = EnsureRoot<AccountId>;
+}
+
+impl pallet_packaging::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type CompanyPrefix = ConstU64<614141>;
+}
+
+impl pallet_receiving::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+}
+
+impl pallet_picking::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+}
+
 // Create the runtime by composing the FRAME pallets that were previously configured.
 #[frame_support::runtime]
 mod runtime {
@@ -361,6 +411,30 @@ mod runtime {
 
     #[runtime::pallet_index(8)]
     pub type Assembly = pallet_assembly;
+
+    #[runtime::pallet_index(9)]
+    pub type Maintenance = pallet_maintenance;
+
+    #[runtime::pallet_index(10)]
+    pub type Recall = pallet_recall;
+
+    #[runtime::pallet_index(11)]
+    pub type Mrp = pallet_mrp;
+
+    #[runtime::pallet_index(12)]
+    pub type Purchasing = pallet_purchasing;
+
+    #[runtime::pallet_index(13)]
+    pub type Sales = pallet_sales;
+
+    #[runtime::pallet_index(14)]
+    pub type Packaging = pallet_packaging;
+
+    #[runtime::pallet_index(15)]
+    pub type Receiving = pallet_receiving;
+
+    #[runtime::pallet_index(16)]
+    pub type Picking = pallet_picking;
 }
 
 /// The address format for describing accounts.
@@ -385,7 +459,10 @@ pub type SignedExtra = (
 ///
 /// This can be a tuple of types, each implementing `OnRuntimeUpgrade`.
 #[allow(unused_parens)]
-type Migrations = ();
+type Migrations = (
+    pallet_inventory::migrations::v1::MigrateV0ToV1<Runtime>,
+    pallet_assembly::migrations::v1::MigrateV0ToV1<Runtime>,
+);
 
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic =
@@ -532,6 +609,45 @@ impl_runtime_apis! {
         }
     }
 
+    impl pallet_assembly_runtime_api::AssemblyApi<Block, AccountId> for Runtime {
+        fn where_used_lot(
+            who: AccountId,
+            sku: pallet_inventory::types::Sku,
+            lot_number: pallet_inventory::types::LotNumber,
+        ) -> Vec<pallet_assembly::types::GenealogyLink> {
+            Assembly::where_used_lot(&who, sku, lot_number)
+        }
+
+        fn where_used_serial(
+            who: AccountId,
+            sku: pallet_inventory::types::Sku,
+            serial_number: pallet_inventory::types::SerialNumber,
+        ) -> Vec<pallet_assembly::types::GenealogyLink> {
+            Assembly::where_used_serial(&who, sku, serial_number)
+        }
+
+        fn as_built_contents(
+            who: AccountId,
+            sku: pallet_inventory::types::Sku,
+            serial_number: pallet_inventory::types::SerialNumber,
+        ) -> Vec<pallet_assembly::types::GenealogyLink> {
+            Assembly::as_built_contents(&who, sku, serial_number)
+        }
+
+        fn component_shortages(
+            who: AccountId,
+            work_order_number: pallet_assembly::types::WorkOrderNumber,
+        ) -> Vec<pallet_assembly::types::ShortComponent> {
+            Assembly::component_shortages(&who, work_order_number)
+        }
+    }
+
+    impl pallet_mrp_runtime_api::MrpApi<Block> for Runtime {
+        fn plan() -> Vec<pallet_mrp::types::PlannedOrder> {
+            Mrp::generate_plan(pallet_mrp::NextPlannedOrderId::<Runtime>::get())
+        }
+    }
+
     impl frame_system_rpc_runtime_api::AccountNonceApi<Block, AccountId, Nonce> for Runtime {
         fn account_nonce(account: AccountId) -> Nonce {
             System::account_nonce(account)